@@ -0,0 +1,134 @@
+//! 🔨 内核构建脚本
+//!
+//! - 只给内核的 bin 传入链接脚本 `memory.x`（用户程序由 user/build.rs 使用自己的 app.ld）
+//! - 生成 `$OUT_DIR/apps.rs`：用 `include_bytes!` 把已经编译好的用户程序 ELF 嵌入内核，供 `loader` 使用
+//!
+//! - 生成 `$OUT_DIR/initramfs.rs`：把 `initramfs/` 目录打包成 cpio（newc 格式），放进 `.initramfs` 段，
+//!   供 `fs::initramfs` 在启动时解包
+//!
+//! 用户程序名取自 `user/src/bin/*.rs`，ELF 默认在与内核相同的 target 目录下查找
+//! （先执行 `cargo build --release -p user --bins`，makefile 的 build 目标会自动完成），
+//! 也可以用环境变量 `USER_APPS_DIR` 指定；找不到的程序不嵌入，内核照常编译
+//!
+//! 环境变量 `INITRAMFS` 指定一个现成的 cpio 归档时直接嵌入它，不再打包 `initramfs/` 目录
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    println!("cargo:rustc-link-arg-bins=-T{}", manifest_dir.join("memory.x").display());
+    println!("cargo:rerun-if-changed=memory.x");
+
+    // OUT_DIR = target/<triple>/<profile>/build/<package>-<hash>/out
+    let apps_dir = match env::var("USER_APPS_DIR") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => out_dir.ancestors().nth(3).unwrap().to_path_buf(),
+    };
+    println!("cargo:rerun-if-env-changed=USER_APPS_DIR");
+
+    let bin_dir = manifest_dir.join("user/src/bin");
+    println!("cargo:rerun-if-changed={}", bin_dir.display());
+    let mut names: Vec<String> = fs::read_dir(&bin_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .filter_map(|name| name.strip_suffix(".rs").map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    let mut apps = String::from("/// 嵌入内核的用户程序（build.rs 生成）\npub static APPS: &[App] = &[\n");
+    for name in names {
+        let elf = apps_dir.join(&name);
+        println!("cargo:rerun-if-changed={}", elf.display());
+        if elf.is_file() {
+            writeln!(apps, "    App {{ name: {:?}, elf: include_bytes!({:?}) }},", name, elf.display().to_string()).unwrap();
+        }
+    }
+    apps.push_str("];\n");
+    fs::write(out_dir.join("apps.rs"), apps).unwrap();
+
+    let archive = match env::var("INITRAMFS") {
+        Ok(path) => {
+            println!("cargo:rerun-if-changed={}", path);
+            fs::read(&path).unwrap_or_else(|err| panic!("cannot read INITRAMFS={}: {}", path, err))
+        }
+        Err(_) => {
+            let root = manifest_dir.join("initramfs");
+            println!("cargo:rerun-if-changed={}", root.display());
+            pack_cpio(&root)
+        }
+    };
+    println!("cargo:rerun-if-env-changed=INITRAMFS");
+    let cpio = out_dir.join("initramfs.cpio");
+    fs::write(&cpio, &archive).unwrap();
+    let initramfs = format!(
+        "/// 嵌入内核的 initramfs 归档（build.rs 生成）\n\
+         #[used]\n\
+         #[unsafe(link_section = \".initramfs\")]\n\
+         static INITRAMFS: [u8; {}] = *include_bytes!({:?});\n",
+        archive.len(),
+        cpio.display().to_string()
+    );
+    fs::write(out_dir.join("initramfs.rs"), initramfs).unwrap();
+}
+
+/// 把目录 `root` 下的所有目录和普通文件打包成 cpio（newc 格式）；目录不存在时只有结束标记
+///
+/// 说明：uid/gid/mtime 统一为 0，项按路径排序，同样的内容总是得到同样的归档
+fn pack_cpio(root: &Path) -> Vec<u8> {
+    let mut paths = Vec::new();
+    collect(root, root, &mut paths);
+    paths.sort();
+
+    let mut archive = Vec::new();
+    for (ino, relative) in paths.iter().enumerate() {
+        let path = root.join(relative);
+        let name = relative.to_str().expect("initramfs path is not UTF-8");
+        if path.is_dir() {
+            push_cpio_entry(&mut archive, ino + 1, 0o040755, name, &[]);
+        } else {
+            push_cpio_entry(&mut archive, ino + 1, 0o100644, name, &fs::read(&path).unwrap());
+        }
+    }
+    push_cpio_entry(&mut archive, 0, 0, "TRAILER!!!", &[]);
+    archive
+}
+
+/// 递归收集 `dir` 下的目录和普通文件，路径相对于 `root`
+fn collect(root: &Path, dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        let file_type = entry.file_type().unwrap();
+        if file_type.is_dir() {
+            paths.push(path.strip_prefix(root).unwrap().to_path_buf());
+            collect(root, &path, paths);
+        } else if file_type.is_file() {
+            paths.push(path.strip_prefix(root).unwrap().to_path_buf());
+        }
+    }
+}
+
+/// 追加一项：110 字节的头、以 NUL 结尾的文件名、文件内容，后两者补齐到 4 字节
+fn push_cpio_entry(archive: &mut Vec<u8>, ino: usize, mode: u32, name: &str, data: &[u8]) {
+    let nlink = if mode & 0o040000 != 0 { 2 } else { 1 };
+    let fields = [ino as u32, mode, 0, 0, nlink, 0, data.len() as u32, 0, 0, 0, 0, name.len() as u32 + 1, 0];
+    archive.extend_from_slice(b"070701");
+    for field in fields {
+        archive.extend_from_slice(format!("{:08x}", field).as_bytes());
+    }
+    archive.extend_from_slice(name.as_bytes());
+    archive.push(0);
+    archive.resize(archive.len().next_multiple_of(4), 0);
+    archive.extend_from_slice(data);
+    archive.resize(archive.len().next_multiple_of(4), 0);
+}
@@ -2,7 +2,14 @@
  * 
  * 定义 RISC-V 64 位系统的内存布局：
  * - RAM: 128MB 内存空间，起始地址 0x80000000
+ *   （仅用于链接检查，运行时的实际内存大小以设备树为准）
  * - 各段按 4KB 对齐
+ * - .initramfs 为 build.rs 打包的 cpio 归档，启动时解包到内存文件系统
+ * - .tdata/.tbss 为线程局部存储模板，每个线程创建时按模板初始化一份 TLS 块
+ * - .stack 段为每个 hart 切出一块 64KB 的启动栈（最多 8 个 hart），
+ *   每块最低的 4KB 是保护区（PMP 禁止访问），栈溢出时触发访存异常
+ * - .apps 为固定地址的用户程序区域，独立编译的用户程序（user/app.ld）链接到这里，由 loader 装载
+ * - __KERNEL_END 之后的内存交给堆分配器使用
  */
 
 MEMORY {
@@ -29,6 +36,13 @@ SECTIONS {
         __RODATA_END = .;
     } > RAM
     
+    /* initramfs 归档（只读），见 fs/initramfs.rs */
+    .initramfs : ALIGN(4K) {
+        __INITRAMFS_START = .;
+        KEEP(*(.initramfs))
+        __INITRAMFS_END = .;
+    } > RAM
+
     /* 已初始化数据段 */
     .data : ALIGN(4K) {
         __DATA_START = .;
@@ -36,6 +50,22 @@ SECTIONS {
         __DATA_END = .;
     } > RAM
     
+    /* 线程局部存储模板：.tdata 为初始值，.tbss 只占 TLS 块中的空间（全零），不占镜像空间
+     * 必须与 thread/tls.rs 中的 TLS_ALIGN 保持一致 */
+    .tdata : ALIGN(64) {
+        __TDATA_START = .;
+        *(.tdata .tdata.*)
+        __TDATA_END = .;
+    } > RAM
+
+    .tbss : {
+        __TBSS_START = .;
+        *(.tbss .tbss.*)
+        *(.tcommon)
+        __TBSS_END = .;
+    } > RAM
+    __TLS_ALIGN = MAX(ALIGNOF(.tdata), ALIGNOF(.tbss));
+
     /* 未初始化数据段 */
     .bss : ALIGN(4K) {
         __BSS_START = .;
@@ -44,13 +74,27 @@ SECTIONS {
         __BSS_END = .;
     } > RAM
     
-    /* 栈内存段 */
+    /* 栈内存段：hart i 的栈为 [__STACK_START + i * 64K, __STACK_START + (i + 1) * 64K)，
+     * 其中最低的 4KB 为保护区 */
     .stack : ALIGN(4K) {
         __STACK_START = .;
-        . += 64K;
+        . += 64K * 8;
         __STACK_TOP = .;
         __STACK_END = .;
     } > RAM
+
+    /* 用户程序区域：必须与 user/app.ld 中的 APP_BASE / APP_SIZE 保持一致
+     * 内核镜像（含嵌入的用户程序 ELF）必须放得下在它前面 */
+    .apps 0x80800000 (NOLOAD) : {
+        __APP_START = .;
+        . += 4M;
+        __APP_END = .;
+    } > RAM
+    ASSERT(__STACK_END <= __APP_START, "kernel image overlaps the user app region")
+
+    /* 内核镜像结束位置（4KB 对齐） */
+    . = ALIGN(4K);
+    __KERNEL_END = .;
     
     /* 丢弃不需要的段 */
     /DISCARD/ : {
@@ -0,0 +1,88 @@
+//! 💽 测试 virtio-blk 块设备驱动
+//!
+//! - 列出所有接了设备的 virtio-mmio 槽位
+//! - 读取第 0 扇区并打印开头的内容
+//! - 在最后 4 个扇区写入测试数据（一次写多个扇区），读回比较后恢复原来的内容
+//! - 读写越界、缓冲区长度不对时返回错误
+//!
+//! 用法: make run APP=blk_test DISK=disk.img（`disk.img` 不存在时自动创建）
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+use alloc::vec;
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+use no_std::virtio::{self, blk::SECTOR_SIZE, blk::VirtioBlk};
+
+/// 测试读写的扇区数
+const TEST_SECTORS: usize = 4;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    for device in virtio::devices() {
+        println!(
+            "virtio-mmio 0x{:08x}: {} (version {}, vendor 0x{:x})",
+            device.base(),
+            device.device_type(),
+            device.version(),
+            device.vendor_id()
+        );
+    }
+
+    let mut blk = match VirtioBlk::probe() {
+        Ok(blk) => blk,
+        Err(err) => {
+            println!("❌ {} (run with DISK=disk.img)", err);
+            return;
+        }
+    };
+    println!("capacity: {} sectors, read-only: {}", blk.capacity(), blk.is_read_only());
+
+    let mut sector = [0u8; SECTOR_SIZE];
+    blk.read_block(0, &mut sector).expect("read sector 0");
+    println!("sector 0: {:02x?}", &sector[..32]);
+
+    if blk.capacity() < TEST_SECTORS {
+        println!("disk too small for the write test");
+        return;
+    }
+    let start = blk.capacity() - TEST_SECTORS;
+    let mut saved = vec![0u8; TEST_SECTORS * SECTOR_SIZE];
+    blk.read_block(start, &mut saved).expect("read test sectors");
+
+    let pattern: alloc::vec::Vec<u8> = (0..saved.len()).map(|i| (i * 7 + i / SECTOR_SIZE) as u8).collect();
+    match blk.write_block(start, &pattern) {
+        Ok(()) => {
+            let mut readback = vec![0u8; pattern.len()];
+            blk.read_block(start, &mut readback).expect("read back");
+            println!(
+                "write/read {} sectors at {}: {}",
+                TEST_SECTORS,
+                start,
+                if readback == pattern { "✅ match" } else { "❌ mismatch" }
+            );
+            blk.write_block(start, &saved).expect("restore test sectors");
+            blk.flush().expect("flush");
+        }
+        Err(err) => println!("write failed: {}", err),
+    }
+
+    println!("read past the end: {:?}", blk.read_block(blk.capacity(), &mut sector).err());
+    println!("read 100 bytes: {:?}", blk.read_block(0, &mut sector[..100]).err());
+    println!("✅ blk_test done");
+}
@@ -0,0 +1,154 @@
+//! 💾 测试 FAT32 文件系统
+//!
+//! - 挂载 virtio-blk 磁盘上的 FAT32 卷，打印卷标和容量，递归列出所有文件
+//! - 读取主机上放进镜像的文件（`make disk.img` 时复制的 `Cargo.toml` 和 `docs/` 目录）
+//! - 在 `/kernel` 下新建文件：跨多个簇写入后读回比较、seek 到文件末尾之后写入（中间补零）、截断、删除
+//! - 留下 `/kernel/Hello From Kernel.txt`，关机后可以在主机上检查：
+//!   `mtype -i disk.img "::/kernel/Hello From Kernel.txt"`、`fsck.vfat -n disk.img`
+//!
+//! 用法: make run APP=fat_test DISK=disk.img（需要 dosfstools 和 mtools 来生成镜像）
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use no_std::fs::fat::{Dir, FatFs};
+use no_std::fs::{FsError, SeekFrom};
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+use no_std::virtio::blk::VirtioBlk;
+
+/// 测试文件的长度：跨越多个簇
+const PATTERN_SIZE: usize = 20 * 1024;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let blk = match VirtioBlk::probe() {
+        Ok(blk) => blk,
+        Err(err) => {
+            println!("❌ {} (run with DISK=disk.img)", err);
+            return;
+        }
+    };
+    let fs = match FatFs::mount(blk) {
+        Ok(fs) => fs,
+        Err(err) => {
+            println!("❌ mount failed: {} (rm disk.img && make disk.img)", err);
+            return;
+        }
+    };
+    let stats = fs.stats().expect("stats");
+    println!(
+        "volume \"{}\": {} KB free of {} KB",
+        fs.volume_label(),
+        stats.free_clusters * stats.cluster_size / 1024,
+        stats.total_clusters * stats.cluster_size / 1024
+    );
+
+    let root = fs.root_dir();
+    println!("📂 /");
+    list(&root, 1);
+
+    for path in ["/Cargo.toml", "/docs/Project README.md"] {
+        match root.open_file(path) {
+            Ok(mut file) => {
+                let mut head = [0u8; 64];
+                let n = file.read(&mut head).expect("read");
+                let text = String::from_utf8_lossy(&head[..n]);
+                println!("{} ({} bytes): {:?}...", path, file.size(), text.lines().next().unwrap_or(""));
+            }
+            Err(err) => println!("{}: {}", path, err),
+        }
+    }
+
+    let dir = match root.create_dir("kernel") {
+        Err(FsError::AlreadyExists) => root.open_dir("kernel").expect("open /kernel"),
+        other => other.expect("create /kernel"),
+    };
+
+    let mut hello = dir.create_file("Hello From Kernel.txt").expect("create hello");
+    for i in 0..3 {
+        hello.write(format!("line {} written by fat_test\n", i).as_bytes()).expect("write hello");
+    }
+
+    // 跨簇写入并读回
+    let pattern: Vec<u8> = (0..PATTERN_SIZE).map(|i| (i * 7 + i / 512) as u8).collect();
+    let mut file = dir.create_file("pattern.bin").expect("create pattern.bin");
+    file.write(&pattern).expect("write pattern");
+    file.seek(SeekFrom::Start(0)).expect("seek");
+    let mut readback = Vec::new();
+    file.read_to_end(&mut readback).expect("read pattern");
+    check("multi-cluster write/read", readback == pattern);
+
+    // seek 到末尾之后写入：中间补零
+    file.seek(SeekFrom::End(1000)).expect("seek past end");
+    file.write(b"tail").expect("write tail");
+    file.seek(SeekFrom::Start(PATTERN_SIZE as u64)).expect("seek");
+    let mut gap = [0xffu8; 1000];
+    file.read(&mut gap).expect("read gap");
+    check("hole is zero-filled", gap.iter().all(|&b| b == 0) && file.size() == PATTERN_SIZE as u64 + 1004);
+
+    // 截断
+    file.seek(SeekFrom::Start(1234)).expect("seek");
+    file.truncate().expect("truncate");
+    file.seek(SeekFrom::End(-4)).expect("seek");
+    let mut last = [0u8; 4];
+    file.read(&mut last).expect("read");
+    check("truncate", file.size() == 1234 && last == pattern[1230..1234]);
+    drop(file);
+
+    // 新建再删除
+    dir.create_file("temporary file.tmp").expect("create temp");
+    dir.remove("temporary file.tmp").expect("remove temp");
+    dir.remove("pattern.bin").expect("remove pattern.bin");
+    check("unlink", matches!(dir.open_file("pattern.bin"), Err(FsError::NotFound)));
+    check("remove non-empty dir fails", root.remove("kernel") == Err(FsError::DirectoryNotEmpty));
+
+    fs.flush().expect("flush");
+    let cache = fs.cache_stats();
+    println!("block cache: {} hits, {} misses", cache.hits, cache.misses);
+    println!("📂 / after the test");
+    list(&root, 1);
+    println!("✅ fat_test done");
+}
+
+/// 递归列出目录
+fn list(dir: &Dir, depth: usize) {
+    let entries = match dir.entries() {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("{:indent$}error: {}", "", err, indent = depth * 2);
+            return;
+        }
+    };
+    for entry in entries {
+        if entry.is_dir() {
+            println!("{:indent$}📂 {}", "", entry.name(), indent = depth * 2);
+            if let Ok(sub) = dir.open_dir(entry.name()) {
+                list(&sub, depth + 1);
+            }
+        } else {
+            println!("{:indent$}📄 {} ({} bytes)", "", entry.name(), entry.size(), indent = depth * 2);
+        }
+    }
+}
+
+fn check(what: &str, ok: bool) {
+    println!("{} {}", if ok { "✅" } else { "❌" }, what);
+}
@@ -0,0 +1,79 @@
+//! 🧮 测试浮点上下文切换
+//!
+//! 多个线程同时做大量 f64 运算，并且各自设置不同的舍入模式（fcsr.frm），
+//! 计时器抢占在运算中途切换线程；每个线程的结果必须与单独计算时逐位相同
+//!
+//! 用法: make run APP=fp_test（可加 SMP=4 在多个 hart 间迁移）
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::arch::asm;
+use core::hint::black_box;
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+
+const WORKERS: usize = 4;
+const TERMS: usize = 200_000;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    // 先在单个线程里依次算出期望值
+    let expected: Vec<u64> = (0..WORKERS).map(|i| compute(i).to_bits()).collect();
+
+    let workers: Vec<thread::JoinHandle<u64>> = (0..WORKERS)
+        .map(|i| {
+            thread::Builder::new()
+                .name(no_std::format!("fp{}", i))
+                .spawn(move || compute(i).to_bits())
+        })
+        .collect();
+
+    for (i, worker) in workers.into_iter().enumerate() {
+        let bits = worker.join().expect("fp worker failed");
+        println!("fp{}: {} (expected {})", i, f64::from_bits(bits), f64::from_bits(expected[i]));
+        assert_eq!(bits, expected[i], "fp context of thread fp{} was corrupted", i);
+    }
+    println!("fp_test passed!");
+}
+
+/// 以第 `index` 种舍入模式计算交错级数，并在中途让出 CPU
+fn compute(index: usize) -> f64 {
+    // frm：0 RNE、1 RTZ、2 RDN、3 RUP
+    let rounding = index % 4;
+    unsafe { asm!("fsrm {0}", in(reg) rounding) };
+
+    let scale = (index + 1) as f64;
+    let mut sum = 0.0f64;
+    let mut sign = 1.0f64;
+    for k in 0..TERMS {
+        sum += black_box(sign * scale / (2 * k + 1) as f64);
+        sign = -sign;
+        if k % 50_000 == 0 {
+            thread::yield_now();
+        }
+    }
+
+    // 舍入模式也是浮点上下文的一部分，必须没有被其它线程改掉
+    let frm: usize;
+    unsafe { asm!("frrm {0}", out(reg) frm) };
+    assert_eq!(frm, rounding, "rounding mode of thread {} was changed", index);
+    unsafe { asm!("fsrm zero") };
+    sum
+}
@@ -0,0 +1,59 @@
+//! 🐞 测试内核 GDB 调试桩
+//!
+//! - 启动后在 `main_thread` 开头的 `gdbstub::breakpoint()` 处停住，等待 GDB 连接
+//! - 创建几个名为 `worker-N` 的线程，循环调用 `work`，可以在 `work` 上下断点、单步、
+//!   用 `info threads` 查看所有线程并切换到它们查看调用栈
+//! - 目标运行时在 GDB 中按 Ctrl-C 可以再次停住
+//!
+//! 用法: make debug-stub APP=gdb_test（可以加上 SMP=2），另一个终端中 make gdb-stub APP=gdb_test
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use no_std::gdbstub;
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+
+const WORKERS: usize = 3;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+    gdbstub::init();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    gdbstub::breakpoint();
+
+    let workers: [_; WORKERS] = core::array::from_fn(|i| {
+        thread::Builder::new().name(no_std::format!("worker-{}", i)).spawn(move || {
+            for round in 0..20 {
+                work(i, round);
+                thread::sleep(500);
+            }
+        })
+    });
+    for worker in workers {
+        let _ = worker.join();
+    }
+    println!("counter = {}", COUNTER.load(Ordering::Relaxed));
+}
+
+/// 断点的目标：不内联，保证有独立的符号
+#[inline(never)]
+fn work(worker: usize, round: usize) {
+    let value = COUNTER.fetch_add(worker + 1, Ordering::Relaxed);
+    println!("worker-{} round {}: counter {}", worker, round, value);
+}
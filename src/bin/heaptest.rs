@@ -40,32 +40,23 @@ pub fn heap_test() {
     use alloc::boxed::Box;
     use alloc::vec::Vec;
     
-    unsafe extern "C" {
-        static __BSS_START: u8;
-        static __BSS_END: u8;
-    }
+    // 测试 Box 分配
+    let a = Box::new(5);
+    assert_eq!(*a, 5);
+    // 检查分配的内存地址是否在堆范围内
+    assert!(heap::contains(a.as_ref() as *const _ as usize));
+    drop(a);
     
-    unsafe {
-        let bss_range = &__BSS_START as *const u8 as usize..&__BSS_END as *const u8 as usize;
-        
-        // 测试 Box 分配
-        let a = Box::new(5);
-        assert_eq!(*a, 5);
-        // 检查分配的内存地址是否在 BSS 段范围内
-        assert!(bss_range.contains(&(a.as_ref() as *const _ as usize)));
-        drop(a);
-        
-        // 测试 Vec 分配
-        let mut v: Vec<usize> = Vec::new();
-        for i in 0..500 {
-            v.push(i);
-        }
-        for i in 0..500 {
-            assert_eq!(v[i], i);
-        }
-        assert!(bss_range.contains(&(v.as_ptr() as usize)));
-        drop(v);
-        
-        println!("heap_test passed!");
+    // 测试 Vec 分配
+    let mut v: Vec<usize> = Vec::new();
+    for i in 0..500 {
+        v.push(i);
+    }
+    for i in 0..500 {
+        assert_eq!(v[i], i);
     }
+    assert!(heap::contains(v.as_ptr() as usize));
+    drop(v);
+    
+    println!("heap_test passed!");
 }
\ No newline at end of file
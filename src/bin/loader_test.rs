@@ -0,0 +1,79 @@
+//! 📦 测试 ELF 装载器
+//!
+//! - 列出嵌入内核的用户程序（`user/src/bin`）
+//! - 带命令行参数运行 `hello`，退出码为参数个数
+//! - 运行 `counter`：程序内部再用 spawn 系统调用创建线程，子线程与它共用同一个映像
+//! - `counter` 运行期间再装载 `hello`，两者链接到同一地址，返回 `LoadError::Busy`
+//! - `counter` 结束、映像释放后，`hello` 可以再次装载
+//! - 命令行参数放不进用户栈时返回 `LoadError::ArgsTooLarge`
+//!
+//! 用法: make run APP=loader_test
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use no_std::heap;
+use no_std::loader::{self, LoadError};
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread::{self, ExitStatus, user};
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let (start, end) = loader::app_region();
+    println!("📦 user app region: 0x{:x}-0x{:x}", start, end);
+    println!("📋 embedded user programs:");
+    for app in loader::apps() {
+        println!("   {:<12} {} bytes", app.name, app.elf.len());
+    }
+    if loader::find("hello").is_none() || loader::find("counter").is_none() {
+        println!("❌ user programs not found, build them first: cargo build --release -p user --bins");
+        return;
+    }
+
+    let status = run("hello", &["hello", "from", "the", "kernel"]);
+    assert_eq!(status, ExitStatus::Exited(4));
+
+    let counter = loader::spawn("counter", &["counter", "4"]).expect("failed to load counter");
+    // counter 还在运行，它的映像占着用户程序区域
+    match loader::spawn("hello", &["hello"]) {
+        Err(err @ LoadError::Busy { .. }) => println!("hello while counter is running: {}", err),
+        Err(err) => panic!("unexpected load error: {}", err),
+        Ok(_) => panic!("hello was loaded over a running image"),
+    }
+    let status = counter.join().expect("counter thread failed");
+    println!("counter: {}", status);
+    assert_eq!(status, ExitStatus::Exited(0));
+
+    // counter 的线程都结束后映像才释放，等分离的工作线程被回收
+    thread::sleep(20);
+    let status = run("hello", &["hello", "again"]);
+    assert_eq!(status, ExitStatus::Exited(2));
+
+    assert_eq!(loader::spawn("missing", &[]).err(), Some(LoadError::NotFound));
+    let long = "x".repeat(user::MAX_ARGS_SIZE);
+    assert_eq!(loader::spawn("hello", &["hello", &long]).err(), Some(LoadError::ArgsTooLarge));
+    println!("loader_test passed");
+}
+
+/// 运行一个用户程序并等待它结束
+fn run(name: &str, args: &[&str]) -> ExitStatus {
+    let status = loader::spawn(name, args)
+        .unwrap_or_else(|err| panic!("failed to load {}: {}", name, err))
+        .join()
+        .expect("user program failed");
+    println!("{}: {}", name, status);
+    status
+}
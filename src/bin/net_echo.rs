@@ -0,0 +1,105 @@
+//! 🌐 TCP/UDP 回显服务器
+//!
+//! - 初始化 virtio-net 网卡，按 QEMU 用户态网络配置地址（10.0.2.15/24，网关 10.0.2.2）
+//! - 在 7 号端口（echo）上同时提供 TCP 和 UDP 回显：每个 TCP 连接一个线程，UDP 由一个线程处理
+//! - TCP 客户端发送一行 `quit` 时打印收发统计并关机
+//!
+//! 用法: make run APP=net_echo NET=1，然后在主机上：
+//! - `nc localhost 5555`（TCP）
+//! - `nc -u localhost 5555`（UDP）
+
+#![no_std]
+#![no_main]
+
+use core::net::SocketAddrV4;
+
+use no_std::heap;
+use no_std::logging;
+use no_std::net::{self, NetError, TcpListener, TcpStream, UdpSocket};
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+use no_std::virtio::net::VirtioNet;
+
+/// echo 服务的端口（RFC 862）
+const ECHO_PORT: u16 = 7;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let Ok(device) = VirtioNet::probe() else {
+        println!("no network device (run with NET=1)");
+        return;
+    };
+    net::init(device, net::Config::QEMU_USER);
+
+    let udp = UdpSocket::bind(ECHO_PORT).expect("bind udp");
+    thread::Builder::new().name("udp-echo").spawn(move || udp_echo(udp));
+
+    let listener = TcpListener::bind(ECHO_PORT).expect("bind tcp");
+    println!("🌐 echo server listening on {} (tcp and udp)", listener.local_addr());
+    loop {
+        match listener.accept() {
+            Ok((stream, peer)) => {
+                println!("🔗 {} connected", peer);
+                thread::Builder::new().name("tcp-echo").spawn(move || tcp_echo(stream, peer));
+            }
+            Err(err) => println!("accept failed: {}", err),
+        }
+    }
+}
+
+/// 把收到的数据原样写回，直到对方关闭连接
+fn tcp_echo(stream: TcpStream, peer: SocketAddrV4) {
+    let mut buf = [0u8; 512];
+    let mut total = 0;
+    let result: Result<(), NetError> = loop {
+        let n = match stream.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(n) => n,
+            Err(err) => break Err(err),
+        };
+        if buf[..n].trim_ascii() == b"quit" {
+            let stats = net::stats();
+            println!(
+                "🛑 quit from {}: {} frames received, {} sent, {} dropped",
+                peer, stats.rx_frames, stats.tx_frames, stats.dropped
+            );
+            let _ = stream.write_all(b"bye\n");
+            // 等对方收到最后的数据
+            thread::sleep(100);
+            system::shutdown();
+        }
+        if let Err(err) = stream.write_all(&buf[..n]) {
+            break Err(err);
+        }
+        total += n;
+    };
+    match result {
+        Ok(()) => println!("👋 {} disconnected after {} bytes", peer, total),
+        Err(err) => println!("❌ {}: {}", peer, err),
+    }
+}
+
+fn udp_echo(socket: UdpSocket) {
+    let mut buf = [0u8; net::udp::MAX_PAYLOAD];
+    loop {
+        match socket.recv_from(&mut buf) {
+            Ok((n, from)) => {
+                println!("📨 {} bytes from {}", n, from);
+                if let Err(err) = socket.send_to(&buf[..n], from) {
+                    println!("udp send failed: {}", err);
+                }
+            }
+            Err(err) => println!("udp receive failed: {}", err),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! 🛡️ 测试 PMP 内存保护
+//!
+//! - 打印当前 hart 的 PMP 表项，查看 `.text`/`.rodata`/空指针页的默认保护
+//! - 查询几个地址由哪个表项决定访问权限
+//! - 最后向空指针附近写入，内核报告
+//!   "store access fault on hart 0 at 0x8 ...: denied by pmp2 ..." 并关机
+//!
+//! 用法: make run APP=pmp_test
+
+#![no_std]
+#![no_main]
+
+use no_std::heap;
+use no_std::logging;
+use no_std::pmp::{self, Access};
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+
+static GREETING: &str = "hello from .rodata";
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    pmp::print_entries();
+
+    let probes = [
+        ("main", main as *const () as usize),
+        ("GREETING", GREETING.as_ptr() as usize),
+        ("null + 8", 8),
+    ];
+    for (name, addr) in probes {
+        match pmp::lookup(addr) {
+            Some(entry) => println!(
+                "{:<10} 0x{:016x} -> pmp{} r={} w={} x={}",
+                name,
+                addr,
+                entry.index,
+                entry.allows(Access::Read),
+                entry.allows(Access::Write),
+                entry.allows(Access::Execute)
+            ),
+            None => println!("{:<10} 0x{:016x} -> no entry", name, addr),
+        }
+    }
+
+    println!("writing through a null pointer...");
+    unsafe { core::ptr::write_volatile(8 as *mut usize, 1) };
+    println!("pmp_test failed: null pointer write was not trapped");
+}
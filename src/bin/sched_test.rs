@@ -0,0 +1,91 @@
+//! 🗂️ 测试调度策略
+//!
+//! 使用固定优先级抢占式调度：两个低优先级的日志线程一直在运行，
+//! 高优先级的控制线程创建后立即抢占它们，连续输出完成后日志线程才继续轮转，
+//! 最后用 `thread::print_list` 打印各线程的 CPU 占用和抢占次数
+//!
+//! 用法: make run APP=sched_test
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread::{self, Policy};
+use no_std::timer;
+
+/// 日志线程优先级
+const LOG_PRIORITY: usize = 1;
+/// 控制线程优先级
+const CONTROL_PRIORITY: usize = 10;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    system::print_memory_layout();
+    heap::init_heap();
+
+    thread::init_with_policy(Policy::FixedPriority, main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let loggers: Vec<thread::JoinHandle<usize>> = (1..=2)
+        .map(|i| {
+            thread::Builder::new()
+                .name(no_std::format!("logger{}", i))
+                .priority(LOG_PRIORITY)
+                .spawn(move || log_task(i))
+        })
+        .collect();
+
+    // 控制线程优先级最高：创建后立即抢占当前线程和日志线程
+    let control = thread::Builder::new()
+        .name("control")
+        .priority(CONTROL_PRIORITY)
+        .spawn(control_task);
+
+    let cycles = control.join().expect("control thread failed");
+    println!("control loop finished {} cycles", cycles);
+    // 控制线程 CPU 占用最高，日志线程被它抢占过
+    thread::print_list();
+    for logger in loggers {
+        let lines = logger.join().expect("logger thread failed");
+        println!("logger wrote {} lines", lines);
+    }
+    println!("sched_test passed!");
+}
+
+/// 控制线程：连续执行若干个控制周期，期间不会被日志线程打断
+fn control_task() -> usize {
+    let cycles = 5;
+    for cycle in 1..=cycles {
+        println!("[control] cycle {} at {}", cycle, timer::get_time());
+        busy_wait(20);
+    }
+    cycles
+}
+
+/// 日志线程：和同优先级的线程按时间片轮转
+fn log_task(index: usize) -> usize {
+    let lines = 10;
+    for line in 1..=lines {
+        println!("[logger{}] line {} at {}", index, line, timer::get_time());
+        busy_wait(10);
+    }
+    lines
+}
+
+/// 忙等指定毫秒数（不让出 CPU，只能被抢占）
+fn busy_wait(ms: usize) {
+    let end = timer::get_time() + timer::clock_freq() * ms / 1000;
+    while timer::get_time() < end {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,100 @@
+//! 🐚 串口交互式 shell
+//!
+//! - 内置命令：`help`、`mem`、`heap`、`ps`、`log`、`time`、`spawn`、`kill`、`peek`、`poke`、`reboot`、`shutdown`
+//! - 本应用另外注册了两个命令，演示 `Command` trait：
+//!   - `echo [text...]`：原样输出参数
+//!   - `busy <ms> [threads]`：创建几个内核线程忙等一段时间，可以用 `ps` 观察 CPU 占用
+//! - 试试 `spawn ticker`，再用 `ps` 找到它的线程 id 后 `kill` 掉
+//!
+//! 用法: make run APP=shell（可以加上 SMP=4）
+
+#![no_std]
+#![no_main]
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::shell::{Command, CommandError, Shell, parse_number};
+use no_std::system;
+use no_std::thread;
+use no_std::timer;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let mut shell = Shell::new();
+    shell.set_prompt("no_std> ");
+    shell.register(Echo);
+    shell.register(Busy);
+    shell.run();
+}
+
+struct Echo;
+
+impl Command for Echo {
+    fn name(&self) -> &str {
+        "echo"
+    }
+
+    fn usage(&self) -> &str {
+        "echo [text...]"
+    }
+
+    fn help(&self) -> &str {
+        "print the arguments"
+    }
+
+    fn run(&self, args: &[&str]) -> Result<(), CommandError> {
+        let mut words = args.iter();
+        if let Some(first) = words.next() {
+            no_std::print!("{}", first);
+            for word in words {
+                no_std::print!(" {}", word);
+            }
+        }
+        println!();
+        Ok(())
+    }
+}
+
+struct Busy;
+
+impl Command for Busy {
+    fn name(&self) -> &str {
+        "busy"
+    }
+
+    fn usage(&self) -> &str {
+        "busy <ms> [threads]"
+    }
+
+    fn help(&self) -> &str {
+        "spin some kernel threads for a while"
+    }
+
+    fn run(&self, args: &[&str]) -> Result<(), CommandError> {
+        let (ms, threads) = match args {
+            [ms] => (parse_number(ms)?, 1),
+            [ms, threads] => (parse_number(ms)?, parse_number(threads)?),
+            _ => return Err(CommandError::Usage),
+        };
+        for i in 0..threads {
+            thread::Builder::new().name(no_std::format!("busy-{}", i)).spawn(move || {
+                let deadline = timer::get_time() + timer::clock_freq() * ms / 1000;
+                while timer::get_time() < deadline {
+                    core::hint::spin_loop();
+                }
+            });
+        }
+        println!("started {} busy thread(s) for {} ms", threads, ms);
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+//! 🧠 测试多核启动
+//!
+//! hart 0 完成全局初始化后释放其它 hart，每个 hart 在自己的启动栈上打印信息，
+//! 之后 hart 0 通过 IPI 在每个从核上执行函数并广播 fence
+//!
+//! 用法: make run APP=smp_test SMP=4
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use no_std::hart;
+use no_std::heap;
+use no_std::ipi;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::timer;
+use no_std::trap;
+
+/// 已经启动完成的从核数量
+static ARRIVED: AtomicUsize = AtomicUsize::new(0);
+/// 通过 `ipi::run_on` 执行过的次数
+static CALLED: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    system::print_memory_layout();
+    heap::init_heap();
+
+    println!("hart {} 完成全局初始化，共 {} 个 hart", hart::id(), hart::count());
+    hart::start_secondaries(secondary);
+
+    // 等待所有从核上线
+    while ARRIVED.load(Ordering::Acquire) < hart::count() - 1 {
+        core::hint::spin_loop();
+    }
+    println!("所有 hart 均已上线: {}", hart::online_count());
+
+    // 在每个从核上执行一次函数
+    for hart_id in 1..hart::count() {
+        ipi::run_on(hart_id, say_hello).expect("run_on failed");
+    }
+    assert_eq!(CALLED.load(Ordering::Acquire), hart::count() - 1);
+
+    // 广播 fence，等待所有 hart 完成
+    ipi::remote_fence(ipi::others_mask());
+    println!("smp_test passed!");
+
+    system::shutdown()
+}
+
+/// 在目标 hart 的 IPI 处理中执行
+fn say_hello() {
+    println!("hart {} 收到 run_on 调用", hart::id());
+    CALLED.fetch_add(1, Ordering::Release);
+}
+
+/// 从核的计时器中断处理：只把下一次触发推迟 1 秒
+fn idle_tick() {
+    timer::set_next_trigger(timer::get_time() + timer::clock_freq());
+}
+
+fn secondary(hart_id: usize) -> ! {
+    let marker = 0usize;
+    println!(
+        "hart {} 已启动, 栈上变量地址: 0x{:08x}",
+        hart_id,
+        &marker as *const usize as usize
+    );
+    // 打开本 hart 的中断，才能接收 IPI
+    timer::init(idle_tick);
+    ARRIVED.fetch_add(1, Ordering::Release);
+    loop {
+        trap::wait_for_interrupt();
+    }
+}
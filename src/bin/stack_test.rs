@@ -0,0 +1,56 @@
+//! 📏 测试栈溢出检测
+//!
+//! - 先打印线程表，查看各线程栈和 hart 启动栈的最高水位
+//! - 然后在一个 4KB 栈的线程里无限递归，撞上栈保护区后内核报告
+//!   "stack overflow in thread ..." 并关机，而不是悄悄改写堆内存
+//!
+//! 说明：QEMU 启用 Smepmp（`-cpu rv64,smepmp=true`）时保护区由 PMP 强制，溢出立即触发访存异常；
+//! 否则在线程切换和计时器 tick 时检查保护区
+//!
+//! 用法: make run APP=stack_test
+
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let shallow = thread::Builder::new().name("shallow").spawn(|| recurse(8));
+    shallow.join().expect("shallow thread failed");
+    thread::print_list();
+
+    println!("overflowing a 4KB stack...");
+    let deep = thread::Builder::new()
+        .name("deep")
+        .stack_size(4 * 1024)
+        .spawn(|| recurse(usize::MAX));
+    let _ = deep.join();
+    println!("stack_test failed: overflow was not detected");
+}
+
+/// 每层占用约 256 字节栈
+fn recurse(depth: usize) -> usize {
+    let buffer = black_box([depth as u8; 256]);
+    if depth == 0 {
+        return buffer[0] as usize;
+    }
+    // 让计时器 tick 有机会检查保护区
+    thread::yield_now();
+    recurse(depth - 1) + black_box(buffer[255]) as usize
+}
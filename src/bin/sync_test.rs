@@ -0,0 +1,143 @@
+//! 🔐 测试阻塞式同步原语
+//!
+//! - 多个线程用 `Mutex` 累加同一个计数器
+//! - 生产者/消费者通过 `Mutex` + `Condvar` 传递数据
+//! - `Semaphore` 限制同时进入临界区的线程数
+//! - `RwLock`、`Barrier`、`Once` 的基本用法
+//! - `channel` / `sync_channel` 消息通道，以及超时和断开检测
+//!
+//! 用法: make run APP=sync_test
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+use no_std::thread::sync::mpsc::{RecvTimeoutError, TryRecvError};
+use no_std::thread::sync::{Barrier, Condvar, Mutex, Once, RwLock, Semaphore};
+
+const WORKERS: usize = 4;
+const ROUNDS: usize = 100;
+
+static COUNTER: Mutex<usize> = Mutex::new(0);
+
+static QUEUE: Mutex<VecDeque<usize>> = Mutex::new(VecDeque::new());
+static QUEUE_READY: Condvar = Condvar::new();
+
+static SLOTS: Semaphore = Semaphore::new(2);
+static INSIDE: AtomicUsize = AtomicUsize::new(0);
+
+static CONFIG: RwLock<usize> = RwLock::new(0);
+static BARRIER: Barrier = Barrier::new(WORKERS);
+static INIT: Once = Once::new();
+static INIT_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    // 生产者/消费者
+    let consumer = thread::spawn(|| {
+        let mut sum = 0;
+        for _ in 0..ROUNDS {
+            let mut queue = QUEUE_READY.wait_while(QUEUE.lock(), |queue| queue.is_empty());
+            sum += queue.pop_front().unwrap();
+        }
+        sum
+    });
+
+    let workers: Vec<thread::JoinHandle<()>> = (0..WORKERS).map(|i| thread::spawn(move || worker(i))).collect();
+
+    for value in 1..=ROUNDS {
+        QUEUE.lock().push_back(value);
+        QUEUE_READY.notify_one();
+    }
+
+    for worker in workers {
+        worker.join().expect("worker failed");
+    }
+    let sum = consumer.join().expect("consumer failed");
+
+    assert_eq!(*COUNTER.lock(), WORKERS * ROUNDS);
+    assert_eq!(sum, ROUNDS * (ROUNDS + 1) / 2);
+    assert_eq!(INIT_RUNS.load(Ordering::Relaxed), 1);
+    assert_eq!(*CONFIG.read(), WORKERS);
+
+    channels();
+    println!("sync_test passed!");
+}
+
+fn channels() {
+    // 多个生产者，全部丢弃后接收方看到断开
+    let (tx, rx) = thread::channel();
+    for i in 0..WORKERS {
+        let tx = tx.clone();
+        thread::spawn(move || {
+            for j in 0..ROUNDS {
+                tx.send(i * ROUNDS + j).unwrap();
+            }
+        });
+    }
+    drop(tx);
+    let received: usize = rx.iter().sum();
+    let total = WORKERS * ROUNDS;
+    assert_eq!(received, total * (total - 1) / 2);
+    assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+
+    // 容量为 2 的有界通道：生产者会在通道满时阻塞
+    let (tx, rx) = thread::sync_channel(2);
+    let producer = thread::spawn(move || {
+        for value in 0..ROUNDS {
+            tx.send(value).unwrap();
+        }
+        // 没有人发送时，接收方超时
+        thread::sleep(50);
+    });
+    for value in 0..ROUNDS {
+        assert_eq!(rx.recv(), Ok(value));
+    }
+    assert_eq!(rx.recv_timeout(10), Err(RecvTimeoutError::Timeout));
+    producer.join().expect("producer failed");
+    assert_eq!(rx.recv_timeout(10), Err(RecvTimeoutError::Disconnected));
+    println!("channels passed");
+}
+
+fn worker(index: usize) {
+    INIT.call_once(|| {
+        INIT_RUNS.fetch_add(1, Ordering::Relaxed);
+    });
+
+    for _ in 0..ROUNDS {
+        *COUNTER.lock() += 1;
+    }
+
+    // 最多两个线程同时持有许可
+    SLOTS.acquire();
+    let inside = INSIDE.fetch_add(1, Ordering::SeqCst) + 1;
+    assert!(inside <= 2, "semaphore admitted {} threads", inside);
+    thread::sleep(10);
+    INSIDE.fetch_sub(1, Ordering::SeqCst);
+    SLOTS.release();
+
+    *CONFIG.write() += 1;
+    let leader = BARRIER.wait().is_leader();
+    let seen = *CONFIG.read();
+    println!("worker {} passed the barrier (leader: {}), config = {}", index, leader, seen);
+    assert_eq!(seen, WORKERS);
+}
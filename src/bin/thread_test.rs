@@ -34,25 +34,34 @@ fn main_thread() {
     // 创建n条子线程
     let n = 3;
     let times = 10;
-    let mut handles: Vec<thread::ThreadHandle> = Vec::new();
+    let mut handles: Vec<thread::JoinHandle<u32>> = Vec::new();
     for i in 1..=n {
         let thread_name = format!("thread{}", i);
-        let thread = thread::new_thread(move || {
-            task(&thread_name, times);
-        });
-        thread.start();
+        let thread = thread::Builder::new()
+            .name(thread_name.clone())
+            .stack_size(16 * 1024)
+            .spawn(move || task(&thread_name, times));
         handles.push(thread);
     }
-    // 等待所有子线程结束后再开始主线程的任务
-    handles.iter().for_each(|&handle| thread::join(handle));
+    // 等待所有子线程结束并取回结果后再开始主线程的任务
+    for handle in handles {
+        match handle.join() {
+            Ok(result) => println!("子线程结束, result: {}", result),
+            Err(e) => println!("join 失败: {}", e),
+        }
+    }
     task("main_thread", times);
 }
 
-fn task(thread_name: &str, times: u32) {
+fn task(thread_name: &str, times: u32) -> u32 {
+    let mut sum = 0;
     for i in 1..=times {
-        println!("我是 {}, result: {}, time: {}", thread_name, fib(i), timer::get_time());
+        let result = fib(i);
+        sum += result;
+        println!("我是 {}, result: {}, time: {}", thread_name, result, timer::get_time());
         sleep(1000);
     }
+    sum
 }
 
 // 递归实现fib
@@ -0,0 +1,81 @@
+//! 🧵 测试线程局部存储
+//!
+//! - 每个线程看到自己的一份 `thread_local!` 变量，互不干扰
+//! - `.tdata`（非零初始值）和 `.tbss`（零初始值）都按模板初始化
+//! - 线程退出时执行 TLS 变量的析构函数
+//!
+//! 用法: make run APP=tls_test
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+
+const WORKERS: usize = 4;
+const ROUNDS: usize = 50;
+
+/// 线程退出时被析构的次数
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// 析构时计数的 TLS 值
+struct Tracker(usize);
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+        println!("thread {} dropped its tracker", self.0);
+    }
+}
+
+no_std::thread_local! {
+    static COUNTER: Cell<usize> = Cell::new(0);
+    static BASE: Cell<usize> = Cell::new(1000);
+    static HISTORY: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+    static TRACKER: RefCell<Option<Tracker>> = RefCell::new(None);
+}
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    // 线程之外没有 TLS 块
+    assert!(COUNTER.try_with(|_| ()).is_err());
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let workers: Vec<thread::JoinHandle<usize>> = (0..WORKERS).map(|i| thread::spawn(move || worker(i))).collect();
+    for (i, worker) in workers.into_iter().enumerate() {
+        let total = worker.join().expect("worker failed");
+        assert_eq!(total, 1000 + i * ROUNDS);
+    }
+
+    assert_eq!(DROPPED.load(Ordering::SeqCst), WORKERS);
+    println!("tls_test passed!");
+}
+
+fn worker(index: usize) -> usize {
+    TRACKER.with_borrow_mut(|tracker| *tracker = Some(Tracker(index)));
+    for _ in 0..ROUNDS {
+        COUNTER.set(COUNTER.get() + index);
+        HISTORY.with_borrow_mut(|history| history.push(COUNTER.get()));
+        // 让出 CPU，确认切换线程后各自的值不会串
+        thread::yield_now();
+    }
+    HISTORY.with(|history| assert_eq!(history.borrow().len(), ROUNDS));
+    BASE.get() + COUNTER.get()
+}
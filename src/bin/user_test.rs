@@ -0,0 +1,84 @@
+//! 👤 测试 U-mode 用户线程和系统调用
+//!
+//! - `hello`：在 U-mode 中通过系统调用输出、读时间、睡眠、让出 CPU，并创建一个子线程
+//! - `bad_pointer`：把内核地址传给 write 系统调用，内核拒绝替它访问，返回 -EFAULT
+//! - `wild_write`：直接写内核的全局变量，被 PMP 拒绝，内核报告后只杀死这个线程
+//!
+//! 用法: make run APP=user_test
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread::{self, ExitStatus};
+
+/// 内核全局变量：用户线程不能访问
+static KERNEL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    let counter = &KERNEL_COUNTER as *const AtomicUsize as usize;
+
+    let status = thread::spawn_user(hello, 42).join().expect("hello thread failed");
+    println!("hello: {}", status);
+    assert_eq!(status, ExitStatus::Exited(42));
+
+    let status = thread::spawn_user(bad_pointer, counter).join().expect("bad_pointer thread failed");
+    println!("bad_pointer: {}", status);
+    assert_eq!(status, ExitStatus::Exited(0));
+
+    let status = thread::spawn_user(wild_write, counter).join().expect("wild_write thread failed");
+    println!("wild_write: {}", status);
+    assert!(matches!(status, ExitStatus::Killed { tval, .. } if tval == counter));
+    assert_eq!(KERNEL_COUNTER.load(Ordering::Relaxed), 0);
+
+    // 等分离的子线程结束
+    thread::sleep(50);
+    println!("user_test passed: the kernel survived a faulting user thread");
+}
+
+extern "C" fn hello(arg: usize) -> i32 {
+    user::println!("hello from U-mode: thread {}, arg {}, {} ms since boot", user::getpid(), arg, user::get_time());
+    for i in 0..3 {
+        user::sleep(10);
+        user::println!("  tick {} at {} ms", i, user::get_time());
+    }
+    let child = user::spawn(child, 7);
+    user::println!("spawned child thread {}", child);
+    user::yield_now();
+    // 返回值就是退出码
+    arg as i32
+}
+
+extern "C" fn child(arg: usize) -> i32 {
+    user::println!("child thread {} got arg {}", user::getpid(), arg);
+    user::exit(arg as i32)
+}
+
+extern "C" fn bad_pointer(addr: usize) -> i32 {
+    let kernel_memory = unsafe { core::slice::from_raw_parts(addr as *const u8, 8) };
+    let ret = user::write(user::STDOUT, kernel_memory);
+    user::println!("write from kernel memory returned {}", ret);
+    if ret == -user::syscall::EFAULT { 0 } else { 1 }
+}
+
+extern "C" fn wild_write(addr: usize) -> i32 {
+    user::println!("writing to kernel memory at 0x{:x}...", addr);
+    unsafe { core::ptr::write_volatile(addr as *mut usize, 1) };
+    user::println!("wild_write was not stopped");
+    0
+}
@@ -0,0 +1,188 @@
+//! 🌲 测试 VFS、tmpfs 和 initramfs
+//!
+//! - 启动时在 `/` 挂载 tmpfs，解包嵌入内核的 initramfs（仓库中的 `initramfs/` 目录），递归列出目录树
+//! - 读取 `/etc/motd` 和配置文件 `/etc/vfs.conf`，按配置的线程数启动 worker
+//! - `/tmp` 挂载另一个 tmpfs：worker 各自打开文件（拿到的描述符号相同，但属于各自的描述符表），
+//!   同时以 `APPEND` 方式写同一个日志文件，主线程读出它们留下的文件
+//! - `dup` 共享读写位置、删除打开着的文件、有文件开着时卸载返回 `Busy`、相对路径和 `chdir`
+//! - 有磁盘时把 FAT32 卷挂到 `/mnt/disk` 上，通过 VFS 列出它的根目录
+//!
+//! 用法: make run APP=vfs_test（可选 DISK=disk.img）
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use no_std::fs::fat::FatFs;
+use no_std::fs::tmpfs::TmpFs;
+use no_std::fs::vfs::{self, OpenFlags};
+use no_std::fs::{self, FsError, SeekFrom};
+use no_std::heap;
+use no_std::logging;
+use no_std::println;
+use no_std::system;
+use no_std::thread;
+use no_std::virtio::blk::VirtioBlk;
+
+/// 配置文件中没有 `workers` 时启动的线程数
+const DEFAULT_WORKERS: usize = 2;
+
+#[unsafe(no_mangle)]
+pub fn main() -> ! {
+    logging::init();
+    heap::init_heap();
+    fs::init();
+
+    thread::init(main_thread);
+
+    system::shutdown()
+}
+
+fn main_thread() {
+    println!("📂 /");
+    tree("/", 1);
+
+    let motd = vfs::read_file("/etc/motd").expect("read /etc/motd");
+    println!("/etc/motd:\n{}", String::from_utf8_lossy(&motd));
+
+    let config = String::from_utf8(vfs::read_file("/etc/vfs.conf").expect("read /etc/vfs.conf")).expect("utf-8");
+    let greeting = config_value(&config, "greeting").unwrap_or("hello");
+    let workers = config_value(&config, "workers")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_WORKERS);
+    println!("config: greeting = {:?}, workers = {}", greeting, workers);
+
+    vfs::mkdir("/tmp").expect("mkdir /tmp");
+    vfs::mount("/tmp", TmpFs::new()).expect("mount /tmp");
+
+    // worker 通过文件交换数据：各自的描述符表，共享的目录树
+    let handles: Vec<thread::JoinHandle<usize>> = (0..workers)
+        .map(|i| {
+            let greeting = String::from(greeting);
+            thread::spawn(move || worker(i, &greeting))
+        })
+        .collect();
+    let fds: Vec<usize> = handles.into_iter().map(|handle| handle.join().expect("worker failed")).collect();
+    check("each thread has its own descriptor table", fds.iter().all(|&fd| fd == fds[0]));
+    check("worker descriptors are not visible here", vfs::fstat(fds[0]) == Err(FsError::BadDescriptor));
+    for i in 0..workers {
+        let path = format!("/tmp/worker-{}.txt", i);
+        let text = vfs::read_file(&path).expect("read worker file");
+        println!("{}: {:?}", path, String::from_utf8_lossy(&text));
+    }
+    let log = vfs::read_file("/tmp/log").expect("read /tmp/log");
+    check("appends from all workers", log.iter().filter(|&&b| b == b'\n').count() == workers * 3);
+
+    // dup 共享读写位置
+    let fd = vfs::open("/etc/hostname", OpenFlags::READ).expect("open /etc/hostname");
+    let copy = vfs::dup(fd).expect("dup");
+    vfs::seek(copy, SeekFrom::Start(2)).expect("seek");
+    let mut buf = [0u8; 64];
+    let n = vfs::read(fd, &mut buf).expect("read");
+    check("dup shares the offset", vfs::read_file("/etc/hostname").unwrap()[2..] == buf[..n]);
+    check("read-only descriptor rejects writes", vfs::write(fd, b"x") == Err(FsError::BadDescriptor));
+    vfs::close(copy).expect("close");
+    vfs::close(fd).expect("close");
+
+    // 删除打开着的文件：描述符仍然可以读写
+    let fd = vfs::open("/tmp/scratch", OpenFlags::READ | OpenFlags::WRITE | OpenFlags::CREATE).expect("create scratch");
+    vfs::write(fd, b"still here").expect("write scratch");
+    vfs::remove("/tmp/scratch").expect("remove scratch");
+    vfs::seek(fd, SeekFrom::Start(0)).expect("seek");
+    let n = vfs::read(fd, &mut buf).expect("read removed file");
+    check("removed file stays readable while open", &buf[..n] == b"still here");
+    check("busy mount point", vfs::unmount("/tmp") == Err(FsError::Busy));
+    vfs::close(fd).expect("close");
+
+    // 相对路径
+    vfs::chdir("/tmp").expect("chdir");
+    vfs::write_file("relative.txt", b"relative").expect("write relative");
+    check("relative path", vfs::read_file("../tmp/./relative.txt").as_deref() == Ok(&b"relative"[..]));
+    vfs::chdir("/").expect("chdir");
+    vfs::unmount("/tmp").expect("unmount /tmp");
+    check("unmounted tmpfs is gone", vfs::stat("/tmp/relative.txt") == Err(FsError::NotFound));
+
+    mount_disk();
+
+    println!("mounts:");
+    for mount in vfs::mounts() {
+        println!("  {} on {}", mount.fs_name, mount.path);
+    }
+    vfs::sync().expect("sync");
+    println!("✅ vfs_test done");
+}
+
+fn worker(index: usize, greeting: &str) -> usize {
+    let path = format!("/tmp/worker-{}.txt", index);
+    let fd = vfs::open(&path, OpenFlags::WRITE | OpenFlags::CREATE).expect("create worker file");
+    vfs::write(fd, format!("{} from worker {}", greeting, index).as_bytes()).expect("write worker file");
+
+    let log = vfs::open("/tmp/log", OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::APPEND).expect("open log");
+    for round in 0..3 {
+        vfs::write(log, format!("worker {} round {}\n", index, round).as_bytes()).expect("append log");
+        thread::yield_now();
+    }
+    vfs::close(log).expect("close log");
+    // 不关闭 fd：线程退出时描述符表随 TLS 一起释放
+    fd
+}
+
+/// 有磁盘时把 FAT32 卷挂到 `/mnt/disk`
+fn mount_disk() {
+    let Ok(blk) = VirtioBlk::probe() else {
+        println!("no disk (run with DISK=disk.img to mount FAT32 on /mnt/disk)");
+        return;
+    };
+    let fat = match FatFs::mount(blk) {
+        Ok(fat) => fat,
+        Err(err) => {
+            println!("FAT32 mount failed: {}", err);
+            return;
+        }
+    };
+    vfs::mkdir_all("/mnt/disk").expect("mkdir /mnt/disk");
+    vfs::mount("/mnt/disk", fat).expect("mount /mnt/disk");
+    println!("📂 /mnt/disk");
+    tree("/mnt/disk", 1);
+}
+
+/// 递归列出目录
+fn tree(path: &str, depth: usize) {
+    let entries = match vfs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            println!("{:indent$}error: {}", "", err, indent = depth * 2);
+            return;
+        }
+    };
+    for entry in entries {
+        let child = format!("{}/{}", path.trim_end_matches('/'), entry.name);
+        match vfs::stat(&child) {
+            Ok(meta) if meta.is_dir() => {
+                println!("{:indent$}📂 {}", "", entry.name, indent = depth * 2);
+                tree(&child, depth + 1);
+            }
+            Ok(meta) => println!("{:indent$}📄 {} ({} bytes)", "", entry.name, meta.size, indent = depth * 2),
+            Err(err) => println!("{:indent$}{}: {}", "", entry.name, err, indent = depth * 2),
+        }
+    }
+}
+
+/// 在 `key = value` 格式的配置中查找 `key`，忽略 `#` 开头的注释行
+fn config_value<'a>(config: &'a str, key: &str) -> Option<&'a str> {
+    config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, value)| value.trim())
+}
+
+fn check(what: &str, ok: bool) {
+    println!("{} {}", if ok { "✅" } else { "❌" }, what);
+}
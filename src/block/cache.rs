@@ -0,0 +1,163 @@
+//! 🗃️ 块缓存
+//!
+//! 在块设备前面缓存最近访问的若干块：
+//! - 按 LRU 淘汰；写入只修改缓存（write-back），块被淘汰或 `flush` 时才写回设备
+//! - 文件系统的元数据（FAT 表、目录项）会被反复读写，缓存可以省去大部分设备请求
+//!
+//! 说明：缓存本身不加锁，由使用者（文件系统）保证互斥访问
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use log::warn;
+
+use super::{BLOCK_SIZE, BlockDevice, BlockError};
+
+/// 默认缓存的块数
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// 缓存命中统计
+#[derive(Debug, Clone, Copy)]
+pub struct CacheStats {
+    pub capacity: usize,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// 一个缓存槽位
+struct Slot {
+    /// 缓存的块号；None 表示空槽位
+    block: Option<usize>,
+    dirty: bool,
+    /// 最近一次访问的时间（`BlockCache::clock`）
+    last_used: u64,
+    data: Box<[u8; BLOCK_SIZE]>,
+}
+
+/// 🗃️ 带 LRU 淘汰和写回的块缓存
+pub struct BlockCache {
+    device: Box<dyn BlockDevice>,
+    slots: Vec<Slot>,
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+impl BlockCache {
+    /// 在 `device` 前面建立一个最多缓存 `capacity` 块的缓存
+    pub fn new(device: Box<dyn BlockDevice>, capacity: usize) -> Self {
+        let slots = (0..capacity.max(1))
+            .map(|_| Slot {
+                block: None,
+                dirty: false,
+                last_used: 0,
+                data: Box::new([0; BLOCK_SIZE]),
+            })
+            .collect();
+        Self {
+            device,
+            slots,
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// 设备容量（块数）
+    pub fn num_blocks(&self) -> usize {
+        self.device.num_blocks()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            capacity: self.slots.len(),
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    /// 找到缓存 `block` 的槽位，没有时淘汰最久未用的槽位并（在 `load` 为真时）从设备读入
+    fn slot(&mut self, block: usize, load: bool) -> Result<usize, BlockError> {
+        self.clock += 1;
+        if let Some(index) = self.slots.iter().position(|slot| slot.block == Some(block)) {
+            self.hits += 1;
+            self.slots[index].last_used = self.clock;
+            return Ok(index);
+        }
+        self.misses += 1;
+        let index = self
+            .slots
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, slot)| (slot.block.is_some(), slot.last_used))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        self.write_back(index)?;
+        let slot = &mut self.slots[index];
+        slot.block = None;
+        if load {
+            self.device.read_blocks(block, &mut slot.data[..])?;
+        }
+        slot.block = Some(block);
+        slot.last_used = self.clock;
+        Ok(index)
+    }
+
+    /// 槽位是脏的就写回设备
+    fn write_back(&mut self, index: usize) -> Result<(), BlockError> {
+        let slot = &mut self.slots[index];
+        if let (Some(block), true) = (slot.block, slot.dirty) {
+            self.device.write_blocks(block, &slot.data[..])?;
+            slot.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// 读取第 `block` 块中从 `offset` 开始的 `buf.len()` 个字节
+    pub fn read(&mut self, block: usize, offset: usize, buf: &mut [u8]) -> Result<(), BlockError> {
+        if offset + buf.len() > BLOCK_SIZE {
+            return Err(BlockError::InvalidBuffer);
+        }
+        let index = self.slot(block, true)?;
+        buf.copy_from_slice(&self.slots[index].data[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    /// 把 `data` 写到第 `block` 块的 `offset` 处（整块写入时不需要先从设备读入）
+    pub fn write(&mut self, block: usize, offset: usize, data: &[u8]) -> Result<(), BlockError> {
+        if offset + data.len() > BLOCK_SIZE {
+            return Err(BlockError::InvalidBuffer);
+        }
+        let index = self.slot(block, data.len() != BLOCK_SIZE)?;
+        let slot = &mut self.slots[index];
+        slot.data[offset..offset + data.len()].copy_from_slice(data);
+        slot.dirty = true;
+        Ok(())
+    }
+
+    /// 把第 `block` 块清零
+    pub fn zero(&mut self, block: usize) -> Result<(), BlockError> {
+        let index = self.slot(block, false)?;
+        let slot = &mut self.slots[index];
+        slot.data.fill(0);
+        slot.dirty = true;
+        Ok(())
+    }
+
+    /// 写回所有脏块，并刷新设备的写缓存
+    pub fn flush(&mut self) -> Result<(), BlockError> {
+        for index in 0..self.slots.len() {
+            self.write_back(index)?;
+        }
+        self.device.flush()
+    }
+}
+
+impl Drop for BlockCache {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            warn!("block cache: flush on drop failed: {}", err);
+        }
+    }
+}
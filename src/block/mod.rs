@@ -0,0 +1,70 @@
+//! 🧱 块设备抽象
+//!
+//! 文件系统通过 `BlockDevice` trait 访问存储，不关心底下是 virtio-blk 还是别的设备：
+//! - 以 `BLOCK_SIZE`（512 字节）为单位读写，一次可以访问连续的多个块
+//! - `BlockCache`（见 `cache`）在设备前面缓存最近访问的块
+
+pub mod cache;
+
+pub use cache::{BlockCache, CacheStats};
+
+use core::fmt::{Display, Formatter};
+
+use crate::virtio::VirtioError;
+
+/// 块大小（字节）
+pub const BLOCK_SIZE: usize = 512;
+
+/// 块设备操作失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockError {
+    /// 访问超出设备容量
+    OutOfRange { block: usize, count: usize },
+    /// 缓冲区长度不是块大小的整数倍，或块内偏移越界
+    InvalidBuffer,
+    /// 设备只读
+    ReadOnly,
+    /// 设备报告错误
+    Io,
+}
+
+impl Display for BlockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            BlockError::OutOfRange { block, count } => {
+                write!(f, "blocks {}..{} are out of range", block, block + count)
+            }
+            BlockError::InvalidBuffer => write!(f, "invalid buffer"),
+            BlockError::ReadOnly => write!(f, "device is read-only"),
+            BlockError::Io => write!(f, "device I/O error"),
+        }
+    }
+}
+
+impl From<VirtioError> for BlockError {
+    fn from(err: VirtioError) -> Self {
+        match err {
+            VirtioError::OutOfRange { block, count } => BlockError::OutOfRange { block, count },
+            VirtioError::InvalidBuffer => BlockError::InvalidBuffer,
+            VirtioError::ReadOnly => BlockError::ReadOnly,
+            _ => BlockError::Io,
+        }
+    }
+}
+
+/// 🧱 块设备
+pub trait BlockDevice: Send {
+    /// 设备容量（块数）
+    fn num_blocks(&self) -> usize;
+
+    /// 从第 `start` 块开始读取，填满 `buf`（长度必须是 `BLOCK_SIZE` 的整数倍）
+    fn read_blocks(&mut self, start: usize, buf: &mut [u8]) -> Result<(), BlockError>;
+
+    /// 把 `buf` 写到第 `start` 块开始的位置（长度必须是 `BLOCK_SIZE` 的整数倍）
+    fn write_blocks(&mut self, start: usize, buf: &[u8]) -> Result<(), BlockError>;
+
+    /// 把设备的写缓存刷到存储介质
+    fn flush(&mut self) -> Result<(), BlockError> {
+        Ok(())
+    }
+}
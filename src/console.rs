@@ -1,26 +1,61 @@
 //! 🖥️ 串口控制台模块
 //! 
-//! 提供基于 QEMU virt 平台的 UART 串口输出功能，
-//! 支持格式化打印和换行输出。
+//! 提供基于 ns16550a UART 的串口输出功能，
+//! 支持格式化打印和换行输出，以及轮询方式的字节输入。UART 基地址来自设备树。
 
 use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-/// QEMU virt UART 基地址
-const UART_BASE: usize = 0x1000_0000;
+use crate::{dtb, hart, trap};
+
+/// 控制台输出锁：记录持有者的 hart id + 1（0 表示空闲）
+///
+/// 说明：
+/// - 多个 hart 同时打印时保证一行输出不被打断
+/// - 同一个 hart 上重入（例如 trap/panic 中打印）直接放行，避免自己等自己
+static CONSOLE_OWNER: AtomicUsize = AtomicUsize::new(0);
+
+/// 持有控制台输出锁执行 `f`
+///
+/// 说明：持锁期间关闭中断，避免线程在输出一半时被抢占，让同一 hart 上的其它线程插入输出
+fn with_console_lock(f: impl FnOnce()) {
+    let me = hart::id() + 1;
+    if CONSOLE_OWNER.load(Ordering::Relaxed) == me {
+        f();
+        return;
+    }
+    let irq_enabled = trap::disable_interrupts();
+    while CONSOLE_OWNER
+        .compare_exchange_weak(0, me, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        core::hint::spin_loop();
+    }
+    f();
+    CONSOLE_OWNER.store(0, Ordering::Release);
+    trap::restore_interrupts(irq_enabled);
+}
 
 /// 🖥️ 通用异步收发器 (UART)
-pub struct Uart;
+pub struct Uart {
+    base: usize,
+}
 
 impl Uart {
-    /// 创建新的 UART 实例
-    pub const fn new() -> Self {
-        Self
+    /// 创建控制台 UART 实例（基地址取自设备树）
+    pub fn new() -> Self {
+        Self::at(dtb::platform().uart)
+    }
+
+    /// 创建指定基地址的 UART 实例
+    pub const fn at(base: usize) -> Self {
+        Self { base }
     }
     
     /// 检查 UART 是否可写
     fn is_writable(&self) -> bool {
         // 检查状态寄存器 (LSR) 的发送就绪位
-        unsafe { (core::ptr::read_volatile((UART_BASE + 0x5) as *const u8) & (1 << 5)) != 0 }
+        unsafe { (core::ptr::read_volatile((self.base + 0x5) as *const u8) & (1 << 5)) != 0 }
     }
     
     /// 写入单个字节
@@ -30,7 +65,7 @@ impl Uart {
         
         // 写入数据寄存器
         unsafe {
-            core::ptr::write_volatile(UART_BASE as *mut u8, byte);
+            core::ptr::write_volatile(self.base as *mut u8, byte);
         }
     }
     
@@ -40,6 +75,17 @@ impl Uart {
             self.write_byte(byte);
         }
     }
+
+    /// 读取一个字节；接收缓冲区为空时返回 None（不等待）
+    pub fn read_byte(&self) -> Option<u8> {
+        // 状态寄存器 (LSR) 的数据就绪位
+        unsafe {
+            if core::ptr::read_volatile((self.base + 0x5) as *const u8) & 1 == 0 {
+                return None;
+            }
+            Some(core::ptr::read_volatile(self.base as *const u8))
+        }
+    }
 }
 
 /// 全局控制台写入器
@@ -55,20 +101,37 @@ impl Write for ConsoleWriter {
 
 /// 初始化控制台
 pub fn init() {
-    // QEMU virt 平台 UART 默认已初始化
+    // QEMU virt 平台 UART 默认已初始化，这里只确保设备树已解析
+    dtb::init();
 }
 
 /// 输出格式化内容
 pub fn _print(args: fmt::Arguments) {
-    let mut writer = ConsoleWriter;
-    let _ = fmt::write(&mut writer, args);
+    with_console_lock(|| {
+        let mut writer = ConsoleWriter;
+        let _ = fmt::write(&mut writer, args);
+    });
 }
 
 /// 输出格式化内容并换行
 pub fn _println(args: fmt::Arguments) {
-    _print(args);
-    let uart = Uart::new();
-    uart.write_byte(b'\n');
+    with_console_lock(|| {
+        _print(args);
+        let uart = Uart::new();
+        uart.write_byte(b'\n');
+    });
+}
+
+/// 原样输出字节序列（供 write 系统调用使用，内容不一定是合法的 UTF-8）
+pub fn write_bytes(bytes: &[u8]) {
+    with_console_lock(|| Uart::new().write_bytes(bytes));
+}
+
+/// 从控制台读取一个字节，没有输入时返回 None
+///
+/// 说明：轮询 UART，不使用中断；需要等待输入时由调用方睡眠或让出 CPU 后重试
+pub fn read_byte() -> Option<u8> {
+    Uart::new().read_byte()
 }
 
 /// print! 宏
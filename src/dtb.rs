@@ -0,0 +1,481 @@
+//! 🌳 设备树（FDT/DTB）解析模块
+//!
+//! QEMU 跳转到 `_start` 时会通过 `a1` 传入设备树地址，入口汇编把它保存在 `__dtb_addr` 中。
+//! 本模块在不分配堆内存的前提下解析设备树，提取内存区域、timebase 频率、hart 数量、
+//! 需要关心的 ISA 扩展，以及 UART、CLINT、PLIC、test、virtio-mmio 设备的基地址。
+//!
+//! 若设备树缺失或格式不正确，则回退到 QEMU virt 平台的默认值。
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+/// 最多记录的内存区域数量
+pub const MAX_MEMORY_REGIONS: usize = 4;
+/// 最多记录的保留内存区域数量（/memreserve/）
+pub const MAX_RESERVED_REGIONS: usize = 4;
+/// 最多记录的 virtio-mmio 设备槽位数量
+pub const MAX_VIRTIO_SLOTS: usize = 8;
+
+/// FDT 头部魔数
+const FDT_MAGIC: u32 = 0xd00d_feed;
+/// 结构块中的 token
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+/// 支持的最大节点嵌套深度
+const MAX_DEPTH: usize = 16;
+
+/// 一段物理内存区域 `[base, base + size)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub base: usize,
+    pub size: usize,
+}
+
+impl MemoryRegion {
+    pub const fn empty() -> Self {
+        Self { base: 0, size: 0 }
+    }
+
+    /// 区域结束地址（不包含）
+    pub fn end(&self) -> usize {
+        self.base + self.size
+    }
+
+    /// 判断地址是否落在区域内
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.base && addr < self.end()
+    }
+}
+
+/// 🗺️ 平台信息：从设备树中解析出的硬件描述
+#[derive(Debug, Clone, Copy)]
+pub struct Platform {
+    memory: [MemoryRegion; MAX_MEMORY_REGIONS],
+    memory_count: usize,
+    reserved: [MemoryRegion; MAX_RESERVED_REGIONS],
+    reserved_count: usize,
+    virtio: [usize; MAX_VIRTIO_SLOTS],
+    virtio_count: usize,
+    /// 设备树自身占用的内存范围（初始化堆时需要避开）；None 表示没有设备树
+    pub dtb: Option<MemoryRegion>,
+    /// mtime 的计数频率（Hz）
+    pub timebase_frequency: usize,
+    /// 可用 hart 数量
+    pub hart_count: usize,
+    /// 所有 hart 都支持 Smepmp（PMP 规则锁定旁路等机器模式增强）
+    pub smepmp: bool,
+    /// ns16550a UART 基地址
+    pub uart: usize,
+    /// 第二个 ns16550a UART 的基地址（供 gdbstub 使用）；没有时为 None
+    pub debug_uart: Option<usize>,
+    /// CLINT（mtime/mtimecmp/msip）基地址
+    pub clint: usize,
+    /// PLIC 基地址
+    pub plic: usize,
+    /// sifive,test 设备基地址（用于关机/重启）
+    pub test: usize,
+}
+
+impl Platform {
+    /// QEMU virt 平台的默认配置（没有设备树时使用）
+    pub const fn qemu_virt() -> Self {
+        let mut memory = [MemoryRegion::empty(); MAX_MEMORY_REGIONS];
+        memory[0] = MemoryRegion {
+            base: 0x8000_0000,
+            size: 128 * 1024 * 1024,
+        };
+        // QEMU virt 在 0x10001000 起有 8 个 virtio-mmio 槽位，每个占 0x1000
+        let mut virtio = [0; MAX_VIRTIO_SLOTS];
+        let mut i = 0;
+        while i < MAX_VIRTIO_SLOTS {
+            virtio[i] = 0x1000_1000 + i * 0x1000;
+            i += 1;
+        }
+        Self {
+            memory,
+            memory_count: 1,
+            reserved: [MemoryRegion::empty(); MAX_RESERVED_REGIONS],
+            reserved_count: 0,
+            virtio,
+            virtio_count: MAX_VIRTIO_SLOTS,
+            dtb: None,
+            timebase_frequency: 10_000_000,
+            hart_count: 1,
+            smepmp: false,
+            uart: 0x1000_0000,
+            debug_uart: None,
+            clint: 0x0200_0000,
+            plic: 0x0c00_0000,
+            test: 0x0010_0000,
+        }
+    }
+
+    /// 所有内存区域（来自 `device_type = "memory"` 节点）
+    pub fn memory_regions(&self) -> &[MemoryRegion] {
+        &self.memory[..self.memory_count]
+    }
+
+    /// 设备树头部 memreserve 块声明的保留区域
+    pub fn reserved_regions(&self) -> &[MemoryRegion] {
+        &self.reserved[..self.reserved_count]
+    }
+
+    /// 所有 virtio-mmio 槽位的基地址（按地址升序；槽位上不一定接了设备）
+    pub fn virtio_mmio(&self) -> &[usize] {
+        &self.virtio[..self.virtio_count]
+    }
+
+    /// 找到包含指定地址的内存区域
+    pub fn memory_region_of(&self, addr: usize) -> Option<MemoryRegion> {
+        self.memory_regions()
+            .iter()
+            .copied()
+            .find(|region| region.contains(addr))
+    }
+
+    fn push_memory(&mut self, region: MemoryRegion) {
+        if region.size != 0 && self.memory_count < MAX_MEMORY_REGIONS {
+            self.memory[self.memory_count] = region;
+            self.memory_count += 1;
+        }
+    }
+
+    fn push_reserved(&mut self, region: MemoryRegion) {
+        if region.size != 0 && self.reserved_count < MAX_RESERVED_REGIONS {
+            self.reserved[self.reserved_count] = region;
+            self.reserved_count += 1;
+        }
+    }
+
+    /// 按地址升序插入一个 virtio-mmio 槽位（设备树中的节点通常是降序排列的）
+    fn push_virtio(&mut self, base: usize) {
+        if self.virtio_count >= MAX_VIRTIO_SLOTS {
+            return;
+        }
+        let pos = self.virtio[..self.virtio_count].partition_point(|&slot| slot < base);
+        self.virtio.copy_within(pos..self.virtio_count, pos + 1);
+        self.virtio[pos] = base;
+        self.virtio_count += 1;
+    }
+}
+
+struct GlobalPlatform(UnsafeCell<Platform>);
+unsafe impl Sync for GlobalPlatform {}
+
+static PLATFORM: GlobalPlatform = GlobalPlatform(UnsafeCell::new(Platform::qemu_virt()));
+
+/// 解析状态：未解析 / 解析中 / 已完成
+const STATE_UNINIT: u8 = 0;
+const STATE_BUSY: u8 = 1;
+const STATE_READY: u8 = 2;
+static STATE: AtomicU8 = AtomicU8::new(STATE_UNINIT);
+
+/// 获取 `_start` 保存下来的设备树地址（0 表示没有）
+pub fn dtb_addr() -> usize {
+    unsafe extern "C" {
+        static __dtb_addr: usize;
+    }
+    unsafe { core::ptr::read_volatile(&raw const __dtb_addr) }
+}
+
+/// 🌳 解析设备树
+///
+/// 说明：
+/// - 只会真正解析一次，之后的调用直接返回
+/// - 必须在启动阶段（只有一个 hart 在运行时）完成
+/// - 解析失败时保留 QEMU virt 的默认配置
+pub fn init() {
+    if STATE
+        .compare_exchange(STATE_UNINIT, STATE_BUSY, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        return;
+    }
+    if let Some(platform) = parse(dtb_addr()) {
+        unsafe { *PLATFORM.0.get() = platform };
+    }
+    STATE.store(STATE_READY, Ordering::Release);
+}
+
+/// 获取平台信息（首次调用时自动解析设备树）
+pub fn platform() -> &'static Platform {
+    if STATE.load(Ordering::Acquire) == STATE_UNINIT {
+        init();
+    }
+    unsafe { &*PLATFORM.0.get() }
+}
+
+/// 节点解析过程中的临时状态
+#[derive(Clone, Copy)]
+struct Node {
+    name: &'static [u8],
+    compatible: &'static [u8],
+    device_type: &'static [u8],
+    reg: &'static [u8],
+    /// `riscv,isa` 或 `riscv,isa-extensions`（只对 cpu 节点有意义）
+    isa: &'static [u8],
+    status_ok: bool,
+    /// 本节点 `reg` 使用的 cells 数（由父节点决定）
+    addr_cells: usize,
+    size_cells: usize,
+    /// 子节点 `reg` 使用的 cells 数（由本节点的 `#address-cells`/`#size-cells` 决定）
+    child_addr_cells: usize,
+    child_size_cells: usize,
+}
+
+impl Node {
+    const fn root() -> Self {
+        Node {
+            name: b"",
+            compatible: b"",
+            device_type: b"",
+            reg: b"",
+            isa: b"",
+            status_ok: true,
+            addr_cells: 2,
+            size_cells: 1,
+            child_addr_cells: 2,
+            child_size_cells: 1,
+        }
+    }
+
+    /// 节点名去掉 `@unit-address` 后的部分
+    fn base_name(&self) -> &[u8] {
+        match self.name.iter().position(|&c| c == b'@') {
+            Some(at) => &self.name[..at],
+            None => self.name,
+        }
+    }
+
+    fn is_compatible(&self, name: &[u8]) -> bool {
+        self.compatible.split(|&c| c == 0).any(|item| item == name)
+    }
+
+    /// cpu 节点是否声明了指定的 ISA 扩展
+    ///
+    /// 说明：`riscv,isa-extensions` 是字符串列表，`riscv,isa` 是以下划线分隔多字母扩展的单个字符串
+    fn has_extension(&self, name: &[u8]) -> bool {
+        self.isa
+            .split(|&c| c == 0 || c == b'_')
+            .any(|item| item.eq_ignore_ascii_case(name))
+    }
+
+    /// 第一个 reg 条目的基地址
+    fn first_reg_base(&self) -> Option<usize> {
+        read_cells(self.reg, self.addr_cells).map(|(base, _)| base)
+    }
+}
+
+/// 解析设备树，返回平台信息；不是合法设备树时返回 None
+fn parse(addr: usize) -> Option<Platform> {
+    if addr == 0 || addr % 4 != 0 {
+        return None;
+    }
+    // 先只看头部，确认魔数和总大小
+    let header = unsafe { core::slice::from_raw_parts(addr as *const u8, 40) };
+    if be32(header, 0)? != FDT_MAGIC {
+        return None;
+    }
+    let total_size = be32(header, 4)? as usize;
+    let data: &'static [u8] = unsafe { core::slice::from_raw_parts(addr as *const u8, total_size) };
+    let off_struct = be32(data, 8)? as usize;
+    let off_strings = be32(data, 12)? as usize;
+    let off_rsvmap = be32(data, 16)? as usize;
+    let size_strings = be32(data, 32)? as usize;
+    let size_struct = be32(data, 36)? as usize;
+    let strings = data.get(off_strings..off_strings.checked_add(size_strings)?)?;
+    let structs = data.get(off_struct..off_struct.checked_add(size_struct)?)?;
+
+    let mut platform = Platform::qemu_virt();
+    platform.memory_count = 0;
+    platform.hart_count = 0;
+    platform.virtio_count = 0;
+    platform.dtb = Some(MemoryRegion {
+        base: addr,
+        size: total_size,
+    });
+
+    // memreserve 块：(u64 address, u64 size) 对，以全 0 结束
+    let mut off = off_rsvmap;
+    loop {
+        let base = be64(data, off)? as usize;
+        let size = be64(data, off + 8)? as usize;
+        if base == 0 && size == 0 {
+            break;
+        }
+        platform.push_reserved(MemoryRegion { base, size });
+        off += 16;
+    }
+
+    let mut stack = [Node::root(); MAX_DEPTH];
+    let mut depth = 0usize;
+    let mut timebase: Option<usize> = None;
+    let mut uart_found = false;
+    let mut off = 0usize;
+    loop {
+        let token = be32(structs, off)?;
+        off += 4;
+        match token {
+            FDT_BEGIN_NODE => {
+                let name = cstr(structs, off)?;
+                off = align4(off + name.len() + 1);
+                let parent = if depth == 0 { Node::root() } else { stack[depth - 1] };
+                if depth >= MAX_DEPTH {
+                    return None;
+                }
+                stack[depth] = Node {
+                    name,
+                    addr_cells: parent.child_addr_cells,
+                    size_cells: parent.child_size_cells,
+                    ..Node::root()
+                };
+                depth += 1;
+            }
+            FDT_END_NODE => {
+                if depth == 0 {
+                    return None;
+                }
+                depth -= 1;
+                let node = stack[depth];
+                let parent_name = if depth == 0 { &b""[..] } else { stack[depth - 1].base_name() };
+                apply_node(&mut platform, &node, parent_name, &mut uart_found);
+            }
+            FDT_PROP => {
+                let len = be32(structs, off)? as usize;
+                let name_off = be32(structs, off + 4)? as usize;
+                let value: &'static [u8] = structs.get(off + 8..off + 8 + len)?;
+                off = align4(off + 8 + len);
+                let name = cstr(strings, name_off)?;
+                if depth == 0 {
+                    continue;
+                }
+                let node = &mut stack[depth - 1];
+                match name {
+                    b"compatible" => node.compatible = value,
+                    b"device_type" => node.device_type = trim_nul(value),
+                    b"reg" => node.reg = value,
+                    b"riscv,isa-extensions" => node.isa = value,
+                    b"riscv,isa" if node.isa.is_empty() => node.isa = value,
+                    b"status" => {
+                        let status = trim_nul(value);
+                        node.status_ok = status == b"okay" || status == b"ok";
+                    }
+                    b"#address-cells" => node.child_addr_cells = be32(value, 0)? as usize,
+                    b"#size-cells" => node.child_size_cells = be32(value, 0)? as usize,
+                    b"timebase-frequency" if timebase.is_none() => {
+                        let cells = value.len() / 4;
+                        timebase = read_cells(value, cells).map(|(freq, _)| freq);
+                    }
+                    _ => {}
+                }
+            }
+            FDT_NOP => {}
+            FDT_END => break,
+            _ => return None,
+        }
+    }
+
+    if let Some(freq) = timebase.filter(|&freq| freq != 0) {
+        platform.timebase_frequency = freq;
+    }
+    if platform.memory_count == 0 {
+        platform.memory = Platform::qemu_virt().memory;
+        platform.memory_count = 1;
+    }
+    if platform.hart_count == 0 {
+        platform.hart_count = 1;
+    }
+    Some(platform)
+}
+
+/// 节点结束时，根据节点类型更新平台信息
+fn apply_node(platform: &mut Platform, node: &Node, parent_name: &[u8], uart_found: &mut bool) {
+    if !node.status_ok {
+        return;
+    }
+    if node.device_type == b"memory" {
+        let mut reg = node.reg;
+        while let Some((base, rest)) = read_cells(reg, node.addr_cells) {
+            let Some((size, rest)) = read_cells(rest, node.size_cells) else {
+                break;
+            };
+            platform.push_memory(MemoryRegion { base, size });
+            reg = rest;
+        }
+        return;
+    }
+    if parent_name == b"cpus" && node.device_type == b"cpu" {
+        let smepmp = node.has_extension(b"smepmp");
+        platform.smepmp = if platform.hart_count == 0 { smepmp } else { platform.smepmp && smepmp };
+        platform.hart_count += 1;
+        return;
+    }
+    let Some(base) = node.first_reg_base() else {
+        return;
+    };
+    if node.is_compatible(b"ns16550a") || node.is_compatible(b"ns16550") {
+        // 第一个 UART 作为控制台，第二个留给调试器
+        if !*uart_found {
+            platform.uart = base;
+            *uart_found = true;
+        } else if platform.debug_uart.is_none() {
+            platform.debug_uart = Some(base);
+        }
+    } else if node.is_compatible(b"riscv,clint0") || node.is_compatible(b"sifive,clint0") {
+        platform.clint = base;
+    } else if node.is_compatible(b"riscv,plic0") || node.is_compatible(b"sifive,plic-1.0.0") {
+        platform.plic = base;
+    } else if node.is_compatible(b"sifive,test0") || node.is_compatible(b"sifive,test1") {
+        platform.test = base;
+    } else if node.is_compatible(b"virtio,mmio") {
+        platform.push_virtio(base);
+    }
+}
+
+/// 读取大端 u32
+fn be32(data: &[u8], off: usize) -> Option<u32> {
+    let bytes = data.get(off..off + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// 读取大端 u64
+fn be64(data: &[u8], off: usize) -> Option<u64> {
+    let hi = be32(data, off)? as u64;
+    let lo = be32(data, off + 4)? as u64;
+    Some((hi << 32) | lo)
+}
+
+/// 读取 `cells` 个 32 位 cell 组成的数值，返回数值和剩余字节
+fn read_cells(data: &[u8], cells: usize) -> Option<(usize, &[u8])> {
+    if cells == 0 || cells > 2 || data.len() < cells * 4 {
+        return None;
+    }
+    let mut value = 0usize;
+    for i in 0..cells {
+        value = (value << 32) | be32(data, i * 4)? as usize;
+    }
+    Some((value, &data[cells * 4..]))
+}
+
+/// 读取以 NUL 结尾的字符串（不含 NUL）
+fn cstr(data: &'static [u8], off: usize) -> Option<&'static [u8]> {
+    let rest = data.get(off..)?;
+    let len = rest.iter().position(|&c| c == 0)?;
+    Some(&rest[..len])
+}
+
+/// 去掉属性值末尾的 NUL
+fn trim_nul(value: &[u8]) -> &[u8] {
+    match value.iter().position(|&c| c == 0) {
+        Some(end) => &value[..end],
+        None => value,
+    }
+}
+
+/// 向上对齐到 4 字节
+const fn align4(off: usize) -> usize {
+    (off + 3) & !3
+}
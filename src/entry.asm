@@ -1,13 +1,79 @@
 # 🚀 系统启动汇编代码
 # 
 # 这是系统的入口点，负责：
-# - 设置栈指针
-# - 调用 Rust 初始化函数
+# - 为每个 hart 设置独立的启动栈，并把保护区之上的部分填充为 STACK_PAINT（统计最高水位）
+# - 清零 tp：线程之外没有 TLS 块，thread_local 据此拒绝访问
+# - 打开浮点单元（mstatus.FS = Initial），否则任何浮点指令都会触发非法指令异常
+# - hart 0：保存 QEMU 传入的设备树地址（a1），调用 Rust 初始化函数
+# - 其它 hart：等待 hart 0 置位释放标志后进入 secondary_main
+
+    # 必须与 hart.rs 中的 MAX_HARTS / HART_STACK_SIZE 保持一致
+    .equ MAX_HARTS, 8
+    .equ HART_STACK_SIZE, 0x10000
+    # 必须与 stack.rs 中的 BOOT_STACK_GUARD / STACK_PAINT 保持一致
+    .equ HART_STACK_GUARD, 0x1000
+    .equ STACK_PAINT, 0x5041494e54454421
 
     .section .text.entry
     .globl _start
 _start:
-    # 设置栈指针到栈顶
-    la sp, __STACK_TOP
+    mv tp, zero
+    li t0, 1 << 13
+    csrs mstatus, t0
+    csrw fcsr, zero
+    csrr t0, mhartid
+    # 超出 MAX_HARTS 的 hart 没有栈可用，直接停住
+    li t1, MAX_HARTS
+    bgeu t0, t1, .Lpark
+
+    # 设置栈指针：__STACK_START + (hartid + 1) * HART_STACK_SIZE
+    addi t1, t0, 1
+    li t2, HART_STACK_SIZE
+    mul t1, t1, t2
+    la sp, __STACK_START
+    add sp, sp, t1
+
+    # 填充启动栈：[栈底 + 保护区, sp)
+    li t2, HART_STACK_SIZE - HART_STACK_GUARD
+    sub t1, sp, t2
+    li t2, STACK_PAINT
+.Lpaint:
+    bgeu t1, sp, .Lpainted
+    sd t2, 0(t1)
+    addi t1, t1, 8
+    j .Lpaint
+.Lpainted:
+
+    bnez t0, .Lsecondary
+
+    # hart 0：保存设备树地址，供 dtb 模块解析
+    la t1, __dtb_addr
+    sd a1, 0(t1)
     # 调用 main 函数
-    call main
\ No newline at end of file
+    call main
+
+.Lsecondary:
+    # 其它 hart：自旋等待释放标志
+    la t1, __hart_release
+1:
+    ld t2, 0(t1)
+    beqz t2, 1b
+    fence
+    mv a0, t0
+    call secondary_main
+
+.Lpark:
+    wfi
+    j .Lpark
+
+    # 以下变量放在 .data 中，避免被 clear_bss 清掉
+    .section .data
+    .globl __dtb_addr
+    .balign 8
+__dtb_addr:
+    .dword 0
+
+    .globl __hart_release
+    .balign 8
+__hart_release:
+    .dword 0
@@ -3,6 +3,7 @@
 //! 提供统一的错误处理机制和 panic 处理器。
 
 /// 简单的错误处理模块 - 只提供基本的 panic 处理
+use crate::ipi;
 use crate::system::shutdown;
 use core::panic::PanicInfo;
 use log::error;
@@ -12,7 +13,9 @@ use log::error;
 /// 当程序发生 panic 时调用此函数
 #[panic_handler]
 pub fn panic(info: &PanicInfo) -> ! {
-    error!("🚨 PANIC: {}", info);
+    // 先让其它 hart 停下来，避免它们继续运行或打乱输出
+    ipi::stop_others();
+    error!("🚨 PANIC on hart {}: {}", crate::hart::id(), info);
     
     // 关机
     shutdown()
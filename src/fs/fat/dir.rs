@@ -0,0 +1,639 @@
+//! 📁 FAT32 目录
+//!
+//! 目录是一条簇链，里面是一个个 32 字节的目录项：
+//! - 短目录项：8.3 文件名、属性、起始簇和文件长度
+//! - 长文件名（LFN）目录项：属性为 0x0f，按倒序放在对应短目录项前面，每项存 13 个 UTF-16 字符，
+//!   并带有短文件名的校验和
+//! - 第一个字节为 0xe5 表示已删除，为 0 表示目录到此结束
+//!
+//! 新建的名字本身是合法的大写 8.3 文件名时只写短目录项，否则写长文件名目录项，
+//! 并生成一个目录内不重复的短文件名（`BASIS~N.EXT`）。文件名比较不区分 ASCII 大小写。
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::file::File;
+use super::{FIRST_CLUSTER, FatFs, Inner, SECTOR_SIZE, le16, le32};
+use crate::fs::FsError;
+
+/// 目录项大小
+const DIR_ENTRY_SIZE: usize = 32;
+
+/// 目录项属性
+const ATTR_READ_ONLY: u8 = 0x01;
+const ATTR_VOLUME_ID: u8 = 0x08;
+const ATTR_DIRECTORY: u8 = 0x10;
+const ATTR_ARCHIVE: u8 = 0x20;
+/// 长文件名目录项的属性（只读 | 隐藏 | 系统 | 卷标），判断时只看低 6 位
+const ATTR_LONG_NAME: u8 = 0x0f;
+const ATTR_LONG_NAME_MASK: u8 = 0x3f;
+
+/// 目录项第一个字节的特殊值
+const ENTRY_END: u8 = 0x00;
+const ENTRY_DELETED: u8 = 0xe5;
+/// 短文件名第一个字节真的是 0xe5 时存为 0x05
+const ENTRY_KANJI_E5: u8 = 0x05;
+
+/// 短目录项的大小写标志（Windows NT 扩展）：文件名主体/扩展名为小写
+const CASE_LOWER_BASE: u8 = 0x08;
+const CASE_LOWER_EXT: u8 = 0x10;
+
+/// 长文件名目录项：序号的“最后一项”标志，以及 13 个字符在目录项中的偏移
+const LFN_LAST: u8 = 0x40;
+const LFN_CHARS: usize = 13;
+const LFN_OFFSETS: [usize; LFN_CHARS] = [1, 3, 5, 7, 9, 14, 16, 18, 20, 22, 24, 28, 30];
+/// 长文件名最多 255 个 UTF-16 字符，最多占 20 个目录项
+const MAX_NAME_UNITS: usize = 255;
+const MAX_LFN_ENTRIES: usize = MAX_NAME_UNITS.div_ceil(LFN_CHARS);
+
+/// 没有实时时钟：时间戳固定为 1980-01-01 00:00（DOS 日期格式）
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+
+/// 目录项在设备上的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct EntryPos {
+    pub sector: usize,
+    pub offset: usize,
+}
+
+/// 📄 一个目录项（文件或子目录）
+#[derive(Debug, Clone)]
+pub struct DirEntry {
+    name: String,
+    short_name: [u8; 11],
+    attr: u8,
+    cluster: u32,
+    size: u32,
+    /// 短目录项的位置
+    pos: EntryPos,
+    /// 长文件名目录项和短目录项占用的所有位置
+    slots: Vec<EntryPos>,
+}
+
+impl DirEntry {
+    /// 文件名（有长文件名时是长文件名）
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 8.3 短文件名
+    pub fn short_name(&self) -> String {
+        short_display_name(&self.short_name, 0)
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.attr & ATTR_DIRECTORY != 0
+    }
+
+    pub fn is_file(&self) -> bool {
+        !self.is_dir()
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.attr & ATTR_READ_ONLY != 0
+    }
+
+    /// 文件长度（字节，目录为 0）
+    pub fn size(&self) -> u64 {
+        self.size as u64
+    }
+
+    pub(super) fn cluster(&self) -> u32 {
+        self.cluster
+    }
+
+    pub(super) fn pos(&self) -> EntryPos {
+        self.pos
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name) || self.short_name().eq_ignore_ascii_case(name)
+    }
+
+    fn is_dot(&self) -> bool {
+        self.short_name[0] == b'.'
+    }
+}
+
+/// 📁 一个打开的目录
+#[derive(Clone)]
+pub struct Dir {
+    fs: Arc<FatFs>,
+    cluster: u32,
+}
+
+impl Dir {
+    pub(super) fn new(fs: Arc<FatFs>, cluster: u32) -> Self {
+        Self { fs, cluster }
+    }
+
+    pub(super) fn fs(&self) -> &Arc<FatFs> {
+        &self.fs
+    }
+
+    /// 列出目录中的文件和子目录（不含 `.` 和 `..`）
+    pub fn entries(&self) -> Result<Vec<DirEntry>, FsError> {
+        let mut entries = self.fs.lock().read_dir(self.cluster)?;
+        entries.retain(|entry| !entry.is_dot());
+        Ok(entries)
+    }
+
+    /// 查找一个文件或子目录的目录项
+    pub fn entry(&self, path: &str) -> Result<DirEntry, FsError> {
+        let (parent, name) = self.resolve_parent(path)?;
+        self.fs.lock().find(parent, name)?.ok_or(FsError::NotFound)
+    }
+
+    /// 打开一个文件，路径相对于本目录（以 `/` 开头时相对于根目录）
+    pub fn open_file(&self, path: &str) -> Result<File, FsError> {
+        let entry = self.entry(path)?;
+        if entry.is_dir() {
+            return Err(FsError::IsADirectory);
+        }
+        Ok(File::new(self.fs.clone(), &entry))
+    }
+
+    /// 打开一个子目录
+    pub fn open_dir(&self, path: &str) -> Result<Dir, FsError> {
+        let mut inner = self.fs.lock();
+        let mut cluster = self.start_cluster(path);
+        for name in components(path) {
+            cluster = self.lookup_dir(&mut inner, cluster, name)?;
+        }
+        Ok(Dir::new(self.fs.clone(), cluster))
+    }
+
+    /// 创建一个文件；已经存在时把它截断为空（与 `std::fs::File::create` 相同）
+    pub fn create_file(&self, path: &str) -> Result<File, FsError> {
+        let (parent, name) = self.resolve_parent(path)?;
+        let existing = self.fs.lock().find(parent, name)?;
+        if let Some(entry) = existing {
+            if entry.is_dir() {
+                return Err(FsError::IsADirectory);
+            }
+            let mut file = File::new(self.fs.clone(), &entry);
+            file.truncate()?;
+            return Ok(file);
+        }
+        let entry = self.fs.lock().create_entry(parent, name, ATTR_ARCHIVE, 0)?;
+        Ok(File::new(self.fs.clone(), &entry))
+    }
+
+    /// 创建一个子目录
+    pub fn create_dir(&self, path: &str) -> Result<Dir, FsError> {
+        let (parent, name) = self.resolve_parent(path)?;
+        let root = self.fs.root_cluster;
+        let mut inner = self.fs.lock();
+        if inner.find(parent, name)?.is_some() {
+            return Err(FsError::AlreadyExists);
+        }
+        let cluster = inner.alloc_cluster(None)?;
+        let created = inner.init_dir(cluster, if parent == root { 0 } else { parent }).and_then(|_| {
+            inner.create_entry(parent, name, ATTR_DIRECTORY, cluster)
+        });
+        if let Err(err) = created {
+            inner.free_chain(cluster)?;
+            return Err(err);
+        }
+        Ok(Dir::new(self.fs.clone(), cluster))
+    }
+
+    /// 删除一个文件或空目录
+    pub fn remove(&self, path: &str) -> Result<(), FsError> {
+        let (parent, name) = self.resolve_parent(path)?;
+        let mut inner = self.fs.lock();
+        let entry = inner.find(parent, name)?.ok_or(FsError::NotFound)?;
+        if entry.is_dot() {
+            return Err(FsError::InvalidName);
+        }
+        if entry.is_dir() && inner.read_dir(entry.cluster)?.iter().any(|child| !child.is_dot()) {
+            return Err(FsError::DirectoryNotEmpty);
+        }
+        for pos in &entry.slots {
+            inner.cache.write(pos.sector, pos.offset, &[ENTRY_DELETED])?;
+        }
+        if entry.cluster != 0 {
+            inner.free_chain(entry.cluster)?;
+        }
+        Ok(())
+    }
+
+    /// 路径解析的起点：以 `/` 开头时是根目录
+    fn start_cluster(&self, path: &str) -> u32 {
+        if path.starts_with('/') { self.fs.root_cluster } else { self.cluster }
+    }
+
+    /// 在目录 `cluster` 中查找子目录 `name`，返回它的簇号
+    fn lookup_dir(&self, inner: &mut Inner, cluster: u32, name: &str) -> Result<u32, FsError> {
+        let root = self.fs.root_cluster;
+        if name == "." || (name == ".." && cluster == root) {
+            return Ok(cluster);
+        }
+        let entry = inner.find(cluster, name)?.ok_or(FsError::NotFound)?;
+        if !entry.is_dir() {
+            return Err(FsError::NotADirectory);
+        }
+        // 子目录中的 `..` 指向根目录时簇号记为 0
+        Ok(if entry.cluster == 0 { root } else { entry.cluster })
+    }
+
+    /// 解析到最后一个分量所在的目录，返回 (目录簇号, 最后一个分量)
+    fn resolve_parent<'p>(&self, path: &'p str) -> Result<(u32, &'p str), FsError> {
+        let mut parts: Vec<&str> = components(path).collect();
+        let name = parts.pop().ok_or(FsError::InvalidName)?;
+        let mut inner = self.fs.lock();
+        let mut cluster = self.start_cluster(path);
+        for part in parts {
+            cluster = self.lookup_dir(&mut inner, cluster, part)?;
+        }
+        Ok((cluster, name))
+    }
+}
+
+/// 路径中的各个分量（忽略多余的 `/`）
+fn components(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// 依次访问目录簇链上每个 32 字节槽位的游标
+struct SlotCursor {
+    cluster: u32,
+    /// 下一个槽位在当前簇中的序号
+    index: usize,
+    /// 已经走过的簇数（用来发现成环的簇链）
+    visited: u32,
+}
+
+impl SlotCursor {
+    fn new(cluster: u32) -> Self {
+        Self {
+            cluster,
+            index: 0,
+            visited: 0,
+        }
+    }
+}
+
+/// 拼接长文件名目录项
+struct LongName {
+    units: [u16; MAX_LFN_ENTRIES * LFN_CHARS],
+    checksum: u8,
+    /// 期望的下一个序号；0 表示已经拼完
+    next: u8,
+    valid: bool,
+    slots: Vec<EntryPos>,
+}
+
+impl LongName {
+    fn new() -> Self {
+        Self {
+            units: [0xffff; MAX_LFN_ENTRIES * LFN_CHARS],
+            checksum: 0,
+            next: 0,
+            valid: false,
+            slots: Vec::new(),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.valid = false;
+        self.slots.clear();
+    }
+
+    /// 加入一个长文件名目录项（磁盘上按序号从大到小排列）
+    fn push(&mut self, raw: &[u8; DIR_ENTRY_SIZE], pos: EntryPos) {
+        let seq = raw[0] & !LFN_LAST;
+        if raw[0] & LFN_LAST != 0 {
+            self.reset();
+            if seq == 0 || seq as usize > MAX_LFN_ENTRIES {
+                return;
+            }
+            self.units.fill(0xffff);
+            self.checksum = raw[13];
+            self.next = seq;
+            self.valid = true;
+        }
+        if !self.valid || seq != self.next || raw[13] != self.checksum {
+            self.reset();
+            return;
+        }
+        let base = (seq as usize - 1) * LFN_CHARS;
+        for (i, &offset) in LFN_OFFSETS.iter().enumerate() {
+            self.units[base + i] = le16(raw, offset);
+        }
+        self.slots.push(pos);
+        self.next -= 1;
+    }
+
+    /// 紧跟着的短目录项校验和为 `checksum`：长文件名完整且匹配时取出文件名和占用的位置
+    fn take(&mut self, checksum: u8) -> Option<(String, Vec<EntryPos>)> {
+        let complete = self.valid && self.next == 0 && self.checksum == checksum;
+        self.valid = false;
+        if !complete {
+            self.slots.clear();
+            return None;
+        }
+        let len = self.units.iter().position(|&unit| unit == 0 || unit == 0xffff).unwrap_or(self.units.len());
+        let name = char::decode_utf16(self.units[..len].iter().copied())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect();
+        Some((name, core::mem::take(&mut self.slots)))
+    }
+}
+
+/// 短文件名的校验和（存在每个长文件名目录项中）
+fn checksum(short_name: &[u8; 11]) -> u8 {
+    short_name
+        .iter()
+        .fold(0u8, |sum, &byte| sum.rotate_right(1).wrapping_add(byte))
+}
+
+/// 把 11 字节的短文件名转成 `NAME.EXT` 形式，按大小写标志转成小写
+fn short_display_name(short_name: &[u8; 11], case: u8) -> String {
+    let mut name = String::new();
+    let convert = |byte: u8, lower: bool| {
+        let byte = if byte == ENTRY_KANJI_E5 { ENTRY_DELETED } else { byte };
+        let c = byte as char;
+        if lower { c.to_ascii_lowercase() } else { c }
+    };
+    let base = short_name[..8].trim_ascii_end();
+    let ext = short_name[8..].trim_ascii_end();
+    name.extend(base.iter().map(|&byte| convert(byte, case & CASE_LOWER_BASE != 0)));
+    if !ext.is_empty() {
+        name.push('.');
+        name.extend(ext.iter().map(|&byte| convert(byte, case & CASE_LOWER_EXT != 0)));
+    }
+    name
+}
+
+/// 可以出现在短文件名中的字符（除字母和数字外）
+fn is_short_name_char(c: char) -> bool {
+    c.is_ascii_uppercase() || c.is_ascii_digit() || "!#$%&'()-@^_`{}~".contains(c)
+}
+
+/// 名字本身就是合法的大写 8.3 文件名时，返回对应的 11 字节短文件名
+fn exact_short_name(name: &str) -> Option<[u8; 11]> {
+    let (base, ext) = match name.rsplit_once('.') {
+        Some((base, ext)) => (base, ext),
+        None => (name, ""),
+    };
+    if base.is_empty() || base.len() > 8 || ext.len() > 3 {
+        return None;
+    }
+    if !base.chars().chain(ext.chars()).all(is_short_name_char) {
+        return None;
+    }
+    let mut short = [b' '; 11];
+    short[..base.len()].copy_from_slice(base.as_bytes());
+    short[8..8 + ext.len()].copy_from_slice(ext.as_bytes());
+    Some(short)
+}
+
+/// 为长文件名生成一个在目录中不重复的短文件名：`BASIS~N.EXT`
+fn generate_short_name(name: &str, existing: &[DirEntry]) -> Result<[u8; 11], FsError> {
+    let convert = |part: &str, max: usize| -> Vec<u8> {
+        part.chars()
+            .filter(|&c| c != ' ' && c != '.')
+            .map(|c| c.to_ascii_uppercase())
+            .map(|c| if is_short_name_char(c) { c as u8 } else { b'_' })
+            .take(max)
+            .collect()
+    };
+    let (base, ext) = match name.rsplit_once('.') {
+        Some((base, ext)) if !base.is_empty() => (convert(base, 8), convert(ext, 3)),
+        _ => (convert(name, 8), Vec::new()),
+    };
+    let base = if base.is_empty() { Vec::from(*b"_") } else { base };
+
+    for n in 1..1_000_000usize {
+        let mut tail = [0u8; 7];
+        let tail = {
+            let digits = n.ilog10() as usize + 1;
+            tail[0] = b'~';
+            let mut value = n;
+            for i in (1..=digits).rev() {
+                tail[i] = b'0' + (value % 10) as u8;
+                value /= 10;
+            }
+            &tail[..digits + 1]
+        };
+        let keep = base.len().min(8 - tail.len());
+        let mut short = [b' '; 11];
+        short[..keep].copy_from_slice(&base[..keep]);
+        short[keep..keep + tail.len()].copy_from_slice(tail);
+        short[8..8 + ext.len()].copy_from_slice(&ext);
+        if !existing.iter().any(|entry| entry.short_name == short) {
+            return Ok(short);
+        }
+    }
+    Err(FsError::NoSpace)
+}
+
+/// 检查新建文件的名字
+fn validate_name(name: &str) -> Result<(), FsError> {
+    let invalid = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.ends_with(['.', ' '])
+        || name.encode_utf16().count() > MAX_NAME_UNITS
+        || name.chars().any(|c| c < ' ' || "\"*/:<>?\\|".contains(c));
+    if invalid { Err(FsError::InvalidName) } else { Ok(()) }
+}
+
+/// 编码一个短目录项
+fn encode_short_entry(short_name: &[u8; 11], attr: u8, cluster: u32, size: u32) -> [u8; DIR_ENTRY_SIZE] {
+    let mut raw = [0u8; DIR_ENTRY_SIZE];
+    raw[..11].copy_from_slice(short_name);
+    raw[11] = attr;
+    raw[14..16].copy_from_slice(&DOS_TIME.to_le_bytes());
+    raw[16..18].copy_from_slice(&DOS_DATE.to_le_bytes());
+    raw[18..20].copy_from_slice(&DOS_DATE.to_le_bytes());
+    raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+    raw[22..24].copy_from_slice(&DOS_TIME.to_le_bytes());
+    raw[24..26].copy_from_slice(&DOS_DATE.to_le_bytes());
+    raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+    raw[28..32].copy_from_slice(&size.to_le_bytes());
+    raw
+}
+
+/// 编码长文件名目录项（按磁盘上的顺序，即序号从大到小）
+fn encode_long_entries(name: &str, checksum: u8) -> Vec<[u8; DIR_ENTRY_SIZE]> {
+    let units: Vec<u16> = name.encode_utf16().collect();
+    let count = units.len().div_ceil(LFN_CHARS);
+    (1..=count)
+        .rev()
+        .map(|seq| {
+            let mut raw = [0u8; DIR_ENTRY_SIZE];
+            raw[0] = seq as u8 | if seq == count { LFN_LAST } else { 0 };
+            raw[11] = ATTR_LONG_NAME;
+            raw[13] = checksum;
+            for (i, &offset) in LFN_OFFSETS.iter().enumerate() {
+                let index = (seq - 1) * LFN_CHARS + i;
+                // 名字之后先放一个 0 结束符，其余填 0xffff
+                let unit = match index.cmp(&units.len()) {
+                    core::cmp::Ordering::Less => units[index],
+                    core::cmp::Ordering::Equal => 0,
+                    core::cmp::Ordering::Greater => 0xffff,
+                };
+                raw[offset..offset + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            raw
+        })
+        .collect()
+}
+
+impl Inner {
+    /// 游标处的下一个槽位；簇链走完时返回 None（此时 `cursor.cluster` 是最后一个簇）
+    fn next_slot(&mut self, cursor: &mut SlotCursor) -> Result<Option<EntryPos>, FsError> {
+        if cursor.index == self.cluster_size() / DIR_ENTRY_SIZE {
+            let Some(next) = self.next_cluster(cursor.cluster)? else {
+                return Ok(None);
+            };
+            cursor.visited += 1;
+            if cursor.visited > self.cluster_count {
+                return Err(FsError::Corrupted("directory cluster chain loops"));
+            }
+            cursor.cluster = next;
+            cursor.index = 0;
+        }
+        let byte = cursor.index * DIR_ENTRY_SIZE;
+        cursor.index += 1;
+        Ok(Some(EntryPos {
+            sector: self.cluster_sector(cursor.cluster) + byte / SECTOR_SIZE,
+            offset: byte % SECTOR_SIZE,
+        }))
+    }
+
+    /// 读出目录中的所有目录项（包括 `.` 和 `..`，不包括卷标）
+    fn read_dir(&mut self, cluster: u32) -> Result<Vec<DirEntry>, FsError> {
+        let mut entries = Vec::new();
+        let mut cursor = SlotCursor::new(cluster);
+        let mut long_name = LongName::new();
+        while let Some(pos) = self.next_slot(&mut cursor)? {
+            let mut raw = [0u8; DIR_ENTRY_SIZE];
+            self.cache.read(pos.sector, pos.offset, &mut raw)?;
+            match raw[0] {
+                ENTRY_END => break,
+                ENTRY_DELETED => {
+                    long_name.reset();
+                    continue;
+                }
+                _ => {}
+            }
+            if raw[11] & ATTR_LONG_NAME_MASK == ATTR_LONG_NAME {
+                long_name.push(&raw, pos);
+                continue;
+            }
+            let mut short_name = [0u8; 11];
+            short_name.copy_from_slice(&raw[..11]);
+            let (name, mut slots) = long_name
+                .take(checksum(&short_name))
+                .unwrap_or_else(|| (short_display_name(&short_name, raw[12]), Vec::new()));
+            slots.push(pos);
+            let attr = raw[11];
+            if attr & ATTR_VOLUME_ID != 0 {
+                continue;
+            }
+            entries.push(DirEntry {
+                name,
+                short_name,
+                attr,
+                cluster: (le16(&raw, 20) as u32) << 16 | le16(&raw, 26) as u32,
+                size: le32(&raw, 28),
+                pos,
+                slots,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// 在目录中按名字查找
+    fn find(&mut self, cluster: u32, name: &str) -> Result<Option<DirEntry>, FsError> {
+        Ok(self.read_dir(cluster)?.into_iter().find(|entry| entry.matches(name)))
+    }
+
+    /// 在目录中找 `count` 个连续的空闲槽位，不够时给目录追加一个清零的簇
+    fn alloc_slots(&mut self, cluster: u32, count: usize) -> Result<Vec<EntryPos>, FsError> {
+        let mut cursor = SlotCursor::new(cluster);
+        let mut run = Vec::with_capacity(count);
+        loop {
+            let Some(pos) = self.next_slot(&mut cursor)? else {
+                let new = self.alloc_cluster(Some(cursor.cluster))?;
+                self.zero_cluster(new)?;
+                cursor.cluster = new;
+                cursor.index = 0;
+                continue;
+            };
+            let mut first = [0u8];
+            self.cache.read(pos.sector, pos.offset, &mut first)?;
+            if first[0] == ENTRY_END || first[0] == ENTRY_DELETED {
+                run.push(pos);
+                if run.len() == count {
+                    return Ok(run);
+                }
+            } else {
+                run.clear();
+            }
+        }
+    }
+
+    /// 在目录 `dir` 中新建一个目录项
+    fn create_entry(&mut self, dir: u32, name: &str, attr: u8, cluster: u32) -> Result<DirEntry, FsError> {
+        validate_name(name)?;
+        let existing = self.read_dir(dir)?;
+        if existing.iter().any(|entry| entry.matches(name)) {
+            return Err(FsError::AlreadyExists);
+        }
+        let (short_name, long_entries) = match exact_short_name(name) {
+            Some(short_name) => (short_name, Vec::new()),
+            None => {
+                let short_name = generate_short_name(name, &existing)?;
+                (short_name, encode_long_entries(name, checksum(&short_name)))
+            }
+        };
+        let slots = self.alloc_slots(dir, long_entries.len() + 1)?;
+        for (raw, pos) in long_entries.iter().zip(&slots) {
+            self.cache.write(pos.sector, pos.offset, raw)?;
+        }
+        let pos = *slots.last().unwrap();
+        self.cache
+            .write(pos.sector, pos.offset, &encode_short_entry(&short_name, attr, cluster, 0))?;
+        Ok(DirEntry {
+            name: String::from(name),
+            short_name,
+            attr,
+            cluster,
+            size: 0,
+            pos,
+            slots,
+        })
+    }
+
+    /// 更新短目录项中的起始簇和长度（修改时间固定，见 `DOS_DATE`）
+    pub(super) fn update_entry(&mut self, pos: EntryPos, cluster: u32, size: u32) -> Result<(), FsError> {
+        let mut raw = [0u8; DIR_ENTRY_SIZE];
+        self.cache.read(pos.sector, pos.offset, &mut raw)?;
+        raw[20..22].copy_from_slice(&((cluster >> 16) as u16).to_le_bytes());
+        raw[22..24].copy_from_slice(&DOS_TIME.to_le_bytes());
+        raw[24..26].copy_from_slice(&DOS_DATE.to_le_bytes());
+        raw[26..28].copy_from_slice(&(cluster as u16).to_le_bytes());
+        raw[28..32].copy_from_slice(&size.to_le_bytes());
+        self.cache.write(pos.sector, pos.offset, &raw)?;
+        Ok(())
+    }
+
+    /// 初始化新目录的簇：清零并写入 `.` 和 `..`（`parent` 为 0 表示根目录）
+    fn init_dir(&mut self, cluster: u32, parent: u32) -> Result<(), FsError> {
+        debug_assert!(cluster >= FIRST_CLUSTER);
+        self.zero_cluster(cluster)?;
+        let sector = self.cluster_sector(cluster);
+        let dot = encode_short_entry(b".          ", ATTR_DIRECTORY, cluster, 0);
+        let dotdot = encode_short_entry(b"..         ", ATTR_DIRECTORY, parent, 0);
+        self.cache.write(sector, 0, &dot)?;
+        self.cache.write(sector, DIR_ENTRY_SIZE, &dotdot)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+//! 📄 FAT32 文件
+//!
+//! 文件内容存放在从目录项中的起始簇开始的簇链上：
+//! - 读写位置换算成“第几个簇 + 簇内偏移”，记住上一次访问的簇，顺序读写时不必每次从头遍历簇链
+//! - 写入超过文件末尾时按需追加簇；位置在文件末尾之后时，中间的空洞补零
+//! - 每次写入或截断后更新目录项中的起始簇和长度
+
+extern crate alloc;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::dir::{DirEntry, EntryPos};
+use super::{FAT_EOC, FatFs, Inner, SECTOR_SIZE};
+use crate::fs::{FsError, SeekFrom};
+
+/// FAT32 文件的最大长度
+const MAX_FILE_SIZE: u64 = u32::MAX as u64;
+
+/// 📄 一个打开的文件
+pub struct File {
+    fs: Arc<FatFs>,
+    /// 短目录项的位置
+    entry: EntryPos,
+    /// 起始簇；空文件为 0
+    cluster: u32,
+    size: u32,
+    /// 读写位置
+    offset: u64,
+    /// 上一次访问的簇：(在链中的序号, 簇号)
+    current: Option<(u32, u32)>,
+}
+
+impl File {
+    pub(super) fn new(fs: Arc<FatFs>, entry: &DirEntry) -> Self {
+        Self {
+            fs,
+            entry: entry.pos(),
+            cluster: entry.cluster(),
+            size: entry.size() as u32,
+            offset: 0,
+            current: None,
+        }
+    }
+
+    pub(super) fn fs(&self) -> &Arc<FatFs> {
+        &self.fs
+    }
+
+    /// 短目录项的位置
+    pub(super) fn entry_pos(&self) -> EntryPos {
+        self.entry
+    }
+
+    /// 文件长度（字节）
+    pub fn size(&self) -> u64 {
+        self.size as u64
+    }
+
+    /// 当前读写位置
+    pub fn position(&self) -> u64 {
+        self.offset
+    }
+
+    /// 移动读写位置，返回新的位置（可以超过文件末尾，之后写入时中间补零）
+    pub fn seek(&mut self, pos: SeekFrom) -> Result<u64, FsError> {
+        let offset = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => (self.size as u64).checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.offset.checked_add_signed(delta),
+        };
+        self.offset = offset.ok_or(FsError::InvalidSeek)?;
+        Ok(self.offset)
+    }
+
+    /// 从当前位置读取，返回读到的字节数（到达文件末尾时为 0）
+    pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, FsError> {
+        let fs = self.fs.clone();
+        let mut inner = fs.lock();
+        let cluster_size = inner.cluster_size();
+        let mut done = 0;
+        while done < buf.len() && self.offset < self.size as u64 {
+            let offset = self.offset as usize;
+            let index = (offset / cluster_size) as u32;
+            let cluster = self
+                .cluster_at(&mut inner, index, false)?
+                .ok_or(FsError::Corrupted("cluster chain is shorter than the file"))?;
+            let sector = inner.cluster_sector(cluster) + offset % cluster_size / SECTOR_SIZE;
+            let in_sector = offset % SECTOR_SIZE;
+            let n = (SECTOR_SIZE - in_sector)
+                .min(buf.len() - done)
+                .min(self.size as usize - offset);
+            inner.cache.read(sector, in_sector, &mut buf[done..done + n])?;
+            done += n;
+            self.offset += n as u64;
+        }
+        Ok(done)
+    }
+
+    /// 从当前位置读到文件末尾，追加到 `buf`，返回读到的字节数
+    pub fn read_to_end(&mut self, buf: &mut Vec<u8>) -> Result<usize, FsError> {
+        let start = buf.len();
+        let remaining = (self.size as u64).saturating_sub(self.offset) as usize;
+        buf.resize(start + remaining, 0);
+        let n = self.read(&mut buf[start..])?;
+        buf.truncate(start + n);
+        Ok(n)
+    }
+
+    /// 在当前位置写入全部数据，返回写入的字节数
+    ///
+    /// 说明：磁盘写满时返回 `FsError::NoSpace`，此前写入的部分保留在文件中
+    pub fn write(&mut self, data: &[u8]) -> Result<usize, FsError> {
+        if data.is_empty() {
+            return Ok(0);
+        }
+        if self.offset + data.len() as u64 > MAX_FILE_SIZE {
+            return Err(FsError::FileTooLarge);
+        }
+        let fs = self.fs.clone();
+        let mut inner = fs.lock();
+        // 位置在文件末尾之后：先把中间的空洞补零
+        let mut result = Ok(());
+        if self.offset > self.size as u64 {
+            let end = self.offset;
+            self.offset = self.size as u64;
+            result = self.write_range(&mut inner, (end - self.offset) as usize, None);
+        }
+        if result.is_ok() {
+            result = self.write_range(&mut inner, data.len(), Some(data));
+        }
+        inner.update_entry(self.entry, self.cluster, self.size)?;
+        result.map(|_| data.len())
+    }
+
+    /// 从当前位置写入 `len` 字节：`data` 为 None 时写入 0
+    fn write_range(&mut self, inner: &mut Inner, len: usize, data: Option<&[u8]>) -> Result<(), FsError> {
+        const ZEROS: [u8; SECTOR_SIZE] = [0; SECTOR_SIZE];
+        let cluster_size = inner.cluster_size();
+        let mut done = 0;
+        while done < len {
+            let offset = self.offset as usize;
+            let index = (offset / cluster_size) as u32;
+            let cluster = self.cluster_at(inner, index, true)?.expect("allocating lookup returns a cluster");
+            let sector = inner.cluster_sector(cluster) + offset % cluster_size / SECTOR_SIZE;
+            let in_sector = offset % SECTOR_SIZE;
+            let n = (SECTOR_SIZE - in_sector).min(len - done);
+            let chunk = match data {
+                Some(data) => &data[done..done + n],
+                None => &ZEROS[..n],
+            };
+            inner.cache.write(sector, in_sector, chunk)?;
+            done += n;
+            self.offset += n as u64;
+            self.size = self.size.max(self.offset as u32);
+        }
+        Ok(())
+    }
+
+    /// 把文件截断到当前位置（位置在文件末尾之后时不做任何事）
+    pub fn truncate(&mut self) -> Result<(), FsError> {
+        if self.offset >= self.size as u64 {
+            return Ok(());
+        }
+        let fs = self.fs.clone();
+        let mut inner = fs.lock();
+        let new_size = self.offset as usize;
+        if new_size == 0 {
+            if self.cluster != 0 {
+                inner.free_chain(self.cluster)?;
+                self.cluster = 0;
+            }
+        } else {
+            let keep = new_size.div_ceil(inner.cluster_size()) as u32;
+            let last = self
+                .cluster_at(&mut inner, keep - 1, false)?
+                .ok_or(FsError::Corrupted("cluster chain is shorter than the file"))?;
+            if let Some(next) = inner.next_cluster(last)? {
+                inner.set_fat_entry(last, FAT_EOC)?;
+                inner.free_chain(next)?;
+            }
+        }
+        self.current = None;
+        self.size = new_size as u32;
+        inner.update_entry(self.entry, self.cluster, self.size)
+    }
+
+    /// 把文件系统的缓存写回磁盘
+    pub fn flush(&self) -> Result<(), FsError> {
+        self.fs.flush()
+    }
+
+    /// 簇链中的第 `index` 个簇；链不够长时，`allocate` 为真就追加新簇，否则返回 None
+    fn cluster_at(&mut self, inner: &mut Inner, index: u32, allocate: bool) -> Result<Option<u32>, FsError> {
+        if self.cluster == 0 {
+            if !allocate {
+                return Ok(None);
+            }
+            self.cluster = inner.alloc_cluster(None)?;
+            self.current = None;
+        }
+        let (mut i, mut cluster) = match self.current {
+            Some((i, cluster)) if i <= index => (i, cluster),
+            _ => (0, self.cluster),
+        };
+        while i < index {
+            cluster = match inner.next_cluster(cluster)? {
+                Some(next) => next,
+                None if allocate => inner.alloc_cluster(Some(cluster))?,
+                None => return Ok(None),
+            };
+            i += 1;
+        }
+        self.current = Some((index, cluster));
+        Ok(Some(cluster))
+    }
+}
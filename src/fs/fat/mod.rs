@@ -0,0 +1,408 @@
+//! 💾 FAT32 文件系统
+//!
+//! 在 `BlockDevice` 上读写 FAT32 卷（`mkfs.vfat -F 32` 生成的整盘镜像，或 MBR 中第一个 FAT32 分区）：
+//! - `FatFs::mount` 挂载，`root_dir` 得到根目录
+//! - `Dir`：按路径打开/创建文件和子目录、删除、列出目录项，支持长文件名（见 `dir`）
+//! - `File`：read/write/seek/truncate，写入时更新目录项中的长度（见 `file`）
+//! - 实现了 VFS 的 `FileSystem`，可以挂载到目录树上（见 `vfs`）
+//! - 扇区经过 `BlockCache` 缓存，`flush` 或卸载（最后一个引用释放）时写回，同时更新 FSInfo 中的空闲簇数
+//!
+//! 说明：
+//! - 只支持 512 字节的扇区，FAT12/FAT16 卷返回 `FsError::Unsupported`
+//! - 没有实时时钟，新建和修改的文件时间戳固定为 1980-01-01 00:00
+//! - 同一个文件同时用多个 `File` 写入时，目录项中的长度以最后一次写入为准；
+//!   通过 VFS 打开时共享同一个 `File`，没有这个问题（见 `vfs`）
+//!
+//! 用法：
+//! ```ignore
+//! let fs = FatFs::mount(VirtioBlk::probe()?)?;
+//! let mut file = fs.root_dir().create_file("/docs/note.txt")?;
+//! file.write(b"hello")?;
+//! fs.flush()?;
+//! ```
+
+mod dir;
+mod file;
+mod vfs;
+
+pub use dir::{Dir, DirEntry};
+pub use file::File;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+
+use log::{info, warn};
+
+use super::FsError;
+use dir::EntryPos;
+use crate::block::{BLOCK_SIZE, BlockCache, BlockDevice, cache::DEFAULT_CAPACITY};
+use crate::thread::sync::{Mutex, MutexGuard};
+
+/// 扇区大小（只支持与块大小相同的 512 字节）
+const SECTOR_SIZE: usize = BLOCK_SIZE;
+
+/// FAT 表项只用低 28 位
+const FAT_ENTRY_MASK: u32 = 0x0fff_ffff;
+/// 簇链结束标记（大于等于 `FAT_EOC_MIN` 的都表示结束）
+const FAT_EOC: u32 = 0x0fff_ffff;
+const FAT_EOC_MIN: u32 = 0x0fff_fff8;
+/// 坏簇
+const FAT_BAD: u32 = 0x0fff_fff7;
+/// 数据区第一个簇的编号
+const FIRST_CLUSTER: u32 = 2;
+
+/// FSInfo 扇区的签名和字段偏移
+const FSINFO_LEAD_SIG: u32 = 0x4161_5252;
+const FSINFO_STRUCT_SIG: u32 = 0x6141_7272;
+const FSINFO_FREE_COUNT: usize = 488;
+const FSINFO_NEXT_FREE: usize = 492;
+/// FSInfo 中表示“未知”的值
+const FSINFO_UNKNOWN: u32 = 0xffff_ffff;
+
+/// MBR 分区类型：FAT32（CHS / LBA）
+const PARTITION_FAT32: u8 = 0x0b;
+const PARTITION_FAT32_LBA: u8 = 0x0c;
+
+fn le16(data: &[u8], off: usize) -> u16 {
+    u16::from_le_bytes([data[off], data[off + 1]])
+}
+
+fn le32(data: &[u8], off: usize) -> u32 {
+    u32::from_le_bytes([data[off], data[off + 1], data[off + 2], data[off + 3]])
+}
+
+/// 文件系统的容量统计
+#[derive(Debug, Clone, Copy)]
+pub struct FsStats {
+    /// 簇大小（字节）
+    pub cluster_size: usize,
+    pub total_clusters: usize,
+    pub free_clusters: usize,
+}
+
+/// 💾 一个挂载的 FAT32 卷
+///
+/// 说明：`Dir` 和 `File` 都持有 `Arc<FatFs>`，所有操作通过内部的阻塞锁串行执行
+pub struct FatFs {
+    inner: Mutex<Inner>,
+    root_cluster: u32,
+    /// 通过 VFS 打开的文件：短目录项的位置 -> 共享的 `File` 和它被打开的次数（见 `vfs`）
+    open_files: Mutex<BTreeMap<EntryPos, vfs::OpenEntry>>,
+}
+
+/// 卷的几何参数和可变状态
+struct Inner {
+    cache: BlockCache,
+    sectors_per_cluster: usize,
+    /// 第一个 FAT 表的起始扇区（设备上的绝对扇区号，下同）
+    fat_start: usize,
+    /// 每个 FAT 表的扇区数
+    fat_sectors: usize,
+    num_fats: usize,
+    /// 数据区（2 号簇）的起始扇区
+    data_start: usize,
+    /// 数据区的簇数：合法簇号为 `2..cluster_count + 2`
+    cluster_count: u32,
+    /// FSInfo 扇区；卷没有 FSInfo 时为 None
+    fsinfo: Option<usize>,
+    /// 空闲簇数；未知时为 None（`stats` 时统计）
+    free_count: Option<u32>,
+    /// 下一次分配簇时开始查找的位置
+    next_free: u32,
+    fsinfo_dirty: bool,
+    label: [u8; 11],
+}
+
+impl FatFs {
+    /// 挂载 `device` 上的 FAT32 卷
+    pub fn mount(device: impl BlockDevice + 'static) -> Result<Arc<Self>, FsError> {
+        let mut cache = BlockCache::new(Box::new(device), DEFAULT_CAPACITY);
+        let mut sector = [0u8; SECTOR_SIZE];
+        cache.read(0, 0, &mut sector)?;
+        let start = if is_fat32_boot_sector(&sector) { 0 } else { find_partition(&sector)? };
+        if start != 0 {
+            cache.read(start, 0, &mut sector)?;
+            if !is_fat32_boot_sector(&sector) {
+                return Err(FsError::Unsupported("partition does not contain a FAT32 volume"));
+            }
+        }
+
+        // BPB（BIOS 参数块）
+        if le16(&sector, 11) as usize != SECTOR_SIZE {
+            return Err(FsError::Unsupported("sector size is not 512 bytes"));
+        }
+        let sectors_per_cluster = sector[13] as usize;
+        let reserved = le16(&sector, 14) as usize;
+        let num_fats = sector[16] as usize;
+        let total_sectors = match le16(&sector, 19) {
+            0 => le32(&sector, 32) as usize,
+            small => small as usize,
+        };
+        let fat_sectors = le32(&sector, 36) as usize;
+        let root_cluster = le32(&sector, 44);
+        let fsinfo = match le16(&sector, 48) {
+            0 | 0xffff => None,
+            offset => Some(start + offset as usize),
+        };
+        if sectors_per_cluster == 0 || !sectors_per_cluster.is_power_of_two() || reserved == 0 {
+            return Err(FsError::Corrupted("bad BIOS parameter block"));
+        }
+
+        let fat_start = start + reserved;
+        let data_start = fat_start + num_fats * fat_sectors;
+        let end = start + total_sectors;
+        if data_start >= end || end > cache.num_blocks() {
+            return Err(FsError::Corrupted("volume size does not match the device"));
+        }
+        // 簇数同时受数据区大小和 FAT 表大小限制
+        let cluster_count = ((end - data_start) / sectors_per_cluster)
+            .min(fat_sectors * SECTOR_SIZE / 4 - FIRST_CLUSTER as usize) as u32;
+        if root_cluster < FIRST_CLUSTER || root_cluster >= cluster_count + FIRST_CLUSTER {
+            return Err(FsError::Corrupted("bad root directory cluster"));
+        }
+        let mut label = [0u8; 11];
+        label.copy_from_slice(&sector[71..82]);
+
+        let mut inner = Inner {
+            cache,
+            sectors_per_cluster,
+            fat_start,
+            fat_sectors,
+            num_fats,
+            data_start,
+            cluster_count,
+            fsinfo,
+            free_count: None,
+            next_free: FIRST_CLUSTER,
+            fsinfo_dirty: false,
+            label,
+        };
+        inner.load_fsinfo()?;
+        info!(
+            "💾 FAT32 volume \"{}\": {} clusters of {} bytes",
+            inner.label(),
+            cluster_count,
+            inner.cluster_size()
+        );
+        Ok(Arc::new(Self {
+            inner: Mutex::new(inner),
+            root_cluster,
+            open_files: Mutex::new(BTreeMap::new()),
+        }))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock()
+    }
+
+    /// 根目录
+    pub fn root_dir(self: &Arc<Self>) -> Dir {
+        Dir::new(self.clone(), self.root_cluster)
+    }
+
+    /// 卷标（引导扇区中的，去掉末尾空格）
+    pub fn volume_label(&self) -> String {
+        String::from(self.lock().label())
+    }
+
+    /// 容量统计；空闲簇数未知时扫描一遍 FAT 表
+    pub fn stats(&self) -> Result<FsStats, FsError> {
+        let mut inner = self.lock();
+        let free = match inner.free_count {
+            Some(free) => free,
+            None => {
+                let mut free = 0;
+                for cluster in FIRST_CLUSTER..inner.cluster_count + FIRST_CLUSTER {
+                    if inner.fat_entry(cluster)? == 0 {
+                        free += 1;
+                    }
+                }
+                inner.free_count = Some(free);
+                inner.fsinfo_dirty = true;
+                free
+            }
+        };
+        Ok(FsStats {
+            cluster_size: inner.cluster_size(),
+            total_clusters: inner.cluster_count as usize,
+            free_clusters: free as usize,
+        })
+    }
+
+    /// 块缓存的命中统计
+    pub fn cache_stats(&self) -> crate::block::CacheStats {
+        self.lock().cache.stats()
+    }
+
+    /// 把缓存中修改过的扇区和 FSInfo 写回磁盘
+    pub fn flush(&self) -> Result<(), FsError> {
+        self.lock().flush()
+    }
+}
+
+impl Drop for FatFs {
+    fn drop(&mut self) {
+        if let Err(err) = self.inner.get_mut().flush() {
+            warn!("FAT32: flush on unmount failed: {}", err);
+        }
+    }
+}
+
+/// 是否是 FAT32 的引导扇区：跳转指令、结束签名，以及 FAT32 才有的 BPB 特征（16 位 FAT 大小为 0）
+fn is_fat32_boot_sector(sector: &[u8; SECTOR_SIZE]) -> bool {
+    (sector[0] == 0xeb || sector[0] == 0xe9)
+        && sector[510..512] == [0x55, 0xaa]
+        && le16(sector, 17) == 0
+        && le16(sector, 22) == 0
+        && le32(sector, 36) != 0
+        && (1..=2).contains(&sector[16])
+}
+
+/// 在 MBR 分区表中找第一个 FAT32 分区，返回起始扇区
+fn find_partition(mbr: &[u8; SECTOR_SIZE]) -> Result<usize, FsError> {
+    if mbr[510..512] != [0x55, 0xaa] {
+        return Err(FsError::Unsupported("no FAT32 boot sector or partition table"));
+    }
+    (0..4)
+        .map(|i| &mbr[446 + i * 16..446 + (i + 1) * 16])
+        .find(|entry| entry[4] == PARTITION_FAT32 || entry[4] == PARTITION_FAT32_LBA)
+        .map(|entry| le32(entry, 8) as usize)
+        .ok_or(FsError::Unsupported("no FAT32 partition"))
+}
+
+impl Inner {
+    fn label(&self) -> &str {
+        core::str::from_utf8(&self.label).unwrap_or("").trim_end()
+    }
+
+    fn cluster_size(&self) -> usize {
+        self.sectors_per_cluster * SECTOR_SIZE
+    }
+
+    /// 簇的第一个扇区
+    fn cluster_sector(&self, cluster: u32) -> usize {
+        self.data_start + (cluster - FIRST_CLUSTER) as usize * self.sectors_per_cluster
+    }
+
+    fn is_valid_cluster(&self, cluster: u32) -> bool {
+        (FIRST_CLUSTER..self.cluster_count + FIRST_CLUSTER).contains(&cluster)
+    }
+
+    fn load_fsinfo(&mut self) -> Result<(), FsError> {
+        let Some(sector) = self.fsinfo else {
+            return Ok(());
+        };
+        let mut buf = [0u8; SECTOR_SIZE];
+        self.cache.read(sector, 0, &mut buf)?;
+        if le32(&buf, 0) != FSINFO_LEAD_SIG || le32(&buf, 484) != FSINFO_STRUCT_SIG {
+            warn!("FAT32: bad FSInfo signature, ignoring it");
+            self.fsinfo = None;
+            return Ok(());
+        }
+        let free = le32(&buf, FSINFO_FREE_COUNT);
+        if free != FSINFO_UNKNOWN && free <= self.cluster_count {
+            self.free_count = Some(free);
+        }
+        let next = le32(&buf, FSINFO_NEXT_FREE);
+        if self.is_valid_cluster(next) {
+            self.next_free = next;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), FsError> {
+        if let (Some(sector), true) = (self.fsinfo, self.fsinfo_dirty) {
+            let free = self.free_count.unwrap_or(FSINFO_UNKNOWN);
+            self.cache.write(sector, FSINFO_FREE_COUNT, &free.to_le_bytes())?;
+            self.cache.write(sector, FSINFO_NEXT_FREE, &self.next_free.to_le_bytes())?;
+        }
+        self.fsinfo_dirty = false;
+        self.cache.flush()?;
+        Ok(())
+    }
+
+    /// 簇在第一个 FAT 表中对应表项的 (扇区, 偏移)
+    fn fat_pos(&self, cluster: u32) -> (usize, usize) {
+        let byte = cluster as usize * 4;
+        (self.fat_start + byte / SECTOR_SIZE, byte % SECTOR_SIZE)
+    }
+
+    fn fat_entry(&mut self, cluster: u32) -> Result<u32, FsError> {
+        let (sector, offset) = self.fat_pos(cluster);
+        let mut raw = [0u8; 4];
+        self.cache.read(sector, offset, &mut raw)?;
+        Ok(u32::from_le_bytes(raw) & FAT_ENTRY_MASK)
+    }
+
+    /// 修改 FAT 表项（所有 FAT 副本一起改，保留高 4 位）
+    fn set_fat_entry(&mut self, cluster: u32, value: u32) -> Result<(), FsError> {
+        let (sector, offset) = self.fat_pos(cluster);
+        for copy in 0..self.num_fats {
+            let sector = sector + copy * self.fat_sectors;
+            let mut raw = [0u8; 4];
+            self.cache.read(sector, offset, &mut raw)?;
+            let entry = (u32::from_le_bytes(raw) & !FAT_ENTRY_MASK) | (value & FAT_ENTRY_MASK);
+            self.cache.write(sector, offset, &entry.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// 簇链中的下一个簇；链在这里结束时返回 None
+    fn next_cluster(&mut self, cluster: u32) -> Result<Option<u32>, FsError> {
+        match self.fat_entry(cluster)? {
+            next if next >= FAT_EOC_MIN => Ok(None),
+            FAT_BAD => Err(FsError::Corrupted("bad cluster in chain")),
+            next if self.is_valid_cluster(next) => Ok(Some(next)),
+            _ => Err(FsError::Corrupted("invalid cluster in chain")),
+        }
+    }
+
+    /// 分配一个空闲簇，标记为链尾；`prev` 不为 None 时把它接到 `prev` 后面
+    fn alloc_cluster(&mut self, prev: Option<u32>) -> Result<u32, FsError> {
+        let start = if self.is_valid_cluster(self.next_free) { self.next_free } else { FIRST_CLUSTER };
+        for i in 0..self.cluster_count {
+            let cluster = FIRST_CLUSTER + (start - FIRST_CLUSTER + i) % self.cluster_count;
+            if self.fat_entry(cluster)? != 0 {
+                continue;
+            }
+            self.set_fat_entry(cluster, FAT_EOC)?;
+            if let Some(prev) = prev {
+                self.set_fat_entry(prev, cluster)?;
+            }
+            self.next_free = cluster + 1;
+            self.free_count = self.free_count.map(|free| free.saturating_sub(1));
+            self.fsinfo_dirty = true;
+            return Ok(cluster);
+        }
+        Err(FsError::NoSpace)
+    }
+
+    /// 释放从 `start` 开始的整条簇链
+    fn free_chain(&mut self, start: u32) -> Result<(), FsError> {
+        let mut cluster = Some(start);
+        let mut freed = 0;
+        while let Some(current) = cluster {
+            if freed > self.cluster_count {
+                return Err(FsError::Corrupted("cluster chain loops"));
+            }
+            cluster = self.next_cluster(current)?;
+            self.set_fat_entry(current, 0)?;
+            freed += 1;
+        }
+        self.free_count = self.free_count.map(|free| free + freed);
+        self.fsinfo_dirty = true;
+        Ok(())
+    }
+
+    /// 把一个簇清零
+    fn zero_cluster(&mut self, cluster: u32) -> Result<(), FsError> {
+        let first = self.cluster_sector(cluster);
+        for sector in first..first + self.sectors_per_cluster {
+            self.cache.zero(sector)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,205 @@
+//! 🔌 把 FAT32 卷接入 VFS
+//!
+//! - `FatFs` 实现 `FileSystem`，可以用 `vfs::mount("/mnt", fs)` 挂到目录树上
+//! - 每次 `lookup` 目录时打开一个新的 `Dir`（只记录簇号）
+//! - 文件按短目录项的位置共享同一个 `File`（`FatFs::open_files`）：同一个文件的所有描述符看到相同的长度和簇链，
+//!   按偏移读写时先 seek 再读写
+//! - 文件还被打开着（`Inode::open` 之后还没有 `close`）时删除返回 `Busy`：删除会立即释放簇链；
+//!   只是查找路径时临时持有的 inode 不算打开
+//!
+//! 说明：查找、打开和删除都在持有 `open_files` 锁时进行；查找之后、打开之前文件被删除时打开返回 `NotFound`
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ptr;
+
+use super::dir::EntryPos;
+use super::{Dir, FatFs, File};
+use crate::fs::vfs::{DirEntry, FileSystem, FileType, Inode, Metadata};
+use crate::fs::{FsError, SeekFrom};
+use crate::thread::sync::Mutex;
+
+impl FileSystem for FatFs {
+    fn name(&self) -> &'static str {
+        "fat32"
+    }
+
+    fn root(self: Arc<Self>) -> Arc<dyn Inode> {
+        Arc::new(Node::Dir(self.root_dir()))
+    }
+
+    fn sync(&self) -> Result<(), FsError> {
+        self.flush()
+    }
+}
+
+/// `FatFs::open_files` 中的一项
+pub(super) struct OpenEntry {
+    file: Weak<Mutex<File>>,
+    /// `Inode::open` 之后还没有 `close` 的次数
+    opened: usize,
+}
+
+/// 把刚打开的 `file` 换成同一个目录项已经共享的 `File`；还没有人使用时登记它
+fn share(open_files: &mut BTreeMap<EntryPos, OpenEntry>, file: File) -> Arc<Mutex<File>> {
+    let pos = file.entry_pos();
+    if let Some(shared) = open_files.get(&pos).and_then(|entry| entry.file.upgrade()) {
+        return shared;
+    }
+    // 顺便清理已经没有人使用的项
+    open_files.retain(|_, entry| entry.file.strong_count() > 0);
+    let shared = Arc::new(Mutex::new(file));
+    open_files.insert(
+        pos,
+        OpenEntry {
+            file: Arc::downgrade(&shared),
+            opened: 0,
+        },
+    );
+    shared
+}
+
+/// 对 `file` 在 `open_files` 中登记的项执行 `f`；文件已经被删除（目录项可能已经被新文件复用）时返回 None
+fn with_entry<R>(file: &Arc<Mutex<File>>, f: impl FnOnce(&mut OpenEntry) -> R) -> Option<R> {
+    let (fs, pos) = {
+        let file = file.lock();
+        (file.fs().clone(), file.entry_pos())
+    };
+    let mut open_files = fs.open_files.lock();
+    open_files
+        .get_mut(&pos)
+        .filter(|entry| ptr::eq(entry.file.as_ptr(), Arc::as_ptr(file)))
+        .map(f)
+}
+
+/// FAT32 卷中的一个文件或目录
+enum Node {
+    Dir(Dir),
+    File(Arc<Mutex<File>>),
+}
+
+impl Node {
+    fn dir(&self) -> Result<&Dir, FsError> {
+        match self {
+            Node::Dir(dir) => Ok(dir),
+            Node::File(_) => Err(FsError::NotADirectory),
+        }
+    }
+
+    fn file(&self) -> Result<&Mutex<File>, FsError> {
+        match self {
+            Node::Dir(_) => Err(FsError::IsADirectory),
+            Node::File(file) => Ok(file.as_ref()),
+        }
+    }
+}
+
+impl Inode for Node {
+    fn metadata(&self) -> Result<Metadata, FsError> {
+        Ok(match self {
+            Node::Dir(_) => Metadata {
+                file_type: FileType::Dir,
+                size: 0,
+            },
+            Node::File(file) => Metadata {
+                file_type: FileType::File,
+                size: file.lock().size(),
+            },
+        })
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let mut file = self.file()?.lock();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read(buf)
+    }
+
+    fn write_at(&self, offset: u64, data: &[u8]) -> Result<usize, FsError> {
+        let mut file = self.file()?.lock();
+        file.seek(SeekFrom::Start(offset))?;
+        file.write(data)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), FsError> {
+        let mut file = self.file()?.lock();
+        if len < file.size() {
+            file.seek(SeekFrom::Start(len))?;
+            file.truncate()
+        } else if len > file.size() {
+            // 在新的末尾写一个 0，中间的空洞由 write 补零
+            file.seek(SeekFrom::Start(len - 1))?;
+            file.write(&[0]).map(|_| ())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        let dir = self.dir()?;
+        let mut open_files = dir.fs().open_files.lock();
+        let node = match dir.open_dir(name) {
+            Ok(sub) => Node::Dir(sub),
+            Err(FsError::NotADirectory) => Node::File(share(&mut open_files, dir.open_file(name)?)),
+            Err(err) => return Err(err),
+        };
+        Ok(Arc::new(node))
+    }
+
+    fn create(&self, name: &str, file_type: FileType) -> Result<Arc<dyn Inode>, FsError> {
+        let dir = self.dir()?;
+        let node = match file_type {
+            FileType::Dir => Node::Dir(dir.create_dir(name)?),
+            FileType::File => {
+                let mut open_files = dir.fs().open_files.lock();
+                // create_file 会截断已经存在的文件，这里要求文件不存在
+                match dir.entry(name) {
+                    Ok(_) => return Err(FsError::AlreadyExists),
+                    Err(FsError::NotFound) => Node::File(share(&mut open_files, dir.create_file(name)?)),
+                    Err(err) => return Err(err),
+                }
+            }
+        };
+        Ok(Arc::new(node))
+    }
+
+    fn remove(&self, name: &str) -> Result<(), FsError> {
+        let dir = self.dir()?;
+        let mut open_files = dir.fs().open_files.lock();
+        let pos = dir.entry(name)?.pos();
+        if open_files.get(&pos).is_some_and(|entry| entry.opened > 0) {
+            return Err(FsError::Busy);
+        }
+        dir.remove(name)?;
+        // 还持有这个文件 inode 的查找者之后 `open` 会失败
+        open_files.remove(&pos);
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<DirEntry>, FsError> {
+        Ok(self
+            .dir()?
+            .entries()?
+            .iter()
+            .map(|entry| DirEntry {
+                name: String::from(entry.name()),
+                file_type: if entry.is_dir() { FileType::Dir } else { FileType::File },
+            })
+            .collect())
+    }
+
+    fn open(&self) -> Result<(), FsError> {
+        match self {
+            Node::Dir(_) => Ok(()),
+            Node::File(file) => with_entry(file, |entry| entry.opened += 1).ok_or(FsError::NotFound),
+        }
+    }
+
+    fn close(&self) {
+        if let Node::File(file) = self {
+            with_entry(file, |entry| entry.opened -= 1);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! 📦 initramfs：启动时解包的 cpio 归档
+//!
+//! - build.rs 把仓库中的 `initramfs/` 目录打包成 cpio（newc 格式），嵌入内核的 `.initramfs` 段，
+//!   链接脚本把它放在 `.rodata` 之后；也可以用环境变量 `INITRAMFS` 指定一个现成的归档
+//!   （如 `find . | cpio -o -H newc > ../initramfs.cpio`）
+//! - `unpack` 把归档中的目录和普通文件复制到 VFS 的某个目录下（通常是挂在 `/` 上的 tmpfs），
+//!   缺少的上级目录自动创建；符号链接和设备文件不支持，跳过并打印警告
+//!
+//! newc 格式：每一项是 110 字节的 ASCII 头（魔数 `070701` 加 13 个 8 位十六进制字段），
+//! 后面是以 NUL 结尾的文件名和文件内容，两者都补齐到 4 字节；名为 `TRAILER!!!` 的项表示结束
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+
+use log::warn;
+
+use super::FsError;
+use super::vfs;
+
+include!(concat!(env!("OUT_DIR"), "/initramfs.rs"));
+
+/// 头部长度
+const HEADER_LEN: usize = 110;
+const MAGIC: &[u8] = b"070701";
+/// 带校验和的变体，头部格式相同
+const MAGIC_CRC: &[u8] = b"070702";
+const TRAILER: &str = "TRAILER!!!";
+
+/// mode 中的文件类型
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+
+/// 嵌入内核的 initramfs 归档（没有时为空）
+pub fn archive() -> &'static [u8] {
+    &INITRAMFS
+}
+
+/// 归档中的一项
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    /// 路径（去掉了开头的 `./` 和 `/`）
+    pub name: &'a str,
+    pub mode: u32,
+    pub data: &'a [u8],
+}
+
+impl Entry<'_> {
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & S_IFMT == S_IFREG
+    }
+}
+
+/// 遍历 cpio 归档的迭代器；遇到格式错误时产生一个 `Err` 后结束
+pub struct Entries<'a> {
+    rest: &'a [u8],
+}
+
+/// 遍历 cpio（newc）归档中的项
+pub fn entries(archive: &[u8]) -> Entries<'_> {
+    Entries { rest: archive }
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, FsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match self.parse() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.rest = &[];
+                None
+            }
+            Err(err) => {
+                self.rest = &[];
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'a> Entries<'a> {
+    /// 解析下一项，到达结尾时返回 None
+    fn parse(&mut self) -> Result<Option<Entry<'a>>, FsError> {
+        let header = self.rest.get(..HEADER_LEN).ok_or(FsError::Corrupted("truncated cpio header"))?;
+        if &header[..6] != MAGIC && &header[..6] != MAGIC_CRC {
+            return Err(FsError::Unsupported("cpio archive is not in newc format"));
+        }
+        let mode = hex_field(header, 1)?;
+        let file_size = hex_field(header, 6)? as usize;
+        let name_size = hex_field(header, 11)? as usize;
+
+        let name_end = HEADER_LEN + name_size;
+        let data_start = align4(name_end);
+        let data_end = data_start + file_size;
+        let name = self
+            .rest
+            .get(HEADER_LEN..name_end)
+            .and_then(|name| name.strip_suffix(b"\0"))
+            .ok_or(FsError::Corrupted("truncated cpio file name"))?;
+        let name = core::str::from_utf8(name).map_err(|_| FsError::InvalidName)?;
+        let data = self.rest.get(data_start..data_end).ok_or(FsError::Corrupted("truncated cpio file data"))?;
+        self.rest = self.rest.get(align4(data_end)..).unwrap_or(&[]);
+
+        if name == TRAILER {
+            return Ok(None);
+        }
+        let name = name.trim_start_matches("./").trim_start_matches('/');
+        Ok(Some(Entry { name, mode, data }))
+    }
+}
+
+/// 头部中第 `index` 个十六进制字段（跳过魔数）
+fn hex_field(header: &[u8], index: usize) -> Result<u32, FsError> {
+    let start = 6 + index * 8;
+    core::str::from_utf8(&header[start..start + 8])
+        .ok()
+        .and_then(|field| u32::from_str_radix(field, 16).ok())
+        .ok_or(FsError::Corrupted("invalid cpio header field"))
+}
+
+fn align4(offset: usize) -> usize {
+    offset.next_multiple_of(4)
+}
+
+/// 把归档解包到 VFS 的目录 `target` 下，返回创建的目录和文件数
+///
+/// 说明：已经存在的目录直接使用，已经存在的文件被覆盖
+pub fn unpack(archive: &[u8], target: &str) -> Result<usize, FsError> {
+    let mut count = 0;
+    for entry in entries(archive) {
+        let entry = entry?;
+        if entry.name.is_empty() || entry.name == "." {
+            continue;
+        }
+        let path = join(target, entry.name);
+        if entry.is_dir() {
+            vfs::mkdir_all(&path)?;
+        } else if entry.is_file() {
+            if let Some((parent, _)) = path.rsplit_once('/') {
+                vfs::mkdir_all(if parent.is_empty() { "/" } else { parent })?;
+            }
+            vfs::write_file(&path, entry.data)?;
+        } else {
+            warn!("initramfs: skipping {} (unsupported mode {:o})", entry.name, entry.mode);
+            continue;
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
@@ -0,0 +1,105 @@
+//! 🗄️ 文件系统
+//!
+//! - `vfs`：虚拟文件系统层：挂载表、路径解析和每个线程各自的文件描述符表
+//! - `tmpfs`：放在堆上的内存文件系统
+//! - `initramfs`：启动时把嵌入内核的 cpio 归档解包到 VFS 中
+//! - `fat`：FAT32 文件系统（读写），运行在任意 `BlockDevice` 上
+//!
+//! 各文件系统共用这里的错误类型 `FsError` 和 `SeekFrom`；`init` 在启动时挂载根文件系统
+
+pub mod fat;
+pub mod initramfs;
+pub mod tmpfs;
+pub mod vfs;
+
+use core::fmt::{Display, Formatter};
+
+use log::{info, warn};
+
+use crate::block::BlockError;
+use tmpfs::TmpFs;
+
+/// 挂载根文件系统：在 `/` 挂载一个 tmpfs，把嵌入内核的 initramfs 解包进去
+///
+/// 说明：在堆初始化之后调用，不需要线程上下文（可以在 `thread::init` 之前）
+pub fn init() {
+    vfs::mount("/", TmpFs::new()).expect("mount root tmpfs");
+    match initramfs::unpack(initramfs::archive(), "/") {
+        Ok(count) => info!("initramfs: unpacked {} entries ({} bytes)", count, initramfs::archive().len()),
+        Err(err) => warn!("initramfs: {}", err),
+    }
+}
+
+/// 文件系统操作失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsError {
+    /// 文件或目录不存在
+    NotFound,
+    /// 同名的文件或目录已经存在
+    AlreadyExists,
+    /// 路径中间的部分不是目录
+    NotADirectory,
+    /// 需要文件的地方是一个目录
+    IsADirectory,
+    /// 删除的目录不为空
+    DirectoryNotEmpty,
+    /// 文件名不合法（为空、过长或包含不允许的字符）
+    InvalidName,
+    /// seek 到了负的位置
+    InvalidSeek,
+    /// 文件超过文件系统允许的最大长度
+    FileTooLarge,
+    /// 磁盘已满
+    NoSpace,
+    /// 磁盘上的数据结构损坏
+    Corrupted(&'static str),
+    /// 不支持的格式
+    Unsupported(&'static str),
+    /// 块设备错误
+    Io(BlockError),
+    /// 文件描述符无效，或者打开方式不允许这个操作
+    BadDescriptor,
+    /// 当前线程打开的文件太多
+    TooManyOpenFiles,
+    /// 挂载点正在使用（有打开的文件或嵌套的挂载），或者路径已经是挂载点
+    Busy,
+}
+
+impl Display for FsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FsError::NotFound => write!(f, "no such file or directory"),
+            FsError::AlreadyExists => write!(f, "file exists"),
+            FsError::NotADirectory => write!(f, "not a directory"),
+            FsError::IsADirectory => write!(f, "is a directory"),
+            FsError::DirectoryNotEmpty => write!(f, "directory not empty"),
+            FsError::InvalidName => write!(f, "invalid file name"),
+            FsError::InvalidSeek => write!(f, "invalid seek"),
+            FsError::FileTooLarge => write!(f, "file too large"),
+            FsError::NoSpace => write!(f, "no space left on device"),
+            FsError::Corrupted(what) => write!(f, "corrupted file system: {}", what),
+            FsError::Unsupported(what) => write!(f, "unsupported file system: {}", what),
+            FsError::Io(err) => write!(f, "I/O error: {}", err),
+            FsError::BadDescriptor => write!(f, "bad file descriptor"),
+            FsError::TooManyOpenFiles => write!(f, "too many open files"),
+            FsError::Busy => write!(f, "device or resource busy"),
+        }
+    }
+}
+
+impl From<BlockError> for FsError {
+    fn from(err: BlockError) -> Self {
+        FsError::Io(err)
+    }
+}
+
+/// seek 的起点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    /// 从文件开头
+    Start(u64),
+    /// 从文件末尾
+    End(i64),
+    /// 从当前位置
+    Current(i64),
+}
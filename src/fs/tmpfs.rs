@@ -0,0 +1,175 @@
+//! 🧠 tmpfs：内存文件系统
+//!
+//! 文件内容和目录都放在全局堆上，关机后消失：
+//! - 文件是一个 `Vec<u8>`，目录是按名字排序的 `BTreeMap`，每个节点各有一把锁
+//! - 堆内存不够时写入返回 `NoSpace`，而不是触发分配失败
+//! - 文件被删除后，已经打开它的描述符仍然可以读写，最后一个引用释放时内存才回收
+//!
+//! 用法：
+//! ```ignore
+//! vfs::mount("/tmp", TmpFs::new())?;
+//! ```
+
+extern crate alloc;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use super::FsError;
+use super::vfs::{DirEntry, FileSystem, FileType, Inode, MAX_NAME_LEN, Metadata};
+use crate::thread::sync::Mutex;
+
+/// 🧠 一个 tmpfs 实例
+pub struct TmpFs {
+    root: Arc<Node>,
+}
+
+impl TmpFs {
+    /// 新建一个只有空根目录的 tmpfs
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            root: Arc::new(Node::Dir(Mutex::new(BTreeMap::new()))),
+        })
+    }
+}
+
+impl FileSystem for TmpFs {
+    fn name(&self) -> &'static str {
+        "tmpfs"
+    }
+
+    fn root(self: Arc<Self>) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+}
+
+/// tmpfs 中的一个文件或目录
+enum Node {
+    File(Mutex<Vec<u8>>),
+    Dir(Mutex<BTreeMap<String, Arc<Node>>>),
+}
+
+impl Node {
+    fn file_type(&self) -> FileType {
+        match self {
+            Node::File(_) => FileType::File,
+            Node::Dir(_) => FileType::Dir,
+        }
+    }
+
+    fn data(&self) -> Result<&Mutex<Vec<u8>>, FsError> {
+        match self {
+            Node::File(data) => Ok(data),
+            Node::Dir(_) => Err(FsError::IsADirectory),
+        }
+    }
+
+    fn children(&self) -> Result<&Mutex<BTreeMap<String, Arc<Node>>>, FsError> {
+        match self {
+            Node::File(_) => Err(FsError::NotADirectory),
+            Node::Dir(children) => Ok(children),
+        }
+    }
+}
+
+/// 把文件扩展到 `len` 字节（补零）；堆内存不够时返回 `NoSpace`
+fn grow(data: &mut Vec<u8>, len: usize) -> Result<(), FsError> {
+    if len > data.len() {
+        data.try_reserve(len - data.len()).map_err(|_| FsError::NoSpace)?;
+        data.resize(len, 0);
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), FsError> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN || name == "." || name == ".." || name.contains(['/', '\0']) {
+        return Err(FsError::InvalidName);
+    }
+    Ok(())
+}
+
+impl Inode for Node {
+    fn metadata(&self) -> Result<Metadata, FsError> {
+        let size = match self {
+            Node::File(data) => data.lock().len() as u64,
+            Node::Dir(_) => 0,
+        };
+        Ok(Metadata {
+            file_type: self.file_type(),
+            size,
+        })
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, FsError> {
+        let data = self.data()?.lock();
+        let Some(available) = usize::try_from(offset).ok().and_then(|offset| data.get(offset..)) else {
+            return Ok(0);
+        };
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        Ok(n)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> Result<usize, FsError> {
+        let mut data = self.data()?.lock();
+        let offset = usize::try_from(offset).map_err(|_| FsError::FileTooLarge)?;
+        let end = offset.checked_add(buf.len()).ok_or(FsError::FileTooLarge)?;
+        grow(&mut data, end)?;
+        data[offset..end].copy_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), FsError> {
+        let mut data = self.data()?.lock();
+        let len = usize::try_from(len).map_err(|_| FsError::FileTooLarge)?;
+        grow(&mut data, len)?;
+        data.truncate(len);
+        // 截断后归还多余的内存
+        data.shrink_to_fit();
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        let children = self.children()?.lock();
+        let node = children.get(name).ok_or(FsError::NotFound)?;
+        Ok(node.clone())
+    }
+
+    fn create(&self, name: &str, file_type: FileType) -> Result<Arc<dyn Inode>, FsError> {
+        check_name(name)?;
+        let mut children = self.children()?.lock();
+        if children.contains_key(name) {
+            return Err(FsError::AlreadyExists);
+        }
+        let node = Arc::new(match file_type {
+            FileType::File => Node::File(Mutex::new(Vec::new())),
+            FileType::Dir => Node::Dir(Mutex::new(BTreeMap::new())),
+        });
+        children.insert(String::from(name), node.clone());
+        Ok(node)
+    }
+
+    fn remove(&self, name: &str) -> Result<(), FsError> {
+        let mut children = self.children()?.lock();
+        let node = children.get(name).ok_or(FsError::NotFound)?;
+        if let Node::Dir(grandchildren) = &**node
+            && !grandchildren.lock().is_empty()
+        {
+            return Err(FsError::DirectoryNotEmpty);
+        }
+        children.remove(name);
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<DirEntry>, FsError> {
+        let children = self.children()?.lock();
+        Ok(children
+            .iter()
+            .map(|(name, node)| DirEntry {
+                name: name.clone(),
+                file_type: node.file_type(),
+            })
+            .collect())
+    }
+}
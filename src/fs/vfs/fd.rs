@@ -0,0 +1,240 @@
+//! 🔢 文件描述符
+//!
+//! 每个线程有自己的文件描述符表和工作目录，放在 `thread_local!` 变量里：
+//! - 描述符从 3 开始分配（0/1/2 留给控制台，与系统调用 `write` 的约定一致），总是分配最小的空闲号
+//! - `dup` 得到的描述符与原来的共享读写位置
+//! - 线程退出时 TLS 析构，没有关闭的文件随之关闭
+//! - 线程之外（启动代码）没有描述符表：相对路径相对于 `/`，按描述符的操作返回 `BadDescriptor`
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ops::BitOr;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{FileType, Metadata, Mount, OpenInode, normalize, resolve, resolve_parent};
+use crate::fs::{FsError, SeekFrom};
+
+/// 每个线程最多同时打开的文件数
+pub const MAX_FDS: usize = 32;
+
+/// 第一个分配给文件的描述符
+const FIRST_FD: usize = 3;
+
+/// 打开文件的方式，可以用 `|` 组合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenFlags(u32);
+
+impl OpenFlags {
+    /// 可读
+    pub const READ: Self = Self(1 << 0);
+    /// 可写
+    pub const WRITE: Self = Self(1 << 1);
+    /// 文件不存在时创建
+    pub const CREATE: Self = Self(1 << 2);
+    /// 打开时把文件截断为空（需要同时指定 `WRITE`）
+    pub const TRUNCATE: Self = Self(1 << 3);
+    /// 每次写入前把位置移到文件末尾
+    pub const APPEND: Self = Self(1 << 4);
+
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for OpenFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// 一个打开的文件
+struct OpenFile {
+    /// 持有挂载点：文件打开期间不能卸载
+    _mount: Arc<Mount>,
+    /// `dup` 共享同一个 `OpenFile`，最后一个描述符关闭时才调用 `Inode::close`
+    inode: OpenInode,
+    flags: OpenFlags,
+    offset: AtomicU64,
+}
+
+/// 一个线程的文件描述符表
+struct FdTable {
+    /// 下标为 描述符 - FIRST_FD
+    files: Vec<Option<Arc<OpenFile>>>,
+    cwd: String,
+}
+
+crate::thread_local! {
+    static TABLE: RefCell<FdTable> = RefCell::new(FdTable {
+        files: Vec::new(),
+        cwd: String::from("/"),
+    });
+}
+
+/// 把路径规范化为绝对路径，相对路径相对于当前线程的工作目录
+pub(super) fn absolute(path: &str) -> Result<String, FsError> {
+    match TABLE.try_with(|table| normalize(&table.borrow().cwd, path)) {
+        Ok(result) => result,
+        Err(_) => normalize("/", path),
+    }
+}
+
+/// 取出描述符对应的文件；不在线程中时返回 `BadDescriptor`
+fn file(fd: usize) -> Result<Arc<OpenFile>, FsError> {
+    TABLE
+        .try_with(|table| {
+            let table = table.borrow();
+            let index = fd.checked_sub(FIRST_FD)?;
+            table.files.get(index)?.clone()
+        })
+        .ok()
+        .flatten()
+        .ok_or(FsError::BadDescriptor)
+}
+
+/// 把文件放进描述符表，返回分配的描述符
+fn install(file: Arc<OpenFile>) -> Result<usize, FsError> {
+    TABLE
+        .try_with(|table| {
+            let mut table = table.borrow_mut();
+            let index = match table.files.iter().position(Option::is_none) {
+                Some(index) => index,
+                None if table.files.len() < MAX_FDS => {
+                    table.files.push(None);
+                    table.files.len() - 1
+                }
+                None => return Err(FsError::TooManyOpenFiles),
+            };
+            table.files[index] = Some(file);
+            Ok(index + FIRST_FD)
+        })
+        .map_err(|_| FsError::BadDescriptor)?
+}
+
+/// 打开文件或目录，返回文件描述符
+///
+/// 说明：
+/// - 文件不存在且指定了 `CREATE` 时创建它（父目录必须已经存在）
+/// - 目录只能以只读方式打开（用于 `fstat`），以 `WRITE` 打开目录返回 `IsADirectory`
+pub fn open(path: &str, flags: OpenFlags) -> Result<usize, FsError> {
+    let path = absolute(path)?;
+    let (mount, inode) = match resolve(&path) {
+        Err(FsError::NotFound) if flags.contains(OpenFlags::CREATE) => {
+            let (parent, name) = resolve_parent(&path)?;
+            let inode = parent.create(name, FileType::File)?;
+            // 重新解析一次，拿到所在的挂载点
+            (resolve(&path)?.0, inode)
+        }
+        other => other?,
+    };
+    let inode = OpenInode::new(inode)?;
+    if flags.contains(OpenFlags::WRITE) {
+        if inode.metadata()?.is_dir() {
+            return Err(FsError::IsADirectory);
+        }
+        if flags.contains(OpenFlags::TRUNCATE) {
+            inode.set_len(0)?;
+        }
+    }
+    install(Arc::new(OpenFile {
+        _mount: mount,
+        inode,
+        flags,
+        offset: AtomicU64::new(0),
+    }))
+}
+
+/// 关闭文件描述符
+pub fn close(fd: usize) -> Result<(), FsError> {
+    let closed = TABLE
+        .try_with(|table| {
+            let mut table = table.borrow_mut();
+            let index = fd.checked_sub(FIRST_FD)?;
+            table.files.get_mut(index)?.take()
+        })
+        .ok()
+        .flatten()
+        .ok_or(FsError::BadDescriptor)?;
+    // 在描述符表之外释放：文件系统的析构可能需要加锁
+    drop(closed);
+    Ok(())
+}
+
+/// 复制文件描述符，新旧描述符共享读写位置
+pub fn dup(fd: usize) -> Result<usize, FsError> {
+    install(file(fd)?)
+}
+
+/// 从当前位置读取，返回读到的字节数（到达文件末尾时为 0）
+pub fn read(fd: usize, buf: &mut [u8]) -> Result<usize, FsError> {
+    let file = file(fd)?;
+    if !file.flags.contains(OpenFlags::READ) {
+        return Err(FsError::BadDescriptor);
+    }
+    let offset = file.offset.load(Ordering::Relaxed);
+    let n = file.inode.read_at(offset, buf)?;
+    file.offset.store(offset + n as u64, Ordering::Relaxed);
+    Ok(n)
+}
+
+/// 在当前位置写入全部数据（`APPEND` 方式打开时写到文件末尾），返回写入的字节数
+pub fn write(fd: usize, data: &[u8]) -> Result<usize, FsError> {
+    let file = file(fd)?;
+    if !file.flags.contains(OpenFlags::WRITE) {
+        return Err(FsError::BadDescriptor);
+    }
+    let offset = if file.flags.contains(OpenFlags::APPEND) {
+        file.inode.metadata()?.size
+    } else {
+        file.offset.load(Ordering::Relaxed)
+    };
+    let n = file.inode.write_at(offset, data)?;
+    file.offset.store(offset + n as u64, Ordering::Relaxed);
+    Ok(n)
+}
+
+/// 移动读写位置，返回新的位置（可以超过文件末尾，之后写入时中间补零）
+pub fn seek(fd: usize, pos: SeekFrom) -> Result<u64, FsError> {
+    let file = file(fd)?;
+    let offset = match pos {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::End(delta) => file.inode.metadata()?.size.checked_add_signed(delta),
+        SeekFrom::Current(delta) => file.offset.load(Ordering::Relaxed).checked_add_signed(delta),
+    }
+    .ok_or(FsError::InvalidSeek)?;
+    file.offset.store(offset, Ordering::Relaxed);
+    Ok(offset)
+}
+
+/// 把文件截断或补零到 `len` 字节，不改变读写位置
+pub fn set_len(fd: usize, len: u64) -> Result<(), FsError> {
+    let file = file(fd)?;
+    if !file.flags.contains(OpenFlags::WRITE) {
+        return Err(FsError::BadDescriptor);
+    }
+    file.inode.set_len(len)
+}
+
+/// 查询打开的文件的元数据
+pub fn fstat(fd: usize) -> Result<Metadata, FsError> {
+    file(fd)?.inode.metadata()
+}
+
+/// 改变当前线程的工作目录
+pub fn chdir(path: &str) -> Result<(), FsError> {
+    let path = absolute(path)?;
+    if !resolve(&path)?.1.metadata()?.is_dir() {
+        return Err(FsError::NotADirectory);
+    }
+    TABLE.try_with(|table| table.borrow_mut().cwd = path).map_err(|_| FsError::BadDescriptor)
+}
+
+/// 当前线程的工作目录
+pub fn cwd() -> String {
+    TABLE.try_with(|table| table.borrow().cwd.clone()).unwrap_or_else(|_| String::from("/"))
+}
@@ -0,0 +1,396 @@
+//! 🌲 虚拟文件系统（VFS）
+//!
+//! 把各个文件系统挂到同一棵目录树上，对外提供按路径和按文件描述符的统一接口：
+//! - 文件系统实现 `FileSystem` 和 `Inode` trait，用 `mount` 挂到一个已经存在的目录上；第一个挂载的必须是 `/`
+//! - 路径先按字面规范化（去掉 `.`、`..` 和多余的 `/`，相对路径相对于当前线程的工作目录），
+//!   再按最长前缀找到挂载点，从该文件系统的根目录逐级 `lookup`
+//! - 文件描述符和工作目录是每个线程各自的（见 `fd`），新线程从 `/` 开始、没有打开的文件
+//! - 挂载点上还有打开的文件或者嵌套的挂载时不能卸载
+//!
+//! 用法：
+//! ```ignore
+//! vfs::mount("/", TmpFs::new())?;
+//! vfs::mkdir("/etc")?;
+//! let fd = vfs::open("/etc/hostname", OpenFlags::WRITE | OpenFlags::CREATE)?;
+//! vfs::write(fd, b"riscv")?;
+//! vfs::close(fd)?;
+//! ```
+
+mod fd;
+
+pub use fd::{MAX_FDS, OpenFlags, chdir, close, cwd, dup, fstat, open, read, seek, set_len, write};
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use super::FsError;
+use crate::thread::sync::RwLock;
+
+/// 文件名的最大长度（字节）
+pub const MAX_NAME_LEN: usize = 255;
+
+/// 文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileType {
+    File,
+    Dir,
+}
+
+/// 文件的元数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Metadata {
+    pub file_type: FileType,
+    /// 文件长度（字节）；目录为 0
+    pub size: u64,
+}
+
+impl Metadata {
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FileType::Dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.file_type == FileType::File
+    }
+}
+
+/// 目录中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub file_type: FileType,
+}
+
+/// 🗂️ 可以挂载的文件系统
+pub trait FileSystem: Send + Sync {
+    /// 文件系统类型的名字，如 `tmpfs`
+    fn name(&self) -> &'static str;
+
+    /// 根目录
+    fn root(self: Arc<Self>) -> Arc<dyn Inode>;
+
+    /// 把缓存的数据写回存储设备
+    fn sync(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+}
+
+/// 📎 文件系统中的一个文件或目录
+///
+/// 说明：
+/// - 文件只需实现 `read_at`/`write_at`/`set_len`，目录只需实现 `lookup`/`create`/`remove`/`entries`，
+///   其余方法的默认实现返回 `IsADirectory` 或 `NotADirectory`
+/// - `name` 总是单个路径分量，不含 `/`，也不是 `.` 或 `..`
+pub trait Inode: Send + Sync {
+    fn metadata(&self) -> Result<Metadata, FsError>;
+
+    /// 从 `offset` 开始读取，返回读到的字节数（到达文件末尾时为 0）
+    fn read_at(&self, _offset: u64, _buf: &mut [u8]) -> Result<usize, FsError> {
+        Err(FsError::IsADirectory)
+    }
+
+    /// 从 `offset` 开始写入全部数据，`offset` 超过文件末尾时中间补零，返回写入的字节数
+    fn write_at(&self, _offset: u64, _data: &[u8]) -> Result<usize, FsError> {
+        Err(FsError::IsADirectory)
+    }
+
+    /// 把文件截断或补零到 `len` 字节
+    fn set_len(&self, _len: u64) -> Result<(), FsError> {
+        Err(FsError::IsADirectory)
+    }
+
+    /// 在目录中查找 `name`
+    fn lookup(&self, _name: &str) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::NotADirectory)
+    }
+
+    /// 在目录中新建文件或子目录；同名的项已经存在时返回 `AlreadyExists`
+    fn create(&self, _name: &str, _file_type: FileType) -> Result<Arc<dyn Inode>, FsError> {
+        Err(FsError::NotADirectory)
+    }
+
+    /// 从目录中删除文件或空目录；不支持删除还打开着（`open` 之后还没有 `close`）的文件时返回 `Busy`
+    fn remove(&self, _name: &str) -> Result<(), FsError> {
+        Err(FsError::NotADirectory)
+    }
+
+    /// 开始使用文件（打开描述符、`read_file`/`write_file`）之前调用，与 `close` 成对；
+    /// 查找之后文件已经被删除时返回 `NotFound`
+    fn open(&self) -> Result<(), FsError> {
+        Ok(())
+    }
+
+    /// 使用结束，与 `open` 成对
+    fn close(&self) {}
+
+    /// 列出目录中的项（不含 `.` 和 `..`）
+    fn entries(&self) -> Result<Vec<DirEntry>, FsError> {
+        Err(FsError::NotADirectory)
+    }
+}
+
+/// 打开着的文件或目录：创建时调用 `Inode::open`，释放时调用 `Inode::close`
+struct OpenInode(Arc<dyn Inode>);
+
+impl OpenInode {
+    fn new(inode: Arc<dyn Inode>) -> Result<Self, FsError> {
+        inode.open()?;
+        Ok(Self(inode))
+    }
+}
+
+impl Deref for OpenInode {
+    type Target = dyn Inode;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.0
+    }
+}
+
+impl Drop for OpenInode {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+/// 一个挂载点
+struct Mount {
+    /// 规范化后的绝对路径
+    path: String,
+    fs: Arc<dyn FileSystem>,
+}
+
+/// 挂载点的信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountInfo {
+    pub path: String,
+    pub fs_name: &'static str,
+}
+
+/// 挂载表
+///
+/// 说明：打开的文件持有挂载点的 `Arc`，卸载时引用计数不为 1 说明还有文件开着
+static MOUNTS: RwLock<Vec<Arc<Mount>>> = RwLock::new(Vec::new());
+
+/// 把文件系统挂载到 `path`
+///
+/// 说明：
+/// - 第一个挂载的必须是 `/`；其他挂载点必须是已经存在的目录，挂载后原来的内容被遮住
+/// - `path` 已经是挂载点时返回 `Busy`
+pub fn mount(path: &str, fs: Arc<dyn FileSystem>) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    if path != "/" {
+        let (_, inode) = resolve(&path)?;
+        if !inode.metadata()?.is_dir() {
+            return Err(FsError::NotADirectory);
+        }
+    }
+    let mut mounts = MOUNTS.write();
+    if mounts.iter().any(|mount| mount.path == path) {
+        return Err(FsError::Busy);
+    }
+    if mounts.is_empty() && path != "/" {
+        return Err(FsError::NotFound);
+    }
+    log::info!("vfs: mounted {} on {}", fs.name(), path);
+    mounts.push(Arc::new(Mount { path, fs }));
+    Ok(())
+}
+
+/// 卸载 `path` 上的文件系统，卸载前把它的缓存写回
+///
+/// 说明：挂载点上还有打开的文件或者嵌套的挂载时返回 `Busy`；不能卸载 `/`
+pub fn unmount(path: &str) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    if path == "/" {
+        return Err(FsError::Busy);
+    }
+    let mut mounts = MOUNTS.write();
+    let index = mounts.iter().position(|mount| mount.path == path).ok_or(FsError::NotFound)?;
+    let nested = mounts.iter().any(|mount| strip_mount(&path, &mount.path).is_some_and(|rest| !rest.is_empty()));
+    if nested || Arc::strong_count(&mounts[index]) > 1 {
+        return Err(FsError::Busy);
+    }
+    let mount = mounts.remove(index);
+    drop(mounts);
+    log::info!("vfs: unmounted {} from {}", mount.fs.name(), mount.path);
+    mount.fs.sync()
+}
+
+/// 列出所有挂载点（按挂载顺序）
+pub fn mounts() -> Vec<MountInfo> {
+    MOUNTS
+        .read()
+        .iter()
+        .map(|mount| MountInfo {
+            path: mount.path.clone(),
+            fs_name: mount.fs.name(),
+        })
+        .collect()
+}
+
+/// 把所有文件系统的缓存写回存储设备，返回遇到的第一个错误
+pub fn sync() -> Result<(), FsError> {
+    let mounts: Vec<Arc<Mount>> = MOUNTS.read().clone();
+    mounts.iter().map(|mount| mount.fs.sync()).fold(Ok(()), Result::and)
+}
+
+/// 查询文件或目录的元数据
+pub fn stat(path: &str) -> Result<Metadata, FsError> {
+    resolve(&fd::absolute(path)?)?.1.metadata()
+}
+
+/// 创建目录（父目录必须已经存在）
+pub fn mkdir(path: &str) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    let (parent, name) = resolve_parent(&path)?;
+    parent.create(name, FileType::Dir).map(|_| ())
+}
+
+/// 创建目录，并按需创建所有上级目录；目录已经存在时什么也不做
+pub fn mkdir_all(path: &str) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    let mut prefix = String::new();
+    for part in path.split('/').filter(|part| !part.is_empty()) {
+        prefix.push('/');
+        prefix.push_str(part);
+        match mkdir(&prefix) {
+            Err(FsError::AlreadyExists) if stat(&prefix)?.is_dir() => {}
+            Err(FsError::AlreadyExists) => return Err(FsError::NotADirectory),
+            other => other?,
+        }
+    }
+    Ok(())
+}
+
+/// 删除文件或空目录
+///
+/// 说明：能否删除已经打开的文件取决于文件系统：tmpfs 可以，删除后仍然可以通过文件描述符读写；
+/// FAT32 返回 `Busy`
+pub fn remove(path: &str) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    if MOUNTS.read().iter().any(|mount| mount.path == path) {
+        return Err(FsError::Busy);
+    }
+    let (parent, name) = resolve_parent(&path)?;
+    parent.remove(name)
+}
+
+/// 列出目录中的项
+pub fn read_dir(path: &str) -> Result<Vec<DirEntry>, FsError> {
+    let path = fd::absolute(path)?;
+    let (_, inode) = resolve(&path)?;
+    let mut entries = inode.entries()?;
+    // 挂在这个目录下、但在底层文件系统中不存在的挂载点也列出来
+    for mount in MOUNTS.read().iter() {
+        if let Some(name) = strip_mount(&path, &mount.path).filter(|rest| !rest.is_empty() && !rest.contains('/'))
+            && !entries.iter().any(|entry| entry.name == name)
+        {
+            entries.push(DirEntry {
+                name: String::from(name),
+                file_type: FileType::Dir,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// 读出整个文件
+pub fn read_file(path: &str) -> Result<Vec<u8>, FsError> {
+    let inode = OpenInode::new(resolve(&fd::absolute(path)?)?.1)?;
+    let size = inode.metadata()?.size as usize;
+    let mut data = alloc::vec![0u8; size];
+    let mut done = 0;
+    while done < size {
+        match inode.read_at(done as u64, &mut data[done..])? {
+            0 => break,
+            n => done += n,
+        }
+    }
+    data.truncate(done);
+    Ok(data)
+}
+
+/// 用 `data` 替换文件的内容，文件不存在时创建它
+pub fn write_file(path: &str, data: &[u8]) -> Result<(), FsError> {
+    let path = fd::absolute(path)?;
+    let inode = match resolve(&path) {
+        Ok((_, inode)) => inode,
+        Err(FsError::NotFound) => {
+            let (parent, name) = resolve_parent(&path)?;
+            parent.create(name, FileType::File)?
+        }
+        Err(err) => return Err(err),
+    };
+    let inode = OpenInode::new(inode)?;
+    inode.set_len(0)?;
+    inode.write_at(0, data).map(|_| ())
+}
+
+/// 把路径按字面规范化为绝对路径：相对路径接在 `base` 后面，去掉 `.`、`..` 和多余的 `/`
+///
+/// 说明：`..` 不会越过 `/`；空路径返回 `NotFound`，某个分量超过 `MAX_NAME_LEN` 时返回 `InvalidName`
+pub fn normalize(base: &str, path: &str) -> Result<String, FsError> {
+    if path.is_empty() {
+        return Err(FsError::NotFound);
+    }
+    let mut parts: Vec<&str> = Vec::new();
+    let start = if path.starts_with('/') { "" } else { base };
+    for part in start.split('/').chain(path.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ if part.len() > MAX_NAME_LEN => return Err(FsError::InvalidName),
+            _ => parts.push(part),
+        }
+    }
+    Ok(format!("/{}", parts.join("/")))
+}
+
+/// `path` 在挂载点 `mount` 之下时，返回去掉挂载点之后的部分（不以 `/` 开头，`path` 就是挂载点时为空）
+fn strip_mount<'p>(mount: &str, path: &'p str) -> Option<&'p str> {
+    if mount == "/" {
+        return Some(path.trim_start_matches('/'));
+    }
+    match path.strip_prefix(mount)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
+}
+
+/// 找到 `path`（规范化的绝对路径）所在的挂载点：最长的匹配前缀
+fn find_mount(path: &str) -> Result<(Arc<Mount>, &str), FsError> {
+    let mounts = MOUNTS.read();
+    mounts
+        .iter()
+        .filter_map(|mount| strip_mount(&mount.path, path).map(|rest| (mount, rest)))
+        .max_by_key(|(mount, _)| mount.path.len())
+        .map(|(mount, rest)| (mount.clone(), rest))
+        .ok_or(FsError::NotFound)
+}
+
+/// 解析规范化的绝对路径，返回 (所在的挂载点, 文件或目录)
+fn resolve(path: &str) -> Result<(Arc<Mount>, Arc<dyn Inode>), FsError> {
+    let (mount, rest) = find_mount(path)?;
+    let mut inode = mount.fs.clone().root();
+    for part in rest.split('/').filter(|part| !part.is_empty()) {
+        inode = inode.lookup(part)?;
+    }
+    Ok((mount, inode))
+}
+
+/// 解析到最后一个分量所在的目录，返回 (目录, 最后一个分量)；`path` 为 `/` 时返回 `Busy`
+fn resolve_parent(path: &str) -> Result<(Arc<dyn Inode>, &str), FsError> {
+    let (parent, name) = path.rsplit_once('/').ok_or(FsError::NotFound)?;
+    if name.is_empty() {
+        return Err(FsError::Busy);
+    }
+    let (_, inode) = resolve(if parent.is_empty() { "/" } else { parent })?;
+    Ok((inode, name))
+}
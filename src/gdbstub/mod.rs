@@ -0,0 +1,825 @@
+//! 🐞 GDB 远程调试桩（gdbstub）
+//!
+//! 在内核里实现 GDB 远程串行协议（RSP），通过一个 UART 与 GDB 通信。与 QEMU 自带的 gdbstub 不同，
+//! 它能看到调度器线程表中的每一个线程：
+//! - 寄存器读写（`g`/`G`/`p`/`P`）：停住的 hart 和被停住的其它 hart 上的线程取自 `TrapFrame`，
+//!   其它线程取自切换时保存的 `ThreadContext`（只有 ra/sp/tp/s0~s11，pc 为 ra，只读）；浮点寄存器报告为不可用
+//! - 内存读写（`m`/`M`）：只允许访问设备树描述的内存，且不能越过加锁的 PMP 表项
+//! - 软件断点（`Z0`/`z0`）：把指令替换成 `ebreak`/`c.ebreak`，命中时由 trap 处理进入调试桩；
+//!   用户线程在 U-mode 命中断点也会停住
+//! - 单步（`s`）：解码当前指令，在下一条可能执行的指令上放临时断点（见 `step`），单步期间被调试的 hart 关中断
+//! - 线程列表（`qfThreadInfo`/`qsThreadInfo`/`qThreadExtraInfo`/`T`/`H`）：GDB 线程 id 为线程槽位 + 1，
+//!   停住时正在 idle 的 hart 显示为 `IDLE_TID_BASE + hart id` 的线程
+//! - 目标描述（`qXfer:features:read`）：告诉 GDB 寄存器布局
+//!
+//! 停住时（all-stop）：进入调试桩的 hart 用 IPI 让其它 hart 在软件中断处理中等待，恢复运行时一起放行。
+//! 在中断长时间关闭的 hart 上等不到 IPI 时只等待 `HALT_TIMEOUT_MS`。
+//! 目标运行时，计时器中断中轮询 UART，收到 GDB 的 Ctrl-C 就停下来。
+//!
+//! 用法：
+//! ```ignore
+//! gdbstub::init();          // 必须在 thread::init 之前：.text 需要保持可写
+//! thread::init(|| {
+//!     gdbstub::breakpoint(); // 在这里等待 GDB 连接
+//!     ...
+//! });
+//! ```
+//!
+//! 说明：QEMU virt 只有一个 UART，没有第二个 UART 时调试桩与控制台共用它（见 `make debug-stub`），
+//! 此时控制台输出会混在协议数据中，GDB 会忽略包之外的字节
+
+mod packet;
+mod step;
+
+use core::arch::asm;
+use core::cell::UnsafeCell;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use log::info;
+
+use crate::hart::{self, MAX_HARTS};
+use crate::pmp::{self, Access};
+use crate::thread::tcb::ThreadId;
+use crate::thread::{self, ThreadState};
+use crate::trap::TrapFrame;
+use crate::{dtb, ipi, timer};
+use packet::{Connection, HexWriter, PACKET_SIZE, Reply, parse_hex, parse_reg};
+
+/// 最多同时存在的软件断点数
+pub const MAX_BREAKPOINTS: usize = 32;
+
+/// 停住时 idle 的 hart 显示为线程 `IDLE_TID_BASE + hart id`
+pub const IDLE_TID_BASE: usize = 0x10000;
+
+/// 停住时等待其它 hart 进入等待状态的最长时间（毫秒）
+const HALT_TIMEOUT_MS: usize = 100;
+
+/// ebreak
+const EBREAK: u32 = 0x0010_0073;
+/// c.ebreak
+const C_EBREAK: u16 = 0x9002;
+
+/// 停止原因（Unix 信号编号）
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+
+/// GDB 的寄存器编号：x0~x31、pc，之后是 f0~f31 和浮点 CSR
+const PC_REGNUM: usize = 32;
+const FIRST_FP_REGNUM: usize = 33;
+const LAST_FP_REGNUM: usize = 64;
+const FFLAGS_REGNUM: usize = 66;
+const FCSR_REGNUM: usize = 68;
+
+/// 目标描述：RV64 整数寄存器和浮点寄存器（内核按 lp64d 编译，GDB 要求目标描述中有浮点寄存器）
+const TARGET_XML: &str = concat!(
+    r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd"><target version="1.0">"#,
+    r#"<architecture>riscv:rv64</architecture><feature name="org.gnu.gdb.riscv.cpu">"#,
+    r#"<reg name="zero" bitsize="64" type="int" regnum="0"/><reg name="ra" bitsize="64" type="code_ptr"/>"#,
+    r#"<reg name="sp" bitsize="64" type="data_ptr"/><reg name="gp" bitsize="64" type="data_ptr"/>"#,
+    r#"<reg name="tp" bitsize="64" type="data_ptr"/><reg name="t0" bitsize="64" type="int"/>"#,
+    r#"<reg name="t1" bitsize="64" type="int"/><reg name="t2" bitsize="64" type="int"/>"#,
+    r#"<reg name="fp" bitsize="64" type="data_ptr"/><reg name="s1" bitsize="64" type="int"/>"#,
+    r#"<reg name="a0" bitsize="64" type="int"/><reg name="a1" bitsize="64" type="int"/>"#,
+    r#"<reg name="a2" bitsize="64" type="int"/><reg name="a3" bitsize="64" type="int"/>"#,
+    r#"<reg name="a4" bitsize="64" type="int"/><reg name="a5" bitsize="64" type="int"/>"#,
+    r#"<reg name="a6" bitsize="64" type="int"/><reg name="a7" bitsize="64" type="int"/>"#,
+    r#"<reg name="s2" bitsize="64" type="int"/><reg name="s3" bitsize="64" type="int"/>"#,
+    r#"<reg name="s4" bitsize="64" type="int"/><reg name="s5" bitsize="64" type="int"/>"#,
+    r#"<reg name="s6" bitsize="64" type="int"/><reg name="s7" bitsize="64" type="int"/>"#,
+    r#"<reg name="s8" bitsize="64" type="int"/><reg name="s9" bitsize="64" type="int"/>"#,
+    r#"<reg name="s10" bitsize="64" type="int"/><reg name="s11" bitsize="64" type="int"/>"#,
+    r#"<reg name="t3" bitsize="64" type="int"/><reg name="t4" bitsize="64" type="int"/>"#,
+    r#"<reg name="t5" bitsize="64" type="int"/><reg name="t6" bitsize="64" type="int"/>"#,
+    r#"<reg name="pc" bitsize="64" type="code_ptr"/></feature><feature name="org.gnu.gdb.riscv.fpu">"#,
+    r#"<reg name="ft0" bitsize="64" type="ieee_double" regnum="33"/><reg name="ft1" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="ft2" bitsize="64" type="ieee_double"/><reg name="ft3" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="ft4" bitsize="64" type="ieee_double"/><reg name="ft5" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="ft6" bitsize="64" type="ieee_double"/><reg name="ft7" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs0" bitsize="64" type="ieee_double"/><reg name="fs1" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fa0" bitsize="64" type="ieee_double"/><reg name="fa1" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fa2" bitsize="64" type="ieee_double"/><reg name="fa3" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fa4" bitsize="64" type="ieee_double"/><reg name="fa5" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fa6" bitsize="64" type="ieee_double"/><reg name="fa7" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs2" bitsize="64" type="ieee_double"/><reg name="fs3" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs4" bitsize="64" type="ieee_double"/><reg name="fs5" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs6" bitsize="64" type="ieee_double"/><reg name="fs7" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs8" bitsize="64" type="ieee_double"/><reg name="fs9" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fs10" bitsize="64" type="ieee_double"/><reg name="fs11" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="ft8" bitsize="64" type="ieee_double"/><reg name="ft9" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="ft10" bitsize="64" type="ieee_double"/><reg name="ft11" bitsize="64" type="ieee_double"/>"#,
+    r#"<reg name="fflags" bitsize="32" type="int" regnum="66"/><reg name="frm" bitsize="32" type="int"/>"#,
+    r#"<reg name="fcsr" bitsize="32" type="int"/></feature></target>"#,
+);
+
+/// 调试桩是否启用
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// 正在调试桩中的 hart id + 1（0 表示没有）
+static OWNER: AtomicUsize = AtomicUsize::new(0);
+/// 要求其它 hart 停住等待
+static HALT: AtomicBool = AtomicBool::new(false);
+/// 每次恢复运行时加一，等待中的 hart 看到变化后离开
+static RESUME: AtomicUsize = AtomicUsize::new(0);
+/// 各 hart 停住等待时被打断的现场（0 表示没有在等待）
+static PARKED: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+
+/// 一个软件断点：地址、长度（2 或 4）和被替换掉的指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Breakpoint {
+    addr: usize,
+    len: usize,
+    original: u32,
+}
+
+/// 进行中的单步
+#[derive(Debug, Clone, Copy)]
+struct Step {
+    hart_id: usize,
+    /// 临时断点
+    targets: [Option<Breakpoint>; 2],
+    /// 单步前的 mstatus.MPIE（单步期间关中断）
+    mpie: usize,
+}
+
+/// 调试桩的状态：只有 `OWNER` 对应的 hart 访问
+struct Stub {
+    conn: Connection,
+    rx: [u8; PACKET_SIZE],
+    tx: Reply,
+    breakpoints: [Option<Breakpoint>; MAX_BREAKPOINTS],
+    step: Option<Step>,
+    /// GDB 已经连接（收到过数据包）
+    attached: bool,
+    /// 最近一次停止的原因（回复 `?`）
+    signal: u8,
+    /// `Hg` 选中的线程（GDB 线程 id），0 表示停住的线程
+    selected: usize,
+}
+
+struct StubCell(UnsafeCell<Stub>);
+
+// 只有持有 OWNER 的 hart 访问
+unsafe impl Sync for StubCell {}
+
+static STUB: StubCell = StubCell(UnsafeCell::new(Stub {
+    conn: Connection::new(0),
+    rx: [0; PACKET_SIZE],
+    tx: Reply::new(),
+    breakpoints: [None; MAX_BREAKPOINTS],
+    step: None,
+    attached: false,
+    signal: SIGTRAP,
+    selected: 0,
+}));
+
+/// 🐞 启用调试桩：有第二个 UART 时使用它，否则与控制台共用 UART
+///
+/// 说明：必须在 `thread::init` 之前调用，这样各 hart 设置 PMP 时 `.text` 不加锁，可以打软件断点
+pub fn init() {
+    let platform = dtb::platform();
+    init_on(platform.debug_uart.unwrap_or(platform.uart));
+}
+
+/// 🐞 在指定基地址的 ns16550a UART 上启用调试桩（要求同 `init`）
+pub fn init_on(uart_base: usize) {
+    pmp::unlock_text();
+    unsafe { (*STUB.0.get()).conn = Connection::new(uart_base) };
+    ENABLED.store(true, Ordering::Release);
+    info!("🐞 gdbstub listening on UART 0x{:x}", uart_base);
+}
+
+/// 调试桩是否已经启用
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Acquire)
+}
+
+/// 🐞 执行一条 `ebreak` 停下来等待 GDB（调试桩没有启用时会被当作未处理的异常）
+#[inline(always)]
+pub fn breakpoint() {
+    unsafe { asm!("ebreak") };
+}
+
+/// 读取 2 字节（指令按 2 字节对齐，32 位指令也分两次读）；地址不可读时返回 None
+pub(crate) fn read_u16(addr: usize) -> Option<u16> {
+    if addr % 2 != 0 || !accessible(addr, 2, Access::Read) {
+        return None;
+    }
+    Some(unsafe { core::ptr::read_volatile(addr as *const u16) })
+}
+
+/// 写入 2 字节
+fn write_u16(addr: usize, value: u16) -> bool {
+    if addr % 2 != 0 || !accessible(addr, 2, Access::Write) {
+        return false;
+    }
+    unsafe { core::ptr::write_volatile(addr as *mut u16, value) };
+    true
+}
+
+/// 调试器能否访问 `[addr, addr + len)`：在设备树描述的内存中，且 PMP 允许机器模式访问
+fn accessible(addr: usize, len: usize, access: Access) -> bool {
+    let in_memory = addr
+        .checked_add(len)
+        .is_some_and(|end| dtb::platform().memory_region_of(addr).is_some_and(|region| end <= region.end()));
+    in_memory && pmp::kernel_accessible(addr, len, access)
+}
+
+impl Breakpoint {
+    /// 在 `addr` 放断点：`len` 为 4 时写 ebreak，为 2 时写 c.ebreak
+    fn insert(addr: usize, len: usize) -> Option<Self> {
+        let low = read_u16(addr)?;
+        let original = match len {
+            2 => low as u32,
+            4 => low as u32 | (read_u16(addr + 2)? as u32) << 16,
+            _ => return None,
+        };
+        let written = match len {
+            2 => write_u16(addr, C_EBREAK),
+            _ => write_u16(addr, EBREAK as u16) && write_u16(addr + 2, (EBREAK >> 16) as u16),
+        };
+        written.then_some(Self { addr, len, original })
+    }
+
+    /// 恢复原来的指令
+    fn remove(&self) {
+        write_u16(self.addr, self.original as u16);
+        if self.len == 4 {
+            write_u16(self.addr + 2, (self.original >> 16) as u16);
+        }
+    }
+}
+
+/// 处理 ebreak 引起的断点异常（由 trap 处理调用）
+///
+/// 说明：
+/// - 命中调试桩的断点或单步的临时断点时停住，等待 GDB 的命令
+/// - 代码中写死的 `ebreak`（例如 `breakpoint()`）先跳过它再停住，继续运行时不会再次命中
+/// - 断点在这次异常之后已被移除时直接返回，重新执行原来的指令
+pub fn handle_breakpoint(frame: &mut TrapFrame) {
+    enter(frame, SIGTRAP, true);
+}
+
+/// 目标运行时轮询 GDB 的 Ctrl-C（由计时器中断调用）
+///
+/// 说明：每个 hart 的计时器中断都会调用，先取得 `OWNER` 才访问 `STUB`；已经被其它 hart 持有时直接返回
+pub fn poll(frame: &mut TrapFrame) {
+    if !is_enabled() {
+        return;
+    }
+    let me = hart::id();
+    if OWNER.compare_exchange(0, me + 1, Ordering::AcqRel, Ordering::Relaxed).is_err() {
+        return;
+    }
+    // 还没有连接时不读 UART：与控制台共用 UART 时输入属于控制台
+    let stub = unsafe { &*STUB.0.get() };
+    if stub.attached && stub.conn.poll_byte() == Some(packet::INTERRUPT) {
+        // enter 看到 OWNER 已经是本 hart，直接进入
+        enter(frame, SIGINT, false);
+    } else {
+        OWNER.store(0, Ordering::Release);
+    }
+}
+
+/// 其它 hart 要求停住时在这里等待，直到恢复运行（由软件中断处理调用）
+pub fn park_if_halted(frame: &mut TrapFrame) {
+    if HALT.load(Ordering::Acquire) {
+        park(frame);
+    }
+}
+
+fn park(frame: &mut TrapFrame) {
+    let me = hart::id();
+    let resume = RESUME.load(Ordering::Acquire);
+    PARKED[me].store(frame as *mut TrapFrame as usize, Ordering::Release);
+    while HALT.load(Ordering::Acquire) && RESUME.load(Ordering::Acquire) == resume {
+        core::hint::spin_loop();
+    }
+    PARKED[me].store(0, Ordering::Release);
+    // 停住期间代码可能被改写（断点）
+    unsafe { asm!("fence.i") };
+}
+
+/// 进入调试桩：停住其它 hart，报告停止原因并处理 GDB 的命令，直到继续运行
+fn enter(frame: &mut TrapFrame, signal: u8, breakpoint: bool) {
+    let me = hart::id();
+    // 单步时 OWNER 一直由单步的 hart 持有
+    while OWNER.load(Ordering::Acquire) != me + 1
+        && OWNER
+            .compare_exchange(0, me + 1, Ordering::AcqRel, Ordering::Relaxed)
+            .is_err()
+    {
+        if HALT.load(Ordering::Acquire) {
+            park(frame);
+        }
+        core::hint::spin_loop();
+    }
+    let stub = unsafe { &mut *STUB.0.get() };
+
+    let stepped = stub.step.take_if(|step| step.hart_id == me);
+    if let Some(step) = stepped {
+        for target in step.targets.iter().flatten() {
+            target.remove();
+        }
+        frame.mstatus = (frame.mstatus & !MSTATUS_MPIE) | step.mpie;
+    } else if breakpoint && !stub.breakpoints.iter().flatten().any(|bp| bp.addr == frame.mepc) {
+        match read_u16(frame.mepc) {
+            Some(C_EBREAK) => frame.mepc += 2,
+            Some(low) if low == EBREAK as u16 && read_u16(frame.mepc + 2) == Some((EBREAK >> 16) as u16) => {
+                frame.mepc += 4
+            }
+            _ => {
+                // 断点已经被移除（另一个 hart 命中后 GDB 删除了它），重新执行原指令
+                OWNER.store(0, Ordering::Release);
+                return;
+            }
+        }
+    }
+
+    if !HALT.swap(true, Ordering::AcqRel) {
+        halt_others(me);
+    }
+    stub.selected = 0;
+    stub.signal = signal;
+    if stub.attached {
+        stub.report_stop();
+    }
+    stub.serve(frame);
+}
+
+/// mstatus.MPIE：mret 之后的中断使能
+const MSTATUS_MPIE: usize = 1 << 7;
+
+/// 用 IPI 让其它在线的 hart 停住，最多等待 `HALT_TIMEOUT_MS`
+fn halt_others(me: usize) {
+    let others = (0..hart::count())
+        .filter(|&h| h != me && hart::get(h).is_some_and(|local| local.is_online()))
+        .fold(0usize, |mask, h| mask | 1 << h);
+    if others == 0 {
+        return;
+    }
+    ipi::send_ipi(others);
+    let deadline = timer::get_time() + timer::clock_freq() * HALT_TIMEOUT_MS / 1000;
+    while timer::get_time() < deadline {
+        let parked = (0..MAX_HARTS).all(|h| others & 1 << h == 0 || PARKED[h].load(Ordering::Acquire) != 0);
+        if parked {
+            return;
+        }
+        core::hint::spin_loop();
+    }
+}
+
+/// 恢复运行：放行所有停住的 hart
+fn resume_all() {
+    unsafe { asm!("fence.i") };
+    HALT.store(false, Ordering::Release);
+    RESUME.fetch_add(1, Ordering::AcqRel);
+    OWNER.store(0, Ordering::Release);
+}
+
+/// 一个线程的寄存器来源
+enum Regs {
+    /// 停住的 hart 上被打断的现场（可读写）
+    Frame(*mut TrapFrame),
+    /// 没有在运行的线程：切换时保存的上下文（只读）
+    Context(thread::tcb::ThreadContext),
+}
+
+impl Regs {
+    fn get(&self, index: usize) -> Option<usize> {
+        match self {
+            Regs::Frame(frame) => unsafe { (**frame).reg(index) },
+            Regs::Context(context) => match index {
+                0 => Some(0),
+                1 | PC_REGNUM => Some(context.ra),
+                2 => Some(context.sp),
+                4 => Some(context.tp),
+                8..=9 => Some(context.s[index - 8]),
+                18..=27 => Some(context.s[index - 16]),
+                _ => None,
+            },
+        }
+    }
+
+    fn set(&mut self, index: usize, value: usize) -> bool {
+        match self {
+            Regs::Frame(frame) => unsafe { (**frame).set_reg(index, value) },
+            Regs::Context(_) => false,
+        }
+    }
+}
+
+/// hart 上正在运行的线程对应的 GDB 线程 id
+fn hart_tid(hart_id: usize) -> usize {
+    match hart::get(hart_id).and_then(|local| local.current_thread()) {
+        Some(raw) => ThreadId::from_raw(raw).slot() + 1,
+        None => IDLE_TID_BASE + hart_id,
+    }
+}
+
+/// hart 停住时被打断的现场；`me` 是当前 hart 的现场
+fn hart_frame(hart_id: usize, me: *mut TrapFrame) -> Option<*mut TrapFrame> {
+    if hart_id == hart::id() {
+        return Some(me);
+    }
+    let frame = PARKED[hart_id].load(Ordering::Acquire);
+    (frame != 0).then_some(frame as *mut TrapFrame)
+}
+
+/// 停住的或正在等待的 hart
+fn stopped_harts() -> impl Iterator<Item = usize> {
+    let me = hart::id();
+    (0..hart::count()).filter(move |&h| h == me || PARKED[h].load(Ordering::Acquire) != 0)
+}
+
+impl Stub {
+    fn send(&mut self) {
+        self.conn.write_packet(self.tx.as_bytes());
+    }
+
+    fn reply(&mut self, text: &str) {
+        self.tx.clear();
+        self.tx.push_str(text);
+        self.send();
+    }
+
+    /// 回复 `T<信号>thread:<id>;`
+    fn report_stop(&mut self) {
+        self.tx.clear();
+        let _ = write!(self.tx, "T{:02x}thread:{:x};", self.signal, hart_tid(hart::id()));
+        self.send();
+    }
+
+    /// GDB 线程 id 对应的寄存器来源
+    fn regs(&self, tid: usize, me: *mut TrapFrame) -> Option<Regs> {
+        let tid = if tid == 0 || tid == usize::MAX { hart_tid(hart::id()) } else { tid };
+        if let Some(hart_id) = tid.checked_sub(IDLE_TID_BASE).filter(|&h| h < MAX_HARTS) {
+            return hart_frame(hart_id, me).map(Regs::Frame);
+        }
+        for hart_id in stopped_harts() {
+            if hart_tid(hart_id) == tid {
+                return hart_frame(hart_id, me).map(Regs::Frame);
+            }
+        }
+        let s = thread::try_sched()?;
+        let thread = s.threads().find(|t| t.id.slot() + 1 == tid && t.state != ThreadState::Terminated)?;
+        Some(Regs::Context(thread.context))
+    }
+
+    /// 线程是否存在
+    fn thread_alive(&self, tid: usize, me: *mut TrapFrame) -> bool {
+        self.regs(tid, me).is_some()
+    }
+
+    /// 处理命令直到继续运行
+    fn serve(&mut self, frame: &mut TrapFrame) {
+        let me: *mut TrapFrame = frame;
+        loop {
+            let len = self.conn.read_packet(&mut self.rx);
+            self.attached = true;
+            let mut packet = [0u8; 64];
+            // 大多数命令很短，复制出来避免同时借用 rx 和 self
+            let (command, rest_len) = match self.rx[..len].first() {
+                Some(&command) => (command, len - 1),
+                None => (0, 0),
+            };
+            let short = rest_len.min(packet.len());
+            packet[..short].copy_from_slice(&self.rx[1..1 + short]);
+            let args = &packet[..short];
+            match command {
+                b'?' => self.report_stop(),
+                b'g' => self.read_registers(me),
+                b'G' => self.write_registers(me, rest_len),
+                b'p' => self.read_register(args, me),
+                b'P' => self.write_register(args, me),
+                b'm' => self.read_memory(args),
+                b'M' => self.write_memory(rest_len),
+                b'Z' => self.insert_breakpoint(args),
+                b'z' => self.remove_breakpoint(args),
+                b'H' => self.set_thread(args, me),
+                b'T' => {
+                    let alive = parse_hex(args).is_some_and(|tid| self.thread_alive(tid, me));
+                    self.reply(if alive { "OK" } else { "E01" });
+                }
+                b'q' => self.query(rest_len),
+                b'c' => {
+                    if let Some(addr) = parse_hex(args) {
+                        frame.mepc = addr;
+                    }
+                    resume_all();
+                    return;
+                }
+                b's' => {
+                    if let Some(addr) = parse_hex(args) {
+                        frame.mepc = addr;
+                    }
+                    if self.start_step(frame) {
+                        return;
+                    }
+                    self.reply("E0e");
+                }
+                b'D' | b'k' => {
+                    for bp in self.breakpoints.iter_mut().filter_map(Option::take) {
+                        bp.remove();
+                    }
+                    self.attached = false;
+                    if command == b'D' {
+                        self.reply("OK");
+                    }
+                    resume_all();
+                    return;
+                }
+                _ => self.reply(""),
+            }
+        }
+    }
+
+    fn read_registers(&mut self, me: *mut TrapFrame) {
+        let regs = self.regs(self.selected, me);
+        self.tx.clear();
+        for index in 0..=PC_REGNUM {
+            self.tx.push_reg(regs.as_ref().and_then(|regs| regs.get(index)));
+        }
+        self.send();
+    }
+
+    fn write_registers(&mut self, me: *mut TrapFrame, len: usize) {
+        let Some(mut regs) = self.regs(self.selected, me) else {
+            return self.reply("E01");
+        };
+        let data = &self.rx[1..1 + len];
+        let mut ok = true;
+        for (index, digits) in data.chunks(16).take(PC_REGNUM + 1).enumerate() {
+            // 不可用的寄存器 GDB 原样送回 x，跳过
+            if digits.contains(&b'x') {
+                continue;
+            }
+            ok &= parse_reg(digits).is_some_and(|value| regs.set(index, value));
+        }
+        self.reply(if ok { "OK" } else { "E01" });
+    }
+
+    fn read_register(&mut self, args: &[u8], me: *mut TrapFrame) {
+        let Some(index) = parse_hex(args) else {
+            return self.reply("E01");
+        };
+        let regs = self.regs(self.selected, me);
+        self.tx.clear();
+        match index {
+            0..=PC_REGNUM => self.tx.push_reg(regs.and_then(|regs| regs.get(index))),
+            FIRST_FP_REGNUM..=LAST_FP_REGNUM => self.tx.push_reg(None),
+            FFLAGS_REGNUM..=FCSR_REGNUM => self.tx.push_str("xxxxxxxx"),
+            _ => self.tx.push_str("E01"),
+        }
+        self.send();
+    }
+
+    fn write_register(&mut self, args: &[u8], me: *mut TrapFrame) {
+        let parsed = args
+            .iter()
+            .position(|&b| b == b'=')
+            .and_then(|eq| Some((parse_hex(&args[..eq])?, parse_reg(&args[eq + 1..])?)));
+        let ok = parsed.is_some_and(|(index, value)| {
+            index <= PC_REGNUM && self.regs(self.selected, me).is_some_and(|mut regs| regs.set(index, value))
+        });
+        self.reply(if ok { "OK" } else { "E01" });
+    }
+
+    /// `m addr,len`
+    fn read_memory(&mut self, args: &[u8]) {
+        let Some((addr, len)) = parse_addr_len(args) else {
+            return self.reply("E01");
+        };
+        let len = len.min((PACKET_SIZE - 4) / 2);
+        if !accessible(addr, len, Access::Read) {
+            return self.reply("E0e");
+        }
+        self.tx.clear();
+        for i in 0..len {
+            let byte = unsafe { core::ptr::read_volatile((addr + i) as *const u8) };
+            self.tx.push_hex_bytes(&[byte]);
+        }
+        self.send();
+    }
+
+    /// `M addr,len:XX...`
+    fn write_memory(&mut self, len: usize) {
+        let data = &self.rx[1..1 + len];
+        let Some(colon) = data.iter().position(|&b| b == b':') else {
+            return self.reply("E01");
+        };
+        let Some((addr, count)) = parse_addr_len(&data[..colon]) else {
+            return self.reply("E01");
+        };
+        // 在 trap 所在的线程栈上运行，不复制数据，边解码边写入
+        let digits = &data[colon + 1..];
+        if digits.len() != count * 2 || !digits.iter().all(u8::is_ascii_hexdigit) {
+            return self.reply("E01");
+        }
+        if !accessible(addr, count, Access::Write) {
+            return self.reply("E0e");
+        }
+        for (i, pair) in digits.chunks(2).enumerate() {
+            let byte = parse_hex(pair).unwrap() as u8;
+            unsafe { core::ptr::write_volatile((addr + i) as *mut u8, byte) };
+        }
+        self.reply("OK");
+    }
+
+    /// `Z0,addr,kind`：只支持软件断点
+    fn insert_breakpoint(&mut self, args: &[u8]) {
+        let Some((addr, kind)) = args.strip_prefix(b"0,").and_then(parse_addr_len) else {
+            return self.reply("");
+        };
+        if self.breakpoints.iter().flatten().any(|bp| bp.addr == addr) {
+            return self.reply("OK");
+        }
+        let Some(slot) = self.breakpoints.iter_mut().find(|slot| slot.is_none()) else {
+            return self.reply("E0c");
+        };
+        match Breakpoint::insert(addr, kind) {
+            Some(bp) => {
+                *slot = Some(bp);
+                self.reply("OK");
+            }
+            None => self.reply("E0e"),
+        }
+    }
+
+    /// `z0,addr,kind`
+    fn remove_breakpoint(&mut self, args: &[u8]) {
+        let Some((addr, _)) = args.strip_prefix(b"0,").and_then(parse_addr_len) else {
+            return self.reply("");
+        };
+        if let Some(bp) = self.breakpoints.iter_mut().find(|slot| slot.is_some_and(|bp| bp.addr == addr)) {
+            bp.take().unwrap().remove();
+        }
+        self.reply("OK");
+    }
+
+    /// `Hg<tid>` 选择读写寄存器的线程；`Hc` 只回复 OK（继续运行时所有线程一起运行）
+    fn set_thread(&mut self, args: &[u8], me: *mut TrapFrame) {
+        let Some((&op, tid)) = args.split_first() else {
+            return self.reply("E01");
+        };
+        let tid = match tid {
+            b"-1" | b"0" => 0,
+            tid => match parse_hex(tid) {
+                Some(tid) if self.thread_alive(tid, me) => tid,
+                _ => return self.reply("E01"),
+            },
+        };
+        if op == b'g' {
+            self.selected = tid;
+        }
+        self.reply("OK");
+    }
+
+    /// 开始单步：在下一条可能执行的指令上放临时断点，关中断后只让当前 hart 继续运行
+    fn start_step(&mut self, frame: &mut TrapFrame) -> bool {
+        let Some(next) = step::next_pcs(frame) else {
+            return false;
+        };
+        let mut targets = [None; 2];
+        for (slot, addr) in targets.iter_mut().zip(next) {
+            // 已经有断点的地址不需要临时断点
+            let Some(addr) = addr.filter(|&addr| !self.breakpoints.iter().flatten().any(|bp| bp.addr == addr)) else {
+                continue;
+            };
+            let Some(low) = read_u16(addr) else {
+                continue;
+            };
+            *slot = Breakpoint::insert(addr, step::insn_len(low));
+        }
+        self.step = Some(Step {
+            hart_id: hart::id(),
+            targets,
+            mpie: frame.mstatus & MSTATUS_MPIE,
+        });
+        frame.mstatus &= !MSTATUS_MPIE;
+        // 其它 hart 保持停住，OWNER 仍由本 hart 持有
+        unsafe { asm!("fence.i") };
+        true
+    }
+
+    fn query(&mut self, len: usize) {
+        let mut query = [0u8; 128];
+        let n = len.min(query.len());
+        query[..n].copy_from_slice(&self.rx[1..1 + n]);
+        let query = &query[..n];
+        if query.starts_with(b"Supported") {
+            self.tx.clear();
+            let _ = write!(self.tx, "PacketSize={:x};qXfer:features:read+", PACKET_SIZE);
+            self.send();
+        } else if query == b"C" {
+            self.tx.clear();
+            let _ = write!(self.tx, "QC{:x}", hart_tid(hart::id()));
+            self.send();
+        } else if query == b"Attached" {
+            self.reply("1");
+        } else if query == b"fThreadInfo" {
+            self.thread_list();
+        } else if query == b"sThreadInfo" {
+            self.reply("l");
+        } else if let Some(tid) = query.strip_prefix(b"ThreadExtraInfo,") {
+            match parse_hex(tid) {
+                Some(tid) => self.thread_extra_info(tid),
+                None => self.reply("E01"),
+            }
+        } else if let Some(rest) = query.strip_prefix(b"Xfer:features:read:target.xml:") {
+            match parse_addr_len(rest) {
+                Some((offset, len)) => self.read_target_xml(offset, len),
+                None => self.reply("E01"),
+            }
+        } else {
+            self.reply("");
+        }
+    }
+
+    /// `qfThreadInfo`：一次列出所有线程，`qsThreadInfo` 回复结束
+    fn thread_list(&mut self) {
+        self.tx.clear();
+        self.tx.push_str("m");
+        let mut first = true;
+        let mut push = |tx: &mut Reply, tid: usize| {
+            if !first {
+                tx.push_str(",");
+            }
+            first = false;
+            tx.push_hex(tid);
+        };
+        for hart_id in stopped_harts() {
+            let tid = hart_tid(hart_id);
+            if tid >= IDLE_TID_BASE {
+                push(&mut self.tx, tid);
+            }
+        }
+        match thread::try_sched() {
+            Some(s) => {
+                for thread in s.threads().filter(|t| t.state != ThreadState::Terminated) {
+                    if self.tx.remaining() < 20 {
+                        break;
+                    }
+                    push(&mut self.tx, thread.id.slot() + 1);
+                }
+            }
+            // 调度器锁被停住的代码持有：只列出停住的 hart 上的线程
+            None => {
+                for hart_id in stopped_harts() {
+                    let tid = hart_tid(hart_id);
+                    if tid < IDLE_TID_BASE {
+                        push(&mut self.tx, tid);
+                    }
+                }
+            }
+        }
+        self.send();
+    }
+
+    /// `qThreadExtraInfo`：线程名、状态和所在的 hart
+    fn thread_extra_info(&mut self, tid: usize) {
+        self.tx.clear();
+        let mut out = HexWriter(&mut self.tx);
+        if let Some(hart_id) = tid.checked_sub(IDLE_TID_BASE) {
+            let _ = write!(out, "idle hart {}", hart_id);
+        } else {
+            let on_hart = (0..hart::count()).find(|&h| hart_tid(h) == tid);
+            match thread::try_sched() {
+                Some(s) => match s.threads().find(|t| t.id.slot() + 1 == tid) {
+                    Some(thread) => {
+                        let _ = write!(out, "{} {} {:?}", thread.id, thread.name.as_deref().unwrap_or("-"), thread.state);
+                        if thread.user.is_some() {
+                            let _ = write!(out, " user");
+                        }
+                    }
+                    None => {
+                        let _ = write!(out, "exited");
+                    }
+                },
+                None => {
+                    let _ = write!(out, "scheduler locked");
+                }
+            }
+            if let Some(hart_id) = on_hart {
+                let _ = write!(out, " on hart {}", hart_id);
+            }
+        }
+        self.send();
+    }
+
+    /// `qXfer:features:read:target.xml:offset,len`
+    fn read_target_xml(&mut self, offset: usize, len: usize) {
+        let xml = TARGET_XML.as_bytes();
+        let start = offset.min(xml.len());
+        let end = start + len.min(xml.len() - start).min(PACKET_SIZE - 1);
+        self.tx.clear();
+        self.tx.push_str(if end == xml.len() { "l" } else { "m" });
+        self.tx.push_bytes(&xml[start..end]);
+        self.send();
+    }
+}
+
+/// 解析 `addr,len`
+fn parse_addr_len(args: &[u8]) -> Option<(usize, usize)> {
+    let comma = args.iter().position(|&b| b == b',')?;
+    Some((parse_hex(&args[..comma])?, parse_hex(&args[comma + 1..])?))
+}
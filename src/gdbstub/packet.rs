@@ -0,0 +1,199 @@
+//! 📦 GDB 远程串行协议的数据包收发
+//!
+//! 数据包格式为 `$<数据>#<两位十六进制校验和>`，校验和是数据所有字节之和的低 8 位：
+//! - 收到校验正确的包回复 `+`，否则回复 `-` 让对方重发
+//! - 发出的包等待对方回复 `+`，收到 `-` 时重发
+//! - 不支持 no-ack 模式和二进制（`X`）包，回复中不使用游程编码
+
+use core::fmt;
+
+use crate::console::Uart;
+
+/// 数据包最大长度（qSupported 中以十六进制告知 GDB）
+pub const PACKET_SIZE: usize = 4096;
+
+/// GDB 在目标运行时发送的中断请求（Ctrl-C）
+pub const INTERRUPT: u8 = 0x03;
+
+/// 一条串口连接
+pub struct Connection {
+    uart: Uart,
+}
+
+impl Connection {
+    pub const fn new(base: usize) -> Self {
+        Self { uart: Uart::at(base) }
+    }
+
+    /// 读取一个字节，没有输入时忙等（在 trap 中运行，中断已关闭）
+    fn read_byte(&self) -> u8 {
+        loop {
+            if let Some(byte) = self.uart.read_byte() {
+                return byte;
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// 不等待地读取一个字节
+    pub fn poll_byte(&self) -> Option<u8> {
+        self.uart.read_byte()
+    }
+
+    /// 读取一个数据包到 `buf`，返回数据长度
+    ///
+    /// 说明：包之外的字节（多余的 `+`、Ctrl-C 等）被丢弃，超出 `buf` 的数据被截断
+    pub fn read_packet(&self, buf: &mut [u8]) -> usize {
+        loop {
+            while self.read_byte() != b'$' {}
+            let mut len = 0;
+            let mut sum = 0u8;
+            loop {
+                let byte = self.read_byte();
+                if byte == b'#' {
+                    break;
+                }
+                sum = sum.wrapping_add(byte);
+                if len < buf.len() {
+                    buf[len] = byte;
+                    len += 1;
+                }
+            }
+            let expected = [self.read_byte(), self.read_byte()];
+            if parse_hex(&expected) == Some(sum as usize) {
+                self.uart.write_byte(b'+');
+                return len;
+            }
+            self.uart.write_byte(b'-');
+        }
+    }
+
+    /// 发送一个数据包并等待确认
+    pub fn write_packet(&self, data: &[u8]) {
+        let sum = data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte));
+        loop {
+            self.uart.write_byte(b'$');
+            self.uart.write_bytes(data);
+            self.uart.write_byte(b'#');
+            self.uart.write_byte(HEX_DIGITS[(sum >> 4) as usize]);
+            self.uart.write_byte(HEX_DIGITS[(sum & 0xf) as usize]);
+            loop {
+                match self.read_byte() {
+                    b'+' => return,
+                    b'-' => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// 待发送的回复
+pub struct Reply {
+    buf: [u8; PACKET_SIZE],
+    len: usize,
+}
+
+impl Reply {
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; PACKET_SIZE],
+            len: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// 追加原始字节，缓冲区满时截断
+    pub fn push_bytes(&mut self, bytes: &[u8]) {
+        let n = bytes.len().min(PACKET_SIZE - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+    }
+
+    pub fn push_str(&mut self, s: &str) {
+        self.push_bytes(s.as_bytes());
+    }
+
+    /// 按两位十六进制追加每个字节
+    pub fn push_hex_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push_bytes(&[HEX_DIGITS[(byte >> 4) as usize], HEX_DIGITS[(byte & 0xf) as usize]]);
+        }
+    }
+
+    /// 追加一个 64 位寄存器的值（目标字节序，即小端）；None 表示不可用，输出 `x`
+    pub fn push_reg(&mut self, value: Option<usize>) {
+        match value {
+            Some(value) => self.push_hex_bytes(&(value as u64).to_le_bytes()),
+            None => self.push_bytes(b"xxxxxxxxxxxxxxxx"),
+        }
+    }
+
+    /// 追加一个数字（大端十六进制，不补零），用于线程 id 等
+    pub fn push_hex(&mut self, value: usize) {
+        let _ = fmt::Write::write_fmt(self, format_args!("{:x}", value));
+    }
+
+    /// 剩余空间（字节）
+    pub fn remaining(&self) -> usize {
+        PACKET_SIZE - self.len
+    }
+}
+
+impl fmt::Write for Reply {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
+
+/// 把格式化输出按十六进制编码后写入 `Reply`（qThreadExtraInfo 等回复文本的包）
+pub struct HexWriter<'a>(pub &'a mut Reply);
+
+impl fmt::Write for HexWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.push_hex_bytes(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// 解析十六进制数（大端，不带前缀）
+pub fn parse_hex(digits: &[u8]) -> Option<usize> {
+    if digits.is_empty() || digits.len() > 16 {
+        return None;
+    }
+    digits.iter().try_fold(0usize, |value, &digit| {
+        let nibble = (digit as char).to_digit(16)?;
+        Some((value << 4) | nibble as usize)
+    })
+}
+
+/// 解析按字节编码的十六进制数据到 `out`，返回字节数
+pub fn decode_hex(digits: &[u8], out: &mut [u8]) -> Option<usize> {
+    if digits.len() % 2 != 0 || digits.len() / 2 > out.len() {
+        return None;
+    }
+    for (i, pair) in digits.chunks(2).enumerate() {
+        out[i] = parse_hex(pair)? as u8;
+    }
+    Some(digits.len() / 2)
+}
+
+/// 解析小端十六进制编码的寄存器值（`G`/`P` 包）
+pub fn parse_reg(digits: &[u8]) -> Option<usize> {
+    let mut bytes = [0u8; 8];
+    if digits.len() != 16 {
+        return None;
+    }
+    decode_hex(digits, &mut bytes)?;
+    Some(u64::from_le_bytes(bytes) as usize)
+}
@@ -0,0 +1,94 @@
+//! 👣 软件单步
+//!
+//! 机器模式没有硬件单步，单步时解码当前指令，算出它执行后可能到达的地址，
+//! 在这些地址上放临时断点后继续运行：
+//! - 分支指令（含 c.beqz/c.bnez）：跳转目标和下一条指令两个地址
+//! - jal/jalr（含 c.j/c.jr/c.jalr）：跳转目标
+//! - 其它指令：下一条指令
+//!
+//! mret 等改变特权级的指令不特殊处理（只在 trap 汇编中使用）
+
+use super::read_u16;
+use crate::trap::TrapFrame;
+
+/// 指令长度：低两位为 11 的是 32 位指令，否则是 16 位压缩指令
+pub fn insn_len(low: u16) -> usize {
+    if low & 3 == 3 { 4 } else { 2 }
+}
+
+/// 取 `value` 的第 `lo` 到 `hi` 位（含两端）
+fn bits(value: u32, hi: u32, lo: u32) -> usize {
+    ((value >> lo) & ((1 << (hi - lo + 1)) - 1)) as usize
+}
+
+/// 把低 `width` 位作为有符号数扩展
+fn sign_extend(value: usize, width: u32) -> usize {
+    let shift = usize::BITS - width;
+    (((value << shift) as isize) >> shift) as usize
+}
+
+/// 当前指令执行后可能到达的地址；读不到指令时返回 None
+pub fn next_pcs(frame: &TrapFrame) -> Option<[Option<usize>; 2]> {
+    let pc = frame.mepc;
+    let low = read_u16(pc)?;
+    if insn_len(low) == 2 {
+        return Some(next_pcs_compressed(frame, low as u32));
+    }
+    let insn = low as u32 | (read_u16(pc + 2)? as u32) << 16;
+    let next = pc.wrapping_add(4);
+    let reg = |index: usize| frame.reg(index).unwrap_or(0);
+    Some(match insn & 0x7f {
+        // jal
+        0x6f => {
+            let imm = bits(insn, 31, 31) << 20 | bits(insn, 19, 12) << 12 | bits(insn, 20, 20) << 11 | bits(insn, 30, 21) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 21))), None]
+        }
+        // jalr
+        0x67 => {
+            let target = reg(bits(insn, 19, 15)).wrapping_add(sign_extend(bits(insn, 31, 20), 12)) & !1;
+            [Some(target), None]
+        }
+        // beq/bne/blt/bge/bltu/bgeu
+        0x63 => {
+            let imm = bits(insn, 31, 31) << 12 | bits(insn, 7, 7) << 11 | bits(insn, 30, 25) << 5 | bits(insn, 11, 8) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 13))), Some(next)]
+        }
+        _ => [Some(next), None],
+    })
+}
+
+/// 16 位压缩指令执行后可能到达的地址
+fn next_pcs_compressed(frame: &TrapFrame, insn: u32) -> [Option<usize>; 2] {
+    let pc = frame.mepc;
+    let next = pc.wrapping_add(2);
+    let quadrant = insn & 3;
+    let funct3 = bits(insn, 15, 13);
+    match (quadrant, funct3) {
+        // c.j（RV64 中 funct3 = 001 是 c.addiw，不是 c.jal）
+        (1, 0b101) => {
+            let imm = bits(insn, 12, 12) << 11
+                | bits(insn, 8, 8) << 10
+                | bits(insn, 10, 9) << 8
+                | bits(insn, 6, 6) << 7
+                | bits(insn, 7, 7) << 6
+                | bits(insn, 2, 2) << 5
+                | bits(insn, 11, 11) << 4
+                | bits(insn, 5, 3) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 12))), None]
+        }
+        // c.beqz/c.bnez
+        (1, 0b110) | (1, 0b111) => {
+            let imm = bits(insn, 12, 12) << 8
+                | bits(insn, 6, 5) << 6
+                | bits(insn, 2, 2) << 5
+                | bits(insn, 11, 10) << 3
+                | bits(insn, 4, 3) << 1;
+            [Some(pc.wrapping_add(sign_extend(imm, 9))), Some(next)]
+        }
+        // c.jr/c.jalr：rs2 为 0、rs1 不为 0（否则是 c.mv/c.add/c.ebreak）
+        (2, 0b100) if bits(insn, 6, 2) == 0 && bits(insn, 11, 7) != 0 => {
+            [Some(frame.reg(bits(insn, 11, 7)).unwrap_or(0) & !1), None]
+        }
+        _ => [Some(next), None],
+    }
+}
@@ -0,0 +1,202 @@
+//! 🧠 多核（hart）模块
+//!
+//! 负责多 hart 启动和每个 hart 的私有数据：
+//! - `_start` 中 hart 0 负责全局初始化，其它 hart 在释放标志上等待
+//! - 每个 hart 使用 `memory.x` 中 `.stack` 段切出的独立启动栈
+//! - 每个 hart 拥有一份 `HartLocal`，`mscratch` 指向它，供 trap 入口使用
+
+use core::arch::asm;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::dtb;
+
+/// 支持的最大 hart 数量（必须与 entry.asm 中的 MAX_HARTS 保持一致）
+pub const MAX_HARTS: usize = 8;
+
+/// 每个 hart 的启动栈大小（必须与 entry.asm / memory.x 保持一致）
+pub const HART_STACK_SIZE: usize = 64 * 1024;
+
+/// 表示“没有线程”的哨兵值
+const NO_THREAD: usize = usize::MAX;
+
+/// 🧠 每个 hart 的私有数据
+///
+/// 说明：
+/// - `trap_scratch` 位于结构体开头，trap 入口可通过 `mscratch` 直接访问：
+///   `[0]` 暂存寄存器，`[1]` trap 发生时的 sp，`[2]` 本 hart 异常栈的栈顶，
+///   `[3]`/`[4]` 当前用户线程的内核栈顶和内核 tp（从 U-mode trap 进来时切换过去）
+/// - 其余字段只由所属 hart 修改，使用原子类型只是为了能放进全局静态
+#[repr(C)]
+pub struct HartLocal {
+    /// trap 入口使用的暂存区
+    pub trap_scratch: [AtomicUsize; 5],
+    /// hart id
+    id: usize,
+    /// 当前在该 hart 上运行的线程 id
+    current_thread: AtomicUsize,
+    /// 当前线程栈保护区的 (起始地址, 大小)，没有线程运行时为 (0, 0)
+    stack_guard: [AtomicUsize; 2],
+    /// 计时器状态：最近一次写入 mtimecmp 的触发时间
+    next_timer: AtomicUsize,
+    /// 是否已经启动完成
+    online: AtomicBool,
+}
+
+impl HartLocal {
+    const fn new(id: usize) -> Self {
+        Self {
+            trap_scratch: [const { AtomicUsize::new(0) }; 5],
+            id,
+            current_thread: AtomicUsize::new(NO_THREAD),
+            stack_guard: [const { AtomicUsize::new(0) }; 2],
+            next_timer: AtomicUsize::new(0),
+            online: AtomicBool::new(false),
+        }
+    }
+
+    /// hart id
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// 当前在该 hart 上运行的线程 id
+    pub fn current_thread(&self) -> Option<usize> {
+        match self.current_thread.load(Ordering::Relaxed) {
+            NO_THREAD => None,
+            id => Some(id),
+        }
+    }
+
+    /// 设置当前线程
+    pub fn set_current_thread(&self, id: Option<usize>) {
+        self.current_thread
+            .store(id.unwrap_or(NO_THREAD), Ordering::Relaxed);
+    }
+
+    /// 当前线程栈保护区 `[base, base + size)`；没有线程运行时为 None
+    pub fn stack_guard(&self) -> Option<(usize, usize)> {
+        let base = self.stack_guard[0].load(Ordering::Relaxed);
+        let size = self.stack_guard[1].load(Ordering::Relaxed);
+        (size != 0).then_some((base, size))
+    }
+
+    /// 记录当前线程栈保护区
+    pub fn set_stack_guard(&self, guard: Option<(usize, usize)>) {
+        let (base, size) = guard.unwrap_or((0, 0));
+        self.stack_guard[0].store(base, Ordering::Relaxed);
+        self.stack_guard[1].store(size, Ordering::Relaxed);
+    }
+
+    /// 设置从 U-mode trap 进来时使用的内核栈顶和内核 tp（当前线程不是用户线程时为 0）
+    pub fn set_user_trap_context(&self, kernel_sp: usize, kernel_tp: usize) {
+        self.trap_scratch[3].store(kernel_sp, Ordering::Relaxed);
+        self.trap_scratch[4].store(kernel_tp, Ordering::Relaxed);
+    }
+
+    /// 最近一次设置的计时器触发时间（tick）
+    pub fn next_timer(&self) -> usize {
+        self.next_timer.load(Ordering::Relaxed)
+    }
+
+    /// 记录计时器触发时间
+    pub fn set_next_timer(&self, time: usize) {
+        self.next_timer.store(time, Ordering::Relaxed);
+    }
+
+    /// 该 hart 是否已上线
+    pub fn is_online(&self) -> bool {
+        self.online.load(Ordering::Acquire)
+    }
+}
+
+static HARTS: [HartLocal; MAX_HARTS] = {
+    let mut i = 0;
+    let mut harts = [const { HartLocal::new(0) }; MAX_HARTS];
+    while i < MAX_HARTS {
+        harts[i] = HartLocal::new(i);
+        i += 1;
+    }
+    harts
+};
+
+/// 从核被释放后要执行的入口函数（0 表示未设置）
+static SECONDARY_ENTRY: AtomicUsize = AtomicUsize::new(0);
+
+/// 读取当前 hart id（mhartid）
+pub fn id() -> usize {
+    let hart_id: usize;
+    unsafe {
+        asm!("csrr {0}, mhartid", out(reg) hart_id);
+    }
+    hart_id
+}
+
+/// 系统中可用的 hart 数量
+///
+/// 说明：
+/// - 取自设备树 `/cpus` 下的 cpu 节点数量
+/// - 超出 `MAX_HARTS` 的 hart 在启动时直接停住，不计入
+pub fn count() -> usize {
+    dtb::platform().hart_count.clamp(1, MAX_HARTS)
+}
+
+/// 当前 hart 的私有数据
+pub fn local() -> &'static HartLocal {
+    &HARTS[id()]
+}
+
+/// 指定 hart 的私有数据
+pub fn get(hart_id: usize) -> Option<&'static HartLocal> {
+    HARTS.get(hart_id)
+}
+
+/// 把 `mscratch` 指向当前 hart 的私有数据
+///
+/// 说明：每个 hart 在开启 trap 之前都需要调用一次（由 `trap::init` 负责）
+pub fn init_local() {
+    let local = local() as *const HartLocal as usize;
+    unsafe {
+        asm!("csrw mscratch, {0}", in(reg) local);
+    }
+}
+
+/// 🚀 释放所有从核
+///
+/// 说明：
+/// - 只能由 hart 0 在完成全局初始化（日志、堆、设备树等）后调用
+/// - 从核会在自己的启动栈上执行 `entry(hart_id)`
+/// - 重复调用无效
+pub fn start_secondaries(entry: fn(usize) -> !) {
+    if SECONDARY_ENTRY
+        .compare_exchange(0, entry as usize, Ordering::AcqRel, Ordering::Relaxed)
+        .is_err()
+    {
+        return;
+    }
+    local().online.store(true, Ordering::Release);
+    release_flag().store(1, Ordering::Release);
+}
+
+/// 已上线的 hart 数量
+pub fn online_count() -> usize {
+    HARTS.iter().filter(|hart| hart.is_online()).count()
+}
+
+/// entry.asm 中定义的释放标志
+fn release_flag() -> &'static AtomicUsize {
+    unsafe extern "C" {
+        static mut __hart_release: usize;
+    }
+    unsafe { AtomicUsize::from_ptr(&raw mut __hart_release) }
+}
+
+/// 从核的 Rust 入口（由 entry.asm 在释放标志置位后调用）
+#[unsafe(no_mangle)]
+pub extern "C" fn secondary_main(hart_id: usize) -> ! {
+    init_local();
+    HARTS[hart_id].online.store(true, Ordering::Release);
+
+    let entry = SECONDARY_ENTRY.load(Ordering::Acquire);
+    let entry = unsafe { core::mem::transmute::<usize, fn(usize) -> !>(entry) };
+    entry(hart_id)
+}
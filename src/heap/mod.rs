@@ -1,12 +1,53 @@
 //! 🌱 堆内存分配器模块
 //!
 //! 提供基于 buddy_system_allocator 的堆内存管理功能
+//!
+//! 堆由两部分组成：
+//! - BSS 段中预留的 1MB 静态空间（保证没有设备树时也能工作）
+//! - 设备树描述的内存中，内核镜像之后的空闲内存（避开设备树本身和保留区域）
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 use buddy_system_allocator::LockedHeap;
+use log::info;
+
+use crate::{dtb, trap};
+
+/// 关中断的堆分配器
+///
+/// 说明：线程可能在任意时刻被计时器中断抢占，如果在持有堆锁时被切走，
+/// 同一个 hart 上的其它线程（或关中断持有调度器锁的代码）再分配内存就会永远自旋，
+/// 所以分配和释放期间关闭当前 hart 的中断
+struct IrqSafeHeap(LockedHeap);
+
+unsafe impl GlobalAlloc for IrqSafeHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let irq_enabled = trap::disable_interrupts();
+        let ptr = unsafe { self.0.alloc(layout) };
+        trap::restore_interrupts(irq_enabled);
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let irq_enabled = trap::disable_interrupts();
+        unsafe { self.0.dealloc(ptr, layout) };
+        trap::restore_interrupts(irq_enabled);
+    }
+}
+
+impl Deref for IrqSafeHeap {
+    type Target = LockedHeap;
+
+    fn deref(&self) -> &LockedHeap {
+        &self.0
+    }
+}
 
 /// 全局堆分配器
 #[global_allocator]
-static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+static HEAP_ALLOCATOR: IrqSafeHeap = IrqSafeHeap(LockedHeap::empty());
 
 /// 堆内存大小：1MB
 static HEAP_SIZE: usize = 1024 * 1024;
@@ -14,15 +55,83 @@ static HEAP_SIZE: usize = 1024 * 1024;
 /// 堆内存空间（存储在 BSS 段中）
 static mut HEAP_SPACE: [u8; HEAP_SIZE] = [0; HEAP_SIZE];
 
+/// 额外堆区域（来自设备树的空闲内存），start == end 表示没有
+static EXTRA_START: AtomicUsize = AtomicUsize::new(0);
+static EXTRA_END: AtomicUsize = AtomicUsize::new(0);
+
 /// 🌱 初始化堆分配器
 ///
-/// 将预分配的堆内存空间注册到全局分配器中
+/// 将预分配的堆内存空间和设备树中的空闲内存注册到全局分配器中
 pub fn init_heap() {
     unsafe {
         HEAP_ALLOCATOR
             .lock()
             .init(core::ptr::addr_of!(HEAP_SPACE) as usize, HEAP_SIZE);
     }
+
+    if let Some((start, end)) = free_memory() {
+        unsafe { HEAP_ALLOCATOR.lock().add_to_heap(start, end) };
+        EXTRA_START.store(start, Ordering::Relaxed);
+        EXTRA_END.store(end, Ordering::Relaxed);
+        info!("🌱 堆扩展: 0x{:08x} - 0x{:08x} ({} KB)", start, end, (end - start) / 1024);
+    }
+}
+
+/// 判断地址是否属于堆内存
+pub fn contains(addr: usize) -> bool {
+    let space = core::ptr::addr_of!(HEAP_SPACE) as usize;
+    let extra = EXTRA_START.load(Ordering::Relaxed)..EXTRA_END.load(Ordering::Relaxed);
+    (space..space + HEAP_SIZE).contains(&addr) || extra.contains(&addr)
+}
+
+/// 堆的使用情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    /// 堆的总大小（字节）
+    pub total: usize,
+    /// 调用方申请的字节数
+    pub user: usize,
+    /// 实际分配的字节数（按伙伴系统的块大小取整）
+    pub actual: usize,
+}
+
+/// 📊 当前堆的使用情况
+pub fn stats() -> HeapStats {
+    let irq_enabled = trap::disable_interrupts();
+    let stats = {
+        let heap = HEAP_ALLOCATOR.lock();
+        HeapStats {
+            total: heap.stats_total_bytes(),
+            user: heap.stats_alloc_user(),
+            actual: heap.stats_alloc_actual(),
+        }
+    };
+    trap::restore_interrupts(irq_enabled);
+    stats
+}
+
+/// 计算内核镜像之后可用的空闲内存 `[start, end)`
+///
+/// 说明：
+/// - 只使用包含内核镜像的那块内存区域
+/// - 区域在遇到设备树或 memreserve 保留区域时截止
+fn free_memory() -> Option<(usize, usize)> {
+    unsafe extern "C" {
+        static __KERNEL_END: u8;
+    }
+    let start = unsafe { &__KERNEL_END as *const u8 as usize };
+    let platform = dtb::platform();
+    let region = platform.memory_region_of(start)?;
+
+    let mut end = region.end();
+    for busy in platform.dtb.iter().chain(platform.reserved_regions()) {
+        if busy.end() > start && busy.base < end {
+            end = busy.base.max(start);
+        }
+    }
+    // 向下对齐到 4KB，太小的区域没有意义
+    let end = end & !0xFFF;
+    (end > start + 0x1000).then_some((start, end))
 }
 
 /// 🚨 内存分配错误处理器
@@ -0,0 +1,243 @@
+//! 📨 核间中断（IPI）模块
+//!
+//! 基于 CLINT 的 msip 寄存器实现核间软件中断：
+//! - 向目标 hart 的 msip 写 1 触发机器软件中断，目标 hart 在中断处理中写 0 清除
+//! - 每个 hart 有一组待处理消息位，随 IPI 一起送达：
+//!   - `RESCHEDULE`：唤醒 wfi 中的 hart，让它重新检查就绪队列；线程系统注册的处理函数可以抢占当前线程
+//!   - `CALL`：在目标 hart 上执行一个函数（`run_on`）
+//!   - `FENCE`：在目标 hart 上执行 `fence.i` + `sfence.vma`（`remote_fence`）
+//!   - `STOP`：让目标 hart 关中断并永久停住（panic 时使用）
+//! - 内置消息处理完之后，再调用通过 `set_soft_interrupt_handler` 注册的处理函数
+
+use core::arch::asm;
+use core::ptr::write_volatile;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::hart::{self, MAX_HARTS};
+use crate::{dtb, trap};
+
+/// msip 寄存器相对 CLINT 基地址的偏移，每个 hart 占用 4 字节
+const MSIP_OFFSET: usize = 0x0;
+
+/// 消息位
+const MSG_RESCHEDULE: usize = 1 << 0;
+const MSG_CALL: usize = 1 << 1;
+const MSG_FENCE: usize = 1 << 2;
+const MSG_STOP: usize = 1 << 3;
+/// 可以在 `run_on`/`remote_fence` 的等待循环中处理的消息（不切换线程）
+const MSG_SYNC: usize = MSG_CALL | MSG_FENCE | MSG_STOP;
+
+/// 每个 hart 待处理的消息位
+static PENDING: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+/// `run_on` 的邮箱：目标 hart 要执行的函数指针（0 表示空闲）
+static CALL_SLOT: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+/// `remote_fence` 的请求序号和完成序号
+static FENCE_REQUEST: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+static FENCE_DONE: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+
+/// 用户注册的软件中断处理函数（0 表示未设置）
+static SOFT_INTERRUPT_HANDLER: AtomicUsize = AtomicUsize::new(0);
+/// 收到重新调度消息时调用的处理函数（由线程系统注册，0 表示未设置）
+static RESCHEDULE_HANDLER: AtomicUsize = AtomicUsize::new(0);
+
+/// IPI 调用错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpiError {
+    /// hart id 超出范围
+    InvalidHart(usize),
+    /// 目标 hart 还没有上线
+    Offline(usize),
+}
+
+/// 指定 hart 的 msip 寄存器地址
+fn msip_addr(hart_id: usize) -> usize {
+    dtb::platform().clint + MSIP_OFFSET + hart_id * core::mem::size_of::<u32>()
+}
+
+/// 📨 向 `hart_mask` 中的每个 hart 发送核间中断
+///
+/// 说明：bit i 对应 hart i，超出 `hart::count()` 的位被忽略
+pub fn send_ipi(hart_mask: usize) {
+    for hart_id in 0..hart::count() {
+        if hart_mask & (1 << hart_id) != 0 {
+            unsafe { write_volatile(msip_addr(hart_id) as *mut u32, 1) };
+        }
+    }
+}
+
+/// 向 `hart_mask` 中的每个 hart 投递消息并发送 IPI
+fn post(hart_mask: usize, message: usize) {
+    for (hart_id, pending) in PENDING.iter().enumerate().take(hart::count()) {
+        if hart_mask & (1 << hart_id) != 0 {
+            pending.fetch_or(message, Ordering::Release);
+        }
+    }
+    send_ipi(hart_mask);
+}
+
+/// 请求 `hart_mask` 中的 hart 重新调度（唤醒 wfi）
+pub fn send_reschedule(hart_mask: usize) {
+    post(hart_mask, MSG_RESCHEDULE);
+}
+
+/// 设置软件中断处理函数
+///
+/// 说明：
+/// - 每次收到 IPI 时，在内置消息处理完之后调用
+/// - 处理函数运行在中断上下文中，必须是 `fn()` 类型（不捕获环境）
+pub fn set_soft_interrupt_handler(handler: fn()) {
+    SOFT_INTERRUPT_HANDLER.store(handler as usize, Ordering::Release);
+}
+
+/// 设置重新调度消息的处理函数（线程系统用它实现抢占）
+pub(crate) fn set_reschedule_handler(handler: fn()) {
+    RESCHEDULE_HANDLER.store(handler as usize, Ordering::Release);
+}
+
+/// 检查目标 hart 是否可以接收调用
+fn check_target(hart_id: usize) -> Result<(), IpiError> {
+    match hart::get(hart_id) {
+        None => Err(IpiError::InvalidHart(hart_id)),
+        Some(local) if hart_id != hart::id() && !local.is_online() => Err(IpiError::Offline(hart_id)),
+        Some(_) => Ok(()),
+    }
+}
+
+/// 🎯 在指定 hart 上执行 `f`，并等待执行完成
+///
+/// 说明：
+/// - 目标是当前 hart 时直接调用
+/// - `f` 运行在目标 hart 的中断上下文中，应当简短且不能阻塞
+/// - 等待期间会处理发给自己的消息，避免两个 hart 互相 `run_on` 时死锁
+pub fn run_on(hart_id: usize, f: fn()) -> Result<(), IpiError> {
+    check_target(hart_id)?;
+    if hart_id == hart::id() {
+        f();
+        return Ok(());
+    }
+
+    // 占用目标 hart 的邮箱
+    while CALL_SLOT[hart_id]
+        .compare_exchange(0, f as usize, Ordering::AcqRel, Ordering::Relaxed)
+        .is_err()
+    {
+        handle_pending(MSG_SYNC);
+        core::hint::spin_loop();
+    }
+    post(1 << hart_id, MSG_CALL);
+
+    // 目标 hart 执行完毕后会清空邮箱
+    while CALL_SLOT[hart_id].load(Ordering::Acquire) != 0 {
+        handle_pending(MSG_SYNC);
+        core::hint::spin_loop();
+    }
+    Ok(())
+}
+
+/// 🧹 在 `hart_mask` 中的所有 hart 上执行指令/地址转换屏障，并等待全部完成
+///
+/// 说明：
+/// - 用于修改代码（例如打断点）或页表后的跨核同步（TLB shootdown）
+/// - 掩码中包含当前 hart 时直接在本地执行；未上线的 hart 被跳过
+pub fn remote_fence(hart_mask: usize) {
+    let me = hart::id();
+    let mut waiting = [0usize; MAX_HARTS];
+    let mut targets = 0usize;
+    for hart_id in 0..hart::count() {
+        if hart_mask & (1 << hart_id) == 0 {
+            continue;
+        }
+        if hart_id == me {
+            local_fence();
+        } else if hart::get(hart_id).is_some_and(|h| h.is_online()) {
+            waiting[hart_id] = FENCE_REQUEST[hart_id].fetch_add(1, Ordering::AcqRel) + 1;
+            targets |= 1 << hart_id;
+        }
+    }
+    post(targets, MSG_FENCE);
+
+    for (hart_id, &request) in waiting.iter().enumerate() {
+        if targets & (1 << hart_id) == 0 {
+            continue;
+        }
+        while FENCE_DONE[hart_id].load(Ordering::Acquire) < request {
+            handle_pending(MSG_SYNC);
+            core::hint::spin_loop();
+        }
+    }
+}
+
+/// 🛑 让除当前 hart 以外的所有 hart 停住（panic 时使用，不等待）
+pub fn stop_others() {
+    post(others_mask(), MSG_STOP);
+}
+
+/// 所有 hart 的掩码（不含当前 hart）
+pub fn others_mask() -> usize {
+    ((1usize << hart::count()) - 1) & !(1 << hart::id())
+}
+
+/// 清除当前 hart 挂起的核间中断
+pub fn clear() {
+    unsafe { write_volatile(msip_addr(hart::id()) as *mut u32, 0) };
+}
+
+/// 机器软件中断处理（由 trap_handler 调用）
+pub(crate) fn handle_interrupt() {
+    // 先清除挂起位再取消息，保证清除之后投递的消息会触发新的中断
+    clear();
+    let messages = handle_pending(MSG_SYNC | MSG_RESCHEDULE);
+
+    // 重新调度放在最后：处理函数可能切换到其它线程，很久之后才返回
+    if messages & MSG_RESCHEDULE != 0 {
+        let handler = RESCHEDULE_HANDLER.load(Ordering::Acquire);
+        if handler != 0 {
+            let handler = unsafe { core::mem::transmute::<usize, fn()>(handler) };
+            handler();
+        }
+    }
+
+    let handler = SOFT_INTERRUPT_HANDLER.load(Ordering::Acquire);
+    if handler != 0 {
+        let handler = unsafe { core::mem::transmute::<usize, fn()>(handler) };
+        handler();
+    }
+}
+
+/// 取出并处理当前 hart 待处理消息中属于 `mask` 的部分，返回取到的消息位
+///
+/// 说明：
+/// - `MSG_RESCHEDULE` 只在中断上下文（`handle_interrupt`）中处理，这里只负责取出
+/// - 等待循环只传 `MSG_SYNC`，重新调度消息留给随后的 `handle_interrupt`（msip 还没有被清除）
+fn handle_pending(mask: usize) -> usize {
+    let me = hart::id();
+    let messages = PENDING[me].fetch_and(!mask, Ordering::AcqRel) & mask;
+
+    if messages & MSG_STOP != 0 {
+        trap::disable_interrupts();
+        loop {
+            trap::wait_for_interrupt();
+        }
+    }
+    if messages & MSG_FENCE != 0 {
+        let request = FENCE_REQUEST[me].load(Ordering::Acquire);
+        local_fence();
+        FENCE_DONE[me].store(request, Ordering::Release);
+    }
+    if messages & MSG_CALL != 0 {
+        let f = CALL_SLOT[me].load(Ordering::Acquire);
+        if f != 0 {
+            let f = unsafe { core::mem::transmute::<usize, fn()>(f) };
+            f();
+            CALL_SLOT[me].store(0, Ordering::Release);
+        }
+    }
+    messages
+}
+
+/// 在本 hart 上执行指令屏障和地址转换屏障
+fn local_fence() {
+    unsafe {
+        asm!("fence rw, rw", "fence.i", "sfence.vma");
+    }
+}
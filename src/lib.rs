@@ -4,14 +4,31 @@
 //! 项目采用 lib + bin 结构，支持多个应用程序。
 //!
 //! ## 项目结构
+//! - `block/` - 块设备抽象和块缓存
 //! - `console.rs` - 串口控制台输出
+//! - `dtb.rs` - 设备树解析（内存、hart、设备地址）
 //! - `error.rs` - 错误处理模块
+//! - `fs/` - 文件系统：VFS、tmpfs、initramfs 和 FAT32
+//! - `gdbstub/` - GDB 远程调试桩
+//! - `hart.rs` - 多核启动和每个 hart 的私有数据
 //! - `system.rs` - 系统功能（关机、重启、内存布局等）
 //! - `heap_allocator.rs` - 堆内存分配器
+//! - `ipi.rs` - 核间中断
+//! - `loader.rs` - ELF 装载器：把独立编译的用户程序装载到用户程序区域并运行
+//! - `net/` - 网络协议栈：ARP、IPv4、ICMP、UDP 和 TCP 套接字
+//! - `pmp.rs` - 物理内存保护（PMP）
+//! - `shell/` - 串口交互式 shell
+//! - `spinlock.rs` - 多核安全的关中断自旋锁
+//! - `stack.rs` - 栈填充、最高水位统计和栈溢出保护
+//! - `virtio/` - virtio-mmio 传输层、virtio-blk 块设备和 virtio-net 网卡驱动
 //! - `bin/` - 应用程序目录
 
 #![no_std]
 #![feature(alloc_error_handler)]
+// thread_local! 宏展开出 #[thread_local] 静态变量，使用方无需自行开启 feature
+#![allow(internal_features)]
+#![feature(allow_internal_unstable)]
+#![feature(thread_local)]
 
 // 设置不用test模块
 
@@ -24,12 +41,25 @@ use core::arch::global_asm;
 global_asm!(include_str!("entry.asm"));
 
 // 导出核心模块
+pub mod block;
 pub mod collection;
 pub mod console;
+pub mod dtb;
 pub mod error;
+pub mod fs;
+pub mod gdbstub;
+pub mod hart;
 pub mod heap;
+pub mod ipi;
+pub mod loader;
 pub mod logging;
+pub mod net;
+pub mod pmp;
+pub mod shell;
+pub mod spinlock;
+pub mod stack;
 pub mod system;
 pub mod thread;
 pub mod timer;
 pub mod trap;
+pub mod virtio;
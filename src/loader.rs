@@ -0,0 +1,331 @@
+//! 📦 ELF 装载器
+//!
+//! 装载独立编译的用户程序（`user/src/bin`），以用户线程运行：
+//! - 用户程序的 ELF 由 build.rs 用 `include_bytes!` 嵌入内核，`apps()` 列出它们
+//! - 没有 MMU，所有程序共用物理地址空间：用户程序链接到 memory.x 中预留的 `.apps` 区域（见 user/app.ld），
+//!   PT_LOAD 段按 p_vaddr 原样复制到该区域，memsz 超出 filesz 的部分（.bss）清零
+//! - 区域中同时装载的映像不能重叠：映像在所有使用它的线程结束后才释放，
+//!   在此之前装载地址重叠的程序返回 `LoadError::Busy`
+//! - 每个段按段标志得到对应权限的 PMP 表项（段边界按 4KB 对齐），随线程切换设置
+//! - 用户栈按 Linux 的约定放入 argc/argv，从 e_entry 开始执行
+//!
+//! 用法：
+//! ```ignore
+//! let handle = loader::spawn("hello", &["hello", "world"])?;
+//! let status = handle.join().unwrap();
+//! ```
+
+extern crate alloc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::arch::asm;
+use core::fmt::{Display, Formatter};
+
+use log::info;
+
+use crate::pmp::{self, PMP_R, PMP_W, PMP_X, Region, USER_REGION_ENTRIES};
+use crate::spinlock::SpinLock;
+use crate::thread::{Builder, ExitStatus, JoinHandle, user};
+
+/// 嵌入内核的用户程序
+#[derive(Debug, Clone, Copy)]
+pub struct App {
+    pub name: &'static str,
+    pub elf: &'static [u8],
+}
+
+include!(concat!(env!("OUT_DIR"), "/apps.rs"));
+
+/// 段边界对齐
+const PAGE_SIZE: usize = 4096;
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const PF_X: u32 = 1;
+const PF_W: u32 = 2;
+const PF_R: u32 = 4;
+/// ELF64 文件头大小
+const EHDR_SIZE: usize = 64;
+/// ELF64 程序头大小
+const PHDR_SIZE: usize = 56;
+
+/// 装载失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadError {
+    /// 没有这个名字的用户程序
+    NotFound,
+    /// 不是合法的 ELF 文件（魔数错误或文件被截断）
+    NotElf,
+    /// 不支持的 ELF 文件
+    Unsupported(&'static str),
+    /// 段的文件范围越界或各段重叠
+    BadSegment { vaddr: usize },
+    /// 段不在用户程序区域内
+    OutOfRegion { start: usize, end: usize },
+    /// 段太多，PMP 表项不够
+    TooManySegments,
+    /// 与已经装载、还在运行的映像重叠
+    Busy { start: usize, end: usize },
+    /// 命令行参数超过 `user::MAX_ARGS_SIZE`
+    ArgsTooLarge,
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LoadError::NotFound => write!(f, "no such user program"),
+            LoadError::NotElf => write!(f, "not a valid ELF file"),
+            LoadError::Unsupported(reason) => write!(f, "unsupported ELF file: {}", reason),
+            LoadError::BadSegment { vaddr } => write!(f, "bad segment at 0x{:x}", vaddr),
+            LoadError::OutOfRegion { start, end } => {
+                write!(f, "segment 0x{:x}-0x{:x} is outside the user app region", start, end)
+            }
+            LoadError::TooManySegments => write!(f, "too many segments for {} PMP entries", USER_REGION_ENTRIES),
+            LoadError::Busy { start, end } => {
+                write!(f, "0x{:x}-0x{:x} is used by a running image", start, end)
+            }
+            LoadError::ArgsTooLarge => write!(f, "program arguments are larger than {} bytes", user::MAX_ARGS_SIZE),
+        }
+    }
+}
+
+/// 已经装载、还有线程在使用的映像所占的区域
+static LOADED: SpinLock<Vec<(usize, usize)>> = SpinLock::new(Vec::new());
+
+/// 📦 装载到内存中的程序映像
+///
+/// 说明：由运行它的所有用户线程共享，最后一个线程结束后释放所占的区域
+#[derive(Debug)]
+pub struct Image {
+    name: String,
+    entry: usize,
+    /// 各段的范围和权限（按地址排序，4KB 对齐）
+    regions: Vec<Region>,
+}
+
+impl Image {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 入口地址（e_entry）
+    pub fn entry(&self) -> usize {
+        self.entry
+    }
+
+    /// 各段的范围和权限
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// 映像占用的整个区域 `[start, end)`
+    pub fn span(&self) -> (usize, usize) {
+        let start = self.regions.first().map_or(0, |region| region.start);
+        let end = self.regions.last().map_or(0, |region| region.end);
+        (start, end)
+    }
+}
+
+impl Drop for Image {
+    fn drop(&mut self) {
+        let span = self.span();
+        LOADED.lock().retain(|&loaded| loaded != span);
+    }
+}
+
+/// 一个 PT_LOAD 段
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    flags: u32,
+    offset: usize,
+    vaddr: usize,
+    filesz: usize,
+    memsz: usize,
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, LoadError> {
+    let bytes = data.get(offset..offset + 2).ok_or(LoadError::NotElf)?;
+    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, LoadError> {
+    let bytes = data.get(offset..offset + 4).ok_or(LoadError::NotElf)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Result<usize, LoadError> {
+    let bytes = data.get(offset..offset + 8).ok_or(LoadError::NotElf)?;
+    Ok(u64::from_le_bytes(bytes.try_into().unwrap()) as usize)
+}
+
+/// 解析 ELF 文件头和程序头，返回入口地址和按地址排序的 PT_LOAD 段
+fn parse(elf: &[u8]) -> Result<(usize, Vec<Segment>), LoadError> {
+    if elf.len() < EHDR_SIZE || elf[..4] != ELF_MAGIC {
+        return Err(LoadError::NotElf);
+    }
+    if elf[4] != ELFCLASS64 || elf[5] != ELFDATA2LSB {
+        return Err(LoadError::Unsupported("not a little-endian ELF64 file"));
+    }
+    if read_u16(elf, 16)? != ET_EXEC {
+        return Err(LoadError::Unsupported("not a statically linked executable"));
+    }
+    if read_u16(elf, 18)? != EM_RISCV {
+        return Err(LoadError::Unsupported("not a RISC-V executable"));
+    }
+    let entry = read_u64(elf, 24)?;
+    let phoff = read_u64(elf, 32)?;
+    let phentsize = read_u16(elf, 54)? as usize;
+    let phnum = read_u16(elf, 56)? as usize;
+    if phentsize < PHDR_SIZE {
+        return Err(LoadError::NotElf);
+    }
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        // 程序头的位置来自文件，要先确认它完整地在文件内，下面读取各字段时才不会溢出
+        let ph = i.checked_mul(phentsize).and_then(|off| off.checked_add(phoff)).ok_or(LoadError::NotElf)?;
+        if ph.checked_add(PHDR_SIZE).is_none_or(|end| end > elf.len()) {
+            return Err(LoadError::NotElf);
+        }
+        if read_u32(elf, ph)? != PT_LOAD {
+            continue;
+        }
+        let segment = Segment {
+            flags: read_u32(elf, ph + 4)?,
+            offset: read_u64(elf, ph + 8)?,
+            vaddr: read_u64(elf, ph + 16)?,
+            filesz: read_u64(elf, ph + 32)?,
+            memsz: read_u64(elf, ph + 40)?,
+        };
+        if segment.memsz == 0 {
+            continue;
+        }
+        let in_file = segment.offset.checked_add(segment.filesz).is_some_and(|end| end <= elf.len());
+        let in_memory = segment.vaddr.checked_add(segment.memsz).is_some();
+        if !in_file || !in_memory || segment.filesz > segment.memsz {
+            return Err(LoadError::BadSegment { vaddr: segment.vaddr });
+        }
+        segments.push(segment);
+    }
+    if segments.is_empty() {
+        return Err(LoadError::Unsupported("no loadable segments"));
+    }
+    segments.sort_by_key(|segment| segment.vaddr);
+    Ok((entry, segments))
+}
+
+/// 段在内存中的区域：边界按 4KB 对齐，权限取自段标志
+fn region_of(segment: &Segment) -> Result<Region, LoadError> {
+    let bad = LoadError::BadSegment { vaddr: segment.vaddr };
+    let end = segment.vaddr.checked_add(segment.memsz).ok_or(bad)?;
+    let mut perm = 0;
+    if segment.flags & PF_R != 0 {
+        perm |= PMP_R;
+    }
+    if segment.flags & PF_W != 0 {
+        perm |= PMP_W;
+    }
+    if segment.flags & PF_X != 0 {
+        perm |= PMP_X;
+    }
+    Ok(Region {
+        start: segment.vaddr & !(PAGE_SIZE - 1),
+        end: end.checked_next_multiple_of(PAGE_SIZE).ok_or(bad)?,
+        perm,
+    })
+}
+
+/// 用户程序区域 `[start, end)`
+pub fn app_region() -> (usize, usize) {
+    unsafe extern "C" {
+        static __APP_START: u8;
+        static __APP_END: u8;
+    }
+    unsafe {
+        (
+            &__APP_START as *const u8 as usize,
+            &__APP_END as *const u8 as usize,
+        )
+    }
+}
+
+/// 嵌入内核的所有用户程序
+pub fn apps() -> &'static [App] {
+    APPS
+}
+
+/// 按名字查找嵌入内核的用户程序
+pub fn find(name: &str) -> Option<&'static App> {
+    APPS.iter().find(|app| app.name == name)
+}
+
+/// 📦 把 ELF 映像装载到用户程序区域
+///
+/// 说明：
+/// - 只支持静态链接、链接到用户程序区域内的 RISC-V ELF64 可执行文件
+/// - 与还在运行的映像重叠时返回 `LoadError::Busy`
+pub fn load(name: &str, elf: &[u8]) -> Result<Arc<Image>, LoadError> {
+    let (entry, segments) = parse(elf)?;
+    let regions = segments.iter().map(region_of).collect::<Result<Vec<_>, _>>()?;
+
+    let (app_start, app_end) = app_region();
+    for (i, region) in regions.iter().enumerate() {
+        if region.start < app_start || region.end > app_end {
+            return Err(LoadError::OutOfRegion {
+                start: region.start,
+                end: region.end,
+            });
+        }
+        if i > 0 && regions[i - 1].end > region.start {
+            return Err(LoadError::BadSegment { vaddr: segments[i].vaddr });
+        }
+    }
+    if pmp::user_region_entries(&regions) > USER_REGION_ENTRIES {
+        return Err(LoadError::TooManySegments);
+    }
+    if !regions.iter().any(|region| region.perm & PMP_X != 0 && (region.start..region.end).contains(&entry)) {
+        return Err(LoadError::Unsupported("entry point is not in an executable segment"));
+    }
+
+    let (start, end) = (regions[0].start, regions[regions.len() - 1].end);
+    {
+        let mut loaded = LOADED.lock();
+        if let Some(&(start, end)) = loaded.iter().find(|&&(s, e)| s < end && start < e) {
+            return Err(LoadError::Busy { start, end });
+        }
+        loaded.push((start, end));
+    }
+    // 从这里开始由 Image 的 Drop 负责归还区域
+    let image = Image {
+        name: name.to_string(),
+        entry,
+        regions,
+    };
+
+    // 先清零整个区域（包括 .bss 和对齐填充），再复制各段的文件内容
+    unsafe {
+        core::ptr::write_bytes(start as *mut u8, 0, end - start);
+        for segment in &segments {
+            let data = &elf[segment.offset..segment.offset + segment.filesz];
+            core::ptr::copy_nonoverlapping(data.as_ptr(), segment.vaddr as *mut u8, data.len());
+        }
+        asm!("fence.i");
+    }
+    info!("📦 Loaded {} at 0x{:x}-0x{:x}, entry 0x{:x}", name, start, end, entry);
+    Ok(Arc::new(image))
+}
+
+/// 📦 装载嵌入内核的用户程序 `name` 并以用户线程运行，`args` 作为命令行参数（通常以程序名开头）
+///
+/// 说明：参数（字符串和指针数组）超过 `user::MAX_ARGS_SIZE` 字节时返回 `LoadError::ArgsTooLarge`
+pub fn spawn(name: &str, args: &[&str]) -> Result<JoinHandle<ExitStatus>, LoadError> {
+    let app = find(name).ok_or(LoadError::NotFound)?;
+    let image = load(app.name, app.elf)?;
+    let args = args.iter().map(|arg| arg.to_string()).collect();
+    user::spawn_image(Builder::new().name(app.name), image, args)
+}
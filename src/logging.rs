@@ -37,12 +37,20 @@ impl Log for SimpleLogger {
 pub fn init() {
     static LOGGER: SimpleLogger = SimpleLogger;
     log::set_logger(&LOGGER).unwrap();
-    log::set_max_level(match option_env!("LOG") {
-        Some("ERROR") => LevelFilter::Error,
-        Some("WARN") => LevelFilter::Warn,
-        Some("INFO") => LevelFilter::Info,
-        Some("DEBUG") => LevelFilter::Debug,
-        Some("TRACE") => LevelFilter::Trace,
-        _ => LevelFilter::Info,
-    });
+    log::set_max_level(option_env!("LOG").and_then(parse_level).unwrap_or(LevelFilter::Info));
+}
+
+/// 解析日志级别名（OFF/ERROR/WARN/INFO/DEBUG/TRACE，不区分大小写）
+pub fn parse_level(name: &str) -> Option<LevelFilter> {
+    name.parse().ok()
+}
+
+/// 运行时修改日志级别
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// 当前的日志级别
+pub fn level() -> LevelFilter {
+    log::max_level()
 }
@@ -0,0 +1,350 @@
+//! 🔀 网络接口
+//!
+//! `Interface` 持有网卡和全部协议状态，本身不加锁、不阻塞，由 `net` 模块放在全局互斥锁里使用：
+//! - `poll(now)` 取出网卡上所有收到的帧逐个处理，再处理 ARP 和 TCP 的超时
+//! - ARP：回应询问本机地址的请求，从收到的 ARP 报文中学习对方的 MAC；
+//!   发往还没有解析的地址的报文先放进等待队列，每秒重发一次请求，三次没有回应就丢弃
+//! - IPv4：只接收发给本机或广播的报文；目的地址不在本地子网时发给网关
+//! - ICMP：回应 ping
+//! - UDP/TCP：交给 `udp`/`tcp` 模块中为 `Interface` 实现的方法
+//!
+//! 说明：网卡发送失败只记录日志，对 UDP 相当于丢包，对 TCP 由重传处理
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+use log::{debug, warn};
+
+use super::tcp::{Listener, Tcb};
+use super::udp::UdpState;
+use super::wire::{self, ArpPacket, EthernetFrame, IcmpEcho, Ipv4Packet, MacAddr, TcpSegment, UdpDatagram};
+use super::{Config, MAX_FRAME_LEN, NetDevice, NetStats};
+
+/// 临时端口的范围（RFC 6335）
+pub const EPHEMERAL_PORTS: core::ops::RangeInclusive<u16> = 49152..=65535;
+
+/// 等待 ARP 解析的报文数上限
+const MAX_PENDING: usize = 16;
+/// ARP 请求的重发间隔（ms）
+const ARP_RETRY_MS: u64 = 1000;
+/// 没有回应时最多发送几次 ARP 请求
+const ARP_MAX_REQUESTS: u32 = 3;
+/// 每次 `poll` 最多处理的帧数，避免一直有报文时占着锁
+const MAX_FRAMES_PER_POLL: usize = 64;
+
+/// 等待下一跳 MAC 地址的 IPv4 报文
+struct Pending {
+    next_hop: Ipv4Addr,
+    /// 以太网头部（目的地址待填）+ IPv4 报文
+    frame: Vec<u8>,
+}
+
+/// 正在解析的地址
+struct ArpQuery {
+    requests: u32,
+    retry_at: u64,
+}
+
+/// 网络接口：网卡、地址配置、ARP 缓存和所有套接字
+pub struct Interface {
+    device: Box<dyn NetDevice>,
+    mac: MacAddr,
+    config: Config,
+    /// 当前时间（ms），由 `poll`/`set_now` 更新
+    pub(super) now: u64,
+    arp_cache: BTreeMap<Ipv4Addr, MacAddr>,
+    arp_queries: BTreeMap<Ipv4Addr, ArpQuery>,
+    pending: Vec<Pending>,
+    ip_id: u16,
+    /// 套接字句柄的分配计数
+    next_handle: usize,
+    /// 下一个尝试分配的临时端口
+    next_port: u16,
+    pub(super) udp: BTreeMap<usize, UdpState>,
+    pub(super) tcp: BTreeMap<usize, Tcb>,
+    pub(super) listeners: BTreeMap<usize, Listener>,
+    /// 生成 TCP 初始序号
+    pub(super) iss_seed: u32,
+    stats: NetStats,
+    rx_buf: Vec<u8>,
+}
+
+impl Interface {
+    pub fn new(device: Box<dyn NetDevice>, config: Config, now: u64) -> Self {
+        Self {
+            mac: device.mac(),
+            device,
+            config,
+            now,
+            arp_cache: BTreeMap::new(),
+            arp_queries: BTreeMap::new(),
+            pending: Vec::new(),
+            ip_id: 1,
+            next_handle: 1,
+            next_port: *EPHEMERAL_PORTS.start(),
+            udp: BTreeMap::new(),
+            tcp: BTreeMap::new(),
+            listeners: BTreeMap::new(),
+            iss_seed: now as u32,
+            stats: NetStats::default(),
+            rx_buf: vec![0; MAX_FRAME_LEN],
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    pub fn stats(&self) -> NetStats {
+        self.stats
+    }
+
+    pub fn set_now(&mut self, now: u64) {
+        self.now = now;
+    }
+
+    /// 处理收到的帧和到期的定时器；有可能唤醒等待者的变化时返回 true
+    pub fn poll(&mut self, now: u64) -> bool {
+        self.now = now;
+        let mut changed = false;
+        for _ in 0..MAX_FRAMES_PER_POLL {
+            let mut buf = core::mem::take(&mut self.rx_buf);
+            let received = self.device.recv(&mut buf);
+            match received {
+                Ok(Some(len)) => {
+                    self.stats.rx_frames += 1;
+                    changed |= self.handle_frame(&buf[..len]);
+                    self.rx_buf = buf;
+                }
+                Ok(None) => {
+                    self.rx_buf = buf;
+                    break;
+                }
+                Err(err) => {
+                    warn!("net: receive failed: {}", err);
+                    self.rx_buf = buf;
+                    break;
+                }
+            }
+        }
+        self.arp_timers();
+        changed |= self.tcp_timers();
+        changed
+    }
+
+    pub(super) fn alloc_handle(&mut self) -> usize {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        handle
+    }
+
+    /// 分配一个 `in_use` 返回 false 的临时端口
+    pub(super) fn alloc_port(&mut self, in_use: impl Fn(&Self, u16) -> bool) -> Option<u16> {
+        for _ in EPHEMERAL_PORTS {
+            let port = self.next_port;
+            self.next_port = if port == *EPHEMERAL_PORTS.end() { *EPHEMERAL_PORTS.start() } else { port + 1 };
+            if !in_use(self, port) {
+                return Some(port);
+            }
+        }
+        None
+    }
+
+    fn handle_frame(&mut self, frame: &[u8]) -> bool {
+        let Some(eth) = EthernetFrame::parse(frame) else {
+            self.stats.dropped += 1;
+            return false;
+        };
+        if eth.dst != self.mac && eth.dst != wire::BROADCAST_MAC {
+            self.stats.dropped += 1;
+            return false;
+        }
+        match eth.ethertype {
+            wire::ETHERTYPE_ARP => {
+                self.handle_arp(eth.payload);
+                false
+            }
+            wire::ETHERTYPE_IPV4 => self.handle_ipv4(eth.payload),
+            _ => {
+                self.stats.dropped += 1;
+                false
+            }
+        }
+    }
+
+    fn handle_arp(&mut self, data: &[u8]) {
+        let Some(arp) = ArpPacket::parse(data) else {
+            self.stats.dropped += 1;
+            return;
+        };
+        if self.config.is_local(arp.sender_ip) && !arp.sender_ip.is_unspecified() {
+            self.learn(arp.sender_ip, arp.sender_mac);
+        }
+        if arp.operation == wire::ARP_REQUEST && arp.target_ip == self.config.ip {
+            let reply = ArpPacket {
+                operation: wire::ARP_REPLY,
+                sender_mac: self.mac,
+                sender_ip: self.config.ip,
+                target_mac: arp.sender_mac,
+                target_ip: arp.sender_ip,
+            };
+            self.send_arp(arp.sender_mac, reply);
+        }
+    }
+
+    /// 记录 `ip` 的 MAC 地址，发出在等待它的报文
+    fn learn(&mut self, ip: Ipv4Addr, mac: MacAddr) {
+        if self.arp_cache.insert(ip, mac).is_none() {
+            debug!("net: arp {} is {:02x?}", ip, mac);
+        }
+        self.arp_queries.remove(&ip);
+        let (ready, waiting) = core::mem::take(&mut self.pending).into_iter().partition(|p| p.next_hop == ip);
+        self.pending = waiting;
+        for Pending { mut frame, .. } in ready {
+            frame[..6].copy_from_slice(&mac);
+            self.transmit(&frame);
+        }
+    }
+
+    fn arp_timers(&mut self) {
+        let now = self.now;
+        let due: Vec<Ipv4Addr> =
+            self.arp_queries.iter().filter(|(_, query)| now >= query.retry_at).map(|(&ip, _)| ip).collect();
+        for ip in due {
+            let query = self.arp_queries.get_mut(&ip).unwrap();
+            if query.requests >= ARP_MAX_REQUESTS {
+                self.arp_queries.remove(&ip);
+                let before = self.pending.len();
+                self.pending.retain(|p| p.next_hop != ip);
+                warn!("net: no arp reply from {}, dropped {} packet(s)", ip, before - self.pending.len());
+                self.stats.dropped += before - self.pending.len();
+            } else {
+                query.requests += 1;
+                query.retry_at = now + ARP_RETRY_MS;
+                self.send_arp_request(ip);
+            }
+        }
+    }
+
+    fn send_arp_request(&mut self, ip: Ipv4Addr) {
+        let request = ArpPacket {
+            operation: wire::ARP_REQUEST,
+            sender_mac: self.mac,
+            sender_ip: self.config.ip,
+            target_mac: [0; 6],
+            target_ip: ip,
+        };
+        self.send_arp(wire::BROADCAST_MAC, request);
+    }
+
+    fn send_arp(&mut self, dst: MacAddr, arp: ArpPacket) {
+        let mut frame = Vec::with_capacity(wire::ETHERNET_HEADER_LEN + wire::ARP_LEN);
+        wire::push_ethernet(&mut frame, dst, self.mac, wire::ETHERTYPE_ARP);
+        arp.push(&mut frame);
+        self.transmit(&frame);
+    }
+
+    fn handle_ipv4(&mut self, data: &[u8]) -> bool {
+        let Some(ip) = Ipv4Packet::parse(data) else {
+            self.stats.dropped += 1;
+            return false;
+        };
+        if ip.dst != self.config.ip && ip.dst != self.config.broadcast() && ip.dst != Ipv4Addr::BROADCAST {
+            self.stats.dropped += 1;
+            return false;
+        }
+        let accepted = match ip.protocol {
+            wire::IP_PROTO_ICMP => self.handle_icmp(&ip),
+            wire::IP_PROTO_UDP => {
+                UdpDatagram::parse(ip.src, ip.dst, ip.payload).is_some_and(|udp| self.udp_input(ip.src, udp))
+            }
+            wire::IP_PROTO_TCP => {
+                // 不响应发给广播地址的 TCP 报文
+                ip.dst == self.config.ip
+                    && TcpSegment::parse(ip.src, ip.dst, ip.payload).is_some_and(|tcp| self.tcp_input(ip.src, tcp))
+            }
+            _ => false,
+        };
+        if !accepted {
+            self.stats.dropped += 1;
+        }
+        // ICMP 不会唤醒套接字，UDP/TCP 收下的报文都可能让等待者继续
+        accepted && ip.protocol != wire::IP_PROTO_ICMP
+    }
+
+    fn handle_icmp(&mut self, ip: &Ipv4Packet) -> bool {
+        let Some(echo) = IcmpEcho::parse(ip.payload) else {
+            return false;
+        };
+        if echo.kind != wire::ICMP_ECHO_REQUEST || ip.dst != self.config.ip {
+            return false;
+        }
+        let reply = IcmpEcho {
+            kind: wire::ICMP_ECHO_REPLY,
+            ..echo
+        };
+        self.send_ipv4(ip.src, wire::IP_PROTO_ICMP, reply.total_len(), |buf, _| reply.push(buf));
+        true
+    }
+
+    /// 发送一个 IPv4 报文，`push_payload(buf, src)` 追加 `payload_len` 字节的负载
+    ///
+    /// 下一跳的 MAC 地址还不知道时先排队并发送 ARP 请求
+    pub(super) fn send_ipv4(
+        &mut self,
+        dst: Ipv4Addr,
+        protocol: u8,
+        payload_len: usize,
+        push_payload: impl FnOnce(&mut Vec<u8>, Ipv4Addr),
+    ) {
+        let src = self.config.ip;
+        let mut frame = Vec::with_capacity(wire::ETHERNET_HEADER_LEN + wire::IPV4_HEADER_LEN + payload_len);
+        wire::push_ethernet(&mut frame, [0; 6], self.mac, wire::ETHERTYPE_IPV4);
+        wire::push_ipv4(&mut frame, src, dst, protocol, self.ip_id, payload_len);
+        self.ip_id = self.ip_id.wrapping_add(1);
+        push_payload(&mut frame, src);
+        debug_assert_eq!(frame.len(), wire::ETHERNET_HEADER_LEN + wire::IPV4_HEADER_LEN + payload_len);
+
+        let next_hop = if self.config.is_local(dst) { dst } else { self.config.gateway };
+        let mac = if dst == Ipv4Addr::BROADCAST || dst == self.config.broadcast() {
+            Some(wire::BROADCAST_MAC)
+        } else {
+            self.arp_cache.get(&next_hop).copied()
+        };
+        match mac {
+            Some(mac) => {
+                frame[..6].copy_from_slice(&mac);
+                self.transmit(&frame);
+            }
+            None => {
+                if self.pending.len() >= MAX_PENDING {
+                    warn!("net: arp queue full, dropped a packet to {}", dst);
+                    self.stats.dropped += 1;
+                    return;
+                }
+                self.pending.push(Pending { next_hop, frame });
+                if !self.arp_queries.contains_key(&next_hop) {
+                    self.arp_queries.insert(
+                        next_hop,
+                        ArpQuery {
+                            requests: 1,
+                            retry_at: self.now + ARP_RETRY_MS,
+                        },
+                    );
+                    self.send_arp_request(next_hop);
+                }
+            }
+        }
+    }
+
+    fn transmit(&mut self, frame: &[u8]) {
+        match self.device.send(frame) {
+            Ok(()) => self.stats.tx_frames += 1,
+            Err(err) => warn!("net: send failed: {}", err),
+        }
+    }
+}
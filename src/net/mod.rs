@@ -0,0 +1,233 @@
+//! 🌐 网络协议栈
+//!
+//! 一个最小的 IPv4 协议栈，运行在任意 `NetDevice`（如 `virtio::net::VirtioNet`）上：
+//! - `wire`：以太网、ARP、IPv4、ICMP、UDP、TCP 的报文格式和校验和
+//! - `iface`：网络接口：ARP 解析（等待解析的报文先排队）、IPv4 收发、回应 ping，把 UDP/TCP 报文交给套接字
+//! - `udp`：`UdpSocket`
+//! - `tcp`：`TcpListener`/`TcpStream`：三次握手、按序接收、超时重传（go-back-N）、FIN 关闭和 RST
+//!
+//! 整个协议栈是一个全局的 `Interface`，由阻塞式互斥锁保护：
+//! - `init` 之后由后台线程 `net-poll` 每毫秒轮询一次设备（还没有外部中断）并处理超时，有变化时通知条件变量
+//! - 套接字的阻塞操作（accept/read/recv_from 等）在这个条件变量上等待
+//!
+//! 说明：
+//! - 地址静态配置（没有 DHCP），默认与 QEMU 用户态网络（`-netdev user`）一致：本机 10.0.2.15/24，网关 10.0.2.2
+//! - 不支持 IP 分片、TCP 乱序重组、窗口缩放和拥塞控制
+//! - `net-poll` 线程不会退出，默认的关机策略（所有线程结束后关机）下需要应用自己调用 `system::shutdown`
+//!
+//! 用法：
+//! ```ignore
+//! net::init(VirtioNet::probe()?, net::Config::QEMU_USER);
+//! let listener = TcpListener::bind(7)?;
+//! let (stream, peer) = listener.accept()?;
+//! let mut buf = [0u8; 512];
+//! let n = stream.read(&mut buf)?;
+//! stream.write_all(&buf[..n])?;
+//! ```
+
+pub mod iface;
+pub mod tcp;
+pub mod udp;
+pub mod wire;
+
+pub use tcp::{TcpListener, TcpStream};
+pub use udp::UdpSocket;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use core::fmt::{Display, Formatter};
+use core::net::Ipv4Addr;
+
+use log::info;
+
+use crate::thread::{self, sync::Condvar, sync::Mutex};
+use crate::timer;
+use crate::virtio::VirtioError;
+use iface::Interface;
+use wire::MacAddr;
+
+/// 以太网帧的最大长度（不含 FCS）
+pub const MAX_FRAME_LEN: usize = 1514;
+
+/// 后台线程轮询设备的间隔（ms）
+pub const POLL_INTERVAL_MS: usize = 1;
+
+/// 网络操作失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetError {
+    /// 还没有调用 `net::init`
+    NotInitialized,
+    /// 非阻塞操作暂时无法完成
+    WouldBlock,
+    /// 端口已经被占用
+    AddrInUse,
+    /// 数据报超过一个 IP 报文能放下的长度
+    MessageTooLong,
+    /// 对方拒绝连接（收到 RST）
+    ConnectionRefused,
+    /// 连接被对方重置
+    ConnectionReset,
+    /// 重传次数超过上限
+    TimedOut,
+    /// 连接还没有建立或已经关闭
+    NotConnected,
+    /// 已经关闭了发送方向，不能再写
+    BrokenPipe,
+    /// 网卡错误
+    Device(VirtioError),
+}
+
+impl Display for NetError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NetError::NotInitialized => write!(f, "network is not initialized"),
+            NetError::WouldBlock => write!(f, "operation would block"),
+            NetError::AddrInUse => write!(f, "address in use"),
+            NetError::MessageTooLong => write!(f, "message too long"),
+            NetError::ConnectionRefused => write!(f, "connection refused"),
+            NetError::ConnectionReset => write!(f, "connection reset by peer"),
+            NetError::TimedOut => write!(f, "connection timed out"),
+            NetError::NotConnected => write!(f, "not connected"),
+            NetError::BrokenPipe => write!(f, "broken pipe"),
+            NetError::Device(err) => write!(f, "network device error: {}", err),
+        }
+    }
+}
+
+impl From<VirtioError> for NetError {
+    fn from(err: VirtioError) -> Self {
+        NetError::Device(err)
+    }
+}
+
+/// 🔌 网卡：收发以太网帧
+pub trait NetDevice: Send {
+    /// 网卡的 MAC 地址
+    fn mac(&self) -> MacAddr;
+
+    /// 发送一帧（不含 FCS，长度不超过 `MAX_FRAME_LEN`）
+    fn send(&mut self, frame: &[u8]) -> Result<(), NetError>;
+
+    /// 取出一个收到的帧，返回它的长度；没有时立即返回 None
+    fn recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>, NetError>;
+}
+
+/// 网络接口的地址配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    pub ip: Ipv4Addr,
+    /// 子网前缀长度
+    pub prefix_len: u8,
+    pub gateway: Ipv4Addr,
+}
+
+impl Config {
+    /// QEMU 用户态网络（slirp）的默认地址
+    pub const QEMU_USER: Config = Config {
+        ip: Ipv4Addr::new(10, 0, 2, 15),
+        prefix_len: 24,
+        gateway: Ipv4Addr::new(10, 0, 2, 2),
+    };
+
+    fn netmask(&self) -> u32 {
+        u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0)
+    }
+
+    /// `addr` 是否在本地子网中（可以直接通过 ARP 找到）
+    pub fn is_local(&self, addr: Ipv4Addr) -> bool {
+        (addr.to_bits() ^ self.ip.to_bits()) & self.netmask() == 0
+    }
+
+    /// 子网广播地址
+    pub fn broadcast(&self) -> Ipv4Addr {
+        Ipv4Addr::from_bits(self.ip.to_bits() | !self.netmask())
+    }
+}
+
+/// 收发统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetStats {
+    pub rx_frames: usize,
+    pub tx_frames: usize,
+    /// 格式错误、不是发给本机或者没有套接字接收而丢弃的帧
+    pub dropped: usize,
+}
+
+/// 全局的网络接口；`init` 之前为 None
+static STACK: Mutex<Option<Interface>> = Mutex::new(None);
+/// 协议栈状态有变化（收到报文、超时、套接字操作完成）时通知
+static EVENT: Condvar = Condvar::new();
+
+/// 当前时间（ms）
+fn now_ms() -> u64 {
+    (timer::get_time() as u64) * 1000 / timer::clock_freq() as u64
+}
+
+/// 在 `device` 上启动协议栈，并创建后台轮询线程
+///
+/// 说明：需要在线程上下文中调用（`thread::init` 之后）；重复调用时替换原来的接口，已有的套接字失效
+pub fn init(device: impl NetDevice + 'static, config: Config) {
+    let mac = device.mac();
+    let first = {
+        let mut stack = STACK.lock();
+        let first = stack.is_none();
+        *stack = Some(Interface::new(Box::new(device), config, now_ms()));
+        first
+    };
+    info!(
+        "🌐 net: {} ({:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}), gateway {}",
+        config.ip, mac[0], mac[1], mac[2], mac[3], mac[4], mac[5], config.gateway
+    );
+    if first {
+        thread::Builder::new().name("net-poll").spawn(poll_loop);
+    }
+}
+
+/// 后台线程：轮询设备、处理超时
+fn poll_loop() {
+    loop {
+        let changed = STACK.lock().as_mut().is_some_and(|iface| iface.poll(now_ms()));
+        if changed {
+            EVENT.notify_all();
+        }
+        thread::sleep(POLL_INTERVAL_MS);
+    }
+}
+
+/// 本机的地址配置；没有初始化时返回 None
+pub fn config() -> Option<Config> {
+    STACK.lock().as_ref().map(|iface| iface.config())
+}
+
+/// 收发统计
+pub fn stats() -> NetStats {
+    STACK.lock().as_ref().map(|iface| iface.stats()).unwrap_or_default()
+}
+
+/// 在协议栈上执行一个不阻塞的操作
+fn with_iface<T>(f: impl FnOnce(&mut Interface) -> Result<T, NetError>) -> Result<T, NetError> {
+    let mut stack = STACK.lock();
+    let iface = stack.as_mut().ok_or(NetError::NotInitialized)?;
+    iface.set_now(now_ms());
+    let result = f(iface);
+    drop(stack);
+    EVENT.notify_all();
+    result
+}
+
+/// 在协议栈上执行 `f`，返回 `WouldBlock` 时等到协议栈有变化再重试
+fn block_on<T>(mut f: impl FnMut(&mut Interface) -> Result<T, NetError>) -> Result<T, NetError> {
+    let mut stack = STACK.lock();
+    loop {
+        let iface = stack.as_mut().ok_or(NetError::NotInitialized)?;
+        iface.set_now(now_ms());
+        match f(iface) {
+            Err(NetError::WouldBlock) => stack = EVENT.wait(stack),
+            result => {
+                drop(stack);
+                EVENT.notify_all();
+                return result;
+            }
+        }
+    }
+}
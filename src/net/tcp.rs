@@ -0,0 +1,838 @@
+//! 🔗 TCP
+//!
+//! 每个连接是一个 `Tcb`（传输控制块），只负责状态机：`input` 处理收到的报文段，`output` 生成要发送的报文段，
+//! `on_timer` 处理超时；真正的收发由 `Interface` 完成。
+//!
+//! - 主动打开（`TcpStream::connect`）和被动打开（`TcpListener::accept`，每个监听套接字最多 `BACKLOG` 个等待接受的连接）
+//! - 只接收按序到达的数据，乱序的报文段丢弃并重复确认，由对方重传
+//! - 超时重传：go-back-N，超时后只重发第一个没有确认的报文段，收到新的确认后再按窗口继续发送；
+//!   超时时间从 `INITIAL_RTO_MS` 开始每次翻倍，连续 `MAX_RETRIES` 次没有进展就放弃（`TimedOut`，并发送 RST）
+//! - 对方的接收窗口为 0 时定时发送 1 字节的窗口探测
+//! - 关闭：`shutdown` 或 drop 时数据发完后发送 FIN；drop 之后连接在后台继续关闭，结束后回收
+//! - 没有对应套接字的报文段回应 RST
+//!
+//! 说明：没有拥塞控制、延迟确认、窗口缩放和 SACK，TIME-WAIT 只保持 `TIME_WAIT_MS`
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddrV4};
+
+use super::iface::Interface;
+use super::wire::{self, TCP_ACK, TCP_FIN, TCP_PSH, TCP_RST, TCP_SYN, TcpSegment};
+use super::{NetError, block_on, with_iface};
+
+/// 本机的最大报文段长度（1500 字节的 MTU 减去 IPv4 和 TCP 头部）
+pub const MSS: usize = 1500 - wire::IPV4_HEADER_LEN - wire::TCP_HEADER_LEN;
+/// 对方没有给出 MSS 选项时使用的默认值（RFC 879）
+const DEFAULT_MSS: usize = 536;
+/// 接收缓冲区大小，也是通告的最大窗口
+pub const RX_BUFFER_SIZE: usize = 8 * 1024;
+/// 发送缓冲区大小：已发送未确认和还没有发送的数据
+pub const TX_BUFFER_SIZE: usize = 16 * 1024;
+/// 每个监听套接字最多排队的连接数
+pub const BACKLOG: usize = 8;
+
+const INITIAL_RTO_MS: u64 = 250;
+const MAX_RTO_MS: u64 = 4000;
+const MAX_RETRIES: u32 = 8;
+/// TIME-WAIT 的持续时间（RFC 要求 2MSL，这里大大缩短）
+const TIME_WAIT_MS: u64 = 2000;
+/// 已经释放的连接在 FIN-WAIT-2 中等待对方 FIN 的时间
+const FIN_WAIT_2_TIMEOUT_MS: u64 = 30_000;
+
+/// 序号比较（模 2^32）
+fn seq_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+fn seq_le(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) <= 0
+}
+
+/// 连接状态（RFC 793）；监听由 `Listener` 表示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    SynSent,
+    SynReceived,
+    Established,
+    FinWait1,
+    FinWait2,
+    CloseWait,
+    Closing,
+    LastAck,
+    TimeWait,
+    Closed,
+}
+
+/// `Tcb::output` 生成的报文段
+struct OutSegment {
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    mss: Option<u16>,
+    payload: Vec<u8>,
+}
+
+/// 传输控制块：一个连接的全部状态
+pub(crate) struct Tcb {
+    local_port: u16,
+    remote: SocketAddrV4,
+    state: State,
+    /// 本方的初始序号
+    iss: u32,
+    /// 第一个没有被确认的序号
+    snd_una: u32,
+    /// 下一个要发送的序号（超时后回退到 `snd_una`）
+    snd_nxt: u32,
+    /// 发送过的最大序号
+    snd_max: u32,
+    /// 对方的接收窗口
+    snd_wnd: u32,
+    /// 对方的最大报文段长度
+    mss: usize,
+    /// 期望收到的下一个序号
+    rcv_nxt: u32,
+    /// 从 `snd_una` 开始的待确认和待发送数据
+    tx: VecDeque<u8>,
+    /// 收到还没有被读取的数据
+    rx: VecDeque<u8>,
+    /// 发送方向已经关闭：数据发完之后发送 FIN
+    fin_queued: bool,
+    /// 收到了对方的 FIN
+    fin_received: bool,
+    /// 下一次 `output` 至少发送一个 ACK
+    ack_pending: bool,
+    /// 下一次 `output` 在零窗口时发送 1 字节的探测
+    probe: bool,
+    /// 超时之后、收到新的确认之前，每次只发送一个报文段
+    recovering: bool,
+    rto: u64,
+    retransmit_at: Option<u64>,
+    retries: u32,
+    /// TIME-WAIT 或 FIN-WAIT-2 的截止时间
+    linger_until: u64,
+    /// 连接被重置或超时：之后的读写返回这个错误
+    error: Option<NetError>,
+    /// 被动打开且还没有被 accept 的连接所属的监听套接字
+    listener: Option<usize>,
+    /// `TcpStream` 已经释放，连接关闭后回收
+    orphaned: bool,
+}
+
+impl Tcb {
+    fn new(local_port: u16, remote: SocketAddrV4, state: State, iss: u32) -> Self {
+        Self {
+            local_port,
+            remote,
+            state,
+            iss,
+            snd_una: iss,
+            snd_nxt: iss,
+            snd_max: iss,
+            snd_wnd: 0,
+            mss: DEFAULT_MSS,
+            rcv_nxt: 0,
+            tx: VecDeque::new(),
+            rx: VecDeque::new(),
+            fin_queued: false,
+            fin_received: false,
+            ack_pending: false,
+            probe: false,
+            recovering: false,
+            rto: INITIAL_RTO_MS,
+            retransmit_at: None,
+            retries: 0,
+            linger_until: 0,
+            error: None,
+            listener: None,
+            orphaned: false,
+        }
+    }
+
+    /// 通告给对方的接收窗口
+    fn window(&self) -> u16 {
+        (RX_BUFFER_SIZE - self.rx.len()).min(u16::MAX as usize) as u16
+    }
+
+    /// 收到 SYN：记录对方的初始序号、窗口和 MSS
+    fn synchronize(&mut self, seg: &TcpSegment) {
+        self.rcv_nxt = seg.seq.wrapping_add(1);
+        self.snd_wnd = seg.window as u32;
+        self.mss = seg.mss.map_or(DEFAULT_MSS, |mss| (mss as usize).clamp(1, MSS));
+    }
+
+    /// 连接已经建立（或者建立之后正在关闭）
+    fn synchronized(&self) -> bool {
+        !matches!(self.state, State::SynSent | State::SynReceived | State::Closed)
+    }
+
+    /// 进入 CLOSED，丢弃待发送的数据
+    fn abort(&mut self, error: Option<NetError>) {
+        self.state = State::Closed;
+        self.error = error;
+        self.tx.clear();
+        self.retransmit_at = None;
+    }
+
+    /// 关闭发送方向
+    fn close(&mut self) {
+        self.state = match self.state {
+            State::SynSent | State::SynReceived => {
+                self.abort(None);
+                return;
+            }
+            State::Established => State::FinWait1,
+            State::CloseWait => State::LastAck,
+            _ => return,
+        };
+        self.fin_queued = true;
+    }
+
+    /// 处理收到的报文段，返回连接是否有读写者关心的变化
+    fn input(&mut self, seg: &TcpSegment, now: u64) -> bool {
+        if seg.flags & TCP_RST != 0 {
+            let valid = match self.state {
+                State::SynSent => seg.flags & TCP_ACK != 0 && seg.ack == self.snd_nxt,
+                State::Closed => false,
+                _ => seg.seq.wrapping_sub(self.rcv_nxt) <= self.window().max(1) as u32,
+            };
+            if valid {
+                let error = match self.state {
+                    State::SynSent => NetError::ConnectionRefused,
+                    _ => NetError::ConnectionReset,
+                };
+                self.abort(Some(error));
+            }
+            return valid;
+        }
+
+        match self.state {
+            State::Closed => return false,
+            State::SynSent => {
+                if seg.flags & (TCP_SYN | TCP_ACK) != TCP_SYN | TCP_ACK || seg.ack != self.iss.wrapping_add(1) {
+                    return false;
+                }
+                self.synchronize(seg);
+                self.snd_una = seg.ack;
+                self.state = State::Established;
+                self.retransmit_at = None;
+                self.retries = 0;
+                self.ack_pending = true;
+                return true;
+            }
+            _ => {}
+        }
+
+        if seg.flags & TCP_SYN != 0 {
+            if self.state == State::SynReceived {
+                // 对方没有收到 SYN-ACK，重发
+                self.snd_nxt = self.iss;
+            } else {
+                self.ack_pending = true;
+            }
+            return false;
+        }
+        if seg.flags & TCP_ACK == 0 {
+            return false;
+        }
+
+        let mut changed = false;
+        if self.state == State::SynReceived {
+            if seg.ack != self.iss.wrapping_add(1) {
+                return false;
+            }
+            self.snd_una = seg.ack;
+            self.state = State::Established;
+            self.retransmit_at = None;
+            self.retries = 0;
+            changed = true;
+        }
+
+        // 确认
+        if seq_lt(self.snd_max, seg.ack) {
+            // 确认了还没有发送的数据
+            self.ack_pending = true;
+            return changed;
+        }
+        if seq_le(self.snd_una, seg.ack) {
+            self.snd_wnd = seg.window as u32;
+            if self.snd_wnd > 0 {
+                self.probe = false;
+            }
+        }
+        if seq_lt(self.snd_una, seg.ack) {
+            let acked = seg.ack.wrapping_sub(self.snd_una) as usize;
+            let fin_acked = acked > self.tx.len();
+            self.tx.drain(..acked.min(self.tx.len()));
+            self.snd_una = seg.ack;
+            if seq_lt(self.snd_nxt, self.snd_una) {
+                self.snd_nxt = self.snd_una;
+            }
+            self.rto = INITIAL_RTO_MS;
+            self.retries = 0;
+            self.recovering = false;
+            self.retransmit_at = (self.snd_una != self.snd_max).then_some(now + self.rto);
+            if fin_acked {
+                match self.state {
+                    State::FinWait1 => {
+                        self.state = State::FinWait2;
+                        self.linger_until = now + FIN_WAIT_2_TIMEOUT_MS;
+                    }
+                    State::Closing => {
+                        self.state = State::TimeWait;
+                        self.linger_until = now + TIME_WAIT_MS;
+                    }
+                    State::LastAck => self.state = State::Closed,
+                    _ => {}
+                }
+            }
+            changed = true;
+        } else if self.snd_wnd == 0 && !self.tx.is_empty() {
+            // 对方回应了窗口探测，连接还活着
+            self.retries = 0;
+        }
+
+        // 数据和 FIN
+        let fin = seg.flags & TCP_FIN != 0;
+        if seg.payload.is_empty() && !fin {
+            return changed;
+        }
+        self.ack_pending = true;
+        match self.state {
+            State::Established | State::FinWait1 | State::FinWait2 => {}
+            State::TimeWait => {
+                // 对方重发了 FIN：我们的 ACK 丢了
+                self.linger_until = now + TIME_WAIT_MS;
+                return changed;
+            }
+            _ => return changed,
+        }
+        // 报文段开头可能和已经收到的数据重叠
+        let offset = self.rcv_nxt.wrapping_sub(seg.seq) as usize;
+        if !seq_le(seg.seq, self.rcv_nxt) || offset > seg.payload.len() {
+            return changed;
+        }
+        let data = &seg.payload[offset..];
+        let n = if self.orphaned {
+            // 没有人会读了，确认之后丢弃
+            data.len()
+        } else {
+            let n = data.len().min(self.window() as usize);
+            self.rx.extend(&data[..n]);
+            n
+        };
+        self.rcv_nxt = self.rcv_nxt.wrapping_add(n as u32);
+        changed |= n > 0;
+        if fin && n == data.len() {
+            self.rcv_nxt = self.rcv_nxt.wrapping_add(1);
+            self.fin_received = true;
+            self.state = match self.state {
+                State::Established => State::CloseWait,
+                State::FinWait1 => State::Closing,
+                _ => {
+                    self.linger_until = now + TIME_WAIT_MS;
+                    State::TimeWait
+                }
+            };
+            changed = true;
+        }
+        changed
+    }
+
+    /// 生成需要发送的报文段：SYN、窗口内的新数据（或重传）、FIN 和单独的 ACK
+    fn output(&mut self, now: u64) -> Vec<OutSegment> {
+        let mut segments = Vec::new();
+        match self.state {
+            State::Closed => return segments,
+            State::SynSent | State::SynReceived => {
+                if self.snd_nxt == self.iss {
+                    let (flags, ack) = match self.state {
+                        State::SynSent => (TCP_SYN, 0),
+                        _ => (TCP_SYN | TCP_ACK, self.rcv_nxt),
+                    };
+                    segments.push(OutSegment {
+                        seq: self.iss,
+                        ack,
+                        flags,
+                        mss: Some(MSS as u16),
+                        payload: Vec::new(),
+                    });
+                    self.advance(1, now);
+                }
+                return segments;
+            }
+            _ => {}
+        }
+
+        let mut offset = self.snd_nxt.wrapping_sub(self.snd_una) as usize;
+        let window = match (self.probe, self.recovering) {
+            (true, _) => self.snd_wnd.max(1),
+            (false, true) => self.snd_wnd.min(self.mss as u32),
+            (false, false) => self.snd_wnd,
+        };
+        let window_end = self.snd_una.wrapping_add(window);
+        while offset < self.tx.len() && seq_lt(self.snd_nxt, window_end) {
+            let n = self.mss.min(self.tx.len() - offset).min(window_end.wrapping_sub(self.snd_nxt) as usize);
+            let flags = if offset + n == self.tx.len() { TCP_ACK | TCP_PSH } else { TCP_ACK };
+            segments.push(OutSegment {
+                seq: self.snd_nxt,
+                ack: self.rcv_nxt,
+                flags,
+                mss: None,
+                payload: self.tx.range(offset..offset + n).copied().collect(),
+            });
+            self.advance(n as u32, now);
+            offset += n;
+        }
+        self.probe = false;
+        if offset < self.tx.len() && self.retransmit_at.is_none() {
+            // 窗口为 0：定时探测
+            self.retransmit_at = Some(now + self.rto);
+        }
+        let fin_state = matches!(self.state, State::FinWait1 | State::Closing | State::LastAck);
+        if self.fin_queued && fin_state && offset == self.tx.len() {
+            segments.push(OutSegment {
+                seq: self.snd_nxt,
+                ack: self.rcv_nxt,
+                flags: TCP_FIN | TCP_ACK,
+                mss: None,
+                payload: Vec::new(),
+            });
+            self.advance(1, now);
+        }
+        if self.ack_pending && segments.is_empty() {
+            segments.push(OutSegment {
+                seq: self.snd_nxt,
+                ack: self.rcv_nxt,
+                flags: TCP_ACK,
+                mss: None,
+                payload: Vec::new(),
+            });
+        }
+        self.ack_pending = false;
+        segments
+    }
+
+    /// 发出了 `len` 个序号，启动重传定时器
+    fn advance(&mut self, len: u32, now: u64) {
+        self.snd_nxt = self.snd_nxt.wrapping_add(len);
+        if seq_lt(self.snd_max, self.snd_nxt) {
+            self.snd_max = self.snd_nxt;
+        }
+        if self.retransmit_at.is_none() {
+            self.retransmit_at = Some(now + self.rto);
+        }
+    }
+
+    /// 处理超时；重传次数超过上限时返回 `Some(true)` 表示要发送 RST
+    fn on_timer(&mut self, now: u64) -> Option<bool> {
+        let lingering = self.state == State::TimeWait || (self.state == State::FinWait2 && self.orphaned);
+        if lingering && now >= self.linger_until {
+            self.state = State::Closed;
+            return Some(false);
+        }
+        match self.retransmit_at {
+            Some(at) if now >= at => {}
+            _ => return None,
+        }
+        self.retransmit_at = None;
+        if self.retries >= MAX_RETRIES {
+            let synchronized = self.synchronized();
+            self.abort(Some(NetError::TimedOut));
+            return Some(synchronized);
+        }
+        self.retries += 1;
+        self.rto = (self.rto * 2).min(MAX_RTO_MS);
+        // go-back-N
+        self.snd_nxt = if self.synchronized() { self.snd_una } else { self.iss };
+        self.probe = self.snd_wnd == 0;
+        self.recovering = true;
+        Some(false)
+    }
+}
+
+/// 协议栈中的一个监听套接字
+pub(crate) struct Listener {
+    port: u16,
+}
+
+impl Interface {
+    fn tcp_port_in_use(&self, port: u16) -> bool {
+        self.listeners.values().any(|listener| listener.port == port)
+            || self.tcp.values().any(|tcb| tcb.local_port == port)
+    }
+
+    fn next_iss(&mut self) -> u32 {
+        self.iss_seed = self.iss_seed.wrapping_mul(1_103_515_245).wrapping_add(12_345) ^ self.now as u32;
+        self.iss_seed
+    }
+
+    pub(crate) fn tcp_listen(&mut self, port: u16) -> Result<(usize, u16), NetError> {
+        let port = match port {
+            0 => self.alloc_port(Self::tcp_port_in_use).ok_or(NetError::AddrInUse)?,
+            port if self.listeners.values().any(|listener| listener.port == port) => return Err(NetError::AddrInUse),
+            port => port,
+        };
+        let handle = self.alloc_handle();
+        self.listeners.insert(handle, Listener { port });
+        Ok((handle, port))
+    }
+
+    /// 关闭监听套接字，重置还没有被接受的连接
+    pub(crate) fn tcp_unlisten(&mut self, listener: usize) {
+        self.listeners.remove(&listener);
+        let queued: Vec<usize> =
+            self.tcp.iter().filter(|(_, tcb)| tcb.listener == Some(listener)).map(|(&handle, _)| handle).collect();
+        for handle in queued {
+            let tcb = self.tcp.remove(&handle).unwrap();
+            if tcb.state != State::Closed {
+                self.send_reset(tcb.local_port, tcb.remote, tcb.snd_nxt);
+            }
+        }
+    }
+
+    /// 取出一个已经建立的连接
+    pub(crate) fn tcp_accept(&mut self, listener: usize) -> Result<(usize, SocketAddrV4), NetError> {
+        if !self.listeners.contains_key(&listener) {
+            return Err(NetError::NotConnected);
+        }
+        let (&handle, tcb) = self
+            .tcp
+            .iter_mut()
+            .find(|(_, tcb)| tcb.listener == Some(listener) && tcb.synchronized())
+            .ok_or(NetError::WouldBlock)?;
+        tcb.listener = None;
+        Ok((handle, tcb.remote))
+    }
+
+    pub(crate) fn tcp_connect(&mut self, remote: SocketAddrV4) -> Result<(usize, u16), NetError> {
+        let port = self.alloc_port(Self::tcp_port_in_use).ok_or(NetError::AddrInUse)?;
+        let iss = self.next_iss();
+        let handle = self.alloc_handle();
+        self.tcp.insert(handle, Tcb::new(port, remote, State::SynSent, iss));
+        self.tcp_output(handle);
+        Ok((handle, port))
+    }
+
+    /// 主动打开的结果：还在握手时返回 `WouldBlock`
+    pub(crate) fn tcp_connect_result(&mut self, handle: usize) -> Result<(), NetError> {
+        let tcb = self.tcp.get(&handle).ok_or(NetError::NotConnected)?;
+        match (tcb.state, tcb.error) {
+            (_, Some(err)) => Err(err),
+            (State::SynSent, None) => Err(NetError::WouldBlock),
+            _ => Ok(()),
+        }
+    }
+
+    /// 把数据放进发送缓冲区，返回放进去的字节数；缓冲区满时返回 `WouldBlock`
+    pub(crate) fn tcp_send(&mut self, handle: usize, data: &[u8]) -> Result<usize, NetError> {
+        let tcb = self.tcp.get_mut(&handle).ok_or(NetError::NotConnected)?;
+        if let Some(err) = tcb.error {
+            return Err(err);
+        }
+        if tcb.fin_queued {
+            return Err(NetError::BrokenPipe);
+        }
+        match tcb.state {
+            State::Established | State::CloseWait => {}
+            State::SynSent | State::SynReceived => return Err(NetError::WouldBlock),
+            _ => return Err(NetError::NotConnected),
+        }
+        let n = data.len().min(TX_BUFFER_SIZE - tcb.tx.len());
+        if n == 0 && !data.is_empty() {
+            return Err(NetError::WouldBlock);
+        }
+        tcb.tx.extend(&data[..n]);
+        self.tcp_output(handle);
+        Ok(n)
+    }
+
+    /// 读取收到的数据；没有数据时返回 `WouldBlock`，对方关闭了发送方向时返回 0
+    pub(crate) fn tcp_recv(&mut self, handle: usize, buf: &mut [u8]) -> Result<usize, NetError> {
+        let tcb = self.tcp.get_mut(&handle).ok_or(NetError::NotConnected)?;
+        if !tcb.rx.is_empty() {
+            let before = tcb.window() as usize;
+            let n = buf.len().min(tcb.rx.len());
+            for (dst, src) in buf.iter_mut().zip(tcb.rx.drain(..n)) {
+                *dst = src;
+            }
+            // 窗口从小于一个报文段重新打开时通知对方
+            if before < tcb.mss && tcb.window() as usize >= tcb.mss {
+                tcb.ack_pending = true;
+                self.tcp_output(handle);
+            }
+            return Ok(n);
+        }
+        if tcb.fin_received {
+            return Ok(0);
+        }
+        if let Some(err) = tcb.error {
+            return Err(err);
+        }
+        match tcb.state {
+            State::SynSent | State::SynReceived | State::Established | State::FinWait1 | State::FinWait2 => {
+                Err(NetError::WouldBlock)
+            }
+            _ => Ok(0),
+        }
+    }
+
+    /// 关闭发送方向：发完缓冲区中的数据后发送 FIN
+    pub(crate) fn tcp_shutdown(&mut self, handle: usize) -> Result<(), NetError> {
+        let tcb = self.tcp.get_mut(&handle).ok_or(NetError::NotConnected)?;
+        if let Some(err) = tcb.error {
+            return Err(err);
+        }
+        tcb.close();
+        self.tcp_output(handle);
+        Ok(())
+    }
+
+    /// `TcpStream` 被释放：关闭连接，关闭完成后回收
+    pub(crate) fn tcp_release(&mut self, handle: usize) {
+        let Some(tcb) = self.tcp.get_mut(&handle) else {
+            return;
+        };
+        tcb.close();
+        tcb.orphaned = true;
+        tcb.rx.clear();
+        if tcb.state == State::FinWait2 {
+            tcb.linger_until = self.now + FIN_WAIT_2_TIMEOUT_MS;
+        }
+        if tcb.state == State::Closed {
+            self.tcp.remove(&handle);
+        } else {
+            self.tcp_output(handle);
+        }
+    }
+
+    /// 处理收到的报文段；有对应的连接或监听套接字时返回 true
+    pub(super) fn tcp_input(&mut self, src: Ipv4Addr, seg: TcpSegment) -> bool {
+        let remote = SocketAddrV4::new(src, seg.src_port);
+        let found = self.tcp.iter().find(|(_, tcb)| tcb.local_port == seg.dst_port && tcb.remote == remote);
+        if let Some((&handle, _)) = found {
+            let now = self.now;
+            self.tcp.get_mut(&handle).unwrap().input(&seg, now);
+            self.tcp_output(handle);
+            return true;
+        }
+
+        let listener = self
+            .listeners
+            .iter()
+            .find(|(_, listener)| listener.port == seg.dst_port)
+            .map(|(&handle, _)| handle);
+        if let Some(listener) = listener
+            && seg.flags & (TCP_SYN | TCP_ACK | TCP_RST) == TCP_SYN
+        {
+            let queued = self.tcp.values().filter(|tcb| tcb.listener == Some(listener)).count();
+            if queued >= BACKLOG {
+                // 不回应，对方会重发 SYN
+                return false;
+            }
+            let iss = self.next_iss();
+            let mut tcb = Tcb::new(seg.dst_port, remote, State::SynReceived, iss);
+            tcb.synchronize(&seg);
+            tcb.listener = Some(listener);
+            let handle = self.alloc_handle();
+            self.tcp.insert(handle, tcb);
+            self.tcp_output(handle);
+            return true;
+        }
+
+        if seg.flags & TCP_RST == 0 {
+            if seg.flags & TCP_ACK != 0 {
+                self.send_reset(seg.dst_port, remote, seg.ack);
+            } else {
+                let segment = TcpSegment {
+                    src_port: seg.dst_port,
+                    dst_port: seg.src_port,
+                    seq: 0,
+                    ack: seg.seq.wrapping_add(seg.seq_len()),
+                    flags: TCP_RST | TCP_ACK,
+                    window: 0,
+                    mss: None,
+                    payload: &[],
+                };
+                self.send_segment(remote, &segment);
+            }
+        }
+        false
+    }
+
+    /// 处理所有连接的定时器，回收关闭了的连接；有连接状态变化时返回 true
+    pub(super) fn tcp_timers(&mut self) -> bool {
+        let now = self.now;
+        let mut changed = false;
+        let handles: Vec<usize> = self.tcp.keys().copied().collect();
+        for handle in handles {
+            let tcb = self.tcp.get_mut(&handle).unwrap();
+            match tcb.on_timer(now) {
+                None => continue,
+                Some(reset) => {
+                    changed = true;
+                    let (port, remote, seq) = (tcb.local_port, tcb.remote, tcb.snd_nxt);
+                    if reset {
+                        self.send_reset(port, remote, seq);
+                    }
+                    self.tcp_output(handle);
+                }
+            }
+        }
+        self.tcp.retain(|_, tcb| !(tcb.state == State::Closed && (tcb.orphaned || tcb.listener.is_some())));
+        changed
+    }
+
+    fn tcp_output(&mut self, handle: usize) {
+        let Some(tcb) = self.tcp.get_mut(&handle) else {
+            return;
+        };
+        let segments = tcb.output(self.now);
+        let (port, remote, window) = (tcb.local_port, tcb.remote, tcb.window());
+        for out in segments {
+            let segment = TcpSegment {
+                src_port: port,
+                dst_port: remote.port(),
+                seq: out.seq,
+                ack: out.ack,
+                flags: out.flags,
+                window,
+                mss: out.mss,
+                payload: &out.payload,
+            };
+            self.send_segment(remote, &segment);
+        }
+    }
+
+    fn send_reset(&mut self, port: u16, remote: SocketAddrV4, seq: u32) {
+        let segment = TcpSegment {
+            src_port: port,
+            dst_port: remote.port(),
+            seq,
+            ack: 0,
+            flags: TCP_RST,
+            window: 0,
+            mss: None,
+            payload: &[],
+        };
+        self.send_segment(remote, &segment);
+    }
+
+    fn send_segment(&mut self, remote: SocketAddrV4, segment: &TcpSegment) {
+        self.send_ipv4(*remote.ip(), wire::IP_PROTO_TCP, segment.total_len(), |buf, src| {
+            segment.push(buf, src, *remote.ip())
+        });
+    }
+}
+
+/// 👂 TCP 监听套接字，drop 时重置还没有被接受的连接
+#[derive(Debug)]
+pub struct TcpListener {
+    handle: usize,
+    local: SocketAddrV4,
+}
+
+impl TcpListener {
+    /// 在本机端口上监听；`port` 为 0 时分配一个临时端口
+    pub fn bind(port: u16) -> Result<Self, NetError> {
+        with_iface(|iface| {
+            let (handle, port) = iface.tcp_listen(port)?;
+            Ok(Self {
+                handle,
+                local: SocketAddrV4::new(iface.config().ip, port),
+            })
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddrV4 {
+        self.local
+    }
+
+    /// 等待并接受一个连接，返回连接和对方的地址
+    pub fn accept(&self) -> Result<(TcpStream, SocketAddrV4), NetError> {
+        let (handle, remote) = block_on(|iface| iface.tcp_accept(self.handle))?;
+        let stream = TcpStream {
+            handle,
+            local: self.local,
+            remote,
+        };
+        Ok((stream, remote))
+    }
+}
+
+impl Drop for TcpListener {
+    fn drop(&mut self) {
+        let _ = with_iface(|iface| {
+            iface.tcp_unlisten(self.handle);
+            Ok(())
+        });
+    }
+}
+
+/// 🔗 TCP 连接
+///
+/// 读写都接受 `&self`，可以在一个线程读、另一个线程写；drop 时关闭连接（缓冲区中的数据仍会发完）
+#[derive(Debug)]
+pub struct TcpStream {
+    handle: usize,
+    local: SocketAddrV4,
+    remote: SocketAddrV4,
+}
+
+impl TcpStream {
+    /// 连接到 `addr`，阻塞到握手完成或失败
+    pub fn connect(addr: SocketAddrV4) -> Result<Self, NetError> {
+        let stream = with_iface(|iface| {
+            let (handle, port) = iface.tcp_connect(addr)?;
+            Ok(Self {
+                handle,
+                local: SocketAddrV4::new(iface.config().ip, port),
+                remote: addr,
+            })
+        })?;
+        block_on(|iface| iface.tcp_connect_result(stream.handle))?;
+        Ok(stream)
+    }
+
+    pub fn local_addr(&self) -> SocketAddrV4 {
+        self.local
+    }
+
+    pub fn peer_addr(&self) -> SocketAddrV4 {
+        self.remote
+    }
+
+    /// 读取数据，没有数据时阻塞；返回 0 表示对方已经关闭了发送方向
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, NetError> {
+        block_on(|iface| iface.tcp_recv(self.handle, buf))
+    }
+
+    /// 写入数据，发送缓冲区满时阻塞；返回放进发送缓冲区的字节数
+    pub fn write(&self, data: &[u8]) -> Result<usize, NetError> {
+        block_on(|iface| iface.tcp_send(self.handle, data))
+    }
+
+    /// 写入全部数据
+    pub fn write_all(&self, mut data: &[u8]) -> Result<(), NetError> {
+        while !data.is_empty() {
+            let n = self.write(data)?;
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    /// 关闭发送方向，对方读完数据后读到 EOF；仍然可以继续读
+    pub fn shutdown(&self) -> Result<(), NetError> {
+        with_iface(|iface| iface.tcp_shutdown(self.handle))
+    }
+}
+
+impl Drop for TcpStream {
+    fn drop(&mut self) {
+        let _ = with_iface(|iface| {
+            iface.tcp_release(self.handle);
+            Ok(())
+        });
+    }
+}
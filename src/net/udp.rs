@@ -0,0 +1,140 @@
+//! 📨 UDP 套接字
+//!
+//! - `UdpSocket::bind(port)` 绑定本机端口（0 表示分配临时端口）
+//! - 收到的数据报按到达顺序排队，队列满时丢弃新的数据报
+//! - `recv_from` 阻塞到有数据报为止；缓冲区比数据报短时多出的部分被丢弃（与 `std` 相同）
+//!
+//! 说明：不支持连接式 UDP 和 IP 分片，一个数据报最多 `MAX_PAYLOAD` 字节
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::net::{Ipv4Addr, SocketAddrV4};
+
+use super::iface::Interface;
+use super::wire::{self, UdpDatagram};
+use super::{NetError, block_on, with_iface};
+
+/// 一个 UDP 数据报最多携带的字节数（1500 字节的 MTU 减去 IPv4 和 UDP 头部）
+pub const MAX_PAYLOAD: usize = 1500 - wire::IPV4_HEADER_LEN - wire::UDP_HEADER_LEN;
+/// 每个套接字最多排队的数据报数
+const RX_QUEUE_LEN: usize = 16;
+
+/// 协议栈中的一个 UDP 套接字
+pub(crate) struct UdpState {
+    port: u16,
+    rx: VecDeque<(SocketAddrV4, Vec<u8>)>,
+}
+
+impl Interface {
+    fn udp_port_in_use(&self, port: u16) -> bool {
+        self.udp.values().any(|socket| socket.port == port)
+    }
+
+    pub(crate) fn udp_bind(&mut self, port: u16) -> Result<(usize, u16), NetError> {
+        let port = match port {
+            0 => self.alloc_port(Self::udp_port_in_use).ok_or(NetError::AddrInUse)?,
+            port if self.udp_port_in_use(port) => return Err(NetError::AddrInUse),
+            port => port,
+        };
+        let handle = self.alloc_handle();
+        self.udp.insert(
+            handle,
+            UdpState {
+                port,
+                rx: VecDeque::new(),
+            },
+        );
+        Ok((handle, port))
+    }
+
+    pub(crate) fn udp_close(&mut self, handle: usize) {
+        self.udp.remove(&handle);
+    }
+
+    pub(crate) fn udp_send_to(&mut self, handle: usize, data: &[u8], dst: SocketAddrV4) -> Result<usize, NetError> {
+        let port = self.udp.get(&handle).ok_or(NetError::NotConnected)?.port;
+        if data.len() > MAX_PAYLOAD {
+            return Err(NetError::MessageTooLong);
+        }
+        let datagram = UdpDatagram {
+            src_port: port,
+            dst_port: dst.port(),
+            payload: data,
+        };
+        self.send_ipv4(*dst.ip(), wire::IP_PROTO_UDP, datagram.total_len(), |buf, src| {
+            datagram.push(buf, src, *dst.ip())
+        });
+        Ok(data.len())
+    }
+
+    pub(crate) fn udp_recv_from(&mut self, handle: usize, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), NetError> {
+        let socket = self.udp.get_mut(&handle).ok_or(NetError::NotConnected)?;
+        let (from, data) = socket.rx.pop_front().ok_or(NetError::WouldBlock)?;
+        let n = data.len().min(buf.len());
+        buf[..n].copy_from_slice(&data[..n]);
+        Ok((n, from))
+    }
+
+    /// 把数据报交给绑定了目的端口的套接字；没有套接字接收时返回 false
+    pub(super) fn udp_input(&mut self, src: Ipv4Addr, datagram: UdpDatagram) -> bool {
+        let Some(socket) = self.udp.values_mut().find(|socket| socket.port == datagram.dst_port) else {
+            return false;
+        };
+        if socket.rx.len() >= RX_QUEUE_LEN {
+            return false;
+        }
+        socket.rx.push_back((SocketAddrV4::new(src, datagram.src_port), Vec::from(datagram.payload)));
+        true
+    }
+}
+
+/// 📨 UDP 套接字，drop 时解除绑定
+#[derive(Debug)]
+pub struct UdpSocket {
+    handle: usize,
+    local: SocketAddrV4,
+}
+
+impl UdpSocket {
+    /// 绑定本机端口；`port` 为 0 时分配一个临时端口
+    pub fn bind(port: u16) -> Result<Self, NetError> {
+        with_iface(|iface| {
+            let (handle, port) = iface.udp_bind(port)?;
+            Ok(Self {
+                handle,
+                local: SocketAddrV4::new(iface.config().ip, port),
+            })
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddrV4 {
+        self.local
+    }
+
+    /// 发送一个数据报，返回发送的字节数
+    ///
+    /// 说明：对方地址还没有解析时数据报先排队，所以返回 Ok 不代表已经发出
+    pub fn send_to(&self, data: &[u8], addr: SocketAddrV4) -> Result<usize, NetError> {
+        with_iface(|iface| iface.udp_send_to(self.handle, data, addr))
+    }
+
+    /// 接收一个数据报，返回写入 `buf` 的字节数和发送方地址；没有数据报时阻塞
+    pub fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), NetError> {
+        block_on(|iface| iface.udp_recv_from(self.handle, buf))
+    }
+
+    /// 与 `recv_from` 相同，但没有数据报时返回 `WouldBlock`
+    pub fn try_recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddrV4), NetError> {
+        with_iface(|iface| iface.udp_recv_from(self.handle, buf))
+    }
+}
+
+impl Drop for UdpSocket {
+    fn drop(&mut self) {
+        let _ = with_iface(|iface| {
+            iface.udp_close(self.handle);
+            Ok(())
+        });
+    }
+}
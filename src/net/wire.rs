@@ -0,0 +1,395 @@
+//! 📐 报文格式
+//!
+//! 以太网帧、ARP、IPv4、ICMP、UDP、TCP 头部的解析和构造（网络字节序），以及互联网校验和。
+//! 解析函数只检查长度和格式，返回头部字段和负载的切片；构造函数把报文追加到 `Vec<u8>` 的末尾。
+
+extern crate alloc;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+/// MAC 地址
+pub type MacAddr = [u8; 6];
+
+/// 广播 MAC 地址
+pub const BROADCAST_MAC: MacAddr = [0xff; 6];
+
+pub const ETHERNET_HEADER_LEN: usize = 14;
+pub const ETHERTYPE_IPV4: u16 = 0x0800;
+pub const ETHERTYPE_ARP: u16 = 0x0806;
+
+pub const ARP_LEN: usize = 28;
+pub const ARP_REQUEST: u16 = 1;
+pub const ARP_REPLY: u16 = 2;
+
+pub const IPV4_HEADER_LEN: usize = 20;
+pub const IP_PROTO_ICMP: u8 = 1;
+pub const IP_PROTO_TCP: u8 = 6;
+pub const IP_PROTO_UDP: u8 = 17;
+/// 发出的 IPv4 报文的 TTL
+const IPV4_TTL: u8 = 64;
+/// 标志：不分片
+const IPV4_DONT_FRAGMENT: u16 = 0x4000;
+/// 标志：还有分片 / 分片偏移的掩码
+const IPV4_MORE_FRAGMENTS: u16 = 0x2000;
+const IPV4_FRAGMENT_OFFSET: u16 = 0x1fff;
+
+pub const ICMP_ECHO_REPLY: u8 = 0;
+pub const ICMP_ECHO_REQUEST: u8 = 8;
+
+pub const UDP_HEADER_LEN: usize = 8;
+
+pub const TCP_HEADER_LEN: usize = 20;
+pub const TCP_FIN: u8 = 0x01;
+pub const TCP_SYN: u8 = 0x02;
+pub const TCP_RST: u8 = 0x04;
+pub const TCP_PSH: u8 = 0x08;
+pub const TCP_ACK: u8 = 0x10;
+/// TCP 选项：最大报文段长度
+const TCP_OPTION_END: u8 = 0;
+const TCP_OPTION_NOP: u8 = 1;
+const TCP_OPTION_MSS: u8 = 2;
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+fn ipv4_at(data: &[u8], offset: usize) -> Ipv4Addr {
+    Ipv4Addr::new(data[offset], data[offset + 1], data[offset + 2], data[offset + 3])
+}
+
+fn mac_at(data: &[u8], offset: usize) -> MacAddr {
+    data[offset..offset + 6].try_into().unwrap()
+}
+
+/// 按 16 位大端字累加（奇数长度时最后一个字节补零），返回未折叠的和
+fn sum_words(data: &[u8]) -> u32 {
+    let (words, rest) = data.as_chunks::<2>();
+    let mut sum: u32 = words.iter().map(|&word| u16::from_be_bytes(word) as u32).sum();
+    if let [last] = rest {
+        sum += (*last as u32) << 8;
+    }
+    sum
+}
+
+/// 把累加和折叠成 16 位并取反
+fn fold(mut sum: u32) -> u16 {
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// 互联网校验和（RFC 1071）；对包含校验和字段的数据计算，结果为 0 说明校验正确
+pub fn checksum(data: &[u8]) -> u16 {
+    fold(sum_words(data))
+}
+
+/// 带 IPv4 伪头部的校验和（UDP/TCP）
+fn checksum_with_pseudo_header(src: Ipv4Addr, dst: Ipv4Addr, protocol: u8, segment: &[u8]) -> u16 {
+    let pseudo = sum_words(&src.octets()) + sum_words(&dst.octets()) + protocol as u32 + segment.len() as u32;
+    fold(pseudo + sum_words(segment))
+}
+
+/// 以太网帧
+#[derive(Debug, Clone, Copy)]
+pub struct EthernetFrame<'a> {
+    pub dst: MacAddr,
+    pub src: MacAddr,
+    pub ethertype: u16,
+    pub payload: &'a [u8],
+}
+
+impl<'a> EthernetFrame<'a> {
+    pub fn parse(frame: &'a [u8]) -> Option<Self> {
+        if frame.len() < ETHERNET_HEADER_LEN {
+            return None;
+        }
+        Some(Self {
+            dst: mac_at(frame, 0),
+            src: mac_at(frame, 6),
+            ethertype: u16_at(frame, 12),
+            payload: &frame[ETHERNET_HEADER_LEN..],
+        })
+    }
+}
+
+/// 追加以太网头部
+pub fn push_ethernet(buf: &mut Vec<u8>, dst: MacAddr, src: MacAddr, ethertype: u16) {
+    buf.extend_from_slice(&dst);
+    buf.extend_from_slice(&src);
+    buf.extend_from_slice(&ethertype.to_be_bytes());
+}
+
+/// ARP 报文（只支持以太网 + IPv4）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    pub operation: u16,
+    pub sender_mac: MacAddr,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: MacAddr,
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        // 硬件类型 1（以太网）、协议类型 IPv4、地址长度 6 和 4
+        let ethernet_ipv4 = u16_at(data.get(..ARP_LEN)?, 0) == 1 && u16_at(data, 2) == ETHERTYPE_IPV4;
+        if !ethernet_ipv4 || data[4] != 6 || data[5] != 4 {
+            return None;
+        }
+        Some(Self {
+            operation: u16_at(data, 6),
+            sender_mac: mac_at(data, 8),
+            sender_ip: ipv4_at(data, 14),
+            target_mac: mac_at(data, 18),
+            target_ip: ipv4_at(data, 24),
+        })
+    }
+
+    pub fn push(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&1u16.to_be_bytes());
+        buf.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+        buf.extend_from_slice(&[6, 4]);
+        buf.extend_from_slice(&self.operation.to_be_bytes());
+        buf.extend_from_slice(&self.sender_mac);
+        buf.extend_from_slice(&self.sender_ip.octets());
+        buf.extend_from_slice(&self.target_mac);
+        buf.extend_from_slice(&self.target_ip.octets());
+    }
+}
+
+/// IPv4 报文
+#[derive(Debug, Clone, Copy)]
+pub struct Ipv4Packet<'a> {
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+    pub protocol: u8,
+    pub payload: &'a [u8],
+}
+
+impl<'a> Ipv4Packet<'a> {
+    /// 解析并校验 IPv4 报文；分片的报文不支持，返回 None
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.len() < IPV4_HEADER_LEN || data[0] >> 4 != 4 {
+            return None;
+        }
+        let header_len = (data[0] & 0x0f) as usize * 4;
+        let total_len = u16_at(data, 2) as usize;
+        if header_len < IPV4_HEADER_LEN || total_len < header_len || total_len > data.len() {
+            return None;
+        }
+        if checksum(&data[..header_len]) != 0 {
+            return None;
+        }
+        let fragment = u16_at(data, 6);
+        if fragment & (IPV4_MORE_FRAGMENTS | IPV4_FRAGMENT_OFFSET) != 0 {
+            return None;
+        }
+        Some(Self {
+            src: ipv4_at(data, 12),
+            dst: ipv4_at(data, 16),
+            protocol: data[9],
+            payload: &data[header_len..total_len],
+        })
+    }
+}
+
+/// 追加 IPv4 头部（没有选项，不分片），`payload_len` 是后面负载的长度
+pub fn push_ipv4(buf: &mut Vec<u8>, src: Ipv4Addr, dst: Ipv4Addr, protocol: u8, id: u16, payload_len: usize) {
+    let start = buf.len();
+    buf.push(0x45);
+    buf.push(0);
+    buf.extend_from_slice(&((IPV4_HEADER_LEN + payload_len) as u16).to_be_bytes());
+    buf.extend_from_slice(&id.to_be_bytes());
+    buf.extend_from_slice(&IPV4_DONT_FRAGMENT.to_be_bytes());
+    buf.push(IPV4_TTL);
+    buf.push(protocol);
+    buf.extend_from_slice(&[0, 0]);
+    buf.extend_from_slice(&src.octets());
+    buf.extend_from_slice(&dst.octets());
+    let sum = checksum(&buf[start..]);
+    buf[start + 10..start + 12].copy_from_slice(&sum.to_be_bytes());
+}
+
+/// ICMP 回显请求/应答
+#[derive(Debug, Clone, Copy)]
+pub struct IcmpEcho<'a> {
+    pub kind: u8,
+    pub ident: u16,
+    pub seq: u16,
+    pub data: &'a [u8],
+}
+
+impl<'a> IcmpEcho<'a> {
+    /// 解析 ICMP 回显报文；其他类型的 ICMP 报文返回 None
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.len() < 8 || checksum(data) != 0 || (data[0] != ICMP_ECHO_REQUEST && data[0] != ICMP_ECHO_REPLY) {
+            return None;
+        }
+        Some(Self {
+            kind: data[0],
+            ident: u16_at(data, 4),
+            seq: u16_at(data, 6),
+            data: &data[8..],
+        })
+    }
+
+    pub fn push(&self, buf: &mut Vec<u8>) {
+        let start = buf.len();
+        buf.extend_from_slice(&[self.kind, 0, 0, 0]);
+        buf.extend_from_slice(&self.ident.to_be_bytes());
+        buf.extend_from_slice(&self.seq.to_be_bytes());
+        buf.extend_from_slice(self.data);
+        let sum = checksum(&buf[start..]);
+        buf[start + 2..start + 4].copy_from_slice(&sum.to_be_bytes());
+    }
+
+    pub fn total_len(&self) -> usize {
+        8 + self.data.len()
+    }
+}
+
+/// UDP 数据报
+#[derive(Debug, Clone, Copy)]
+pub struct UdpDatagram<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub payload: &'a [u8],
+}
+
+impl<'a> UdpDatagram<'a> {
+    /// 解析并校验 UDP 数据报（校验和为 0 表示发送方没有计算）
+    pub fn parse(src: Ipv4Addr, dst: Ipv4Addr, data: &'a [u8]) -> Option<Self> {
+        if data.len() < UDP_HEADER_LEN {
+            return None;
+        }
+        let len = u16_at(data, 4) as usize;
+        if len < UDP_HEADER_LEN || len > data.len() {
+            return None;
+        }
+        let data = &data[..len];
+        if u16_at(data, 6) != 0 && checksum_with_pseudo_header(src, dst, IP_PROTO_UDP, data) != 0 {
+            return None;
+        }
+        Some(Self {
+            src_port: u16_at(data, 0),
+            dst_port: u16_at(data, 2),
+            payload: &data[UDP_HEADER_LEN..],
+        })
+    }
+
+    /// 追加 UDP 头部和负载，计算校验和
+    pub fn push(&self, buf: &mut Vec<u8>, src: Ipv4Addr, dst: Ipv4Addr) {
+        let start = buf.len();
+        buf.extend_from_slice(&self.src_port.to_be_bytes());
+        buf.extend_from_slice(&self.dst_port.to_be_bytes());
+        buf.extend_from_slice(&((UDP_HEADER_LEN + self.payload.len()) as u16).to_be_bytes());
+        buf.extend_from_slice(&[0, 0]);
+        buf.extend_from_slice(self.payload);
+        let sum = match checksum_with_pseudo_header(src, dst, IP_PROTO_UDP, &buf[start..]) {
+            // 算出来是 0 时发送全 1（0 表示没有校验和）
+            0 => 0xffff,
+            sum => sum,
+        };
+        buf[start + 6..start + 8].copy_from_slice(&sum.to_be_bytes());
+    }
+
+    pub fn total_len(&self) -> usize {
+        UDP_HEADER_LEN + self.payload.len()
+    }
+}
+
+/// TCP 报文段
+#[derive(Debug, Clone, Copy)]
+pub struct TcpSegment<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub seq: u32,
+    pub ack: u32,
+    pub flags: u8,
+    pub window: u16,
+    /// SYN 中携带的最大报文段长度选项
+    pub mss: Option<u16>,
+    pub payload: &'a [u8],
+}
+
+impl<'a> TcpSegment<'a> {
+    /// 解析并校验 TCP 报文段
+    pub fn parse(src: Ipv4Addr, dst: Ipv4Addr, data: &'a [u8]) -> Option<Self> {
+        if data.len() < TCP_HEADER_LEN {
+            return None;
+        }
+        let header_len = (data[12] >> 4) as usize * 4;
+        if header_len < TCP_HEADER_LEN || header_len > data.len() {
+            return None;
+        }
+        if checksum_with_pseudo_header(src, dst, IP_PROTO_TCP, data) != 0 {
+            return None;
+        }
+        Some(Self {
+            src_port: u16_at(data, 0),
+            dst_port: u16_at(data, 2),
+            seq: u32_at(data, 4),
+            ack: u32_at(data, 8),
+            flags: data[13],
+            window: u16_at(data, 14),
+            mss: parse_mss(&data[TCP_HEADER_LEN..header_len]),
+            payload: &data[header_len..],
+        })
+    }
+
+    /// 追加 TCP 头部（有 `mss` 时带上 MSS 选项）和负载，计算校验和
+    pub fn push(&self, buf: &mut Vec<u8>, src: Ipv4Addr, dst: Ipv4Addr) {
+        let start = buf.len();
+        let header_len = if self.mss.is_some() { TCP_HEADER_LEN + 4 } else { TCP_HEADER_LEN };
+        buf.extend_from_slice(&self.src_port.to_be_bytes());
+        buf.extend_from_slice(&self.dst_port.to_be_bytes());
+        buf.extend_from_slice(&self.seq.to_be_bytes());
+        buf.extend_from_slice(&self.ack.to_be_bytes());
+        buf.push(((header_len / 4) as u8) << 4);
+        buf.push(self.flags);
+        buf.extend_from_slice(&self.window.to_be_bytes());
+        buf.extend_from_slice(&[0, 0, 0, 0]);
+        if let Some(mss) = self.mss {
+            buf.extend_from_slice(&[TCP_OPTION_MSS, 4]);
+            buf.extend_from_slice(&mss.to_be_bytes());
+        }
+        buf.extend_from_slice(self.payload);
+        let sum = checksum_with_pseudo_header(src, dst, IP_PROTO_TCP, &buf[start..]);
+        buf[start + 16..start + 18].copy_from_slice(&sum.to_be_bytes());
+    }
+
+    pub fn total_len(&self) -> usize {
+        TCP_HEADER_LEN + if self.mss.is_some() { 4 } else { 0 } + self.payload.len()
+    }
+
+    /// 报文段占用的序号数：数据长度，SYN 和 FIN 各占一个
+    pub fn seq_len(&self) -> u32 {
+        self.payload.len() as u32 + (self.flags & TCP_SYN != 0) as u32 + (self.flags & TCP_FIN != 0) as u32
+    }
+}
+
+/// 在 TCP 选项中查找 MSS
+fn parse_mss(mut options: &[u8]) -> Option<u16> {
+    while let [kind, rest @ ..] = options {
+        match *kind {
+            TCP_OPTION_END => break,
+            TCP_OPTION_NOP => options = rest,
+            _ => {
+                let len = *rest.first()? as usize;
+                if len < 2 || len > options.len() {
+                    break;
+                }
+                if *kind == TCP_OPTION_MSS && len == 4 {
+                    return Some(u16_at(options, 2));
+                }
+                options = &options[len..];
+            }
+        }
+    }
+    None
+}
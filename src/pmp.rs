@@ -0,0 +1,523 @@
+//! 🛡️ 物理内存保护（PMP）
+//!
+//! RISC-V PMP 按 hart 配置，每个表项由一个 `pmpaddr` 和 `pmpcfg` 中的一个字节组成：
+//! - 编号越小优先级越高，第一个匹配的表项决定访问权限
+//! - 机器模式只受加锁（L 位）表项的约束；加锁的表项在复位前不能修改，
+//!   除非支持 Smepmp 并打开 `mseccfg.RLB`（规则锁定旁路）
+//! - U-mode 受所有表项约束，没有表项匹配的地址一律拒绝访问
+//!
+//! 内核默认的表项布局（每个 hart 在 `init_hart` 中设置）：
+//!
+//! | 表项 | 区域 | 权限 |
+//! |------|------|------|
+//! | 0 | 启动栈保护区（`stack` 模块设置） | 无 |
+//! | 1 | 当前线程栈保护区（需要 Smepmp，`stack` 模块设置） | 无 |
+//! | 2 | 空指针页 `[0, 4K)` | 无 |
+//! | 3~4 | `.text`（TOR） | 只可执行 |
+//! | 5 | `.rodata`（TOR，紧接 `.text`） | 只读 |
+//! | 6 | 当前用户线程的用户栈（不加锁，切换线程时设置） | 读写 |
+//! | 7~12 | 当前用户线程所属程序映像的各段（TOR，不加锁，切换线程时设置） | 按段 |
+//! | 13~15 | 空闲，供应用使用 | |
+//!
+//! 所以 U-mode 只能执行 `.text`、读 `.rodata`、读写自己的用户栈、按段权限访问自己的程序映像，
+//! 其余内存都要通过系统调用访问
+//!
+//! 访问被拒绝时产生 instruction/load/store access fault，`decode_fault` 把它解码成可读的报告
+
+use core::arch::asm;
+use core::fmt::{Display, Formatter};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use log::info;
+
+use crate::dtb;
+
+/// PMP 表项数量（QEMU virt 为 16）
+pub const PMP_ENTRIES: usize = 16;
+
+/// 可读
+pub const PMP_R: u8 = 1 << 0;
+/// 可写
+pub const PMP_W: u8 = 1 << 1;
+/// 可执行
+pub const PMP_X: u8 = 1 << 2;
+/// 加锁：对机器模式也生效
+pub const PMP_L: u8 = 1 << 7;
+/// pmpcfg 中地址匹配模式字段（A）
+const PMP_A: u8 = 3 << 3;
+
+/// 启动栈保护区
+pub const ENTRY_BOOT_STACK_GUARD: usize = 0;
+/// 当前线程栈保护区
+pub const ENTRY_THREAD_STACK_GUARD: usize = 1;
+/// 空指针页
+pub const ENTRY_NULL_GUARD: usize = 2;
+/// `.text` 的 TOR 下界
+const ENTRY_TEXT_BASE: usize = 3;
+/// `.text`
+pub const ENTRY_TEXT: usize = 4;
+/// `.rodata`
+pub const ENTRY_RODATA: usize = 5;
+/// 当前用户线程的用户栈
+pub const ENTRY_USER_STACK: usize = 6;
+/// 用户程序映像各段使用的第一个表项
+pub const ENTRY_USER_REGIONS: usize = 7;
+/// 用户程序映像各段最多使用的表项数
+pub const USER_REGION_ENTRIES: usize = 6;
+/// 第一个空闲表项
+pub const FIRST_FREE_ENTRY: usize = ENTRY_USER_REGIONS + USER_REGION_ENTRIES;
+
+/// 空指针保护页大小
+const NULL_GUARD_SIZE: usize = 4 * 1024;
+
+/// mseccfg.RLB：允许修改/删除已加锁的表项
+const MSECCFG_RLB: usize = 1 << 2;
+
+/// 是否已经打开规则锁定旁路
+static RULE_LOCKING_BYPASS: AtomicBool = AtomicBool::new(false);
+
+/// `.text` 表项是否加锁（调试器打软件断点时需要改写代码，见 `unlock_text`）
+static TEXT_LOCKED: AtomicBool = AtomicBool::new(true);
+
+/// 地址匹配模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressMode {
+    /// 关闭
+    Off,
+    /// Top of range：`[上一个表项的地址, 本表项地址)`
+    Tor,
+    /// 4 字节区域
+    Na4,
+    /// 2 的幂大小、按大小对齐的区域
+    Napot,
+}
+
+impl AddressMode {
+    const fn bits(self) -> u8 {
+        match self {
+            AddressMode::Off => 0,
+            AddressMode::Tor => 1 << 3,
+            AddressMode::Na4 => 2 << 3,
+            AddressMode::Napot => 3 << 3,
+        }
+    }
+
+    const fn from_bits(cfg: u8) -> Self {
+        match (cfg & PMP_A) >> 3 {
+            1 => AddressMode::Tor,
+            2 => AddressMode::Na4,
+            3 => AddressMode::Napot,
+            _ => AddressMode::Off,
+        }
+    }
+}
+
+impl Display for AddressMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.pad(match self {
+            AddressMode::Off => "OFF",
+            AddressMode::Tor => "TOR",
+            AddressMode::Na4 => "NA4",
+            AddressMode::Napot => "NAPOT",
+        })
+    }
+}
+
+/// 一个 PMP 表项（从 CSR 中读出并解码）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PmpEntry {
+    pub index: usize,
+    pub mode: AddressMode,
+    /// `PMP_R`/`PMP_W`/`PMP_X` 的组合
+    pub perm: u8,
+    pub locked: bool,
+    /// 覆盖的地址范围 `[start, end)`；表项关闭时为空
+    pub start: usize,
+    pub end: usize,
+}
+
+impl PmpEntry {
+    pub fn contains(&self, addr: usize) -> bool {
+        self.mode != AddressMode::Off && (self.start..self.end).contains(&addr)
+    }
+
+    /// 是否允许 `access` 访问（只考虑本表项，机器模式下未加锁的表项不生效）
+    pub fn allows(&self, access: Access) -> bool {
+        self.perm & access.perm() != 0
+    }
+
+    /// 内核默认布局中的用途
+    pub fn label(&self) -> &'static str {
+        match self.index {
+            ENTRY_BOOT_STACK_GUARD => "boot stack guard",
+            ENTRY_THREAD_STACK_GUARD => "thread stack guard",
+            ENTRY_NULL_GUARD => "null page",
+            ENTRY_TEXT_BASE | ENTRY_TEXT => ".text",
+            ENTRY_RODATA => ".rodata",
+            ENTRY_USER_STACK => "user stack",
+            index if (ENTRY_USER_REGIONS..FIRST_FREE_ENTRY).contains(&index) => "user image",
+            _ => "user",
+        }
+    }
+}
+
+impl Display for PmpEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "pmp{:<2} {:<18} {:<5} 0x{:016x}-0x{:016x} {}{}{}{}",
+            self.index,
+            self.label(),
+            self.mode,
+            self.start,
+            self.end,
+            if self.perm & PMP_R != 0 { 'r' } else { '-' },
+            if self.perm & PMP_W != 0 { 'w' } else { '-' },
+            if self.perm & PMP_X != 0 { 'x' } else { '-' },
+            if self.locked { " locked" } else { "" },
+        )
+    }
+}
+
+/// 访问类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Execute,
+    Read,
+    Write,
+}
+
+impl Access {
+    const fn perm(self) -> u8 {
+        match self {
+            Access::Execute => PMP_X,
+            Access::Read => PMP_R,
+            Access::Write => PMP_W,
+        }
+    }
+}
+
+/// 把 `[base, base + size)` 编码为 NAPOT 形式的 pmpaddr
+///
+/// 说明：`size` 必须是 2 的幂且不小于 8，`base` 必须按 `size` 对齐
+pub fn napot_addr(base: usize, size: usize) -> usize {
+    assert!(size.is_power_of_two() && size >= 8, "invalid NAPOT size 0x{:x}", size);
+    assert!(base.is_multiple_of(size), "NAPOT base 0x{:x} not aligned to 0x{:x}", base, size);
+    (base | (size / 2 - 1)) >> 2
+}
+
+/// 把表项 `index` 设置为 NAPOT 区域 `[base, base + size)`，权限为 `perm`（`PMP_R`/`PMP_W`/`PMP_X`/`PMP_L` 的组合）
+pub fn set_napot(index: usize, base: usize, size: usize, perm: u8) {
+    let mode = if size == 4 { AddressMode::Na4 } else { AddressMode::Napot };
+    let addr = if size == 4 { base >> 2 } else { napot_addr(base, size) };
+    write_entry(index, addr, perm | mode.bits());
+}
+
+/// 把表项 `index` 设置为 TOR 区域 `[start, end)`
+///
+/// 说明：
+/// - TOR 的下界取自表项 `index - 1` 的地址，所以占用 `index - 1` 和 `index` 两个表项，
+///   `index - 1` 被设置为关闭状态、只提供下界（`index` 为 0 时下界为 0，`start` 必须为 0）
+/// - `start`、`end` 必须 4 字节对齐
+pub fn set_tor(index: usize, start: usize, end: usize, perm: u8) {
+    assert!(start.is_multiple_of(4) && end.is_multiple_of(4), "TOR range must be 4-byte aligned");
+    assert!(start < end, "empty TOR range 0x{:x}-0x{:x}", start, end);
+    if index == 0 {
+        assert!(start == 0, "TOR entry 0 always starts at 0");
+    } else {
+        write_entry(index - 1, start >> 2, AddressMode::Off.bits());
+    }
+    write_entry(index, end >> 2, perm | AddressMode::Tor.bits());
+}
+
+/// 把表项 `index` 设置为紧接上一个表项的 TOR 区域 `[上一个表项的地址, end)`，只占用一个表项
+pub fn set_tor_after(index: usize, end: usize, perm: u8) {
+    assert!(end.is_multiple_of(4), "TOR range must be 4-byte aligned");
+    write_entry(index, end >> 2, perm | AddressMode::Tor.bits());
+}
+
+/// U-mode 可以访问的一段内存 `[start, end)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub start: usize,
+    pub end: usize,
+    /// `PMP_R`/`PMP_W`/`PMP_X` 的组合
+    pub perm: u8,
+}
+
+/// 按地址排好序的区域需要的 TOR 表项数：与上一个区域相邻时共用下界，只占一个表项，否则占两个
+pub fn user_region_entries(regions: &[Region]) -> usize {
+    regions
+        .iter()
+        .enumerate()
+        .map(|(i, region)| if i > 0 && regions[i - 1].end == region.start { 1 } else { 2 })
+        .sum()
+}
+
+/// 用不加锁的 TOR 表项授权 U-mode 访问 `regions`（按地址排序、4 字节对齐），其余用户映像表项关闭
+///
+/// 说明：表项不够（见 `user_region_entries`）时 panic，调用方应在装载程序时检查
+pub fn set_user_regions(regions: &[Region]) {
+    assert!(
+        user_region_entries(regions) <= USER_REGION_ENTRIES,
+        "{} user regions need more than {} PMP entries",
+        regions.len(),
+        USER_REGION_ENTRIES
+    );
+    let mut index = ENTRY_USER_REGIONS;
+    let mut prev_end = None;
+    for region in regions {
+        if prev_end == Some(region.start) {
+            set_tor_after(index, region.end, region.perm);
+            index += 1;
+        } else {
+            set_tor(index + 1, region.start, region.end, region.perm);
+            index += 2;
+        }
+        prev_end = Some(region.end);
+    }
+    for index in index..FIRST_FREE_ENTRY {
+        clear(index);
+    }
+}
+
+/// 关闭表项 `index`（已加锁时需要先打开规则锁定旁路）
+pub fn clear(index: usize) {
+    write_entry(index, 0, AddressMode::Off.bits());
+}
+
+/// 读取并解码表项 `index`
+pub fn entry(index: usize) -> PmpEntry {
+    let cfg = read_cfg_byte(index);
+    let addr = read_addr(index);
+    let mode = AddressMode::from_bits(cfg);
+    let (start, end) = match mode {
+        AddressMode::Off => (0, 0),
+        AddressMode::Tor => {
+            let start = if index == 0 { 0 } else { read_addr(index - 1) << 2 };
+            (start, addr << 2)
+        }
+        AddressMode::Na4 => (addr << 2, (addr << 2) + 4),
+        AddressMode::Napot => {
+            let ones = addr.trailing_ones() as usize;
+            let size = 1usize << (ones + 3);
+            let base = (addr & !((1usize << ones) - 1)) << 2;
+            (base, base.wrapping_add(size))
+        }
+    };
+    PmpEntry {
+        index,
+        mode,
+        perm: cfg & (PMP_R | PMP_W | PMP_X),
+        locked: cfg & PMP_L != 0,
+        start,
+        end,
+    }
+}
+
+/// 第一个匹配 `addr` 的表项（即决定访问权限的表项）
+pub fn lookup(addr: usize) -> Option<PmpEntry> {
+    (0..PMP_ENTRIES).map(entry).find(|entry| entry.contains(addr))
+}
+
+/// U-mode 能否以 `access` 方式访问整个 `[start, start + len)`（检查系统调用传入的指针）
+///
+/// 说明：区间必须落在同一个允许该访问的表项内，并且不与优先级更高的表项重叠
+pub fn user_accessible(start: usize, len: usize, access: Access) -> bool {
+    if len == 0 {
+        return true;
+    }
+    let Some(end) = start.checked_add(len) else {
+        return false;
+    };
+    let Some(matched) = lookup(start) else {
+        return false;
+    };
+    matched.allows(access)
+        && end <= matched.end
+        && (0..matched.index)
+            .map(entry)
+            .all(|entry| entry.mode == AddressMode::Off || entry.end <= start || end <= entry.start)
+}
+
+/// M-mode 能否以 `access` 方式访问整个 `[start, start + len)`（不会触发访问异常）
+///
+/// 说明：
+/// - M-mode 只受加锁的表项约束：与区间重叠的加锁表项都必须允许该访问
+/// - 只检查 PMP，不检查地址上是否真的有内存或设备
+pub fn kernel_accessible(start: usize, len: usize, access: Access) -> bool {
+    let Some(end) = start.checked_add(len) else {
+        return false;
+    };
+    (0..PMP_ENTRIES)
+        .map(entry)
+        .filter(|entry| entry.locked && entry.mode != AddressMode::Off)
+        .all(|entry| entry.end <= start || end <= entry.start || entry.allows(access))
+}
+
+/// 打印当前 hart 所有启用的表项
+pub fn print_entries() {
+    info!("🛡️ PMP entries:");
+    for entry in (0..PMP_ENTRIES).map(entry).filter(|entry| entry.mode != AddressMode::Off) {
+        info!("   {}", entry);
+    }
+}
+
+/// 打开 Smepmp 的规则锁定旁路（`mseccfg.RLB`），之后加锁的表项仍可修改
+///
+/// 说明：必须在加锁任何表项之前调用；调用方需要确认 hart 支持 Smepmp，否则访问 mseccfg 会触发非法指令异常
+pub fn enable_rule_locking_bypass() {
+    unsafe { asm!("csrs 0x747, {0}", in(reg) MSECCFG_RLB) };
+    RULE_LOCKING_BYPASS.store(true, Ordering::Relaxed);
+}
+
+/// 是否已经打开规则锁定旁路（加锁的表项仍可修改）
+pub fn rule_locking_bypass() -> bool {
+    RULE_LOCKING_BYPASS.load(Ordering::Relaxed)
+}
+
+/// 让 `.text` 表项不加锁：机器模式可以改写代码（打软件断点），U-mode 仍然只能执行
+///
+/// 说明：只影响之后调用 `init_hart` 的 hart，必须在 `thread::init`（其中调用 `trap::init`）之前调用
+pub fn unlock_text() {
+    TEXT_LOCKED.store(false, Ordering::Relaxed);
+}
+
+/// 🛡️ 为当前 hart 设置内核默认的保护（由 `trap::init` 调用）
+///
+/// 说明：
+/// - 设备树声明 Smepmp 时先打开规则锁定旁路
+/// - 空指针页禁止访问，`.text` 只可执行，`.rodata` 只读；这些表项都加锁，对机器模式生效
+///   （调用过 `unlock_text` 时 `.text` 表项不加锁，只约束 U-mode）
+/// - 其它内存（`.data`、`.bss`、堆、外设）不匹配任何表项，机器模式可以任意访问
+pub fn init_hart() {
+    unsafe extern "C" {
+        static __TEXT_START: u8;
+        static __TEXT_END: u8;
+        static __RODATA_END: u8;
+    }
+    if dtb::platform().smepmp {
+        enable_rule_locking_bypass();
+    }
+    let (text_start, text_end, rodata_end) = unsafe {
+        (
+            &__TEXT_START as *const u8 as usize,
+            &__TEXT_END as *const u8 as usize,
+            &__RODATA_END as *const u8 as usize,
+        )
+    };
+    set_napot(ENTRY_NULL_GUARD, 0, NULL_GUARD_SIZE, PMP_L);
+    let text_lock = if TEXT_LOCKED.load(Ordering::Relaxed) { PMP_L } else { 0 };
+    set_tor(ENTRY_TEXT, text_start, text_end.next_multiple_of(4), PMP_X | text_lock);
+    // .rodata 紧接 .text（中间只有对齐填充）
+    set_tor_after(ENTRY_RODATA, rodata_end.next_multiple_of(4), PMP_R | PMP_L);
+}
+
+/// 解码后的访问异常
+#[derive(Debug, Clone, Copy)]
+pub struct AccessFault {
+    pub access: Access,
+    /// 出错的地址（mtval）
+    pub addr: usize,
+    /// 出错的指令地址（mepc）
+    pub epc: usize,
+    pub hart_id: usize,
+    /// 拒绝这次访问的表项；None 表示没有表项匹配（访问了不存在的物理地址）
+    pub entry: Option<PmpEntry>,
+}
+
+impl Display for AccessFault {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let access = match self.access {
+            Access::Execute => "instruction fetch",
+            Access::Read => "load",
+            Access::Write => "store",
+        };
+        write!(f, "{} access fault on hart {} at 0x{:x} (pc=0x{:x})", access, self.hart_id, self.addr, self.epc)?;
+        match &self.entry {
+            Some(entry) if !entry.allows(self.access) => write!(f, ": denied by {}", entry),
+            Some(entry) => write!(f, ": {} allows it, bus error?", entry),
+            None => write!(f, ": no PMP entry matches, bus error?"),
+        }
+    }
+}
+
+/// 把 access fault（mcause 1/5/7）解码成可读的报告；其它 cause 返回 None
+pub fn decode_fault(cause: usize, addr: usize, epc: usize) -> Option<AccessFault> {
+    let access = match cause {
+        1 => Access::Execute,
+        5 => Access::Read,
+        7 => Access::Write,
+        _ => return None,
+    };
+    Some(AccessFault {
+        access,
+        addr,
+        epc,
+        hart_id: crate::hart::id(),
+        entry: lookup(addr),
+    })
+}
+
+/// 写入一个表项：先写 pmpaddr，再替换 pmpcfg 中对应的字节
+fn write_entry(index: usize, addr: usize, cfg: u8) {
+    assert!(index < PMP_ENTRIES, "PMP entry {} out of range", index);
+    // RV64 只有偶数编号的 pmpcfg，每个包含 8 个表项
+    let shift = (index % 8) * 8;
+    let mut value = read_cfg(index / 8);
+    value &= !(0xff << shift);
+    value |= (cfg as usize) << shift;
+    write_addr(index, addr);
+    write_cfg(index / 8, value);
+    unsafe { asm!("sfence.vma") };
+}
+
+fn read_cfg_byte(index: usize) -> u8 {
+    (read_cfg(index / 8) >> ((index % 8) * 8)) as u8
+}
+
+fn read_cfg(reg: usize) -> usize {
+    let value: usize;
+    unsafe {
+        match reg {
+            0 => asm!("csrr {0}, pmpcfg0", out(reg) value),
+            _ => asm!("csrr {0}, pmpcfg2", out(reg) value),
+        }
+    }
+    value
+}
+
+fn write_cfg(reg: usize, value: usize) {
+    unsafe {
+        match reg {
+            0 => asm!("csrw pmpcfg0, {0}", in(reg) value),
+            _ => asm!("csrw pmpcfg2, {0}", in(reg) value),
+        }
+    }
+}
+
+fn read_addr(index: usize) -> usize {
+    let addr: usize;
+    macro_rules! read_pmpaddr {
+        ($($n:literal),*) => {
+            match index {
+                $($n => unsafe { asm!(concat!("csrr {0}, pmpaddr", $n), out(reg) addr) },)*
+                _ => unreachable!(),
+            }
+        };
+    }
+    read_pmpaddr!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+    addr
+}
+
+fn write_addr(index: usize, addr: usize) {
+    macro_rules! write_pmpaddr {
+        ($($n:literal),*) => {
+            match index {
+                $($n => unsafe { asm!(concat!("csrw pmpaddr", $n, ", {0}"), in(reg) addr) },)*
+                _ => unreachable!(),
+            }
+        };
+    }
+    write_pmpaddr!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+}
@@ -0,0 +1,231 @@
+//! 🧰 内置命令
+//!
+//! | 命令 | 说明 |
+//! |------|------|
+//! | `mem` | 内存布局（`system::print_memory_layout`） |
+//! | `heap` | 堆的使用情况 |
+//! | `ps` | 线程列表（`thread::print_list`） |
+//! | `log [level]` | 查看或修改日志级别 |
+//! | `time` | 启动以来的时间 |
+//! | `spawn [program] [args...]` | 运行 `loader` 中嵌入的用户程序，不带参数时列出所有程序 |
+//! | `kill <id>` | 结束用户线程，id 为 `ps` 中的 `槽位.代数`，也可以只写槽位 |
+//! | `peek <addr> [count]` | 读内存（按 8 字节） |
+//! | `poke <addr> <value>` | 写内存（8 字节） |
+//! | `reboot` / `shutdown` | 重启 / 关机 |
+//!
+//! peek/poke 只允许访问设备树描述的内存，并且不能越过加锁的 PMP 表项，避免一次误操作让内核 panic
+
+extern crate alloc;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use super::{Command, CommandError, parse_number};
+use crate::pmp::{self, Access};
+use crate::thread::{self, ThreadInfo};
+use crate::{dtb, heap, loader, logging, println, system, timer};
+
+/// peek 一次最多读取的字数
+const MAX_PEEK_WORDS: usize = 64;
+
+/// 由函数实现的内置命令
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub run: fn(&[&str]) -> Result<(), CommandError>,
+}
+
+impl Command for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn usage(&self) -> &str {
+        self.usage
+    }
+
+    fn help(&self) -> &str {
+        self.help
+    }
+
+    fn run(&self, args: &[&str]) -> Result<(), CommandError> {
+        (self.run)(args)
+    }
+}
+
+/// 所有内置命令
+pub const BUILTINS: &[Builtin] = &[
+    Builtin { name: "mem", usage: "mem", help: "print the memory layout", run: mem },
+    Builtin { name: "heap", usage: "heap", help: "print heap usage", run: heap },
+    Builtin { name: "ps", usage: "ps", help: "list threads", run: ps },
+    Builtin { name: "log", usage: "log [off|error|warn|info|debug|trace]", help: "show or set the log level", run: log },
+    Builtin { name: "time", usage: "time", help: "print the time since boot", run: time },
+    Builtin { name: "spawn", usage: "spawn [program] [args...]", help: "run a user program, or list them", run: spawn },
+    Builtin { name: "kill", usage: "kill <id>", help: "terminate a user thread", run: kill },
+    Builtin { name: "peek", usage: "peek <addr> [count]", help: "read 64-bit words from memory", run: peek },
+    Builtin { name: "poke", usage: "poke <addr> <value>", help: "write a 64-bit word to memory", run: poke },
+    Builtin { name: "reboot", usage: "reboot", help: "reboot the machine", run: reboot },
+    Builtin { name: "shutdown", usage: "shutdown", help: "power off the machine", run: shutdown },
+];
+
+fn no_args(args: &[&str]) -> Result<(), CommandError> {
+    if args.is_empty() { Ok(()) } else { Err(CommandError::Usage) }
+}
+
+fn mem(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    system::print_memory_layout();
+    Ok(())
+}
+
+fn heap(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    let stats = heap::stats();
+    println!(
+        "heap: {} / {} bytes used ({}%), {} bytes requested",
+        stats.actual,
+        stats.total,
+        stats.actual * 100 / stats.total.max(1),
+        stats.user
+    );
+    Ok(())
+}
+
+fn ps(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    thread::print_list();
+    Ok(())
+}
+
+fn log(args: &[&str]) -> Result<(), CommandError> {
+    match args {
+        [] => println!("log level: {}", logging::level()),
+        [name] => {
+            let level = logging::parse_level(name)
+                .ok_or_else(|| CommandError::InvalidArgument(format!("unknown log level '{}'", name)))?;
+            logging::set_level(level);
+            println!("log level set to {}", level);
+        }
+        _ => return Err(CommandError::Usage),
+    }
+    Ok(())
+}
+
+fn time(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    let ticks = timer::get_time();
+    let freq = timer::clock_freq();
+    let ms = ticks / (freq / 1000);
+    println!("uptime: {}.{:03} s ({} ticks at {} Hz)", ms / 1000, ms % 1000, ticks, freq);
+    Ok(())
+}
+
+fn spawn(args: &[&str]) -> Result<(), CommandError> {
+    let Some(&name) = args.first() else {
+        println!("user programs:");
+        for app in loader::apps() {
+            println!("  {}", app.name);
+        }
+        return Ok(());
+    };
+    let handle = loader::spawn(name, args).map_err(|err| CommandError::Failed(err.to_string()))?;
+    let id = handle.thread().id();
+    println!("[{}] {} started", id, name);
+    // 由一个内核线程等待它结束并报告退出状态，shell 不必等待
+    let name = name.to_string();
+    thread::Builder::new().name("spawn-wait").spawn(move || match handle.join() {
+        Ok(status) => println!("[{}] {} {}", id, name, status),
+        Err(err) => println!("[{}] {}: {}", id, name, err),
+    });
+    Ok(())
+}
+
+fn kill(args: &[&str]) -> Result<(), CommandError> {
+    let [id] = args else {
+        return Err(CommandError::Usage);
+    };
+    let thread = find_thread(id)?;
+    thread::kill(thread.handle()).map_err(|err| CommandError::Failed(err.to_string()))?;
+    println!("[{}] killed", thread.id);
+    Ok(())
+}
+
+/// 按 `槽位.代数` 或只按槽位查找线程
+fn find_thread(id: &str) -> Result<ThreadInfo, CommandError> {
+    let invalid = || CommandError::InvalidArgument(format!("'{}' is not a thread id", id));
+    let (slot, generation) = match id.split_once('.') {
+        Some((slot, generation)) => (slot, Some(generation.parse::<u32>().map_err(|_| invalid())?)),
+        None => (id, None),
+    };
+    let slot: usize = slot.parse().map_err(|_| invalid())?;
+    thread::list()
+        .into_iter()
+        .find(|t| t.id.slot() == slot && generation.is_none_or(|g| t.id.generation() == g))
+        .ok_or_else(|| CommandError::Failed(format!("no thread {}", id)))
+}
+
+/// 检查 peek/poke 的地址：8 字节对齐、在内存中且 PMP 允许访问
+fn check_address(addr: usize, len: usize, access: Access) -> Result<(), CommandError> {
+    if addr % 8 != 0 {
+        return Err(CommandError::InvalidArgument(format!("0x{:x} is not 8-byte aligned", addr)));
+    }
+    let end = addr.saturating_add(len);
+    let in_memory = dtb::platform()
+        .memory_region_of(addr)
+        .is_some_and(|region| end <= region.end());
+    if !in_memory {
+        return Err(CommandError::InvalidArgument(format!("0x{:x}-0x{:x} is not in memory", addr, end)));
+    }
+    if !pmp::kernel_accessible(addr, len, access) {
+        return Err(CommandError::Failed(format!("0x{:x}-0x{:x} is protected by PMP", addr, end)));
+    }
+    Ok(())
+}
+
+fn peek(args: &[&str]) -> Result<(), CommandError> {
+    let (addr, count) = match args {
+        [addr] => (parse_number(addr)?, 1),
+        [addr, count] => (parse_number(addr)?, parse_number(count)?),
+        _ => return Err(CommandError::Usage),
+    };
+    if count == 0 || count > MAX_PEEK_WORDS {
+        return Err(CommandError::InvalidArgument(format!("count must be 1..={}", MAX_PEEK_WORDS)));
+    }
+    check_address(addr, count * 8, Access::Read)?;
+    let words: Vec<usize> = (0..count)
+        .map(|i| unsafe { core::ptr::read_volatile((addr + i * 8) as *const usize) })
+        .collect();
+    for (i, chunk) in words.chunks(2).enumerate() {
+        match chunk {
+            [a, b] => println!("0x{:016x}: 0x{:016x} 0x{:016x}", addr + i * 16, a, b),
+            [a] => println!("0x{:016x}: 0x{:016x}", addr + i * 16, a),
+            _ => unreachable!(),
+        }
+    }
+    Ok(())
+}
+
+fn poke(args: &[&str]) -> Result<(), CommandError> {
+    let [addr, value] = args else {
+        return Err(CommandError::Usage);
+    };
+    let (addr, value) = (parse_number(addr)?, parse_number(value)?);
+    check_address(addr, 8, Access::Write)?;
+    unsafe { core::ptr::write_volatile(addr as *mut usize, value) };
+    println!("0x{:016x} <- 0x{:016x}", addr, value);
+    Ok(())
+}
+
+fn reboot(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    println!("rebooting...");
+    system::reboot()
+}
+
+fn shutdown(args: &[&str]) -> Result<(), CommandError> {
+    no_args(args)?;
+    println!("bye");
+    system::shutdown()
+}
@@ -0,0 +1,139 @@
+//! ⌨️ 行编辑
+//!
+//! 从串口逐字节读取一行输入并回显，支持：
+//! - 退格（Backspace/DEL）删除最后一个字符
+//! - Ctrl-C 放弃当前行，Ctrl-U 清空当前行
+//! - 上/下方向键浏览历史记录
+//!
+//! 只接受可打印的 ASCII 字符；没有输入时线程睡眠一小段时间再轮询，不占用 CPU
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{console, print, println, thread};
+
+/// 一行最多的字符数
+pub const MAX_LINE: usize = 256;
+
+/// 最多保留的历史记录条数
+pub const HISTORY_SIZE: usize = 32;
+
+/// 没有输入时的轮询间隔（毫秒）
+const POLL_INTERVAL_MS: usize = 10;
+
+const CTRL_C: u8 = 0x03;
+const BACKSPACE: u8 = 0x08;
+const CTRL_U: u8 = 0x15;
+const ESC: u8 = 0x1b;
+const DEL: u8 = 0x7f;
+
+/// 转义序列的解析状态（方向键为 `ESC [ A` 这样的 3 字节序列）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Esc,
+    Csi,
+}
+
+/// ⌨️ 带历史记录的行编辑器
+pub struct LineEditor {
+    history: Vec<String>,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineEditor {
+    pub const fn new() -> Self {
+        Self { history: Vec::new() }
+    }
+
+    /// 历史记录（从旧到新）
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// 打印提示符并读取一行（不含换行符）
+    ///
+    /// 说明：非空、且与上一条不同的行加入历史记录
+    pub fn read_line(&mut self, prompt: &str) -> String {
+        let mut line = String::new();
+        // 正在浏览的历史记录下标，history.len() 表示正在编辑的新行
+        let mut cursor = self.history.len();
+        let mut escape = Escape::None;
+        print!("{}", prompt);
+        loop {
+            let byte = read_byte();
+            match (escape, byte) {
+                (Escape::Esc, b'[') => escape = Escape::Csi,
+                (Escape::Csi, b'A') | (Escape::Csi, b'B') => {
+                    escape = Escape::None;
+                    let next = if byte == b'A' {
+                        cursor.checked_sub(1)
+                    } else {
+                        (cursor < self.history.len()).then_some(cursor + 1)
+                    };
+                    if let Some(next) = next {
+                        cursor = next;
+                        line = self.history.get(cursor).cloned().unwrap_or_default();
+                        redraw(prompt, &line);
+                    }
+                }
+                // 其它转义序列（左右方向键等）忽略
+                (Escape::Esc, _) | (Escape::Csi, _) => escape = Escape::None,
+                (Escape::None, ESC) => escape = Escape::Esc,
+                (Escape::None, b'\r') | (Escape::None, b'\n') => {
+                    println!();
+                    break;
+                }
+                (Escape::None, BACKSPACE) | (Escape::None, DEL) => {
+                    if line.pop().is_some() {
+                        print!("\x08 \x08");
+                    }
+                }
+                (Escape::None, CTRL_C) => {
+                    println!("^C");
+                    line.clear();
+                    cursor = self.history.len();
+                    print!("{}", prompt);
+                }
+                (Escape::None, CTRL_U) => {
+                    line.clear();
+                    redraw(prompt, &line);
+                }
+                (Escape::None, 0x20..=0x7e) if line.len() < MAX_LINE => {
+                    line.push(byte as char);
+                    print!("{}", byte as char);
+                }
+                _ => {}
+            }
+        }
+
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            if self.history.len() == HISTORY_SIZE {
+                self.history.remove(0);
+            }
+            self.history.push(line.clone());
+        }
+        line
+    }
+}
+
+/// 等待并读取一个输入字节
+fn read_byte() -> u8 {
+    loop {
+        if let Some(byte) = console::read_byte() {
+            return byte;
+        }
+        thread::sleep(POLL_INTERVAL_MS);
+    }
+}
+
+/// 清除当前行并重新输出提示符和内容
+fn redraw(prompt: &str, line: &str) {
+    print!("\r\x1b[K{}{}", prompt, line);
+}
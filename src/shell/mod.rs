@@ -0,0 +1,177 @@
+//! 🐚 串口交互式 shell
+//!
+//! 在串口控制台上提供一个带行编辑的命令提示符，不用改 `main` 重启就能查看和操作系统状态：
+//! - 内置命令见 `builtins`：`mem`、`heap`、`ps`、`log`、`time`、`spawn`、`kill`、`peek`、`poke`、`reboot`、`shutdown`，
+//!   以及列出所有命令的 `help`
+//! - 应用实现 `Command` trait 注册自己的命令，与内置命令同名时覆盖内置命令
+//! - 命令行按空白切分为参数，不支持引号和转义
+//!
+//! 用法：
+//! ```ignore
+//! let mut shell = Shell::new();
+//! shell.register(MyCommand);
+//! shell.run();
+//! ```
+
+pub mod builtins;
+pub mod line;
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use crate::println;
+
+pub use line::LineEditor;
+
+/// 默认提示符
+pub const DEFAULT_PROMPT: &str = "> ";
+
+/// 命令执行失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    /// 参数个数或格式不对，shell 会打印命令的用法
+    Usage,
+    /// 参数的值不合法
+    InvalidArgument(String),
+    /// 执行失败
+    Failed(String),
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CommandError::Usage => write!(f, "invalid usage"),
+            CommandError::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            CommandError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// 🐚 shell 命令
+///
+/// 用法：
+/// ```ignore
+/// struct Echo;
+///
+/// impl Command for Echo {
+///     fn name(&self) -> &str { "echo" }
+///     fn usage(&self) -> &str { "echo [text...]" }
+///     fn help(&self) -> &str { "print the arguments" }
+///     fn run(&self, args: &[&str]) -> Result<(), CommandError> {
+///         println!("{}", args.join(" "));
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Command {
+    /// 命令名（命令行的第一个词）
+    fn name(&self) -> &str;
+
+    /// 用法，例如 `peek <addr> [count]`，默认只有命令名
+    fn usage(&self) -> &str {
+        self.name()
+    }
+
+    /// 一行说明，`help` 中显示
+    fn help(&self) -> &str;
+
+    /// 执行命令，`args` 不包含命令名
+    fn run(&self, args: &[&str]) -> Result<(), CommandError>;
+}
+
+/// 🐚 交互式 shell
+pub struct Shell {
+    prompt: String,
+    commands: Vec<Box<dyn Command>>,
+    editor: LineEditor,
+}
+
+impl Default for Shell {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shell {
+    /// 创建 shell 并注册所有内置命令
+    pub fn new() -> Self {
+        let mut shell = Self {
+            prompt: String::from(DEFAULT_PROMPT),
+            commands: Vec::new(),
+            editor: LineEditor::new(),
+        };
+        for &builtin in builtins::BUILTINS {
+            shell.register(builtin);
+        }
+        shell
+    }
+
+    /// 设置提示符
+    pub fn set_prompt(&mut self, prompt: &str) {
+        self.prompt = String::from(prompt);
+    }
+
+    /// 注册命令；已有同名命令时替换它
+    pub fn register(&mut self, command: impl Command + 'static) {
+        let command: Box<dyn Command> = Box::new(command);
+        match self.commands.iter_mut().find(|c| c.name() == command.name()) {
+            Some(slot) => *slot = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    /// 按名字查找命令
+    pub fn command(&self, name: &str) -> Option<&dyn Command> {
+        self.commands.iter().find(|c| c.name() == name).map(|c| c.as_ref())
+    }
+
+    /// 执行一行命令，出错时打印原因
+    pub fn execute(&self, line: &str) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = words.split_first() else {
+            return;
+        };
+        if name == "help" {
+            self.print_help();
+            return;
+        }
+        let Some(command) = self.command(name) else {
+            println!("{}: command not found, type 'help' for a list of commands", name);
+            return;
+        };
+        match command.run(args) {
+            Ok(()) => {}
+            Err(CommandError::Usage) => println!("usage: {}", command.usage()),
+            Err(err) => println!("{}: {}", name, err),
+        }
+    }
+
+    /// 打印所有命令的用法和说明
+    pub fn print_help(&self) {
+        println!("{:<28} {}", "help", "list all commands");
+        for command in &self.commands {
+            println!("{:<28} {}", command.usage(), command.help());
+        }
+    }
+
+    /// 🐚 循环读取并执行命令（不会返回）
+    pub fn run(&mut self) -> ! {
+        println!("🐚 shell ready, type 'help' for a list of commands");
+        loop {
+            let line = self.editor.read_line(&self.prompt);
+            self.execute(&line);
+        }
+    }
+}
+
+/// 解析数字参数：`0x` 开头按十六进制，否则按十进制
+pub fn parse_number(arg: &str) -> Result<usize, CommandError> {
+    let parsed = match arg.strip_prefix("0x").or_else(|| arg.strip_prefix("0X")) {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => arg.parse(),
+    };
+    parsed.map_err(|_| CommandError::InvalidArgument(alloc::format!("'{}' is not a number", arg)))
+}
@@ -0,0 +1,130 @@
+//! 🔒 自旋锁模块
+//!
+//! 提供多核安全、关中断的自旋锁 `SpinLock<T>`：
+//! - 加锁时关闭当前 hart 的中断，避免中断处理函数在同一个 hart 上重复加锁导致死锁
+//! - 解锁时恢复加锁前的中断状态
+//! - 支持“跨上下文切换持锁”：切换前 `leak` 掉 guard，切换后由新上下文 `adopt` 并释放
+
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::trap;
+
+/// 🔒 关中断自旋锁
+pub struct SpinLock<T> {
+    locked: AtomicBool,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send> Sync for SpinLock<T> {}
+unsafe impl<T: Send> Send for SpinLock<T> {}
+
+/// 自旋锁的 RAII guard，离开作用域时自动解锁并恢复中断状态
+pub struct SpinLockGuard<'a, T> {
+    lock: &'a SpinLock<T>,
+    /// 加锁前中断是否开启
+    irq_enabled: bool,
+    /// 让 guard 不自动实现 Sync（否则 `T: Send` 就够了），由下面按 `T: Sync` 实现
+    _not_sync: PhantomData<*const ()>,
+}
+
+// 共享 guard 相当于共享 &T
+unsafe impl<T: Sync> Sync for SpinLockGuard<'_, T> {}
+
+impl<T> SpinLock<T> {
+    pub const fn new(data: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// 加锁（自旋等待），期间关闭当前 hart 的中断
+    pub fn lock(&self) -> SpinLockGuard<'_, T> {
+        let irq_enabled = trap::disable_interrupts();
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        SpinLockGuard {
+            lock: self,
+            irq_enabled,
+            _not_sync: PhantomData,
+        }
+    }
+
+    /// 尝试加锁，失败时立即返回 None
+    pub fn try_lock(&self) -> Option<SpinLockGuard<'_, T>> {
+        let irq_enabled = trap::disable_interrupts();
+        if self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            Some(SpinLockGuard {
+                lock: self,
+                irq_enabled,
+                _not_sync: PhantomData,
+            })
+        } else {
+            trap::restore_interrupts(irq_enabled);
+            None
+        }
+    }
+
+    /// 是否处于加锁状态（仅用于调试/断言）
+    pub fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::Relaxed)
+    }
+
+    /// 接管一把已经被 `leak` 的锁
+    ///
+    /// # Safety
+    /// 调用者必须保证锁当前由本 hart 通过 `SpinLockGuard::leak` 持有，
+    /// `irq_enabled` 为解锁后要恢复的中断状态
+    pub unsafe fn adopt(&self, irq_enabled: bool) -> SpinLockGuard<'_, T> {
+        debug_assert!(self.is_locked());
+        SpinLockGuard {
+            lock: self,
+            irq_enabled,
+            _not_sync: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> SpinLockGuard<'a, T> {
+    /// 放弃 guard 但不释放锁，返回加锁前的中断状态
+    ///
+    /// 说明：用于上下文切换时跨越 `__switch` 持锁，之后必须由 `SpinLock::adopt` 接管
+    pub fn leak(self) -> bool {
+        let irq_enabled = self.irq_enabled;
+        core::mem::forget(self);
+        irq_enabled
+    }
+}
+
+impl<T> Deref for SpinLockGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T> DerefMut for SpinLockGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T> Drop for SpinLockGuard<'_, T> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+        trap::restore_interrupts(self.irq_enabled);
+    }
+}
@@ -0,0 +1,120 @@
+//! 📏 栈保护
+//!
+//! - 所有栈创建时都填充为 `STACK_PAINT`，从栈底数仍保持原值的字即可得到最高水位
+//! - 每个 hart 的启动栈最低 4KB 是保护区，用加锁的 PMP 表项禁止访问，溢出时触发访存异常
+//! - 线程栈底部有 `THREAD_STACK_GUARD` 字节的保护区，填充为 `STACK_CANARY`：
+//!   - 设备树声明 Smepmp 时打开规则锁定旁路，切换线程时把一个加锁的 PMP 表项移到当前线程的保护区，溢出立即触发异常
+//!   - 否则只能在每次切换和计时器 tick 时检查保护区是否被改写
+//! - 访存异常在每个 hart 独立的异常栈上处理（溢出的栈上已经没有空间），落在保护区内时报告是哪个栈溢出
+
+use core::sync::atomic::Ordering;
+
+use crate::hart::{self, HART_STACK_SIZE, MAX_HARTS};
+use crate::thread::ThreadId;
+use crate::pmp::{self, ENTRY_BOOT_STACK_GUARD, ENTRY_THREAD_STACK_GUARD};
+
+/// 栈金丝雀：填充在线程栈保护区中，被改写说明发生了栈溢出
+pub const STACK_CANARY: usize = 0x5354_4b43_414e_5259; // "STKCANRY"
+
+/// 栈填充值：创建栈时先填满它，之后从低地址数还保持原值的字，得到栈使用的最高水位
+///
+/// 说明：必须与 entry.asm 中的 STACK_PAINT 保持一致
+pub const STACK_PAINT: usize = 0x5041_494e_5445_4421; // "PAINTED!"
+
+/// 启动栈保护区大小（必须与 entry.asm / memory.x 保持一致）
+pub const BOOT_STACK_GUARD: usize = 4 * 1024;
+
+/// 线程栈保护区大小（2 的幂，线程栈按它对齐，便于用一个 NAPOT 表项覆盖）
+pub const THREAD_STACK_GUARD: usize = 1024;
+
+/// 异常栈大小
+const FAULT_STACK_SIZE: usize = 4 * 1024;
+
+#[repr(C, align(16))]
+struct FaultStack([u8; FAULT_STACK_SIZE]);
+
+/// 每个 hart 的异常栈：只在 trap 入口发现访存异常时使用
+static mut FAULT_STACKS: [FaultStack; MAX_HARTS] = [const { FaultStack([0; FAULT_STACK_SIZE]) }; MAX_HARTS];
+
+/// 从 `bottom` 开始数仍是 `STACK_PAINT` 的字，返回 `[bottom, top)` 中被使用过的最高水位（字节）
+pub fn high_water(bottom: usize, top: usize) -> usize {
+    let words = (top - bottom) / core::mem::size_of::<usize>();
+    let stack = unsafe { core::slice::from_raw_parts(bottom as *const usize, words) };
+    let untouched = stack.iter().take_while(|&&word| word == STACK_PAINT).count();
+    (words - untouched) * core::mem::size_of::<usize>()
+}
+
+/// hart 启动栈中可用的部分 `[bottom, top)`（不含保护区）
+pub fn boot_stack(hart_id: usize) -> (usize, usize) {
+    unsafe extern "C" {
+        static __STACK_START: u8;
+    }
+    let start = unsafe { &__STACK_START as *const u8 as usize } + hart_id * HART_STACK_SIZE;
+    (start + BOOT_STACK_GUARD, start + HART_STACK_SIZE)
+}
+
+/// hart 启动栈使用的最高水位（字节）
+pub fn boot_stack_high_water(hart_id: usize) -> usize {
+    let (bottom, top) = boot_stack(hart_id);
+    high_water(bottom, top)
+}
+
+/// 初始化当前 hart 的栈保护（由 `trap::init` 在 `pmp::init_hart` 之后、设置 mtvec 之前调用）
+///
+/// 说明：
+/// - 设置 trap 入口使用的异常栈
+/// - 用加锁的 PMP 表项禁止访问启动栈保护区
+pub fn init_hart() {
+    let hart_id = hart::id();
+    let fault_stack = unsafe { &raw const FAULT_STACKS[hart_id] } as usize;
+    hart::local().trap_scratch[2].store(fault_stack + FAULT_STACK_SIZE, Ordering::Relaxed);
+
+    let (bottom, _) = boot_stack(hart_id);
+    pmp::set_napot(ENTRY_BOOT_STACK_GUARD, bottom - BOOT_STACK_GUARD, BOOT_STACK_GUARD, pmp::PMP_L);
+}
+
+/// 线程栈保护区是否由 PMP 强制（打开了规则锁定旁路，加锁的表项可以随线程移动）
+pub fn pmp_thread_guards() -> bool {
+    pmp::rule_locking_bypass()
+}
+
+/// 切换线程时设置当前 hart 的线程栈保护区（切回 idle 时为 None）
+pub fn set_thread_guard(guard: Option<(usize, usize)>) {
+    hart::local().set_stack_guard(guard);
+    if pmp_thread_guards() {
+        match guard {
+            Some((base, size)) => pmp::set_napot(ENTRY_THREAD_STACK_GUARD, base, size, pmp::PMP_L),
+            None => pmp::clear(ENTRY_THREAD_STACK_GUARD),
+        }
+    }
+}
+
+/// 访存异常：地址落在某个栈的保护区内时报告栈溢出（不会返回），否则返回交给调用方处理
+pub fn check_fault(addr: usize, epc: usize) {
+    let local = hart::local();
+    let sp = local.trap_scratch[1].load(Ordering::Relaxed);
+    // 这里不能分配内存：溢出可能发生在持有堆锁的时候
+    if let Some((base, size)) = local.stack_guard()
+        && let Some(thread) = local.current_thread().map(ThreadId::from_raw)
+        && (base..base + size).contains(&addr)
+    {
+        panic!(
+            "stack overflow in thread {}: access to 0x{:x} hit the stack guard [0x{:x}, 0x{:x}) (sp=0x{:x}, pc=0x{:x})",
+            thread,
+            addr,
+            base,
+            base + size,
+            sp,
+            epc
+        );
+    }
+    for hart_id in 0..MAX_HARTS {
+        let (bottom, _) = boot_stack(hart_id);
+        if (bottom - BOOT_STACK_GUARD..bottom).contains(&addr) {
+            panic!(
+                "stack overflow on hart {} boot stack: access to 0x{:x} hit the stack guard (sp=0x{:x}, pc=0x{:x})",
+                hart_id, addr, sp, epc
+            );
+        }
+    }
+}
@@ -4,23 +4,17 @@
 
 use log::info;
 
-/// 🖥️ RISC-V 系统关机函数
-/// 
-/// 在 QEMU virt 平台上，通过向 Power Management 寄存器写入特定值来实现关机
-/// 这是 QEMU 特有的关机机制，在实际硬件上需要根据具体平台实现
-
-// QEMU virt 平台的 Power Management 寄存器地址
-const VIRT_TEST: usize = 0x100000;
+use crate::dtb;
 
 /// 🖥️ 系统关机函数
 /// 
-/// 在 QEMU virt 平台上，通过向 Power Management 寄存器写入特定值来实现关机
+/// 在 QEMU virt 平台上，通过向 Power Management 寄存器（sifive,test 设备）写入特定值来实现关机
 /// 这是 QEMU 特有的关机机制，在实际硬件上需要根据具体平台实现
 pub fn shutdown() -> ! {    
     // 关机命令：写入 0x5555 到 Power Management 寄存器
     // 这个值告诉 QEMU 模拟器关闭虚拟机
     unsafe {
-        core::ptr::write_volatile(VIRT_TEST as *mut u32, 0x5555);
+        core::ptr::write_volatile(dtb::platform().test as *mut u32, 0x5555);
 
         // 如果关机失败，进入无限循环
         loop {
@@ -37,7 +31,7 @@ pub fn shutdown() -> ! {
 pub fn reboot() -> ! {
     // 重启命令：写入 0x7777 到 Power Management 寄存器
     unsafe {
-        core::ptr::write_volatile(VIRT_TEST as *mut u32, 0x7777);
+        core::ptr::write_volatile(dtb::platform().test as *mut u32, 0x7777);
 
         // 如果重启失败，进入无限循环
         loop {
@@ -50,7 +44,7 @@ pub fn reboot() -> ! {
 /// 🗺️ 打印内存段地址信息
 /// 
 /// 显示所有内存段的开始地址、结束地址和大小信息
-/// 包括 .text、.rodata、.data、.bss 和 .stack 段
+/// 包括 .text、.rodata、.initramfs、.data、.bss 和 .stack 段
 pub fn print_memory_layout() {
     // 外部链接声明，引用链接脚本中定义的段地址变量
     unsafe extern "C" {
@@ -58,6 +52,8 @@ pub fn print_memory_layout() {
         static __TEXT_END: u8;
         static __RODATA_START: u8;
         static __RODATA_END: u8;
+        static __INITRAMFS_START: u8;
+        static __INITRAMFS_END: u8;
         static __DATA_START: u8;
         static __DATA_END: u8;
         static __BSS_START: u8;
@@ -79,6 +75,10 @@ pub fn print_memory_layout() {
         info!("   开始地址: 0x{:08x}", &__RODATA_START as *const u8 as usize);
         info!("   结束地址: 0x{:08x}", &__RODATA_END as *const u8 as usize);
         
+        info!("📦 .initramfs 段:");
+        info!("   开始地址: 0x{:08x}", &__INITRAMFS_START as *const u8 as usize);
+        info!("   结束地址: 0x{:08x}", &__INITRAMFS_END as *const u8 as usize);
+        
         info!("💾 .data 段:");
         info!("   开始地址: 0x{:08x}", &__DATA_START as *const u8 as usize);
         info!("   结束地址: 0x{:08x}", &__DATA_END as *const u8 as usize);
@@ -91,8 +91,22 @@ pub fn print_memory_layout() {
         info!("   开始地址: 0x{:08x}", &__STACK_START as *const u8 as usize);
         info!("   结束地址: 0x{:08x}", &__STACK_END as *const u8 as usize);
         info!("   栈顶地址: 0x{:08x}", &__STACK_TOP as *const u8 as usize);
-        info!("==================");
     }
+
+    let platform = dtb::platform();
+    info!("🌳 设备树信息:");
+    match platform.dtb {
+        Some(region) => info!("   DTB 地址: 0x{:08x} ({} 字节)", region.base, region.size),
+        None => info!("   未找到设备树，使用 QEMU virt 默认配置"),
+    }
+    for region in platform.memory_regions() {
+        info!("   内存: 0x{:08x} - 0x{:08x}", region.base, region.end());
+    }
+    info!("   hart 数量: {}", platform.hart_count);
+    info!("   timebase 频率: {} Hz", platform.timebase_frequency);
+    info!("   UART: 0x{:08x}, CLINT: 0x{:08x}", platform.uart, platform.clint);
+    info!("   PLIC: 0x{:08x}, TEST: 0x{:08x}", platform.plic, platform.test);
+    info!("==================");
 }
 
 
@@ -0,0 +1,107 @@
+extern crate alloc;
+use alloc::string::String;
+
+use super::handle::{JoinHandle, boxed_job};
+use super::user::{self, ExitStatus, UserEntry};
+use super::{DEFAULT_PRIORITY, DEFAULT_STACK_SIZE, MIN_STACK_SIZE, ThreadHandle, sched};
+
+/// 🧵 线程构建器
+///
+/// 用法：
+/// ```ignore
+/// let handle = thread::Builder::new()
+///     .name("worker")
+///     .stack_size(32 * 1024)
+///     .priority(5)
+///     .affinity(0b10)
+///     .spawn(|| 42);
+/// let value = handle.join().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+    pub(crate) name: Option<String>,
+    pub(crate) stack_size: usize,
+    pub(crate) priority: usize,
+    pub(crate) affinity: usize,
+    pub(crate) period_ms: Option<usize>,
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self {
+            name: None,
+            stack_size: DEFAULT_STACK_SIZE,
+            priority: DEFAULT_PRIORITY,
+            affinity: 0,
+            period_ms: None,
+        }
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 设置线程名（用于日志和栈溢出报告）
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// 设置线程栈大小（字节）
+    ///
+    /// 说明：向上对齐到 16 字节，且不小于 `MIN_STACK_SIZE`
+    pub fn stack_size(mut self, size: usize) -> Self {
+        self.stack_size = size.max(MIN_STACK_SIZE).next_multiple_of(16);
+        self
+    }
+
+    /// 设置线程优先级（数值越大优先级越高）
+    pub fn priority(mut self, priority: usize) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// 设置线程亲和性：只允许在掩码中的 hart 上运行（bit i 对应 hart i）
+    ///
+    /// 说明：掩码为 0 表示不限制
+    pub fn affinity(mut self, hart_mask: usize) -> Self {
+        self.affinity = hart_mask;
+        self
+    }
+
+    /// 设置线程周期（ms），用于 EDF 调度
+    ///
+    /// 说明：
+    /// - 截止时间为每个周期的结束时刻，线程在周期内完成工作后调用 `thread::wait_for_period`
+    /// - 其它调度策略忽略该参数
+    pub fn period(mut self, ms: usize) -> Self {
+        self.period_ms = Some(ms);
+        self
+    }
+
+    /// 创建线程并立即启动，返回可以取回线程返回值的 `JoinHandle`
+    pub fn spawn<F, T>(self, f: F) -> JoinHandle<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let mut s = sched();
+        let id = s.add_thread(boxed_job(f), &self);
+        if let Some(thread) = s.get_thread(id) {
+            thread.joinable = true;
+        }
+        s.start_thread(id).expect("new thread must be startable");
+        JoinHandle::new(ThreadHandle { id })
+    }
+
+    /// 创建用户线程并立即启动：`entry(arg)` 在 U-mode 执行，返回值作为退出码
+    ///
+    /// 说明：
+    /// - `stack_size` 等设置作用于线程的内核栈，用户栈大小固定为 `user::USER_STACK_SIZE`
+    /// - `entry` 只能访问自己的用户栈和 `.rodata`，其余操作都要通过系统调用（见 `user` crate）
+    pub fn spawn_user(self, entry: UserEntry, arg: usize) -> JoinHandle<ExitStatus> {
+        user::spawn(self, entry as usize, arg)
+    }
+}
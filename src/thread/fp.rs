@@ -0,0 +1,73 @@
+//! 🧮 浮点上下文
+//!
+//! rv64gc 的 F/D 扩展寄存器（f0~f31、fcsr）不在 `__switch` 保存的整数上下文中，
+//! 这里按 `mstatus.FS` 做惰性保存/恢复：
+//! - 切出线程时只有 FS 为 Dirty（线程改写过浮点寄存器）才保存，随后置为 Clean
+//! - 每个 hart 记录浮点寄存器当前属于哪个线程（owner），切回 owner 时不需要恢复 f0~f31
+//! - 切入不是 owner 的线程时恢复它保存的内容；从未用过浮点的线程的上下文全为 0，
+//!   所以不会看到上一个线程留下的浮点寄存器和 fcsr
+//! - 进入 U-mode 之前清空线程的浮点上下文（`Scheduler::reset_fp`）
+//!
+//! 说明：中断处理函数运行在被打断线程的浮点上下文中，不能使用浮点运算
+
+use core::arch::{asm, global_asm};
+
+global_asm!(include_str!("fp.S"));
+
+unsafe extern "C" {
+    fn __fp_save(cx: *mut FpContext);
+    fn __fp_restore(cx: *const FpContext);
+}
+
+/// mstatus.FS 字段（bit 13~14）
+const MSTATUS_FS: usize = 0b11 << 13;
+/// FS = Clean：浮点寄存器与最近一次保存/恢复的内容一致
+const FS_CLEAN: usize = 0b10 << 13;
+/// FS = Dirty：浮点寄存器被改写过
+const FS_DIRTY: usize = 0b11 << 13;
+
+/// 浮点上下文：布局必须与 fp.S 一致
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FpContext {
+    pub f: [u64; 32],
+    pub fcsr: usize,
+}
+
+impl FpContext {
+    /// 把当前 hart 的浮点寄存器保存到这里，并把 FS 置为 Clean
+    pub fn save(&mut self) {
+        unsafe { __fp_save(self) };
+        set_clean();
+    }
+
+    /// 用这里的内容恢复当前 hart 的浮点寄存器，并把 FS 置为 Clean
+    pub fn restore(&self) {
+        unsafe { __fp_restore(self) };
+        set_clean();
+    }
+
+    /// 只恢复 fcsr（舍入模式和异常标志）
+    pub fn restore_fcsr(&self) {
+        unsafe { asm!("fscsr {0}", in(reg) self.fcsr) };
+        set_clean();
+    }
+}
+
+/// 当前 hart 的浮点寄存器是否被改写过（mstatus.FS == Dirty）
+pub fn is_dirty() -> bool {
+    let mstatus: usize;
+    unsafe { asm!("csrr {0}, mstatus", out(reg) mstatus) };
+    mstatus & MSTATUS_FS == FS_DIRTY
+}
+
+fn set_clean() {
+    unsafe {
+        asm!(
+            "csrc mstatus, {0}",
+            "csrs mstatus, {1}",
+            in(reg) MSTATUS_FS,
+            in(reg) FS_CLEAN,
+        )
+    };
+}
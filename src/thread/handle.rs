@@ -0,0 +1,118 @@
+extern crate alloc;
+use alloc::boxed::Box;
+use core::any::Any;
+use core::fmt::{Display, Formatter};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+
+use super::tcb::ThreadState;
+use super::{ThreadHandle, scheduler, sched};
+
+/// join 失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    /// 句柄对应的线程已经不存在（已结束并被回收，或槽位已被新线程复用）
+    Stale,
+    /// 不能 join 当前线程自己
+    JoinSelf,
+    /// 不在线程上下文中调用（例如在 idle 循环或 `thread::init` 之前）
+    NotInThread,
+    /// 线程返回值的类型与句柄不一致
+    TypeMismatch,
+}
+
+impl Display for JoinError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            JoinError::Stale => write!(f, "thread no longer exists (already reaped or slot reused)"),
+            JoinError::JoinSelf => write!(f, "a thread cannot join itself"),
+            JoinError::NotInThread => write!(f, "join called outside of a thread"),
+            JoinError::TypeMismatch => write!(f, "thread result has an unexpected type"),
+        }
+    }
+}
+
+/// 🧵 可以取回线程返回值的句柄
+///
+/// 说明：
+/// - 线程结束后，返回值保存在 TCB 中，直到通过 `join` 取走
+/// - 句柄被丢弃（没有 join）时线程变为分离状态，结束后立即回收
+pub struct JoinHandle<T> {
+    handle: ThreadHandle,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Send + 'static> JoinHandle<T> {
+    pub(crate) fn new(handle: ThreadHandle) -> Self {
+        Self {
+            handle,
+            _marker: PhantomData,
+        }
+    }
+
+    /// 对应线程的普通句柄
+    pub fn thread(&self) -> ThreadHandle {
+        self.handle
+    }
+
+    /// 线程是否已经结束
+    pub fn is_finished(&self) -> bool {
+        sched()
+            .find_thread(self.handle.id)
+            .is_none_or(|t| t.state == ThreadState::Terminated)
+    }
+
+    /// 等待线程结束并取回返回值
+    ///
+    /// 说明：
+    /// - 线程还在运行时，当前线程进入 Blocked，直到目标线程结束
+    /// - 线程已经结束但结果还没被取走时，立即返回结果
+    pub fn join(self) -> Result<T, JoinError> {
+        // 出错时 self 在这里被丢弃，由 Drop 分离线程，避免 TCB 和返回值泄漏
+        wait_for(self.handle)?;
+        // 结果取走后槽位就被释放，不再需要 Drop 中的分离逻辑
+        let this = ManuallyDrop::new(self);
+        let result = sched().take_result(this.handle.id).ok_or(JoinError::Stale)?;
+        result
+            .downcast::<T>()
+            .map(|value| *value)
+            .map_err(|_| JoinError::TypeMismatch)
+    }
+}
+
+impl<T> Drop for JoinHandle<T> {
+    fn drop(&mut self) {
+        sched().detach_thread(self.handle.id);
+    }
+}
+
+/// 阻塞等待句柄对应的线程结束
+///
+/// 说明：
+/// - 目标线程已经结束但 TCB 还在（有 `JoinHandle` 保留）时立即返回
+/// - 槽位已被回收或复用时返回 `JoinError::Stale`，而不是等待一个无关的新线程
+pub(crate) fn wait_for(handle: ThreadHandle) -> Result<(), JoinError> {
+    let mut s = sched();
+    let current_id = s.current().ok_or(JoinError::NotInThread)?;
+    let target = s.find_thread(handle.id).ok_or(JoinError::Stale)?;
+    if target.state == ThreadState::Terminated {
+        return Ok(());
+    }
+    if handle.id == current_id {
+        return Err(JoinError::JoinSelf);
+    }
+    // 等待目标线程结束。Join 等待只会被目标线程退出唤醒，被唤醒就说明已经结束；
+    // 不能再查一次：分离的线程可能在我们运行之前就被 reap 回收了，会误报 Stale
+    s.block_thread(current_id, handle.id);
+    scheduler::run_next(s);
+    Ok(())
+}
+
+/// 把任务包装成返回装箱结果的形式
+pub(crate) fn boxed_job<F, T>(f: F) -> super::tcb::Job
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Box::new(move || Box::new(f()) as Box<dyn Any + Send>)
+}
@@ -0,0 +1,150 @@
+//! 💤 idle 上下文
+//!
+//! 每个 hart 在自己的启动栈上运行 `idle_loop`：
+//! - 有就绪线程时切换过去；线程阻塞/睡眠/退出且没有其它线程时切回这里
+//! - 没有线程可运行时执行 wfi，等待计时器中断（睡眠到期）、IPI 或外设中断
+//! - 统计每个 hart 的空闲时间和 wfi 次数（`thread::idle_stats`）
+//! - 按 `ShutdownPolicy` 决定什么时候关机
+
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+
+use log::{info, warn};
+
+use super::{scheduler, sched};
+use crate::hart::{self, MAX_HARTS};
+use crate::{system, timer, trap};
+
+/// 关机策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownPolicy {
+    /// 所有线程都已经结束时关机（默认）；线程都阻塞/睡眠时只会空闲等待
+    #[default]
+    AllTerminated,
+    /// 没有可运行的线程（没有 Ready/Running/Sleeping）时关机，即使还有线程阻塞在 I/O 上
+    NoRunnable,
+    /// 从不自动关机
+    Never,
+}
+
+impl ShutdownPolicy {
+    const fn to_raw(self) -> u8 {
+        match self {
+            ShutdownPolicy::AllTerminated => 0,
+            ShutdownPolicy::NoRunnable => 1,
+            ShutdownPolicy::Never => 2,
+        }
+    }
+
+    const fn from_raw(raw: u8) -> Self {
+        match raw {
+            1 => ShutdownPolicy::NoRunnable,
+            2 => ShutdownPolicy::Never,
+            _ => ShutdownPolicy::AllTerminated,
+        }
+    }
+}
+
+/// 当前关机策略
+static SHUTDOWN_POLICY: AtomicU8 = AtomicU8::new(ShutdownPolicy::AllTerminated.to_raw());
+
+/// 每个 hart 进入 idle 循环的时间（mtime）
+static IDLE_SINCE: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+/// 每个 hart 在 wfi 中度过的时间（mtime 计数）
+static IDLE_TIME: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+/// 每个 hart 执行 wfi 的次数
+static WFI_COUNT: [AtomicUsize; MAX_HARTS] = [const { AtomicUsize::new(0) }; MAX_HARTS];
+
+/// 是否已经提示过“所有线程都在阻塞”（有线程运行后重置，避免每次 wfi 都打印）
+static BLOCKED_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// 一个 hart 的空闲统计
+#[derive(Debug, Clone, Copy)]
+pub struct IdleStats {
+    pub hart_id: usize,
+    /// 在 wfi 中度过的时间（timebase tick）
+    pub idle_time: usize,
+    /// 执行 wfi 的次数
+    pub wfi_count: usize,
+    /// 进入调度循环以来的时间（timebase tick）
+    pub uptime: usize,
+}
+
+impl IdleStats {
+    /// 忙碌时间占比（百分比）
+    pub fn busy_percent(&self) -> usize {
+        if self.uptime == 0 {
+            return 0;
+        }
+        100 - (self.idle_time.min(self.uptime) * 100 / self.uptime)
+    }
+}
+
+/// 设置关机策略
+pub fn set_shutdown_policy(policy: ShutdownPolicy) {
+    SHUTDOWN_POLICY.store(policy.to_raw(), Ordering::Relaxed);
+}
+
+/// 当前关机策略
+pub fn shutdown_policy() -> ShutdownPolicy {
+    ShutdownPolicy::from_raw(SHUTDOWN_POLICY.load(Ordering::Relaxed))
+}
+
+/// 📊 指定 hart 的空闲统计；hart 不存在或还没有进入调度循环时返回 None
+pub fn idle_stats(hart_id: usize) -> Option<IdleStats> {
+    let since = IDLE_SINCE.get(hart_id)?.load(Ordering::Relaxed);
+    if since == 0 {
+        return None;
+    }
+    Some(IdleStats {
+        hart_id,
+        idle_time: IDLE_TIME[hart_id].load(Ordering::Relaxed),
+        wfi_count: WFI_COUNT[hart_id].load(Ordering::Relaxed),
+        uptime: timer::get_time() - since,
+    })
+}
+
+/// 每个 hart 的调度循环（运行在 hart 的启动栈上，即 idle 上下文）
+///
+/// 说明：
+/// - 先关中断再检查就绪队列，之后执行 wfi：即使 mstatus.MIE 关闭，只要有使能的中断挂起 wfi 也会返回，
+///   随后立即开中断处理它，这样检查之后、wfi 之前到达的 IPI 不会被错过
+/// - 所有线程都阻塞时不会关机，只有满足关机策略时才关机
+pub(crate) fn idle_loop() -> ! {
+    let hart_id = hart::id();
+    IDLE_SINCE[hart_id].store(timer::get_time().max(1), Ordering::Relaxed);
+
+    loop {
+        trap::disable_interrupts();
+        let mut s = sched();
+        s.wake_sleepers(timer::get_time());
+        let shutdown = match shutdown_policy() {
+            ShutdownPolicy::AllTerminated => s.all_terminated(),
+            ShutdownPolicy::NoRunnable => s.nothing_to_run(),
+            ShutdownPolicy::Never => false,
+        };
+        if shutdown {
+            info!("All threads finished, shutting down...");
+            system::shutdown();
+        }
+
+        if scheduler::run_next(s) {
+            // 有线程运行过，下次全部阻塞时重新提示
+            BLOCKED_WARNED.store(false, Ordering::Relaxed);
+        } else {
+            let s = sched();
+            s.arm_timer();
+            let blocked = s.nothing_to_run();
+            drop(s);
+            if blocked && !BLOCKED_WARNED.swap(true, Ordering::Relaxed) {
+                warn!("All threads are blocked, idling until an interrupt arrives");
+            }
+
+            let start = timer::get_time();
+            trap::wait_for_interrupt();
+            IDLE_TIME[hart_id].fetch_add(timer::get_time() - start, Ordering::Relaxed);
+            WFI_COUNT[hart_id].fetch_add(1, Ordering::Relaxed);
+        }
+        // 开中断，处理挂起的计时器中断/IPI
+        trap::enable_interrupts();
+    }
+}
@@ -0,0 +1,149 @@
+//! 📋 线程列表
+//!
+//! `thread::list()` 返回所有线程的快照（类似 `ps`），`thread::print_list()` 把它打印成表格：
+//! 线程 id、名字、状态、等待对象、CPU 占用、切换次数和栈使用的最高水位（以及各 hart 启动栈的最高水位）
+
+extern crate alloc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use super::{ThreadHandle, sched};
+use super::stack::ThreadStack;
+use super::tcb::{TCB, ThreadId, ThreadState, WaitReason};
+use crate::hart::{self, MAX_HARTS};
+use crate::{println, stack, timer};
+
+/// 一个线程在某一时刻的快照
+#[derive(Debug, Clone)]
+pub struct ThreadInfo {
+    pub id: ThreadId,
+    pub name: Option<String>,
+    pub state: ThreadState,
+    /// 有效优先级
+    pub priority: usize,
+    /// 阻塞等待的对象；没有阻塞时为 None
+    pub waiting_for: Option<WaitReason>,
+    /// 累计运行时间（mtime 计数）
+    pub cpu_time: usize,
+    /// 创建以来的时间（mtime 计数）
+    pub lifetime: usize,
+    /// 被切换进来运行的次数
+    pub switches: usize,
+    /// 主动让出 CPU 的次数
+    pub voluntary: usize,
+    /// 被抢占的次数
+    pub involuntary: usize,
+    /// 栈大小（字节），线程结束后栈已释放时为 0
+    pub stack_size: usize,
+    /// 栈使用的最高水位（字节）
+    pub stack_used: usize,
+}
+
+impl ThreadInfo {
+    fn new(thread: &TCB, now: usize) -> Self {
+        Self {
+            id: thread.id,
+            name: thread.name.clone(),
+            state: thread.state,
+            priority: thread.priority,
+            waiting_for: thread.blocked_on,
+            cpu_time: thread.cpu_time(now),
+            lifetime: now.saturating_sub(thread.created_at),
+            switches: thread.switches,
+            voluntary: thread.voluntary,
+            involuntary: thread.involuntary,
+            stack_size: thread.stack.as_deref().map_or(0, ThreadStack::size),
+            // 由 `list` 在释放调度器锁之后扫描
+            stack_used: 0,
+        }
+    }
+
+    /// 线程句柄（线程可能已经结束）
+    pub fn handle(&self) -> ThreadHandle {
+        ThreadHandle { id: self.id }
+    }
+
+    /// 创建以来的 CPU 占用（百分比）
+    pub fn cpu_percent(&self) -> usize {
+        if self.lifetime == 0 {
+            return 0;
+        }
+        self.cpu_time.min(self.lifetime) * 100 / self.lifetime
+    }
+
+    /// 累计运行时间（毫秒）
+    pub fn cpu_time_ms(&self) -> usize {
+        self.cpu_time * 1000 / timer::clock_freq()
+    }
+
+    /// 表头，与 `Display` 输出的列对齐
+    pub const HEADER: &'static str =
+        "ID        NAME             STATE       PRIO  CPU%   TIME(ms)  SWITCH  VOL     INVOL   STACK          WAITING";
+}
+
+impl Display for ThreadInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let id = alloc::format!("{}", self.id);
+        let state = alloc::format!("{:?}", self.state);
+        let stack = alloc::format!("{}/{}", self.stack_used, self.stack_size);
+        write!(
+            f,
+            "{:<9} {:<16} {:<11} {:<5} {:<6} {:<9} {:<7} {:<7} {:<7} {:<14} ",
+            id,
+            self.name.as_deref().unwrap_or("-"),
+            state,
+            self.priority,
+            self.cpu_percent(),
+            self.cpu_time_ms(),
+            self.switches,
+            self.voluntary,
+            self.involuntary,
+            stack,
+        )?;
+        match self.waiting_for {
+            Some(reason) => write!(f, "{}", reason),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// 📋 所有线程的快照（按槽位排序）
+///
+/// 说明：扫描栈的最高水位比较慢，不在调度器锁内进行：先在锁内持有每个栈的引用，释放锁之后再扫描
+/// （扫描期间结束的线程，栈要等扫描完才释放）
+pub fn list() -> Vec<ThreadInfo> {
+    let now = timer::get_time();
+    let (mut threads, stacks): (Vec<_>, Vec<_>) = {
+        let s = sched();
+        s.threads()
+            .map(|thread| (ThreadInfo::new(thread, now), thread.stack.clone()))
+            .unzip()
+    };
+    for (info, stack) in threads.iter_mut().zip(stacks) {
+        if let Some(stack) = stack {
+            info.stack_used = stack.high_water();
+        }
+    }
+    threads
+}
+
+/// 📋 把线程列表打印成表格，最后附上各 hart 启动栈的最高水位
+///
+/// 说明：先取快照再打印，打印时不持有调度器锁
+pub fn print_list() {
+    let threads = list();
+    println!("{}", ThreadInfo::HEADER);
+    for thread in &threads {
+        println!("{}", thread);
+    }
+    for hart_id in (0..MAX_HARTS).filter(|&id| hart::get(id).is_some_and(|h| h.is_online())) {
+        let (bottom, top) = stack::boot_stack(hart_id);
+        println!(
+            "hart {} boot stack: {}/{}",
+            hart_id,
+            stack::boot_stack_high_water(hart_id),
+            top - bottom
+        );
+    }
+}
@@ -1,132 +1,345 @@
+pub mod builder;
+pub mod fp;
+pub mod handle;
+pub mod idle;
+pub mod info;
+pub mod policy;
 pub mod scheduler;
+pub mod stack;
+pub mod sync;
 pub mod tcb;
+pub mod tls;
+pub mod user;
 
 extern crate alloc;
-use crate::timer;
 use alloc::boxed::Box;
+use core::any::Any;
+use crate::spinlock::{SpinLock, SpinLockGuard};
+use crate::{hart, ipi, timer};
 use log::warn;
 use core::arch::global_asm;
-use core::cell::UnsafeCell;
+use core::fmt::{Display, Formatter};
 
 use scheduler::Scheduler;
 
+pub use builder::Builder;
+pub use handle::{JoinError, JoinHandle};
+pub use idle::{IdleStats, ShutdownPolicy, idle_stats, set_shutdown_policy};
+pub use info::{ThreadInfo, list, print_list};
+pub use policy::{Policy, SchedPolicy};
+pub use sync::mpsc::{Receiver, Sender, SyncSender, channel, sync_channel};
+pub use tcb::{ThreadId, ThreadState, WaitKey, WaitKind, WaitReason};
+pub use tls::{AccessError, LocalKey};
+pub use user::{ExitStatus, UserEntry};
+
 global_asm!(include_str!("switch.S"));
 
-struct GlobalScheduler(UnsafeCell<Option<Scheduler>>);
-unsafe impl Sync for GlobalScheduler {}
+/// 全局调度器：所有 hart 共享线程表，每个 hart 一条就绪队列
+static SCHEDULER: SpinLock<Scheduler> = SpinLock::new(Scheduler::new());
+
+/// 默认线程栈大小（字节）
+pub const DEFAULT_STACK_SIZE: usize = 8 * 1024;
 
-static SCHEDULER: GlobalScheduler = GlobalScheduler(UnsafeCell::new(None));
+/// 最小线程栈大小（字节）
+pub const MIN_STACK_SIZE: usize = 2 * 1024;
 
-pub static STACK_SIZE: usize = 1024;
+/// 默认线程优先级
+pub const DEFAULT_PRIORITY: usize = 1;
 
 pub static INTERVAL: usize = 10; // 自动切换间隔时间（ms）
 
-fn sched() -> &'static mut Scheduler {
-    unsafe {
-        let slot = &mut *SCHEDULER.0.get();
-        if slot.is_none() {
-            *slot = Some(Scheduler::new());
+fn sched() -> SpinLockGuard<'static, Scheduler> {
+    SCHEDULER.lock()
+}
+
+/// 不等待地获取调度器锁，锁被占用时返回 None
+///
+/// 说明：供调试器在 trap 中查看线程表使用，被停住的代码可能正持有调度器锁
+pub(crate) fn try_sched() -> Option<SpinLockGuard<'static, Scheduler>> {
+    SCHEDULER.try_lock()
+}
+
+/// 线程操作错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadError {
+    /// 线程已经结束并被回收，或槽位已被新线程复用
+    Stale(ThreadId),
+    /// 线程已经启动过
+    AlreadyStarted(ThreadId),
+    /// 只能对用户线程执行的操作（例如 `kill`）用在了内核线程上
+    NotUser(ThreadId),
+}
+
+impl Display for ThreadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ThreadError::Stale(id) => write!(f, "thread {} no longer exists", id),
+            ThreadError::AlreadyStarted(id) => write!(f, "thread {} was already started", id),
+            ThreadError::NotUser(id) => write!(f, "thread {} is not a user thread", id),
         }
-        slot.as_mut().unwrap()
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// 线程句柄：内部是带代数的线程 id，槽位被复用后旧句柄不会指向新线程
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ThreadHandle {
-    id: usize,
+    id: ThreadId,
 }
 
 impl ThreadHandle {
-    pub fn start(self) {
-        sched().yield_thread(self.id);
+    /// 启动线程；句柄已经失效时返回 `ThreadError::Stale`
+    pub fn start(self) -> Result<(), ThreadError> {
+        sched().start_thread(self.id)
+    }
+
+    pub fn id(&self) -> ThreadId {
+        self.id
     }
 }
 
+/// 创建线程（默认配置），需要调用 `start` 后才会运行
+///
+/// 说明：这样创建的线程是分离的，结束后立即回收；需要返回值时使用 `spawn`
 pub fn new_thread(job: impl FnOnce() + Send + 'static) -> ThreadHandle {
-    let id = sched().add_thread(Box::new(job));
+    let id = sched().add_thread(handle::boxed_job(job), &Builder::new());
     ThreadHandle { id }
 }
 
+/// 🧵 以默认配置创建并启动线程，通过返回的 `JoinHandle` 取回线程返回值
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    Builder::new().spawn(f)
+}
+
+/// 👤 以默认配置创建并启动用户线程：`entry(arg)` 在 U-mode 执行，见 `user` 模块
+pub fn spawn_user(entry: UserEntry, arg: usize) -> JoinHandle<ExitStatus> {
+    Builder::new().spawn_user(entry, arg)
+}
+
+/// 🔪 结束一个用户线程，退出状态为 `ExitStatus::Terminated`
+///
+/// 说明：
+/// - 只能结束用户线程：内核线程可能正持有锁，强行结束会让整个系统卡死，返回 `ThreadError::NotUser`
+/// - 目标线程在下一次从 U-mode 进入内核时（系统调用、计时器中断）结束；正在睡眠时立即唤醒它
+pub fn kill(handle: ThreadHandle) -> Result<(), ThreadError> {
+    user::kill(handle.id)
+}
+
+/// 计时器中断处理：唤醒到期的睡眠线程，设置下一次触发时间，并由调度策略决定是否抢占当前线程
+fn tick() {
+    let mut s = sched();
+    // 没有 PMP 强制保护时，至少每个 tick 检查一次当前线程的栈保护区
+    if let Some(thread) = s.current().and_then(|id| s.find_thread(id)) {
+        thread.check_stack();
+    }
+    s.wake_sleepers(timer::get_time());
+    let preempt_current = s.tick(hart::id());
+    s.arm_timer();
+    if preempt_current {
+        preempt(s);
+    }
+}
+
+/// 重新调度 IPI 处理：有更紧急的线程在本 hart 就绪时抢占当前线程
+fn reschedule() {
+    let mut s = sched();
+    if s.take_resched(hart::id()) {
+        preempt(s);
+    }
+}
+
+/// 在中断上下文中抢占当前线程：放回就绪队列并切换
+///
+/// 说明：被抢占线程的中断现场保存在它自己的栈上，切换回来后从中断返回继续执行
+fn preempt(mut s: SpinLockGuard<'static, Scheduler>) {
+    if let Some(current_id) = s.current() {
+        s.preempt_thread(current_id);
+        scheduler::run_next(s);
+    }
+}
+
+/// 🚀 初始化线程系统并运行主线程（由 hart 0 调用，不会返回）
+///
+/// 说明：
+/// - 使用默认的时间片轮转调度，见 `init_with_policy`
+/// - 创建主线程后释放其它 hart，所有 hart 都进入调度循环
+/// - 默认在所有线程都结束后关机，见 `set_shutdown_policy`
 pub fn init(main_thread: impl FnOnce() + Send + 'static) {
-    // 初始化计数器，为了实现抢占式调度
-    timer::init(|| {
-        timer::set_next_trigger(timer::get_time() + timer::CLOCK_FREQ * INTERVAL / 1000);
-        // yield_now();
-    });
-    timer::set_next_trigger(timer::get_time() + timer::CLOCK_FREQ * INTERVAL / 1000);
+    init_with_policy(Policy::default(), main_thread);
+}
+
+/// 🚀 使用指定的调度策略初始化线程系统并运行主线程（不会返回）
+pub fn init_with_policy(policy: Policy, main_thread: impl FnOnce() + Send + 'static) {
+    sched().set_policy(policy);
+    ipi::set_reschedule_handler(reschedule);
+
+    // 初始化计数器，为了实现抢占式调度和睡眠唤醒
+    timer::init(tick);
 
     let h = new_thread(main_thread);
-    h.start();
-    sched().run_next();
+    h.start().expect("failed to start main thread");
+    sched().activate_hart(hart::id());
+    hart::start_secondaries(secondary_main);
+    idle::idle_loop();
+}
+
+/// 从核入口：初始化本 hart 的计时器后进入调度循环
+fn secondary_main(hart_id: usize) -> ! {
+    timer::init(tick);
+    sched().activate_hart(hart_id);
+    idle::idle_loop();
 }
 
 pub fn current_thread() -> Option<ThreadHandle> {
-    sched().current.map(|id| ThreadHandle { id })
+    current_id().map(|id| ThreadHandle { id })
+}
+
+/// 当前 hart 上正在运行的线程 id
+fn current_id() -> Option<ThreadId> {
+    hart::local().current_thread().map(ThreadId::from_raw)
 }
 
 /// 线程入口（trampoline）：从当前 TCB 取出 job 执行
 pub(crate) extern "C" fn thread_entry() {
-    let current_id = sched().current.expect("current thread not set");
+    // 新线程第一次被切换进来时，调度器锁仍由切换前的上下文持有
+    scheduler::finish_switch(true);
 
-    // 取出 job：离开该作用域后会释放对调度器的可变借用
+    let current_id = current_id().expect("current thread not set");
+
+    // 取出 job：离开该作用域后会释放调度器锁
     let job = {
-        let t = sched()
-            .get_thread(current_id)
-            .expect("current thread not found");
+        let mut s = sched();
+        let t = s.get_thread(current_id).expect("current thread not found");
         t.job.take()
     };
 
-    // 在不持有 &mut Scheduler 的情况下执行 job，允许线程内再创建线程
-    if let Some(job) = job {
-        job();
-    }
+    // 在不持有调度器锁的情况下执行 job，允许线程内再创建线程
+    let result = job.map(|job| job());
+    exit_current(result);
+}
+
+/// 结束当前线程：析构 TLS 变量，保存返回值并切换到下一个就绪线程（不会返回）
+///
+/// 说明：除了线程入口，用户线程的 exit 系统调用和被杀死时也从 trap 处理中调用它
+pub(crate) fn exit_current(result: Option<Box<dyn Any + Send>>) -> ! {
+    let current_id = current_id().expect("current thread not set");
 
-    // 线程退出，同时唤醒等待它的线程
-    sched().exit_thread(current_id);
+    // 析构本线程的 TLS 变量：析构函数可能加锁、分配内存，必须在进入调度器临界区之前执行
+    tls::run_dtors();
 
-    // 切换到下一个就绪线程（通常不会返回）
-    sched().run_next();
+    // 保存返回值，线程退出，同时唤醒等待它的线程
+    let mut s = sched();
+    if let Some(t) = s.get_thread(current_id) {
+        t.result = result;
+    }
+    s.exit_thread(current_id);
+
+    // 切换到下一个就绪线程（不会返回）
+    scheduler::run_next(s);
+    unreachable!("exited thread {} was scheduled again", current_id);
 }
 
 pub fn yield_now() {
+    let mut s = sched();
     // 获取当前线程id
-    if let Some(current_id) = sched().current {
+    if let Some(current_id) = s.current() {
         // 标记当前线程为就绪状态
-        sched().yield_thread(current_id);
+        s.yield_thread(current_id);
 
-        // 切换到下一个就绪线程（通常不会返回）
-        sched().run_next();
+        // 切换到下一个就绪线程
+        scheduler::run_next(s);
     }
 }
 
+/// 💤 当前线程睡眠 `ms` 毫秒
+///
+/// 说明：见 `sleep_until`
 pub fn sleep(ms: usize) {
-    let current_id = sched().current.expect("current thread not set");
-    
-    let end_time = timer::get_time() + timer::CLOCK_FREQ * ms / 1000;
-    
-    warn!("Thread{} is going to be sleep {} ms", current_id, ms);
-    while timer::get_time() < end_time {
-        yield_now();
-    }
+    sleep_until(timer::get_time() + timer::clock_freq() * ms / 1000);
 }
 
-/// 等待指定线程结束（协作式 join）
+/// 💤 当前线程睡眠到 `deadline`（绝对时间，timebase tick）
 ///
 /// 说明：
-/// - 这是“阻塞式”的 join：当前线程会进入 Blocked，直到目标线程结束
-/// - 依赖目标线程能运行并最终退出，否则当前线程会一直阻塞
-/// - 若传入的是当前线程，直接返回，避免死等
-pub fn join(handle: ThreadHandle) {
-    // 获取当前线程id
-    let current_id = sched().current.expect("current thread not set");
-
-    if handle.id == current_id {
+/// - 线程进入 Sleeping 并让出 CPU，计时器在到期时唤醒它，期间不占用 CPU
+/// - 所有线程都在睡眠时，hart 在 idle 循环中执行 wfi 等到最早的唤醒时间
+/// - 不在线程中调用时（例如 `thread::init` 之前）退化为忙等
+pub fn sleep_until(deadline: usize) {
+    let mut s = sched();
+    let Some(current_id) = s.current() else {
+        drop(s);
+        while timer::get_time() < deadline {
+            core::hint::spin_loop();
+        }
+        return;
+    };
+    if timer::get_time() >= deadline {
         return;
     }
+    s.sleep_thread(current_id, deadline);
+    scheduler::run_next(s);
+}
 
-    // 标记当前线程被目标阻塞
-    sched().block_thread(current_id, handle.id);
+/// 设置线程的基础优先级（数值越大优先级越高）
+pub fn set_priority(handle: ThreadHandle, priority: usize) -> Result<(), ThreadError> {
+    sched().set_priority(handle.id, priority)
+}
 
-    // 切换到下一个就绪线程
-    sched().run_next();
+/// 🔗 优先级继承钩子：`holder` 持有 `waiter` 等待的资源时调用，holder 的有效优先级提升到不低于 waiter
+///
+/// 说明：供阻塞式锁等同步原语使用；资源释放后由 holder 调用 `restore_priority`
+pub fn inherit_priority(holder: ThreadHandle, waiter: ThreadHandle) {
+    sched().inherit_priority(holder.id, waiter.id);
+}
+
+/// 恢复线程的基础优先级（放弃继承来的优先级）
+pub fn restore_priority(handle: ThreadHandle) {
+    sched().restore_priority(handle.id);
+}
+
+/// ⏰ 周期性线程等待下一个周期（用于 EDF 调度，见 `Builder::period`）
+///
+/// 说明：
+/// - 截止时间推进到下一个周期结束时刻，然后等待下一个周期的释放时间
+/// - 错过截止时间时记录警告，并跳过已经错过的周期
+/// - 非周期线程调用时直接返回
+pub fn wait_for_period() {
+    let Some(current_id) = current_id() else {
+        return;
+    };
+    let release = {
+        let mut s = sched();
+        let Some(t) = s.get_thread(current_id) else {
+            return;
+        };
+        let Some(period) = t.period else {
+            return;
+        };
+        let now = timer::get_time();
+        if now > t.deadline {
+            warn!("Thread {} missed its deadline by {} ticks", t.display_name(), now - t.deadline);
+        }
+        t.release += period;
+        while t.release + period <= now {
+            t.release += period;
+        }
+        t.deadline = t.release + period;
+        t.release
+    };
+
+    sleep_until(release);
+}
+
+/// 等待指定线程结束（协作式 join）
+///
+/// 说明：
+/// - 这是“阻塞式”的 join：当前线程会进入 Blocked，直到目标线程结束
+/// - 依赖目标线程能运行并最终退出，否则当前线程会一直阻塞
+/// - 传入当前线程返回 `JoinError::JoinSelf`，避免死等
+/// - 线程已经被回收（或槽位被复用）时返回 `JoinError::Stale`；需要可靠地等待结果时使用 `JoinHandle`
+pub fn join(handle: ThreadHandle) -> Result<(), JoinError> {
+    handle::wait_for(handle)
 }
@@ -0,0 +1,78 @@
+extern crate alloc;
+use alloc::vec::Vec;
+
+use super::SchedPolicy;
+use crate::thread::tcb::{TCB, ThreadId};
+
+/// ⏰ 最早截止时间优先（EDF）
+///
+/// 说明：
+/// - 按线程的绝对截止时间（`TCB::deadline`）排序，截止时间最早的先运行
+/// - 没有设置周期的线程截止时间为 `usize::MAX`，只在实时线程都不就绪时运行（相互之间 FIFO）
+/// - 截止时间更早的线程就绪时立即抢占
+pub struct Edf {
+    /// 按 (截止时间, 入队序号) 升序排列
+    queue: Vec<(usize, u64, ThreadId)>,
+    seq: u64,
+}
+
+impl Edf {
+    pub const fn new() -> Self {
+        Self {
+            queue: Vec::new(),
+            seq: 0,
+        }
+    }
+}
+
+impl Default for Edf {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for Edf {
+    fn name(&self) -> &'static str {
+        "edf"
+    }
+
+    fn enqueue(&mut self, thread: &TCB) {
+        let key = (thread.deadline, self.seq, thread.id);
+        self.seq += 1;
+        let position = self.queue.partition_point(|entry| *entry < key);
+        self.queue.insert(position, key);
+    }
+
+    fn pick_next(&mut self) -> Option<ThreadId> {
+        (!self.queue.is_empty()).then(|| self.queue.remove(0).2)
+    }
+
+    fn remove(&mut self, id: ThreadId) -> bool {
+        match self.queue.iter().position(|entry| entry.2 == id) {
+            Some(position) => {
+                self.queue.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn steal(&mut self, can_run: &dyn Fn(ThreadId) -> bool) -> Option<ThreadId> {
+        let position = self.queue.iter().rposition(|entry| can_run(entry.2))?;
+        Some(self.queue.remove(position).2)
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn tick(&mut self, current: &mut TCB) -> bool {
+        self.queue
+            .first()
+            .is_some_and(|&(deadline, _, _)| deadline < current.deadline)
+    }
+
+    fn should_preempt(&self, woken: &TCB, current: &TCB) -> bool {
+        woken.deadline < current.deadline
+    }
+}
@@ -0,0 +1,106 @@
+extern crate alloc;
+use alloc::collections::{BTreeMap, VecDeque};
+
+use super::SchedPolicy;
+use crate::thread::tcb::{TCB, ThreadId};
+
+/// 同优先级线程之间的时间片长度（tick）
+const TIME_SLICE: usize = 1;
+
+/// 🏅 固定优先级抢占式调度
+///
+/// 说明：
+/// - 总是运行优先级（有效优先级，含继承）最高的就绪线程
+/// - 更高优先级的线程就绪时立即抢占
+/// - 同优先级线程之间按时间片轮转
+pub struct FixedPriority {
+    /// 优先级 -> 该优先级的 FIFO 队列
+    queues: BTreeMap<usize, VecDeque<ThreadId>>,
+    len: usize,
+}
+
+impl FixedPriority {
+    pub const fn new() -> Self {
+        Self {
+            queues: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    /// 队列中最高的优先级
+    fn highest(&self) -> Option<usize> {
+        self.queues.keys().next_back().copied()
+    }
+}
+
+impl Default for FixedPriority {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for FixedPriority {
+    fn name(&self) -> &'static str {
+        "fixed-priority"
+    }
+
+    fn enqueue(&mut self, thread: &TCB) {
+        self.queues.entry(thread.priority).or_default().push_back(thread.id);
+        self.len += 1;
+    }
+
+    fn pick_next(&mut self) -> Option<ThreadId> {
+        let mut entry = self.queues.last_entry()?;
+        let id = entry.get_mut().pop_front();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+        self.len -= 1;
+        id
+    }
+
+    fn remove(&mut self, id: ThreadId) -> bool {
+        for (&priority, queue) in self.queues.iter_mut() {
+            if let Some(position) = queue.iter().position(|&queued| queued == id) {
+                queue.remove(position);
+                if queue.is_empty() {
+                    self.queues.remove(&priority);
+                }
+                self.len -= 1;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn steal(&mut self, can_run: &dyn Fn(ThreadId) -> bool) -> Option<ThreadId> {
+        // 从最低优先级开始偷，把紧急的线程留在原 hart
+        let (priority, position) = self
+            .queues
+            .iter()
+            .find_map(|(&priority, queue)| queue.iter().rposition(|&id| can_run(id)).map(|p| (priority, p)))?;
+        let queue = self.queues.get_mut(&priority)?;
+        let id = queue.remove(position);
+        if queue.is_empty() {
+            self.queues.remove(&priority);
+        }
+        self.len -= 1;
+        id
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn tick(&mut self, current: &mut TCB) -> bool {
+        match self.highest() {
+            Some(highest) if highest > current.priority => true,
+            Some(highest) if highest == current.priority => current.slice_ticks >= TIME_SLICE,
+            _ => false,
+        }
+    }
+
+    fn should_preempt(&self, woken: &TCB, current: &TCB) -> bool {
+        woken.priority > current.priority
+    }
+}
@@ -0,0 +1,116 @@
+extern crate alloc;
+use alloc::collections::VecDeque;
+
+use super::SchedPolicy;
+use crate::thread::tcb::{TCB, ThreadId};
+
+/// 队列级数（0 级最高）
+const LEVELS: usize = 3;
+/// 每一级的时间片长度（tick），级别越低时间片越长
+const QUANTUM: [usize; LEVELS] = [2, 4, 8];
+/// 每隔多少 tick 把所有线程提升回 0 级，防止饥饿
+const BOOST_INTERVAL: usize = 100;
+
+/// 🪜 多级反馈队列
+///
+/// 说明：
+/// - 新线程从 0 级开始；用完本级时间片降一级，主动让出/阻塞则保持级别
+/// - 高级别队列非空时抢占低级别线程
+/// - 每 `BOOST_INTERVAL` 个 tick 把所有线程提升回 0 级
+pub struct Mlfq {
+    queues: [VecDeque<ThreadId>; LEVELS],
+    /// 最近一次 `pick_next`/`steal` 取出的线程所在级别（由 `on_run` 写回 TCB）
+    picked_level: usize,
+    ticks: usize,
+}
+
+impl Mlfq {
+    pub const fn new() -> Self {
+        Self {
+            queues: [const { VecDeque::new() }; LEVELS],
+            picked_level: 0,
+            ticks: 0,
+        }
+    }
+
+    /// 把所有队列中的线程提升到 0 级
+    fn boost(&mut self) {
+        let (top, rest) = self.queues.split_at_mut(1);
+        for queue in rest {
+            top[0].extend(queue.drain(..));
+        }
+    }
+}
+
+impl Default for Mlfq {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for Mlfq {
+    fn name(&self) -> &'static str {
+        "mlfq"
+    }
+
+    fn enqueue(&mut self, thread: &TCB) {
+        self.queues[thread.level.min(LEVELS - 1)].push_back(thread.id);
+    }
+
+    fn pick_next(&mut self) -> Option<ThreadId> {
+        for (level, queue) in self.queues.iter_mut().enumerate() {
+            if let Some(id) = queue.pop_front() {
+                self.picked_level = level;
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    fn remove(&mut self, id: ThreadId) -> bool {
+        for queue in self.queues.iter_mut() {
+            if let Some(position) = queue.iter().position(|&queued| queued == id) {
+                queue.remove(position);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn steal(&mut self, can_run: &dyn Fn(ThreadId) -> bool) -> Option<ThreadId> {
+        for (level, queue) in self.queues.iter_mut().enumerate().rev() {
+            if let Some(position) = queue.iter().rposition(|&id| can_run(id)) {
+                self.picked_level = level;
+                return queue.remove(position);
+            }
+        }
+        None
+    }
+
+    fn len(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    fn on_run(&mut self, thread: &mut TCB) {
+        thread.level = self.picked_level;
+    }
+
+    fn tick(&mut self, current: &mut TCB) -> bool {
+        self.ticks += 1;
+        if self.ticks % BOOST_INTERVAL == 0 {
+            self.boost();
+            current.level = 0;
+        }
+
+        let level = current.level.min(LEVELS - 1);
+        if current.slice_ticks >= QUANTUM[level] {
+            current.level = (level + 1).min(LEVELS - 1);
+            return !self.is_empty();
+        }
+        self.queues[..level].iter().any(|queue| !queue.is_empty())
+    }
+
+    fn should_preempt(&self, woken: &TCB, current: &TCB) -> bool {
+        woken.level < current.level
+    }
+}
@@ -0,0 +1,94 @@
+//! 🗂️ 调度策略
+//!
+//! 每个 hart 持有一个调度策略实例，负责管理本 hart 的就绪队列：
+//! - `RoundRobin`：时间片轮转（默认）
+//! - `FixedPriority`：固定优先级抢占式调度，同优先级之间轮转
+//! - `Mlfq`：多级反馈队列，用完时间片降级，定期整体提升
+//! - `Edf`：最早截止时间优先，适用于周期性实时线程（见 `Builder::period`）
+//!
+//! 也可以实现 `SchedPolicy` 并通过 `Policy::Custom` 使用自定义策略。
+
+extern crate alloc;
+use alloc::boxed::Box;
+
+use super::tcb::{TCB, ThreadId};
+
+mod edf;
+mod fixed_priority;
+mod mlfq;
+mod round_robin;
+
+pub use edf::Edf;
+pub use fixed_priority::FixedPriority;
+pub use mlfq::Mlfq;
+pub use round_robin::RoundRobin;
+
+/// 🗂️ 调度策略接口
+///
+/// 说明：
+/// - 所有方法都在持有调度器锁时调用，不能阻塞，也不能再次加锁
+/// - 队列里只存线程 id；线程的优先级、截止时间等参数从 `TCB` 读取
+/// - 时间以计时器 tick 为单位（见 `thread::INTERVAL`）
+pub trait SchedPolicy: Send {
+    /// 策略名（用于日志）
+    fn name(&self) -> &'static str;
+
+    /// 线程进入就绪状态，放入队列
+    fn enqueue(&mut self, thread: &TCB);
+
+    /// 取出下一个要运行的线程
+    fn pick_next(&mut self) -> Option<ThreadId>;
+
+    /// 从队列中移除指定线程，返回是否找到（优先级变化时重新入队使用）
+    fn remove(&mut self, id: ThreadId) -> bool;
+
+    /// 取出一个允许被其它 hart 偷走的线程（`can_run` 返回 true 的），优先选最不紧急的
+    fn steal(&mut self, can_run: &dyn Fn(ThreadId) -> bool) -> Option<ThreadId>;
+
+    /// 队列中的线程数
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 刚从本队列取出的线程即将开始运行（`pick_next`/`steal` 之后立即调用）
+    fn on_run(&mut self, _thread: &mut TCB) {}
+
+    /// 计时器 tick：当前线程又运行了一个 tick（`thread.slice_ticks` 已经加一），返回是否应当抢占它
+    fn tick(&mut self, current: &mut TCB) -> bool;
+
+    /// 新就绪的线程是否应当立即抢占正在运行的线程
+    fn should_preempt(&self, _woken: &TCB, _current: &TCB) -> bool {
+        false
+    }
+}
+
+/// 调度策略选择（在 `thread::init_with_policy` 时指定）
+#[derive(Debug, Clone, Copy, Default)]
+pub enum Policy {
+    /// 时间片轮转
+    #[default]
+    RoundRobin,
+    /// 固定优先级抢占式调度
+    FixedPriority,
+    /// 多级反馈队列
+    Mlfq,
+    /// 最早截止时间优先
+    Edf,
+    /// 自定义策略：为每个 hart 调用一次，创建该 hart 的策略实例
+    Custom(fn() -> Box<dyn SchedPolicy>),
+}
+
+impl Policy {
+    /// 为一个 hart 创建策略实例
+    pub(crate) fn create(&self) -> Box<dyn SchedPolicy> {
+        match self {
+            Policy::RoundRobin => Box::new(RoundRobin::new()),
+            Policy::FixedPriority => Box::new(FixedPriority::new()),
+            Policy::Mlfq => Box::new(Mlfq::new()),
+            Policy::Edf => Box::new(Edf::new()),
+            Policy::Custom(factory) => factory(),
+        }
+    }
+}
@@ -0,0 +1,61 @@
+extern crate alloc;
+use alloc::collections::VecDeque;
+
+use super::SchedPolicy;
+use crate::thread::tcb::{TCB, ThreadId};
+
+/// 时间片长度（tick）
+const TIME_SLICE: usize = 1;
+
+/// 🔁 时间片轮转：FIFO 队列，时间片用完且有其它线程等待时切换
+pub struct RoundRobin {
+    queue: VecDeque<ThreadId>,
+}
+
+impl RoundRobin {
+    pub const fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for RoundRobin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SchedPolicy for RoundRobin {
+    fn name(&self) -> &'static str {
+        "round-robin"
+    }
+
+    fn enqueue(&mut self, thread: &TCB) {
+        self.queue.push_back(thread.id);
+    }
+
+    fn pick_next(&mut self) -> Option<ThreadId> {
+        self.queue.pop_front()
+    }
+
+    fn remove(&mut self, id: ThreadId) -> bool {
+        match self.queue.iter().position(|&queued| queued == id) {
+            Some(position) => self.queue.remove(position).is_some(),
+            None => false,
+        }
+    }
+
+    fn steal(&mut self, can_run: &dyn Fn(ThreadId) -> bool) -> Option<ThreadId> {
+        let position = self.queue.iter().rposition(|&id| can_run(id))?;
+        self.queue.remove(position)
+    }
+
+    fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    fn tick(&mut self, current: &mut TCB) -> bool {
+        current.slice_ticks >= TIME_SLICE && !self.queue.is_empty()
+    }
+}
@@ -1,53 +1,159 @@
 extern crate alloc;
-use super::tcb::{TCB, ThreadContext, ThreadState};
-use crate::system;
-use alloc::{boxed::Box, vec::Vec};
-use log::{info, warn};
+use super::fp::{self, FpContext};
+use super::policy::{Policy, SchedPolicy};
+use super::tcb::{Job, TCB, ThreadContext, ThreadId, ThreadState, WaitKey, WaitReason};
+use super::{Builder, INTERVAL, SCHEDULER, ThreadError};
+use crate::hart::{self, MAX_HARTS};
+use crate::{ipi, timer};
+use crate::spinlock::SpinLockGuard;
+use alloc::boxed::Box;
+use alloc::collections::{BTreeMap, BinaryHeap, VecDeque};
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::any::Any;
+use log::{debug, info};
+
+unsafe extern "C" {
+    pub fn __switch(
+        current_thread_cx_ptr: *mut ThreadContext,
+        next_thread_cx_ptr: *const ThreadContext,
+    );
+}
+
+/// 线程表中的一个槽位
+struct Slot {
+    /// 槽位代数：每回收一次加一，使指向旧线程的 id 失效
+    generation: u32,
+    tcb: Option<TCB>,
+}
 
 pub struct Scheduler {
     // Fields for the Scheduler
-    pub current: Option<usize>,
-    /// 线程表：索引即线程 id，空槽位用 None 表示（便于复用）
-    threads: Vec<Option<TCB>>,
-    /// 当没有当前线程时的“占位上下文”，避免引用临时值
-    idle_context: ThreadContext,
+    /// 线程表：下标即槽位，空槽位的 `tcb` 为 None（便于复用）
+    threads: Vec<Slot>,
+    /// 每个 hart 一个调度策略实例，管理该 hart 的就绪队列（添加第一个线程时按默认策略创建）
+    policies: Vec<Box<dyn SchedPolicy>>,
+    /// 每个 hart 是否需要在下一次中断返回前重新调度（有更紧急的线程就绪）
+    need_resched: [bool; MAX_HARTS],
+    /// 每个 hart 的 idle 上下文：没有线程可运行时切回这里（即 hart 的启动栈）
+    idle_contexts: [ThreadContext; MAX_HARTS],
+    /// 每个 hart 的浮点寄存器当前保存的是哪个线程的上下文
+    fp_owner: [Option<ThreadId>; MAX_HARTS],
+    /// 每个 hart 上刚刚退出、等待回收的线程（切换完成后才能释放它的栈）
+    zombies: [Option<ThreadId>; MAX_HARTS],
+    /// 同步原语的等待队列：key（原语地址和种类）-> FIFO 队列 (线程 id, 原语自定义标记)
+    wait_queues: BTreeMap<WaitKey, VecDeque<(ThreadId, usize)>>,
+    /// 睡眠线程的唤醒时间小根堆：(唤醒时间, 线程 id)
+    sleepers: BinaryHeap<Reverse<(usize, ThreadId)>>,
+    /// 已进入调度循环的 hart 掩码
+    active_harts: usize,
 }
 
 impl Scheduler {
     // Methods for the Scheduler
-    pub fn new() -> Self {
+    pub const fn new() -> Self {
         Scheduler {
-            current: None,
             threads: Vec::new(),
-            idle_context: ThreadContext::default(),
+            policies: Vec::new(),
+            need_resched: [false; MAX_HARTS],
+            idle_contexts: [ThreadContext::zero(); MAX_HARTS],
+            fp_owner: [None; MAX_HARTS],
+            zombies: [None; MAX_HARTS],
+            wait_queues: BTreeMap::new(),
+            sleepers: BinaryHeap::new(),
+            active_harts: 0,
+        }
+    }
+
+    /// 当前 hart 上正在运行的线程 id（保存在 hart 私有数据中）
+    pub fn current(&self) -> Option<ThreadId> {
+        hart::local().current_thread().map(ThreadId::from_raw)
+    }
+
+    fn set_current(&mut self, id: Option<ThreadId>) {
+        hart::local().set_current_thread(id.map(ThreadId::as_raw));
+    }
+
+    /// 为每个 hart 创建调度策略实例，替换原有的策略
+    ///
+    /// 说明：已经在旧策略队列里的就绪线程按原来的 hart 转移到新策略中
+    pub fn set_policy(&mut self, policy: Policy) {
+        let mut old = core::mem::replace(&mut self.policies, (0..MAX_HARTS).map(|_| policy.create()).collect());
+        for (hart_id, old_policy) in old.iter_mut().enumerate() {
+            while let Some(id) = old_policy.pick_next() {
+                if let Some(thread) = tcb(&self.threads, id).filter(|t| t.state == ThreadState::Ready) {
+                    self.policies[hart_id].enqueue(thread);
+                }
+            }
         }
+        info!("Scheduling policy: {}", self.policies[0].name());
+    }
+
+    /// 标记 hart 已进入调度循环，可以接收线程
+    pub fn activate_hart(&mut self, hart_id: usize) {
+        self.active_harts |= 1 << hart_id;
     }
 
     /// 添加一个线程，返回线程 id
-    pub fn add_thread(&mut self, job: Box<dyn FnOnce() + Send + 'static>) -> usize {
-        // 线程 id 使用“空槽位优先”的策略，避免 id 无限增长
-        let id = self
+    ///
+    /// 说明：名字、栈大小、优先级和亲和性取自 `config`
+    pub fn add_thread(&mut self, job: Job, config: &Builder) -> ThreadId {
+        // `new` 是 const fn 无法分配，线程系统初始化之前启动的线程先进入默认策略的队列
+        if self.policies.is_empty() {
+            self.policies = (0..MAX_HARTS).map(|_| Policy::default().create()).collect();
+        }
+        // 槽位使用“空槽位优先”的策略，避免线程表无限增长；代数区分复用前后的线程
+        let slot = self
             .threads
             .iter()
-            .position(|slot| slot.is_none())
+            .position(|slot| slot.tcb.is_none())
             .unwrap_or_else(|| {
-                self.threads.push(None);
+                self.threads.push(Slot {
+                    generation: 0,
+                    tcb: None,
+                });
                 self.threads.len() - 1
             });
-        let new_thread = TCB::new(id, Some(job));
-        self.threads[id] = Some(new_thread);
+        let id = ThreadId::new(slot, self.threads[slot].generation);
+        self.threads[slot].tcb = Some(TCB::new(id, Some(job), config));
         id
     }
 
-    pub fn yield_thread(&mut self, thread_id: usize) {
-        if let Some(thread) = self.get_thread(thread_id) {
+    /// 启动一个尚未运行的线程：Uninit -> Ready
+    ///
+    /// 说明：id 已经失效时返回 `ThreadError::Stale`，已经启动过时返回 `ThreadError::AlreadyStarted`
+    pub fn start_thread(&mut self, thread_id: ThreadId) -> Result<(), ThreadError> {
+        let thread = self.find_thread(thread_id).ok_or(ThreadError::Stale(thread_id))?;
+        if thread.state != ThreadState::Uninit {
+            return Err(ThreadError::AlreadyStarted(thread_id));
+        }
+        self.make_ready(thread_id);
+        Ok(())
+    }
+
+    /// 当前线程让出 CPU：Running -> Ready，放回本 hart 队尾
+    pub fn yield_thread(&mut self, thread_id: ThreadId) {
+        let hart_id = hart::id();
+        if let Some(thread) = tcb_mut(&mut self.threads, thread_id) {
             thread.state = ThreadState::Ready;
-            info!("Thread {} switched", thread_id);
-            info!("ThreadState {:?}", thread.state);
+            debug!("Thread {} switched", thread_id);
+            if thread.affinity & (1 << hart_id) != 0 {
+                self.policies[hart_id].enqueue(thread);
+            } else {
+                self.make_ready(thread_id);
+            }
         }
     }
 
-    pub fn block_thread(&mut self, current_id: usize, target_id: usize) {
+    /// 抢占线程：与 `yield_thread` 相同，但切出时计为被抢占
+    pub fn preempt_thread(&mut self, thread_id: ThreadId) {
+        if let Some(thread) = self.get_thread(thread_id) {
+            thread.preempted = true;
+        }
+        self.yield_thread(thread_id);
+    }
+
+    pub fn block_thread(&mut self, current_id: ThreadId, target_id: ThreadId) {
         // 只有当目标线程存在且没有结束时才阻塞
         if let Some(target) = self.find_thread(target_id) {
             if target.state == ThreadState::Terminated {
@@ -58,15 +164,165 @@ impl Scheduler {
         }
         if let Some(current) = self.get_thread(current_id) {
             current.state = ThreadState::Blocked;
-            current.waiting_for = Some(target_id);
+            current.blocked_on = Some(WaitReason::Join(target_id));
+        }
+    }
+
+    /// 把线程阻塞在 `key` 对应的等待队列末尾：Running -> Blocked
+    ///
+    /// 说明：
+    /// - `tag` 由同步原语自己解释（例如读写锁区分读者和写者），唤醒时原样返回
+    /// - 调用方负责随后调用 `run_next` 让出 CPU
+    pub fn block_on(&mut self, thread_id: ThreadId, key: WaitKey, tag: usize) {
+        if let Some(thread) = self.get_thread(thread_id) {
+            thread.state = ThreadState::Blocked;
+            thread.blocked_on = Some(WaitReason::Queue(key));
+            self.wait_queues.entry(key).or_default().push_back((thread_id, tag));
+        }
+    }
+
+    /// 与 `block_on` 相同，但最晚在 `deadline`（mtime）被计时器唤醒
+    ///
+    /// 说明：超时唤醒时线程从等待队列中移除，由调用方重新检查条件判断是否超时
+    pub fn block_on_until(&mut self, thread_id: ThreadId, key: WaitKey, tag: usize, deadline: usize) {
+        self.block_on(thread_id, key, tag);
+        if let Some(thread) = self.get_thread(thread_id) {
+            thread.wake_at = Some(deadline);
+            self.sleepers.push(Reverse((deadline, thread_id)));
         }
     }
 
-    pub fn exit_thread(&mut self, thread_id: usize) {
-        // 先从线程表里移除，完成真正的槽位清理
-        let mut thread = match self.threads.get_mut(thread_id).and_then(|slot| slot.take()) {
-            Some(t) => t,
-            None => return,
+    /// 唤醒 `key` 等待队列中最早进入的线程，返回它的 id 和标记
+    pub fn wake_one(&mut self, key: WaitKey) -> Option<(ThreadId, usize)> {
+        let queue = self.wait_queues.get_mut(&key)?;
+        let mut woken = None;
+        while let Some((thread_id, tag)) = queue.pop_front() {
+            // 跳过已经不在这个队列上等待的线程（例如已经被回收）
+            if let Some(thread) = tcb_mut(&mut self.threads, thread_id) {
+                if thread.state == ThreadState::Blocked && thread.blocked_on == Some(WaitReason::Queue(key)) {
+                    thread.blocked_on = None;
+                    thread.wake_at = None;
+                    woken = Some((thread_id, tag));
+                    break;
+                }
+            }
+        }
+        if queue.is_empty() {
+            self.wait_queues.remove(&key);
+        }
+        let (thread_id, _) = woken?;
+        self.make_ready(thread_id);
+        woken
+    }
+
+    /// 唤醒 `key` 等待队列中的所有线程，返回唤醒的数量
+    pub fn wake_all(&mut self, key: WaitKey) -> usize {
+        let mut count = 0;
+        while self.wake_one(key).is_some() {
+            count += 1;
+        }
+        count
+    }
+
+    /// `key` 等待队列队首线程的标记（队列为空时返回 None）
+    pub fn first_waiter(&self, key: WaitKey) -> Option<usize> {
+        self.wait_queues.get(&key)?.front().map(|&(_, tag)| tag)
+    }
+
+    /// `key` 等待队列中是否有线程
+    pub fn has_waiters(&self, key: WaitKey) -> bool {
+        self.wait_queues.get(&key).is_some_and(|queue| !queue.is_empty())
+    }
+
+    /// 当前线程睡眠到 `deadline`（mtime）：Running -> Sleeping，加入唤醒时间堆
+    pub fn sleep_thread(&mut self, thread_id: ThreadId, deadline: usize) {
+        if let Some(thread) = self.get_thread(thread_id) {
+            thread.state = ThreadState::Sleeping;
+            thread.wake_at = Some(deadline);
+            self.sleepers.push(Reverse((deadline, thread_id)));
+        }
+    }
+
+    /// 提前唤醒睡眠中的线程（不在睡眠时什么也不做）
+    ///
+    /// 说明：唤醒时间堆中的旧记录在到期时因 `wake_at` 不匹配被忽略
+    pub fn interrupt_sleep(&mut self, thread_id: ThreadId) {
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
+        };
+        if thread.state != ThreadState::Sleeping {
+            return;
+        }
+        thread.wake_at = None;
+        self.make_ready(thread_id);
+    }
+
+    /// 唤醒所有唤醒时间已到的睡眠线程，以及等待超时的阻塞线程
+    pub fn wake_sleepers(&mut self, now: usize) {
+        while let Some(&Reverse((deadline, thread_id))) = self.sleepers.peek() {
+            if deadline > now {
+                break;
+            }
+            self.sleepers.pop();
+            // 线程可能已经被回收，或者已经不再是这次睡眠/等待（例如已经被 `wake_one` 唤醒）
+            let Some(thread) = self.get_thread(thread_id) else {
+                continue;
+            };
+            if thread.wake_at != Some(deadline) {
+                continue;
+            }
+            match (thread.state, thread.blocked_on) {
+                (ThreadState::Sleeping, _) => {}
+                (ThreadState::Blocked, Some(WaitReason::Queue(key))) => {
+                    thread.blocked_on = None;
+                    self.remove_waiter(key, thread_id);
+                }
+                _ => continue,
+            }
+            if let Some(thread) = self.get_thread(thread_id) {
+                thread.wake_at = None;
+            }
+            self.make_ready(thread_id);
+        }
+    }
+
+    /// 从 `key` 的等待队列中移除指定线程
+    fn remove_waiter(&mut self, key: WaitKey, thread_id: ThreadId) {
+        if let Some(queue) = self.wait_queues.get_mut(&key) {
+            queue.retain(|&(id, _)| id != thread_id);
+            if queue.is_empty() {
+                self.wait_queues.remove(&key);
+            }
+        }
+    }
+
+    /// 最早的唤醒时间
+    pub fn next_wakeup(&self) -> Option<usize> {
+        self.sleepers.peek().map(|&Reverse((deadline, _))| deadline)
+    }
+
+    /// 为本 hart 设置下一次计时器中断
+    ///
+    /// 说明：
+    /// - 正在运行线程时：下一个时间片和最早唤醒时间中较早的一个
+    /// - idle 时：只在最早唤醒时间触发，没有睡眠线程时不设置计时器（wfi 只等 IPI）
+    pub fn arm_timer(&self) {
+        let mut next = self.next_wakeup().unwrap_or(usize::MAX);
+        if self.current().is_some() {
+            next = next.min(timer::get_time() + timer::clock_freq() * INTERVAL / 1000);
+        }
+        timer::set_next_trigger(next);
+    }
+
+    /// 线程退出
+    ///
+    /// 说明：
+    /// - 线程此时仍在自己的栈上运行，因此只标记为 Terminated 并登记为待回收，
+    ///   真正释放（包括栈）要等切换到其它上下文之后由 `reap` 完成
+    /// - 同时唤醒等待它结束的所有线程
+    pub fn exit_thread(&mut self, thread_id: ThreadId) {
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
         };
 
         // 标记状态并记录日志
@@ -74,100 +330,426 @@ impl Scheduler {
         info!("Thread {} exited", thread.id);
         info!("ThreadState {:?}", thread.state);
         info!("ThreadContext {}", thread.context);
-        info!("Threads Count {}", self.threads.iter().filter(|t| t.is_some()).count());
+        self.zombies[hart::id()] = Some(thread_id);
+        info!(
+            "Threads Count {}",
+            self.threads()
+                .filter(|t| t.state != ThreadState::Terminated)
+                .count()
+        );
 
-        // 当前线程已退出，清空 current，避免后续调度访问到已被清理的槽位
-        if self.current == Some(thread_id) {
-            self.current = None;
+        // 唤醒等待指定线程结束的所有线程
+        let waiters: Vec<ThreadId> = self
+            .threads()
+            .filter(|t| t.state == ThreadState::Blocked && t.blocked_on == Some(WaitReason::Join(thread_id)))
+            .map(|t| t.id)
+            .collect();
+        for id in waiters {
+            if let Some(t) = self.get_thread(id) {
+                t.blocked_on = None;
+            }
+            self.make_ready(id);
         }
+    }
 
-        // 唤醒等待指定线程结束的所有线程
-        for slot in self.threads.iter_mut() {
-            if let Some(t) = slot.as_mut() {
-                if t.state == ThreadState::Blocked && t.waiting_for == Some(thread_id) {
-                    t.state = ThreadState::Ready;
-                    t.waiting_for = None;
+    /// 回收本 hart 上已经切换离开的退出线程
+    ///
+    /// 说明：还有 `JoinHandle` 的线程只释放栈和 TLS 块，TCB（连同返回值）保留到被 join 或分离
+    fn reap(&mut self, hart_id: usize) {
+        if let Some(id) = self.zombies[hart_id].take() {
+            match self.get_thread(id) {
+                Some(thread) if thread.joinable => {
+                    thread.stack = None;
+                    thread.tls = None;
+                    thread.user = None;
                 }
+                _ => self.free_slot(id),
             }
         }
     }
 
-    pub fn run_next(&mut self) {
-        unsafe extern "C" {
-            pub fn __switch(
-                current_thread_cx_ptr: *mut ThreadContext,
-                next_thread_cx_ptr: *const ThreadContext,
-            );
+    /// 释放已结束线程的槽位（仍在等待 `reap` 的线程除外），并让槽位代数加一
+    fn free_slot(&mut self, thread_id: ThreadId) {
+        if self.zombies.contains(&Some(thread_id)) || self.find_thread(thread_id).is_none() {
+            return;
         }
+        let slot = &mut self.threads[thread_id.slot()];
+        slot.tcb = None;
+        slot.generation = slot.generation.wrapping_add(1);
+    }
 
-        let current_id = self.current;
-        // 注意：这是“顺序执行版”的跑法（还没接上下文切换），只用于先把基础结构跑通
-        let next_id = match self.find_ready_thread_id() {
-            Some(id) => id,
-            None => {
-                // 如果没有就绪线程，直接关机
-                warn!("No ready threads, shutting down...");
-                system::shutdown();
-            }
+    /// 取走已结束线程的返回值，并释放它的槽位
+    pub(crate) fn take_result(&mut self, thread_id: ThreadId) -> Option<Box<dyn Any + Send>> {
+        let result = self.get_thread(thread_id)?.result.take();
+        self.free_slot(thread_id);
+        result
+    }
+
+    /// 分离线程：不再保留它的返回值，已经结束的线程立即回收
+    pub(crate) fn detach_thread(&mut self, thread_id: ThreadId) {
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
         };
+        thread.joinable = false;
+        if thread.state == ThreadState::Terminated {
+            self.free_slot(thread_id);
+        }
+    }
 
-        let next_thread = self.get_thread(next_id).unwrap();
-        next_thread.state = ThreadState::Running;
-        info!("");
-        info!("Running thread {}", next_thread.id);
-        info!("ThreadState {:?}", next_thread.state);
-        info!("ThreadContext {}", next_thread.context);
-        let next_thread_cx_ptr = &next_thread.context as *const ThreadContext;
-        self.current = Some(next_id);
-
-        let current_thread_cx = match current_id {
-            Some(id) => &mut self.get_thread(id).unwrap().context,
-            None => {
-                // 如果没有当前线程，使用调度器内的占位上下文
-                &mut self.idle_context
-            }
+    /// 把线程标记为 Ready 并放入合适的 hart 就绪队列
+    ///
+    /// 说明：
+    /// - 在允许的 hart 中选择就绪队列最短的一个（优先上次运行的 hart）
+    /// - 目标 hart 不是当前 hart 时，通过 IPI 唤醒它
+    /// - 调度策略认为它应当抢占目标 hart 上正在运行的线程时，标记重新调度并发送 IPI（包括发给自己）
+    fn make_ready(&mut self, thread_id: ThreadId) {
+        let Some(thread) = self.find_thread(thread_id) else {
+            return;
+        };
+        let allowed = thread.affinity & self.active_harts;
+        let last_hart = thread.last_hart;
+        let hart_id = if allowed == 0 {
+            // 允许的 hart 都还没有进入调度循环：放到第一个允许的 hart 上等待
+            (0..MAX_HARTS)
+                .find(|&h| thread.affinity & (1 << h) != 0)
+                .unwrap_or(hart::id())
+        } else {
+            (0..MAX_HARTS)
+                .filter(|&h| allowed & (1 << h) != 0)
+                .min_by_key(|&h| (self.policies[h].len(), Some(h) != last_hart))
+                .unwrap()
         };
 
-        unsafe {
-            __switch(current_thread_cx, next_thread_cx_ptr);
+        let Some(thread) = tcb_mut(&mut self.threads, thread_id) else {
+            return;
+        };
+        thread.state = ThreadState::Ready;
+        self.policies[hart_id].enqueue(thread);
+
+        let preempt = self.running_on(hart_id).is_some_and(|current| {
+            let woken = tcb(&self.threads, thread_id).unwrap();
+            self.policies[hart_id].should_preempt(woken, current)
+        });
+        if preempt {
+            self.need_resched[hart_id] = true;
+        }
+        if hart_id != hart::id() || preempt {
+            ipi::send_reschedule(1 << hart_id);
         }
     }
 
-    /// 找到第一个就绪线程的 id（优先找其他线程）（只读遍历，避免把整个 `&mut self` 借用住）
-    fn find_ready_thread_id(&self) -> Option<usize> {
-        // 需求：优先挑选“非当前线程”的 Ready；若没有，再考虑当前线程是否 Ready
-        let current_id = if self.current.is_some() {
-            self.current.unwrap()
-        } else {
-            0
+    /// 指定 hart 上正在运行的线程
+    fn running_on(&self, hart_id: usize) -> Option<&TCB> {
+        let id = hart::get(hart_id)?.current_thread().map(ThreadId::from_raw)?;
+        self.find_thread(id)
+    }
+
+    /// 计时器 tick：更新当前线程的时间片，返回是否应当抢占当前线程
+    pub fn tick(&mut self, hart_id: usize) -> bool {
+        let resched = self.take_resched(hart_id);
+        let Some(current_id) = self.current() else {
+            return false;
         };
-        
-        let num = self.threads.len();
-
-        let mut curr = (current_id + 1) % num;
-        loop {
-            let Some(slot) = &self.threads[curr] else {
-                curr = (curr + 1) % num;
-                if curr == current_id {
-                    return None;
-                }
-                continue;
+        let Some(current) = tcb_mut(&mut self.threads, current_id) else {
+            return false;
+        };
+        current.slice_ticks += 1;
+        self.policies[hart_id].tick(current) || resched
+    }
+
+    /// 取出并清除 hart 的重新调度标记
+    pub fn take_resched(&mut self, hart_id: usize) -> bool {
+        core::mem::take(&mut self.need_resched[hart_id])
+    }
+
+    /// 设置线程的基础优先级；线程正在继承更高的优先级时，有效优先级保持不变
+    pub fn set_priority(&mut self, thread_id: ThreadId, priority: usize) -> Result<(), ThreadError> {
+        let thread = self.get_thread(thread_id).ok_or(ThreadError::Stale(thread_id))?;
+        let inherited = thread.priority > thread.base_priority;
+        thread.base_priority = priority;
+        let effective = if inherited { thread.priority.max(priority) } else { priority };
+        self.change_priority(thread_id, effective);
+        Ok(())
+    }
+
+    /// 🔗 优先级继承：`holder` 持有 `waiter` 等待的资源时，把 holder 的有效优先级提升到不低于 waiter
+    ///
+    /// 说明：由阻塞式锁在 waiter 进入等待时调用，释放资源后调用 `restore_priority`
+    pub fn inherit_priority(&mut self, holder: ThreadId, waiter: ThreadId) {
+        let (Some(h), Some(w)) = (self.find_thread(holder), self.find_thread(waiter)) else {
+            return;
+        };
+        if w.priority > h.priority {
+            let priority = w.priority;
+            self.change_priority(holder, priority);
+        }
+    }
+
+    /// 恢复线程的基础优先级（释放继承来的优先级）
+    pub fn restore_priority(&mut self, thread_id: ThreadId) {
+        if let Some(thread) = self.find_thread(thread_id) {
+            let base = thread.base_priority;
+            self.change_priority(thread_id, base);
+        }
+    }
+
+    /// 修改有效优先级；线程在就绪队列中时重新入队，使新的优先级立即生效
+    fn change_priority(&mut self, thread_id: ThreadId, priority: usize) {
+        let Some(thread) = tcb_mut(&mut self.threads, thread_id) else {
+            return;
+        };
+        if thread.priority == priority {
+            return;
+        }
+        thread.priority = priority;
+        if thread.state == ThreadState::Ready {
+            if let Some(hart_id) = self.policies.iter_mut().position(|policy| policy.remove(thread_id)) {
+                self.policies[hart_id].enqueue(thread);
+            }
+        }
+    }
+
+    /// 为指定 hart 选出下一个就绪线程
+    ///
+    /// 说明：
+    /// - 先由本 hart 的调度策略选择
+    /// - 本地队列为空时，从最长的其它队列中“偷”一个允许在本 hart 运行的线程
+    fn pick_next(&mut self, hart_id: usize) -> Option<ThreadId> {
+        while let Some(id) = self.policies[hart_id].pick_next() {
+            if self.is_ready(id) {
+                self.prepare_run(hart_id, id);
+                return Some(id);
+            }
+        }
+
+        let mut victims: Vec<usize> = (0..MAX_HARTS)
+            .filter(|&h| h != hart_id && !self.policies[h].is_empty())
+            .collect();
+        victims.sort_by_key(|&h| core::cmp::Reverse(self.policies[h].len()));
+        for victim in victims {
+            let threads = &self.threads;
+            let can_run = |id: ThreadId| {
+                tcb(threads, id)
+                    .is_some_and(|t| t.state == ThreadState::Ready && t.affinity & (1 << hart_id) != 0)
             };
-            if slot.state == ThreadState::Ready {
-                return Some(curr);
+            if let Some(id) = self.policies[victim].steal(&can_run) {
+                info!("Hart {} stole thread {} from hart {}", hart_id, id, victim);
+                self.prepare_run(victim, id);
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    /// 线程被选中后重置时间片，并通知它所在队列的调度策略
+    fn prepare_run(&mut self, source_hart: usize, thread_id: ThreadId) {
+        if let Some(thread) = tcb_mut(&mut self.threads, thread_id) {
+            thread.slice_ticks = 0;
+            self.policies[source_hart].on_run(thread);
+        }
+    }
+
+    fn is_ready(&self, thread_id: ThreadId) -> bool {
+        self.find_thread(thread_id)
+            .is_some_and(|t| t.state == ThreadState::Ready)
+    }
+
+    /// 是否所有线程都已经结束（只剩等待回收或等待取走结果的 Terminated 线程）
+    pub fn all_terminated(&self) -> bool {
+        self.threads().all(|t| t.state == ThreadState::Terminated)
+    }
+
+    /// 切出线程时保存浮点上下文（仅当 mstatus.FS 为 Dirty）
+    ///
+    /// 说明：保存后本 hart 成为该线程浮点上下文的 owner，其它 hart 上的旧副本作废
+    fn save_fp(&mut self, hart_id: usize, thread_id: ThreadId) {
+        if !fp::is_dirty() {
+            return;
+        }
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
+        };
+        thread.fp.save();
+        thread.fp_used = true;
+        for owner in self.fp_owner.iter_mut() {
+            if *owner == Some(thread_id) {
+                *owner = None;
             }
-            curr = (curr + 1) % num;
-            if curr == current_id {
-                return None;
+        }
+        self.fp_owner[hart_id] = Some(thread_id);
+    }
+
+    /// 切入线程时恢复浮点上下文
+    ///
+    /// 说明：
+    /// - 本 hart 的浮点寄存器已经是该线程的内容时不需要恢复 f0~f31
+    /// - 没用过浮点的线程的上下文是全 0，同样整个恢复：不能让它看到上一个线程留下的浮点寄存器
+    fn load_fp(&mut self, hart_id: usize, thread_id: ThreadId) {
+        let owned = self.fp_owner[hart_id] == Some(thread_id);
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
+        };
+        if owned {
+            thread.fp.restore_fcsr();
+        } else {
+            thread.fp.restore();
+            self.fp_owner[hart_id] = Some(thread_id);
+        }
+    }
+
+    /// 把当前 hart 上运行的线程的浮点上下文清零（f0~f31 和 fcsr），用于第一次进入 U-mode 之前
+    ///
+    /// 说明：线程在内核中用过的浮点寄存器不会带进用户程序
+    pub(crate) fn reset_fp(&mut self, hart_id: usize, thread_id: ThreadId) {
+        let Some(thread) = self.get_thread(thread_id) else {
+            return;
+        };
+        thread.fp = FpContext::default();
+        thread.fp_used = false;
+        thread.fp.restore();
+        for owner in self.fp_owner.iter_mut() {
+            if *owner == Some(thread_id) {
+                *owner = None;
             }
         }
+        self.fp_owner[hart_id] = Some(thread_id);
+    }
+
+    /// 是否已经没有任何可运行的线程（没有 Ready、Running，也没有会被计时器唤醒的睡眠/限时等待线程）
+    pub fn nothing_to_run(&self) -> bool {
+        !self.threads().any(|t| {
+            matches!(
+                t.state,
+                ThreadState::Ready | ThreadState::Running | ThreadState::Sleeping
+            ) || t.wake_at.is_some()
+        })
+    }
+
+    /// 按 id 查找线程：槽位已被回收或复用（代数不匹配）时返回 None
+    pub(crate) fn get_thread(&mut self, thread_id: ThreadId) -> Option<&mut TCB> {
+        tcb_mut(&mut self.threads, thread_id)
+    }
+
+    pub(crate) fn find_thread(&self, thread_id: ThreadId) -> Option<&TCB> {
+        tcb(&self.threads, thread_id)
+    }
+
+    /// 遍历所有存在的线程
+    pub(crate) fn threads(&self) -> impl Iterator<Item = &TCB> {
+        self.threads.iter().filter_map(|slot| slot.tcb.as_ref())
+    }
+}
+
+/// 在线程表中按 id 查找（检查槽位代数）
+///
+/// 说明：写成独立函数，便于在借用调度策略的同时借用线程表
+fn tcb(threads: &[Slot], thread_id: ThreadId) -> Option<&TCB> {
+    threads
+        .get(thread_id.slot())
+        .filter(|slot| slot.generation == thread_id.generation())
+        .and_then(|slot| slot.tcb.as_ref())
+}
+
+fn tcb_mut(threads: &mut [Slot], thread_id: ThreadId) -> Option<&mut TCB> {
+    threads
+        .get_mut(thread_id.slot())
+        .filter(|slot| slot.generation == thread_id.generation())
+        .and_then(|slot| slot.tcb.as_mut())
+}
+
+/// 切换到本 hart 的下一个线程
+///
+/// 说明：
+/// - 调用前当前线程的状态必须已经更新好（Ready 并入队 / Blocked / Terminated）
+/// - 没有就绪线程时切回本 hart 的 idle 上下文
+/// - 调度器锁跨越 `__switch` 持有，由切换后的上下文在 `finish_switch` 中释放，
+///   这样其它 hart 不会在寄存器保存完成之前把当前线程取走运行
+/// - 返回是否真的发生了切换（当前线程被重新选中或 idle 中没有线程可运行时返回 false）
+pub(crate) fn run_next(mut s: SpinLockGuard<'static, Scheduler>) -> bool {
+    let hart_id = hart::id();
+    let current_id = s.current();
+    // 每次切换都检查当前线程的栈金丝雀
+    if let Some(thread) = current_id.and_then(|id| s.find_thread(id)) {
+        thread.check_stack();
+    }
+    let next_id = s.pick_next(hart_id);
+
+    if next_id.is_some() && next_id == current_id {
+        // 没有其它就绪线程，继续运行当前线程
+        if let Some(thread) = s.get_thread(current_id.unwrap()) {
+            thread.state = ThreadState::Running;
+            thread.preempted = false;
+        }
+        return false;
+    }
+    if next_id.is_none() && current_id.is_none() {
+        return false;
     }
 
-    pub(crate) fn get_thread(&mut self, thread_id: usize) -> Option<&mut TCB> {
-        self.threads.get_mut(thread_id).and_then(|t| t.as_mut())
+    // CPU 时间统计：结算切出线程的运行时间，记录切入时间
+    let now = timer::get_time();
+    if let Some(thread) = current_id.and_then(|id| s.get_thread(id)) {
+        thread.run_time += now.saturating_sub(thread.run_start);
+        if core::mem::take(&mut thread.preempted) {
+            thread.involuntary += 1;
+        } else {
+            thread.voluntary += 1;
+        }
+    }
+    if let Some(thread) = next_id.and_then(|id| s.get_thread(id)) {
+        thread.run_start = now;
+        thread.switches += 1;
     }
 
-    pub(crate) fn find_thread(&self, thread_id: usize) -> Option<&TCB> {
-        self.threads.get(thread_id).and_then(|t| t.as_ref())
+    // 栈保护区跟随当前线程（切回 idle 时撤销）
+    let guard = next_id.and_then(|id| s.find_thread(id)).and_then(|t| t.stack.as_ref().map(|stack| stack.guard()));
+    crate::stack::set_thread_guard(guard);
+    // 用户线程的内核栈位置和用户栈访问权限也跟随当前线程
+    super::user::switch_to(next_id.and_then(|id| s.find_thread(id)));
+
+    // 惰性保存/恢复浮点上下文
+    if let Some(id) = current_id {
+        s.save_fp(hart_id, id);
     }
+    if let Some(id) = next_id {
+        s.load_fp(hart_id, id);
+    }
+
+    let next_thread_cx_ptr = match next_id {
+        Some(id) => {
+            let next_thread = s.get_thread(id).unwrap();
+            next_thread.check_stack();
+            next_thread.state = ThreadState::Running;
+            next_thread.last_hart = Some(hart_id);
+            debug!("Running thread {} on hart {}", next_thread.display_name(), hart_id);
+            debug!("ThreadContext {}", next_thread.context);
+            &next_thread.context as *const ThreadContext
+        }
+        None => &s.idle_contexts[hart_id] as *const ThreadContext,
+    };
+
+    let current_thread_cx = match current_id {
+        Some(id) => &mut s.get_thread(id).unwrap().context as *mut ThreadContext,
+        // 如果没有当前线程，说明是从 idle 上下文切出
+        None => &mut s.idle_contexts[hart_id] as *mut ThreadContext,
+    };
+    s.set_current(next_id);
+
+    let irq_enabled = s.leak();
+    unsafe {
+        __switch(current_thread_cx, next_thread_cx_ptr);
+    }
+    finish_switch(irq_enabled);
+    true
+}
+
+/// 上下文切换完成后的收尾工作（在切换进来的上下文中执行）
+///
+/// 说明：
+/// - 回收本 hart 上退出的线程，释放跨切换持有的调度器锁并恢复中断状态
+/// - 按切换进来的上下文（线程或 idle）重新设置计时器
+pub(crate) fn finish_switch(irq_enabled: bool) {
+    let mut s = unsafe { SCHEDULER.adopt(irq_enabled) };
+    s.reap(hart::id());
+    s.arm_timer();
 }
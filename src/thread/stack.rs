@@ -0,0 +1,85 @@
+//! 📚 线程栈
+//!
+//! 栈按 `THREAD_STACK_GUARD` 对齐分配，最低的 `THREAD_STACK_GUARD` 字节是保护区（填充 `STACK_CANARY`），
+//! 其上是可用部分（填充 `STACK_PAINT`，用于统计最高水位）
+
+extern crate alloc;
+use alloc::alloc::{alloc, dealloc, handle_alloc_error};
+use core::alloc::Layout;
+use core::mem;
+use core::ptr::NonNull;
+
+use crate::stack::{self, STACK_CANARY, STACK_PAINT, THREAD_STACK_GUARD};
+
+/// 一个线程的栈（包括底部的保护区）
+pub struct ThreadStack {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// 栈内存只被所属线程使用，TCB 只负责持有和释放它
+unsafe impl Send for ThreadStack {}
+// 其它线程通过共享引用只会读取栈内容（统计最高水位、检查保护区）
+unsafe impl Sync for ThreadStack {}
+
+impl ThreadStack {
+    /// 分配可用大小为 `size` 字节（向上取整到 16 字节）的栈
+    pub fn new(size: usize) -> Self {
+        let size = size.next_multiple_of(16);
+        let layout =
+            Layout::from_size_align(THREAD_STACK_GUARD + size, THREAD_STACK_GUARD).expect("invalid stack layout");
+        let ptr = unsafe { alloc(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            handle_alloc_error(layout);
+        };
+        let stack = Self { ptr, layout };
+        let words = unsafe {
+            core::slice::from_raw_parts_mut(ptr.as_ptr() as *mut usize, layout.size() / mem::size_of::<usize>())
+        };
+        let (guard, usable) = words.split_at_mut(THREAD_STACK_GUARD / mem::size_of::<usize>());
+        guard.fill(STACK_CANARY);
+        usable.fill(STACK_PAINT);
+        stack
+    }
+
+    /// 保护区 `(起始地址, 大小)`
+    pub fn guard(&self) -> (usize, usize) {
+        (self.ptr.as_ptr() as usize, THREAD_STACK_GUARD)
+    }
+
+    /// 可用部分的最低地址
+    pub fn bottom(&self) -> usize {
+        self.ptr.as_ptr() as usize + THREAD_STACK_GUARD
+    }
+
+    /// 栈顶（16 字节对齐）
+    pub fn top(&self) -> usize {
+        self.ptr.as_ptr() as usize + self.layout.size()
+    }
+
+    /// 可用大小（字节，不含保护区）
+    pub fn size(&self) -> usize {
+        self.layout.size() - THREAD_STACK_GUARD
+    }
+
+    /// 使用的最高水位（字节）
+    pub fn high_water(&self) -> usize {
+        stack::high_water(self.bottom(), self.top())
+    }
+
+    /// 检查保护区：返回第一个被改写的字的 (地址, 值)，完好时返回 None
+    pub fn check_guard(&self) -> Option<(usize, usize)> {
+        let (base, size) = self.guard();
+        let guard = unsafe { core::slice::from_raw_parts(base as *const usize, size / mem::size_of::<usize>()) };
+        guard
+            .iter()
+            .rposition(|&word| word != STACK_CANARY)
+            .map(|i| (base + i * mem::size_of::<usize>(), guard[i]))
+    }
+}
+
+impl Drop for ThreadStack {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
@@ -0,0 +1,79 @@
+use core::cell::UnsafeCell;
+
+use super::{key_of, park, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::WaitKind;
+
+/// 屏障状态（只在持有调度器锁时访问）
+struct State {
+    /// 本轮已经到达的线程数
+    arrived: usize,
+    /// 轮次：每放行一次加一
+    generation: usize,
+}
+
+/// 🚧 屏障：等齐 `n` 个线程后一起放行，可以重复使用
+pub struct Barrier {
+    n: usize,
+    state: UnsafeCell<State>,
+}
+
+unsafe impl Send for Barrier {}
+unsafe impl Sync for Barrier {}
+
+/// `Barrier::wait` 的结果：每一轮恰好有一个线程是 leader（最后到达的那个）
+#[derive(Debug, Clone, Copy)]
+pub struct BarrierWaitResult(bool);
+
+impl BarrierWaitResult {
+    pub fn is_leader(&self) -> bool {
+        self.0
+    }
+}
+
+impl Barrier {
+    pub const fn new(n: usize) -> Self {
+        Self {
+            n,
+            state: UnsafeCell::new(State {
+                arrived: 0,
+                generation: 0,
+            }),
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _s: &mut Scheduler) -> &mut State {
+        unsafe { &mut *self.state.get() }
+    }
+
+    /// 等待本轮所有线程到达
+    pub fn wait(&self) -> BarrierWaitResult {
+        let key = key_of(self, WaitKind::Barrier);
+        let mut s = sched();
+        let state = self.state(&mut s);
+        state.arrived += 1;
+        if state.arrived >= self.n {
+            state.arrived = 0;
+            state.generation = state.generation.wrapping_add(1);
+            s.wake_all(key);
+            return BarrierWaitResult(true);
+        }
+
+        let generation = state.generation;
+        loop {
+            let Some(me) = s.current() else {
+                // 不在线程上下文中：自旋直到轮次变化
+                spin_wait(s);
+                s = sched();
+                if self.state(&mut s).generation != generation {
+                    return BarrierWaitResult(false);
+                }
+                continue;
+            };
+            park(s, me, key, 0);
+            return BarrierWaitResult(false);
+        }
+    }
+}
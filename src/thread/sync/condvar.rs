@@ -0,0 +1,63 @@
+use core::mem::ManuallyDrop;
+
+use super::{MutexGuard, key_of, park};
+use crate::thread::sched;
+use crate::thread::tcb::WaitKind;
+
+/// 🔔 条件变量
+///
+/// 说明：
+/// - `wait` 在同一个调度器临界区内释放互斥锁并挂起，不会丢失 `notify`
+/// - 被唤醒后重新获取互斥锁再返回；条件可能已经被其它线程改变，应当在循环中检查（或使用 `wait_while`）
+pub struct Condvar {
+    /// 占位，保证每个条件变量有独立的地址（等待队列 key）
+    _key: u8,
+}
+
+impl Condvar {
+    pub const fn new() -> Self {
+        Self { _key: 0 }
+    }
+
+    /// 释放 `guard` 对应的互斥锁并等待通知，返回重新加锁后的 guard
+    ///
+    /// 说明：不在线程上下文中调用时不会阻塞，直接重新加锁返回
+    pub fn wait<'a, T: ?Sized>(&self, guard: MutexGuard<'a, T>) -> MutexGuard<'a, T> {
+        let mutex = ManuallyDrop::new(guard).mutex;
+        let mut s = sched();
+        mutex.unlock_locked(&mut s);
+        match s.current() {
+            Some(me) => park(s, me, key_of(self, WaitKind::Condvar), 0),
+            None => drop(s),
+        }
+        mutex.lock()
+    }
+
+    /// 等待直到 `condition` 返回 false
+    pub fn wait_while<'a, T: ?Sized>(
+        &self,
+        mut guard: MutexGuard<'a, T>,
+        mut condition: impl FnMut(&mut T) -> bool,
+    ) -> MutexGuard<'a, T> {
+        while condition(&mut *guard) {
+            guard = self.wait(guard);
+        }
+        guard
+    }
+
+    /// 唤醒一个等待的线程（最早等待的那个）
+    pub fn notify_one(&self) {
+        sched().wake_one(key_of(self, WaitKind::Condvar));
+    }
+
+    /// 唤醒所有等待的线程
+    pub fn notify_all(&self) {
+        sched().wake_all(key_of(self, WaitKind::Condvar));
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,65 @@
+//! 🔐 阻塞式同步原语
+//!
+//! 与 `spin::Mutex` 不同，这里的原语在竞争时把线程挂到调度器的等待队列上并让出 CPU：
+//! - `Mutex<T>`：互斥锁，解锁时直接把锁交给最早等待的线程（FIFO），支持优先级继承
+//! - `Condvar`：条件变量，配合 `Mutex` 使用
+//! - `Semaphore`：计数信号量
+//! - `RwLock<T>`：读写锁，读者和写者按到达顺序排队
+//! - `Barrier`：屏障，等齐 n 个线程后一起放行
+//! - `Once`：只执行一次的初始化
+//! - `mpsc`：线程间消息通道（`channel` / `sync_channel`）
+//!
+//! 实现说明：
+//! - 每个原语以自己的地址和种类作为 key，在调度器中拥有一条 FIFO 等待队列
+//! - 原语的内部状态只在持有调度器锁时访问：检查状态和挂起线程在同一个临界区内完成，
+//!   不会丢失唤醒；调度器锁关中断，所以计时器抢占也不会打断这些操作
+//! - 不在线程上下文中（例如 `thread::init` 之前）使用时，竞争情况下退化为自旋等待
+
+mod barrier;
+mod condvar;
+pub mod mpsc;
+mod mutex;
+mod once;
+mod rwlock;
+mod semaphore;
+
+pub use barrier::{Barrier, BarrierWaitResult};
+pub use condvar::Condvar;
+pub use mutex::{Mutex, MutexGuard};
+pub use once::Once;
+pub use rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use semaphore::Semaphore;
+
+use super::scheduler::{self, Scheduler};
+use super::tcb::{ThreadId, WaitKey, WaitKind};
+use crate::spinlock::SpinLockGuard;
+
+/// 同步原语在调度器中的等待队列 key：原语的地址和种类
+///
+/// 说明：带上种类是因为不同原语可能地址相同（例如结构体和它的第一个字段）
+fn key_of<T: ?Sized>(primitive: &T, kind: WaitKind) -> WaitKey {
+    WaitKey {
+        addr: primitive as *const T as *const u8 as usize,
+        kind,
+    }
+}
+
+/// 把当前线程挂到 `key` 的等待队列上并让出 CPU，被唤醒后返回
+///
+/// 说明：调用前必须已经在同一个调度器临界区内检查过原语状态
+fn park(mut s: SpinLockGuard<'static, Scheduler>, me: ThreadId, key: WaitKey, tag: usize) {
+    s.block_on(me, key, tag);
+    scheduler::run_next(s);
+}
+
+/// 与 `park` 相同，但最晚在 `deadline`（mtime）被唤醒
+fn park_until(mut s: SpinLockGuard<'static, Scheduler>, me: ThreadId, key: WaitKey, tag: usize, deadline: usize) {
+    s.block_on_until(me, key, tag, deadline);
+    scheduler::run_next(s);
+}
+
+/// 不在线程上下文中时的等待：释放调度器锁，自旋一会儿再重试
+fn spin_wait(s: SpinLockGuard<'static, Scheduler>) {
+    drop(s);
+    core::hint::spin_loop();
+}
@@ -0,0 +1,329 @@
+//! 📬 线程间消息通道
+//!
+//! - `channel()`：无界多生产者单消费者通道，`Sender` 可以克隆
+//! - `sync_channel(n)`：容量为 `n` 的有界单生产者单消费者通道，满时 `send` 阻塞
+//!
+//! 接收方在通道为空时阻塞在调度器的等待队列上，发送方发送后唤醒它；
+//! 所有发送方都被丢弃后，接收方取完剩余消息会得到 `Disconnected`。
+
+extern crate alloc;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::cell::UnsafeCell;
+use core::fmt::{Display, Formatter};
+
+use super::{key_of, park, park_until, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::{WaitKey, WaitKind};
+use crate::timer;
+
+/// 接收方已经被丢弃，发送失败，消息原样返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+/// 所有发送方都已经被丢弃，并且通道中没有剩余消息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    /// 有界通道已满
+    Full(T),
+    /// 接收方已经被丢弃
+    Disconnected(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// 通道当前为空
+    Empty,
+    /// 所有发送方都已经被丢弃
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvTimeoutError {
+    /// 超时前没有收到消息
+    Timeout,
+    /// 所有发送方都已经被丢弃
+    Disconnected,
+}
+
+impl<T> Display for SendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "sending on a closed channel")
+    }
+}
+
+impl Display for RecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "receiving on a closed channel")
+    }
+}
+
+impl<T> Display for TrySendError<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TrySendError::Full(_) => write!(f, "sending on a full channel"),
+            TrySendError::Disconnected(_) => write!(f, "sending on a closed channel"),
+        }
+    }
+}
+
+impl Display for TryRecvError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "receiving on an empty channel"),
+            TryRecvError::Disconnected => write!(f, "receiving on a closed channel"),
+        }
+    }
+}
+
+impl Display for RecvTimeoutError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RecvTimeoutError::Timeout => write!(f, "timed out waiting on channel"),
+            RecvTimeoutError::Disconnected => write!(f, "channel is empty and sending half is closed"),
+        }
+    }
+}
+
+/// 通道状态（只在持有调度器锁时访问）
+struct State<T> {
+    queue: VecDeque<T>,
+    /// 容量；None 表示无界
+    capacity: Option<usize>,
+    /// 存活的发送方数量
+    senders: usize,
+    /// 接收方是否存活
+    receiver_alive: bool,
+}
+
+/// 通道的共享部分
+///
+/// 说明：等待队列 key 取自它的地址，接收方等待 `recv_key`，发送方（有界通道满时）等待 `send_key`
+struct Channel<T> {
+    state: UnsafeCell<State<T>>,
+}
+
+unsafe impl<T: Send> Send for Channel<T> {}
+unsafe impl<T: Send> Sync for Channel<T> {}
+
+impl<T> Channel<T> {
+    fn new(capacity: Option<usize>) -> Arc<Self> {
+        Arc::new(Self {
+            state: UnsafeCell::new(State {
+                queue: VecDeque::new(),
+                capacity,
+                senders: 1,
+                receiver_alive: true,
+            }),
+        })
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _s: &mut Scheduler) -> &mut State<T> {
+        unsafe { &mut *self.state.get() }
+    }
+
+    fn recv_key(&self) -> WaitKey {
+        key_of(self, WaitKind::ChannelRecv)
+    }
+
+    fn send_key(&self) -> WaitKey {
+        key_of(self, WaitKind::ChannelSend)
+    }
+
+    /// 发送一条消息；有界通道满时 `block` 为 true 则阻塞等待
+    fn send(&self, value: T, block: bool) -> Result<(), TrySendError<T>> {
+        loop {
+            let mut s = sched();
+            let state = self.state(&mut s);
+            if !state.receiver_alive {
+                return Err(TrySendError::Disconnected(value));
+            }
+            if state.capacity.is_none_or(|capacity| state.queue.len() < capacity) {
+                state.queue.push_back(value);
+                s.wake_one(self.recv_key());
+                return Ok(());
+            }
+            if !block {
+                return Err(TrySendError::Full(value));
+            }
+            match s.current() {
+                Some(me) => park(s, me, self.send_key(), 0),
+                None => spin_wait(s),
+            }
+        }
+    }
+
+    /// 接收一条消息
+    ///
+    /// 说明：`deadline` 为 None 时一直等待；`block` 为 false 时不等待
+    fn recv(&self, block: bool, deadline: Option<usize>) -> Result<T, RecvTimeoutError> {
+        loop {
+            let mut s = sched();
+            let state = self.state(&mut s);
+            if let Some(value) = state.queue.pop_front() {
+                s.wake_one(self.send_key());
+                return Ok(value);
+            }
+            if state.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            if !block || deadline.is_some_and(|deadline| timer::get_time() >= deadline) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            match (s.current(), deadline) {
+                (Some(me), Some(deadline)) => park_until(s, me, self.recv_key(), 0, deadline),
+                (Some(me), None) => park(s, me, self.recv_key(), 0),
+                (None, _) => spin_wait(s),
+            }
+        }
+    }
+
+    /// 发送方被丢弃
+    fn drop_sender(&self) {
+        let mut s = sched();
+        let state = self.state(&mut s);
+        state.senders -= 1;
+        if state.senders == 0 {
+            // 唤醒接收方，让它发现通道已经断开
+            s.wake_all(self.recv_key());
+        }
+    }
+}
+
+/// 📤 无界通道的发送方，可以克隆给多个线程
+pub struct Sender<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// 📤 有界通道的发送方（单生产者）
+pub struct SyncSender<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// 📥 接收方
+pub struct Receiver<T> {
+    channel: Arc<Channel<T>>,
+}
+
+/// 📬 创建无界多生产者单消费者通道
+pub fn channel<T: Send>() -> (Sender<T>, Receiver<T>) {
+    let channel = Channel::new(None);
+    (
+        Sender {
+            channel: channel.clone(),
+        },
+        Receiver { channel },
+    )
+}
+
+/// 📬 创建容量为 `bound` 的有界单生产者单消费者通道
+///
+/// 说明：容量至少为 1
+pub fn sync_channel<T: Send>(bound: usize) -> (SyncSender<T>, Receiver<T>) {
+    let channel = Channel::new(Some(bound.max(1)));
+    (
+        SyncSender {
+            channel: channel.clone(),
+        },
+        Receiver { channel },
+    )
+}
+
+impl<T: Send> Sender<T> {
+    /// 发送消息（不会阻塞），接收方已经被丢弃时返回 `SendError`
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.channel.send(value, false).map_err(|e| match e {
+            TrySendError::Full(value) | TrySendError::Disconnected(value) => SendError(value),
+        })
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        let mut s = sched();
+        self.channel.state(&mut s).senders += 1;
+        Self {
+            channel: self.channel.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.channel.drop_sender();
+    }
+}
+
+impl<T: Send> SyncSender<T> {
+    /// 发送消息，通道满时阻塞等待接收方取走消息
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.channel.send(value, true).map_err(|e| match e {
+            TrySendError::Full(value) | TrySendError::Disconnected(value) => SendError(value),
+        })
+    }
+
+    /// 尝试发送消息，通道满时立即返回 `TrySendError::Full`
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.channel.send(value, false)
+    }
+}
+
+impl<T> Drop for SyncSender<T> {
+    fn drop(&mut self) {
+        self.channel.drop_sender();
+    }
+}
+
+impl<T: Send> Receiver<T> {
+    /// 接收消息，通道为空时阻塞等待
+    ///
+    /// 说明：所有发送方都被丢弃且没有剩余消息时返回 `RecvError`
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.channel.recv(true, None).map_err(|_| RecvError)
+    }
+
+    /// 尝试接收消息，不阻塞
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        self.channel.recv(false, None).map_err(|e| match e {
+            RecvTimeoutError::Timeout => TryRecvError::Empty,
+            RecvTimeoutError::Disconnected => TryRecvError::Disconnected,
+        })
+    }
+
+    /// 接收消息，最多等待 `ms` 毫秒
+    pub fn recv_timeout(&self, ms: usize) -> Result<T, RecvTimeoutError> {
+        let deadline = timer::get_time() + timer::clock_freq() * ms / 1000;
+        self.channel.recv(true, Some(deadline))
+    }
+
+    /// 阻塞迭代收到的消息，直到通道断开
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(move || self.recv().ok())
+    }
+
+    /// 迭代当前已经到达的消息，不阻塞
+    pub fn try_iter(&self) -> impl Iterator<Item = T> + '_ {
+        core::iter::from_fn(move || self.try_recv().ok())
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        let pending = {
+            let mut s = sched();
+            let state = self.channel.state(&mut s);
+            state.receiver_alive = false;
+            let pending = core::mem::take(&mut state.queue);
+            // 唤醒阻塞在满通道上的发送方，让它发现接收方已经断开
+            s.wake_all(self.channel.send_key());
+            pending
+        };
+        // 剩余消息在释放调度器锁之后再析构
+        drop(pending);
+    }
+}
@@ -0,0 +1,168 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use super::{key_of, park, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::{ThreadId, WaitKind};
+
+/// 互斥锁状态（只在持有调度器锁时访问）
+struct State {
+    locked: bool,
+    /// 持有者；不在线程上下文中加锁时为 None
+    owner: Option<ThreadId>,
+}
+
+/// 🔐 阻塞式互斥锁
+///
+/// 说明：
+/// - 锁被占用时，当前线程在调度器中排队并让出 CPU
+/// - 解锁时直接把锁交给最早等待的线程（FIFO），后来者不能插队
+/// - 等待者优先级更高时，持有者临时继承它的优先级，解锁后恢复
+///   （简化实现：同时持有多把锁时，释放任意一把都会恢复基础优先级）
+pub struct Mutex<T: ?Sized> {
+    state: UnsafeCell<State>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+
+/// 互斥锁的 RAII guard，离开作用域时解锁
+pub struct MutexGuard<'a, T: ?Sized> {
+    pub(super) mutex: &'a Mutex<T>,
+    /// 让 guard 不自动实现 Sync（否则 `T: Send` 就够了），由下面按 `T: Sync` 实现
+    _not_sync: PhantomData<*const ()>,
+}
+
+// 共享 guard 相当于共享 &T
+unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+impl<T> Mutex<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: UnsafeCell::new(State {
+                locked: false,
+                owner: None,
+            }),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// 访问内部状态；要求调用方持有调度器锁（以 `&mut Scheduler` 作为凭证）
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _s: &mut Scheduler) -> &mut State {
+        unsafe { &mut *self.state.get() }
+    }
+
+    /// 加锁，锁被占用时阻塞等待
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        let key = key_of(self, WaitKind::Mutex);
+        loop {
+            let mut s = sched();
+            let me = s.current();
+            let state = self.state(&mut s);
+            if !state.locked {
+                state.locked = true;
+                state.owner = me;
+                return MutexGuard {
+                    mutex: self,
+                    _not_sync: PhantomData,
+                };
+            }
+            let owner = state.owner;
+            let Some(me) = me else {
+                spin_wait(s);
+                continue;
+            };
+            if let Some(owner) = owner {
+                s.inherit_priority(owner, me);
+            }
+            // 被唤醒时解锁方已经把锁交给了我们
+            park(s, me, key, 0);
+            return MutexGuard {
+                mutex: self,
+                _not_sync: PhantomData,
+            };
+        }
+    }
+
+    /// 尝试加锁，锁被占用时立即返回 None
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        let mut s = sched();
+        let me = s.current();
+        let state = self.state(&mut s);
+        if state.locked {
+            return None;
+        }
+        state.locked = true;
+        state.owner = me;
+        Some(MutexGuard {
+            mutex: self,
+            _not_sync: PhantomData,
+        })
+    }
+
+    /// 是否已被锁住
+    pub fn is_locked(&self) -> bool {
+        let mut s = sched();
+        self.state(&mut s).locked
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// 解锁：有等待者时把锁直接交给队首线程
+    ///
+    /// 说明：在调度器临界区内调用，供 guard 和 `Condvar` 使用
+    pub(super) fn unlock_locked(&self, s: &mut Scheduler) {
+        let key = key_of(self, WaitKind::Mutex);
+        if let Some(owner) = self.state(s).owner {
+            s.restore_priority(owner);
+        }
+        let next = s.wake_one(key).map(|(thread_id, _)| thread_id);
+        let state = self.state(s);
+        match next {
+            Some(next) => state.owner = Some(next),
+            None => {
+                state.locked = false;
+                state.owner = None;
+            }
+        }
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut s = sched();
+        self.mutex.unlock_locked(&mut s);
+    }
+}
@@ -0,0 +1,74 @@
+use core::cell::UnsafeCell;
+
+use super::{key_of, park, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::WaitKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Incomplete,
+    Running,
+    Complete,
+}
+
+/// 1️⃣ 只执行一次的初始化
+///
+/// 说明：多个线程同时调用 `call_once` 时，只有第一个执行闭包，其它线程阻塞到执行完成
+pub struct Once {
+    state: UnsafeCell<State>,
+}
+
+unsafe impl Send for Once {}
+unsafe impl Sync for Once {}
+
+impl Once {
+    pub const fn new() -> Self {
+        Self {
+            state: UnsafeCell::new(State::Incomplete),
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _s: &mut Scheduler) -> &mut State {
+        unsafe { &mut *self.state.get() }
+    }
+
+    /// 执行 `f`（只有第一次调用会执行）
+    ///
+    /// 说明：`f` 在不持有调度器锁的情况下执行，可以阻塞、创建线程
+    pub fn call_once(&self, f: impl FnOnce()) {
+        let key = key_of(self, WaitKind::Once);
+        loop {
+            let mut s = sched();
+            match *self.state(&mut s) {
+                State::Complete => return,
+                State::Running => match s.current() {
+                    Some(me) => park(s, me, key, 0),
+                    None => spin_wait(s),
+                },
+                State::Incomplete => {
+                    *self.state(&mut s) = State::Running;
+                    drop(s);
+                    f();
+                    let mut s = sched();
+                    *self.state(&mut s) = State::Complete;
+                    s.wake_all(key);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// 是否已经执行完成
+    pub fn is_completed(&self) -> bool {
+        let mut s = sched();
+        *self.state(&mut s) == State::Complete
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
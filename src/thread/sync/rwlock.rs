@@ -0,0 +1,222 @@
+use core::cell::UnsafeCell;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+
+use super::{key_of, park, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::WaitKind;
+
+/// 等待队列中的标记：读者 / 写者
+const READER: usize = 0;
+const WRITER: usize = 1;
+
+/// 读写锁状态（只在持有调度器锁时访问）
+struct State {
+    readers: usize,
+    writer: bool,
+}
+
+/// 📖 阻塞式读写锁
+///
+/// 说明：
+/// - 读者和写者按到达顺序排队（FIFO）：有线程在排队时新来的读者也要排队，写者不会饿死
+/// - 写者解锁时，如果队首是读者，会把队首连续的读者一起放行
+pub struct RwLock<T: ?Sized> {
+    state: UnsafeCell<State>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: ?Sized + Send> Send for RwLock<T> {}
+unsafe impl<T: ?Sized + Send + Sync> Sync for RwLock<T> {}
+
+/// 读锁 guard
+pub struct RwLockReadGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_sync: PhantomData<*const ()>,
+}
+
+/// 写锁 guard
+pub struct RwLockWriteGuard<'a, T: ?Sized> {
+    lock: &'a RwLock<T>,
+    _not_sync: PhantomData<*const ()>,
+}
+
+// guard 不自动实现 Sync（写锁 guard 在 `T: Send` 时就会是 Sync），共享 guard 相当于共享 &T
+unsafe impl<T: ?Sized + Sync> Sync for RwLockReadGuard<'_, T> {}
+unsafe impl<T: ?Sized + Sync> Sync for RwLockWriteGuard<'_, T> {}
+
+impl<T> RwLock<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            state: UnsafeCell::new(State {
+                readers: 0,
+                writer: false,
+            }),
+            data: UnsafeCell::new(value),
+        }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+}
+
+impl<T: ?Sized> RwLock<T> {
+    #[allow(clippy::mut_from_ref)]
+    fn state(&self, _s: &mut Scheduler) -> &mut State {
+        unsafe { &mut *self.state.get() }
+    }
+
+    /// 获取读锁
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        let key = key_of(self, WaitKind::RwLock);
+        loop {
+            let mut s = sched();
+            let queued = s.has_waiters(key);
+            let state = self.state(&mut s);
+            if !state.writer && !queued {
+                state.readers += 1;
+                return RwLockReadGuard {
+                    lock: self,
+                    _not_sync: PhantomData,
+                };
+            }
+            let Some(me) = s.current() else {
+                spin_wait(s);
+                continue;
+            };
+            // 被唤醒时解锁方已经为我们计入了读者数
+            park(s, me, key, READER);
+            return RwLockReadGuard {
+                lock: self,
+                _not_sync: PhantomData,
+            };
+        }
+    }
+
+    /// 获取写锁
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        let key = key_of(self, WaitKind::RwLock);
+        loop {
+            let mut s = sched();
+            let queued = s.has_waiters(key);
+            let state = self.state(&mut s);
+            if !state.writer && state.readers == 0 && !queued {
+                state.writer = true;
+                return RwLockWriteGuard {
+                    lock: self,
+                    _not_sync: PhantomData,
+                };
+            }
+            let Some(me) = s.current() else {
+                spin_wait(s);
+                continue;
+            };
+            // 被唤醒时解锁方已经把写锁交给了我们
+            park(s, me, key, WRITER);
+            return RwLockWriteGuard {
+                lock: self,
+                _not_sync: PhantomData,
+            };
+        }
+    }
+
+    /// 尝试获取读锁
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        let mut s = sched();
+        let queued = s.has_waiters(key_of(self, WaitKind::RwLock));
+        let state = self.state(&mut s);
+        if state.writer || queued {
+            return None;
+        }
+        state.readers += 1;
+        Some(RwLockReadGuard {
+            lock: self,
+            _not_sync: PhantomData,
+        })
+    }
+
+    /// 尝试获取写锁
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        let mut s = sched();
+        let state = self.state(&mut s);
+        if state.writer || state.readers > 0 {
+            return None;
+        }
+        state.writer = true;
+        Some(RwLockWriteGuard {
+            lock: self,
+            _not_sync: PhantomData,
+        })
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    /// 锁完全释放后，把锁交给队首的写者，或者放行队首连续的读者
+    fn hand_off(&self, s: &mut Scheduler) {
+        let key = key_of(self, WaitKind::RwLock);
+        match s.first_waiter(key) {
+            Some(WRITER) => {
+                s.wake_one(key);
+                self.state(s).writer = true;
+            }
+            Some(_) => {
+                while s.first_waiter(key) == Some(READER) {
+                    s.wake_one(key);
+                    self.state(s).readers += 1;
+                }
+            }
+            None => {}
+        }
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockReadGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockReadGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut s = sched();
+        let state = self.lock.state(&mut s);
+        state.readers -= 1;
+        if state.readers == 0 {
+            self.lock.hand_off(&mut s);
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for RwLockWriteGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> DerefMut for RwLockWriteGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.lock.data.get() }
+    }
+}
+
+impl<T: ?Sized> Drop for RwLockWriteGuard<'_, T> {
+    fn drop(&mut self) {
+        let mut s = sched();
+        self.lock.state(&mut s).writer = false;
+        self.lock.hand_off(&mut s);
+    }
+}
@@ -0,0 +1,75 @@
+use core::cell::UnsafeCell;
+
+use super::{key_of, park, spin_wait};
+use crate::thread::scheduler::Scheduler;
+use crate::thread::sched;
+use crate::thread::tcb::WaitKind;
+
+/// 🎫 计数信号量
+///
+/// 说明：
+/// - `acquire` 在没有许可时阻塞；`release` 优先把许可直接交给最早等待的线程（FIFO）
+pub struct Semaphore {
+    /// 可用许可数（只在持有调度器锁时访问）
+    permits: UnsafeCell<usize>,
+}
+
+unsafe impl Send for Semaphore {}
+unsafe impl Sync for Semaphore {}
+
+impl Semaphore {
+    pub const fn new(permits: usize) -> Self {
+        Self {
+            permits: UnsafeCell::new(permits),
+        }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    fn permits(&self, _s: &mut Scheduler) -> &mut usize {
+        unsafe { &mut *self.permits.get() }
+    }
+
+    /// 获取一个许可，没有许可时阻塞等待
+    pub fn acquire(&self) {
+        loop {
+            let mut s = sched();
+            let permits = self.permits(&mut s);
+            if *permits > 0 {
+                *permits -= 1;
+                return;
+            }
+            let Some(me) = s.current() else {
+                spin_wait(s);
+                continue;
+            };
+            // 被唤醒时释放方已经把许可交给了我们
+            park(s, me, key_of(self, WaitKind::Semaphore), 0);
+            return;
+        }
+    }
+
+    /// 尝试获取一个许可，没有许可时立即返回 false
+    pub fn try_acquire(&self) -> bool {
+        let mut s = sched();
+        let permits = self.permits(&mut s);
+        if *permits == 0 {
+            return false;
+        }
+        *permits -= 1;
+        true
+    }
+
+    /// 归还一个许可：有等待者时直接交给队首线程
+    pub fn release(&self) {
+        let mut s = sched();
+        if s.wake_one(key_of(self, WaitKind::Semaphore)).is_none() {
+            *self.permits(&mut s) += 1;
+        }
+    }
+
+    /// 当前可用许可数
+    pub fn available(&self) -> usize {
+        let mut s = sched();
+        *self.permits(&mut s)
+    }
+}
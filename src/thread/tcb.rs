@@ -1,9 +1,110 @@
 extern crate alloc;
 use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::any::Any;
 use core::fmt::{Display, Formatter};
-use core::mem;
 
-use super::{STACK_SIZE, thread_entry};
+use super::fp::FpContext;
+use super::stack::ThreadStack;
+use super::tls::TlsBlock;
+use super::user::UserContext;
+use super::{Builder, thread_entry};
+use crate::timer;
+
+pub use crate::stack::{STACK_CANARY, STACK_PAINT};
+
+/// 线程任务：执行完毕后返回装箱的结果，由 `JoinHandle::join` 取回
+pub type Job = Box<dyn FnOnce() -> Box<dyn Any + Send> + Send + 'static>;
+
+/// 线程 id：槽位下标 + 槽位代数
+///
+/// 说明：
+/// - 槽位每回收一次代数加一，所以槽位被复用后，旧 id 不会再匹配到新线程
+/// - 可以打包成一个 `usize` 存放在 hart 私有数据中（低 32 位槽位，高 32 位代数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ThreadId {
+    slot: u32,
+    generation: u32,
+}
+
+impl ThreadId {
+    pub(crate) const fn new(slot: usize, generation: u32) -> Self {
+        Self {
+            slot: slot as u32,
+            generation,
+        }
+    }
+
+    /// 线程表中的槽位下标
+    pub fn slot(&self) -> usize {
+        self.slot as usize
+    }
+
+    /// 槽位代数
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    pub(crate) fn as_raw(self) -> usize {
+        ((self.generation as usize) << 32) | self.slot as usize
+    }
+
+    pub(crate) fn from_raw(raw: usize) -> Self {
+        Self {
+            slot: raw as u32,
+            generation: (raw >> 32) as u32,
+        }
+    }
+}
+
+impl Display for ThreadId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}", self.slot, self.generation)
+    }
+}
+
+/// 等待队列所属的同步原语种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WaitKind {
+    Mutex,
+    Condvar,
+    Semaphore,
+    RwLock,
+    Barrier,
+    Once,
+    /// 通道的接收方
+    ChannelRecv,
+    /// 有界通道的发送方
+    ChannelSend,
+}
+
+/// 调度器等待队列的 key：原语地址 + 原语种类
+///
+/// 说明：只用地址会冲突，例如条件变量嵌在互斥锁保护的结构体开头、或者结构体的第一个字段本身是另一个原语时
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WaitKey {
+    pub addr: usize,
+    pub kind: WaitKind,
+}
+
+/// 线程阻塞的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitReason {
+    /// 等待指定线程结束（join）
+    Join(ThreadId),
+    /// 在调度器的等待队列上等待（同步原语）
+    Queue(WaitKey),
+}
+
+impl Display for WaitReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            WaitReason::Join(id) => write!(f, "join {}", id),
+            WaitReason::Queue(key) => write!(f, "{:?} 0x{:x}", key.kind, key.addr),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ThreadState {
@@ -11,73 +112,215 @@ pub enum ThreadState {
     Running,
     Ready,
     Blocked,
+    /// 在 `thread::sleep` 中等待唤醒时间到达
+    Sleeping,
     Terminated,
 }
 
+/// 线程上下文：由 `__switch` 保存/恢复，字段偏移必须与 switch.S 一致
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ThreadContext {
     pub ra: usize,
     pub sp: usize,
     pub s: [usize; 12],
+    /// 线程指针：指向线程的 TLS 块，线程之外为 0
+    pub tp: usize,
+}
+
+impl ThreadContext {
+    /// 全零上下文（可用于常量初始化）
+    pub const fn zero() -> Self {
+        Self {
+            ra: 0,
+            sp: 0,
+            s: [0; 12],
+            tp: 0,
+        }
+    }
 }
 
 impl Display for ThreadContext {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
-            "ThreadContext {{ ra: 0x{:x}, sp: 0x{:x}, s: [{:?}] }}",
-            self.ra, self.sp, self.s
+            "ThreadContext {{ ra: 0x{:x}, sp: 0x{:x}, s: [{:?}], tp: 0x{:x} }}",
+            self.ra, self.sp, self.s, self.tp
         )
     }
 }
 
 pub struct TCB {
     // Fields for the TCB
-    pub id: usize,
+    pub id: ThreadId,
+    /// 线程名（可选）
+    pub name: Option<String>,
+    /// 有效优先级（数值越大优先级越高），可能因优先级继承而高于 `base_priority`
+    pub priority: usize,
+    /// 基础优先级（创建时或 `thread::set_priority` 设置的值）
+    pub base_priority: usize,
     pub state: ThreadState,
     pub context: ThreadContext,
+    /// 浮点上下文：切出时 mstatus.FS 为 Dirty 才保存
+    pub fp: FpContext,
+    /// 是否用过浮点寄存器（至少保存过一次浮点上下文）
+    pub fp_used: bool,
     /// 线程要执行的任务（闭包）
     ///
     /// 说明：
     /// - `FnOnce` 表示只能执行一次（执行时会 move 掉闭包本身）
     /// - 用 `Option` 包一层，便于在 `run()` 里通过 `take()` 安全地取出并执行
     /// - 加 `Send` 是为了后续能把调度器放进全局静态（例如 `lazy_static`），满足类型约束
-    pub job: Option<Box<dyn FnOnce() + Send + 'static>>,
+    pub job: Option<Job>,
+
+    /// 线程返回值：线程结束后保存在这里，直到被 join 取走
+    pub result: Option<Box<dyn Any + Send>>,
+    /// 是否还有 `JoinHandle` 指向该线程；为 true 时线程结束后保留 TCB，等待取走结果
+    pub joinable: bool,
+
+    /// 阻塞原因；None 表示没有阻塞等待
+    pub blocked_on: Option<WaitReason>,
+    /// 睡眠线程或限时等待线程的唤醒时间（mtime）；None 表示没有定时唤醒
+    pub wake_at: Option<usize>,
 
-    /// join 等待目标线程的 id；None 表示未阻塞等待
-    pub waiting_for: Option<usize>,
+    /// 线程栈（底部是保护区）；线程结束后为 None，`thread::list` 扫描期间持有的引用释放后才真正释放
+    pub stack: Option<Arc<ThreadStack>>,
+    /// 线程局部存储块，`context.tp` 指向它；线程结束后与栈一起释放
+    pub tls: Option<TlsBlock>,
+    /// 用户线程的用户栈和内核栈位置；普通线程为 None
+    pub user: Option<UserContext>,
 
-    pub stack: Box<[usize; STACK_SIZE]>,
+    /// 允许运行的 hart 掩码（bit i 对应 hart i），默认不限制
+    pub affinity: usize,
+    /// 上一次运行所在的 hart，用于调度时优先放回原 hart
+    pub last_hart: Option<usize>,
+
+    /// 本次被调度以来已经运行的计时器 tick 数（时间片计数）
+    pub slice_ticks: usize,
+    /// 多级反馈队列中的级别（0 级最高）
+    pub level: usize,
+    /// 周期（mtime 计数）；None 表示不是周期性线程
+    pub period: Option<usize>,
+    /// 当前周期的释放时间（mtime）
+    pub release: usize,
+    /// 当前周期的绝对截止时间（mtime），非周期线程为 `usize::MAX`
+    pub deadline: usize,
+
+    /// 创建时间（mtime）
+    pub created_at: usize,
+    /// 累计运行时间（mtime 计数，不含当前这次运行）
+    pub run_time: usize,
+    /// 本次被切换进来的时间（mtime）
+    pub run_start: usize,
+    /// 被切换进来运行的次数
+    pub switches: usize,
+    /// 主动让出 CPU 的次数（yield、阻塞、睡眠、退出）
+    pub voluntary: usize,
+    /// 被抢占的次数
+    pub involuntary: usize,
+    /// 本次让出 CPU 是否是被抢占（切出时计入 `involuntary`）
+    pub preempted: bool,
 }
 
 impl TCB {
-    pub fn new(id: usize, job: Option<Box<dyn FnOnce() + Send + 'static>>) -> Self {
+    pub fn new(id: ThreadId, job: Option<Job>, config: &Builder) -> Self {
+        let now = timer::get_time();
+        let period = config.period_ms.map(|ms| (timer::clock_freq() * ms / 1000).max(1));
         let mut tcb = TCB {
             id,
+            name: config.name.clone(),
+            priority: config.priority,
+            base_priority: config.priority,
             state: ThreadState::Uninit,
             context: ThreadContext::default(),
+            fp: FpContext::default(),
+            fp_used: false,
             job,
-            waiting_for: None,
-            stack: Box::new([0; STACK_SIZE]),
+            result: None,
+            joinable: false,
+            blocked_on: None,
+            wake_at: None,
+            stack: Some(Arc::new(ThreadStack::new(config.stack_size))),
+            tls: Some(TlsBlock::new()),
+            user: None,
+            affinity: if config.affinity == 0 { usize::MAX } else { config.affinity },
+            last_hart: None,
+            slice_ticks: 0,
+            level: 0,
+            period,
+            release: now,
+            deadline: period.map_or(usize::MAX, |period| now + period),
+            created_at: now,
+            run_time: 0,
+            run_start: now,
+            switches: 0,
+            voluntary: 0,
+            involuntary: 0,
+            preempted: false,
         };
         // 初始化线程上下文：
         // - ra 指向线程入口 trampoline（统一入口负责调用 job）
-        // - sp 指向“栈顶”（RISC-V 栈向低地址增长），并按 16 字节对齐
-        let sp_top = tcb.stack.as_ptr() as usize + STACK_SIZE * mem::size_of::<usize>();
-        let sp_top_aligned = sp_top & !0xF;
+        // - sp 指向“栈顶”（RISC-V 栈向低地址增长），已按 16 字节对齐
+        // - tp 指向按 .tdata/.tbss 模板初始化好的 TLS 块
+        let sp_top_aligned = tcb.stack.as_deref().map_or(0, ThreadStack::top);
         tcb.context = ThreadContext {
             ra: thread_entry as usize,
             sp: sp_top_aligned,
             s: [0; 12],
+            tp: tcb.tls.as_ref().map_or(0, TlsBlock::tp),
         };
         tcb
     }
 
     // Methods for the TCB
+    /// 用于日志和错误信息的线程名：有名字时为 `name#slot.gen`，否则为 `thread#slot.gen`
+    pub fn display_name(&self) -> String {
+        match &self.name {
+            Some(name) => alloc::format!("{}#{}", name, self.id),
+            None => alloc::format!("thread#{}", self.id),
+        }
+    }
+
+    /// 检查栈保护区，被改写时 panic 并报告线程名
+    ///
+    /// 说明：保护区由 PMP 强制时不需要检查（当前线程的保护区不可读，溢出会直接触发异常）
+    pub fn check_stack(&self) {
+        if crate::stack::pmp_thread_guards() {
+            return;
+        }
+        let Some(stack) = &self.stack else {
+            return;
+        };
+        if let Some((addr, value)) = stack.check_guard() {
+            panic!(
+                "stack overflow in thread {}: guard at 0x{:x} overwritten with 0x{:x} (stack size {} bytes)",
+                self.display_name(),
+                addr,
+                value,
+                stack.size()
+            );
+        }
+    }
+
+    /// 栈使用的最高水位（字节）
+    ///
+    /// 说明：栈已经释放（线程结束）时返回 0
+    pub fn stack_high_water(&self) -> usize {
+        self.stack.as_deref().map_or(0, ThreadStack::high_water)
+    }
+
+    /// 累计运行时间（mtime 计数），正在运行的线程包括当前这次运行
+    pub fn cpu_time(&self, now: usize) -> usize {
+        if self.state == ThreadState::Running {
+            self.run_time + now.saturating_sub(self.run_start)
+        } else {
+            self.run_time
+        }
+    }
+
     pub fn run(&mut self) {
         if let Some(job) = self.job.take() {
-            job();
+            self.result = Some(job());
         }
     }
 }
@@ -87,8 +330,8 @@ impl Display for TCB {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
-            "TCB {{ id: {}, state: {:?}, context: {:?}}}",
-            self.id, self.state, self.context
+            "TCB {{ id: {}, name: {:?}, state: {:?}, context: {:?}}}",
+            self.id, self.name, self.state, self.context
         )
     }
 }
@@ -0,0 +1,281 @@
+//! 🧵 线程局部存储（TLS）
+//!
+//! - 链接脚本中的 `.tdata`/`.tbss` 构成 TLS 模板：`.tdata` 为初始值，`.tbss` 为全零部分
+//! - 每个线程创建时分配一块 TLS 块并按模板初始化，`tp` 寄存器指向块的起始地址，
+//!   由 `__switch` 随线程上下文一起保存/恢复
+//! - `#[thread_local]` 静态变量按 local-exec 模型编译，直接通过 `tp` + 偏移访问
+//! - `thread_local!` 宏在此基础上提供惰性初始化和线程退出时的析构
+//!
+//! 说明：线程之外（启动代码、idle 循环）`tp` 为 0，此时访问 `LocalKey` 返回 `AccessError`
+
+extern crate alloc;
+use alloc::alloc::{alloc, dealloc, handle_alloc_error};
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::arch::asm;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::fmt::{Display, Formatter};
+use core::mem::{self, MaybeUninit};
+use core::ptr::{self, NonNull};
+
+/// TLS 块的对齐；必须与 memory.x 中 `.tdata` 的对齐一致
+const TLS_ALIGN: usize = 64;
+
+/// TLS 模板：(`.tdata` 初始值, TLS 块总大小)
+fn template() -> (&'static [u8], usize) {
+    unsafe extern "C" {
+        static __TDATA_START: u8;
+        static __TDATA_END: u8;
+        static __TBSS_END: u8;
+        static __TLS_ALIGN: u8;
+    }
+    unsafe {
+        let start = &__TDATA_START as *const u8;
+        let tdata_len = &__TDATA_END as *const u8 as usize - start as usize;
+        let total = &__TBSS_END as *const u8 as usize - start as usize;
+        let align = &__TLS_ALIGN as *const u8 as usize;
+        assert!(align <= TLS_ALIGN, "TLS alignment {} exceeds {}", align, TLS_ALIGN);
+        (core::slice::from_raw_parts(start, tdata_len), total)
+    }
+}
+
+/// 一个线程的 TLS 块
+pub struct TlsBlock {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// TLS 块只被所属线程通过 tp 访问，TCB 只负责持有和释放它
+unsafe impl Send for TlsBlock {}
+
+impl TlsBlock {
+    /// 分配一块 TLS 块：`.tdata` 部分复制初始值，`.tbss` 部分清零
+    pub fn new() -> Self {
+        let (tdata, total) = template();
+        let layout = Layout::from_size_align(total.max(1), TLS_ALIGN).expect("invalid TLS layout");
+        let ptr = unsafe { alloc(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            handle_alloc_error(layout);
+        };
+        unsafe {
+            ptr::copy_nonoverlapping(tdata.as_ptr(), ptr.as_ptr(), tdata.len());
+            ptr::write_bytes(ptr.as_ptr().add(tdata.len()), 0, layout.size() - tdata.len());
+        }
+        Self { ptr, layout }
+    }
+
+    /// 线程指针（tp）的值：TLS 块起始地址
+    pub fn tp(&self) -> usize {
+        self.ptr.as_ptr() as usize
+    }
+}
+
+impl Default for TlsBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TlsBlock {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+/// 当前上下文是否有 TLS 块（在线程中运行）
+fn available() -> bool {
+    let tp: usize;
+    unsafe { asm!("mv {0}, tp", out(reg) tp) };
+    tp != 0
+}
+
+/// 待执行的析构函数：(变量地址, 析构函数)
+type Dtor = (*mut u8, unsafe fn(*mut u8));
+
+/// 当前线程已经初始化、需要在退出时析构的 TLS 变量
+#[thread_local]
+static DTORS: RefCell<Vec<Dtor>> = RefCell::new(Vec::new());
+
+fn register_dtor(ptr: *mut u8, dtor: unsafe fn(*mut u8)) {
+    DTORS.borrow_mut().push((ptr, dtor));
+}
+
+/// 执行当前线程所有 TLS 变量的析构函数（后初始化的先析构）
+///
+/// 说明：
+/// - 在线程退出时、不持有调度器锁的情况下调用
+/// - 析构函数里又初始化了新的 TLS 变量时，继续析构直到列表为空
+pub(crate) fn run_dtors() {
+    if !available() {
+        return;
+    }
+    loop {
+        let dtors = mem::take(&mut *DTORS.borrow_mut());
+        if dtors.is_empty() {
+            break;
+        }
+        for (ptr, dtor) in dtors.into_iter().rev() {
+            unsafe { dtor(ptr) };
+        }
+    }
+}
+
+/// 访问 TLS 变量失败：不在线程中，或变量已经被析构
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccessError;
+
+impl Display for AccessError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "thread local accessed outside a thread or after destruction")
+    }
+}
+
+const UNINIT: u8 = 0;
+const INITIALIZING: u8 = 1;
+const ALIVE: u8 = 2;
+const DESTROYED: u8 = 3;
+
+/// `thread_local!` 变量在 TLS 块中的存储：状态 + 值
+///
+/// 说明：全零即未初始化状态，所以整个槽位落在 `.tbss` 中
+#[doc(hidden)]
+pub struct LazySlot<T> {
+    state: Cell<u8>,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+impl<T> LazySlot<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: Cell::new(UNINIT),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+
+    /// 取得值，第一次访问时调用 `init` 初始化
+    fn get_or_init(&self, init: fn() -> T) -> Result<&T, AccessError> {
+        match self.state.get() {
+            ALIVE => {}
+            UNINIT => {
+                self.state.set(INITIALIZING);
+                let value = init();
+                unsafe { (*self.value.get()).write(value) };
+                self.state.set(ALIVE);
+                if mem::needs_drop::<T>() {
+                    register_dtor(self as *const Self as *mut u8, Self::destroy);
+                }
+            }
+            INITIALIZING => panic!("thread local initializer recursively accessed itself"),
+            _ => return Err(AccessError),
+        }
+        Ok(unsafe { (*self.value.get()).assume_init_ref() })
+    }
+
+    /// 析构函数：先标记为已析构，再释放值（析构期间再访问会得到 `AccessError`）
+    unsafe fn destroy(ptr: *mut u8) {
+        let slot = unsafe { &*(ptr as *const Self) };
+        slot.state.set(DESTROYED);
+        unsafe { (*slot.value.get()).assume_init_drop() };
+    }
+}
+
+impl<T> Default for LazySlot<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 🔑 `thread_local!` 声明的变量：每个线程各有一份，第一次访问时初始化，线程退出时析构
+pub struct LocalKey<T: 'static> {
+    slot: fn() -> *const LazySlot<T>,
+    init: fn() -> T,
+}
+
+impl<T: 'static> LocalKey<T> {
+    #[doc(hidden)]
+    pub const fn new(slot: fn() -> *const LazySlot<T>, init: fn() -> T) -> Self {
+        Self { slot, init }
+    }
+
+    /// 以引用访问当前线程的值
+    ///
+    /// 说明：不在线程中或变量已经被析构时 panic
+    pub fn with<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        match self.try_with(f) {
+            Ok(r) => r,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// 以引用访问当前线程的值，不在线程中或变量已经被析构时返回 `AccessError`
+    pub fn try_with<F, R>(&'static self, f: F) -> Result<R, AccessError>
+    where
+        F: FnOnce(&T) -> R,
+    {
+        if !available() {
+            return Err(AccessError);
+        }
+        let slot = unsafe { &*(self.slot)() };
+        slot.get_or_init(self.init).map(f)
+    }
+}
+
+impl<T: 'static> LocalKey<Cell<T>> {
+    /// 设置当前线程的值
+    pub fn set(&'static self, value: T) {
+        self.with(|cell| cell.set(value));
+    }
+
+    /// 取出当前线程的值（`Copy` 类型）
+    pub fn get(&'static self) -> T
+    where
+        T: Copy,
+    {
+        self.with(Cell::get)
+    }
+}
+
+impl<T: 'static> LocalKey<RefCell<T>> {
+    /// 以可变引用访问当前线程的值
+    pub fn with_borrow_mut<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.with(|cell| f(&mut cell.borrow_mut()))
+    }
+}
+
+/// 🧵 声明线程局部变量
+///
+/// ```ignore
+/// no_std::thread_local! {
+///     static COUNTER: Cell<usize> = Cell::new(0);
+/// }
+/// COUNTER.set(COUNTER.get() + 1);
+/// ```
+#[macro_export]
+#[allow_internal_unstable(thread_local)]
+macro_rules! thread_local {
+    () => {};
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => {
+        $crate::thread_local!($(#[$attr])* $vis static $name: $t = $init);
+        $crate::thread_local!($($rest)*);
+    };
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr) => {
+        $(#[$attr])*
+        $vis static $name: $crate::thread::LocalKey<$t> = {
+            fn __slot() -> *const $crate::thread::tls::LazySlot<$t> {
+                #[thread_local]
+                static SLOT: $crate::thread::tls::LazySlot<$t> = $crate::thread::tls::LazySlot::new();
+                &SLOT
+            }
+            fn __init() -> $t {
+                $init
+            }
+            $crate::thread::LocalKey::new(__slot, __init)
+        };
+    };
+}
@@ -0,0 +1,300 @@
+//! 👤 用户线程（U-mode）
+//!
+//! - 用户线程也是一个普通线程：内核栈、TLS 块和调度都与其它线程相同，
+//!   它的任务只是构造一个 U-mode 的 `TrapFrame` 并 `mret` 进去，之后不再返回
+//! - 每个用户线程另有一个用户栈（2 的幂大小、按大小对齐），切换到它时用一个不加锁的 PMP 表项
+//!   授权 U-mode 读写；U-mode 另外只能执行 `.text`、读 `.rodata`
+//! - 从 U-mode trap 进来时，trap 入口切换到该线程的内核栈：即进入 U-mode 时 `TrapFrame` 所在的位置，
+//!   记录在 TCB 中，切换线程时写入 hart 私有数据
+//! - 用户代码通过系统调用（见 `trap::syscall`）访问内核；出现异常时内核只杀死这个线程
+//! - `thread::kill` 只做标记，线程下一次从 U-mode trap 进来时由 trap 处理结束它
+//! - 由 `loader` 装载的程序映像也以用户线程运行：线程持有映像的引用，切换到它时再为映像的各段设置 PMP 表项
+
+extern crate alloc;
+use alloc::alloc::{alloc, dealloc, handle_alloc_error};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::arch::asm;
+use core::fmt::{Display, Formatter};
+use core::ptr::NonNull;
+
+use super::handle::JoinHandle;
+use super::tcb::{TCB, ThreadId, ThreadState};
+use super::tls::TlsBlock;
+use super::{Builder, ThreadError, exit_current, sched};
+use crate::hart;
+use crate::loader::{Image, LoadError};
+use crate::pmp::{self, ENTRY_USER_STACK, PMP_R, PMP_W};
+use crate::trap::TrapFrame;
+
+/// 用户栈大小（2 的幂，便于用一个 NAPOT 表项覆盖）
+pub const USER_STACK_SIZE: usize = 16 * 1024;
+
+/// 命令行参数最多占用的用户栈空间（字节）：栈的一半，另一半留给程序
+pub const MAX_ARGS_SIZE: usize = USER_STACK_SIZE / 2;
+
+/// 用户线程入口：在 U-mode 执行，参数为创建时传入的 `arg`，返回值作为退出码
+pub type UserEntry = extern "C" fn(usize) -> i32;
+
+unsafe extern "C" {
+    /// 按 frame 恢复寄存器并 mret（trap.S）
+    fn __trap_return(frame: *const TrapFrame) -> !;
+    /// 入口函数返回后执行的 exit 系统调用（trap.S）
+    fn __user_exit();
+}
+
+/// 用户线程的退出状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// 调用了 exit 或入口函数返回，附带退出码
+    Exited(i32),
+    /// 因异常被内核杀死
+    Killed {
+        /// 异常原因（mcause）
+        cause: usize,
+        /// 出错的地址或指令（mtval）
+        tval: usize,
+        /// 出错的指令地址（mepc）
+        epc: usize,
+    },
+    /// 被 `thread::kill` 结束
+    Terminated,
+}
+
+impl Display for ExitStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ExitStatus::Exited(code) => write!(f, "exited with code {}", code),
+            ExitStatus::Killed { cause, tval, epc } => {
+                write!(f, "killed by exception {} (mtval=0x{:x}, pc=0x{:x})", cause, tval, epc)
+            }
+            ExitStatus::Terminated => write!(f, "terminated"),
+        }
+    }
+}
+
+/// 用户栈
+pub struct UserStack {
+    ptr: NonNull<u8>,
+    layout: Layout,
+}
+
+// 用户栈只被所属线程访问，TCB 只负责持有和释放它
+unsafe impl Send for UserStack {}
+
+impl UserStack {
+    /// 分配一个 `size` 字节、按 `size` 对齐的用户栈
+    pub fn new(size: usize) -> Self {
+        let layout = Layout::from_size_align(size, size).expect("invalid user stack layout");
+        let ptr = unsafe { alloc(layout) };
+        let Some(ptr) = NonNull::new(ptr) else {
+            handle_alloc_error(layout);
+        };
+        Self { ptr, layout }
+    }
+
+    /// 用户栈区域 `(起始地址, 大小)`
+    pub fn region(&self) -> (usize, usize) {
+        (self.ptr.as_ptr() as usize, self.layout.size())
+    }
+
+    /// 栈顶（初始 sp）
+    pub fn top(&self) -> usize {
+        self.ptr.as_ptr() as usize + self.layout.size()
+    }
+
+    /// 按 Linux 的约定在栈顶放入命令行参数，返回 `(sp, argv)`
+    ///
+    /// 说明：
+    /// - 参数字符串（以 NUL 结尾）放在最上面，下面依次是 argc、argv[]、NULL、envp（空）、auxv（只有 AT_NULL）
+    /// - sp 指向 argc，按 16 字节对齐
+    /// - 调用方需要先用 `args_size` 检查参数放得下（不超过栈大小的一半），否则 panic
+    pub fn push_args(&self, args: &[String]) -> (usize, usize) {
+        assert!(
+            args_size(args).is_some_and(|size| size <= self.layout.size() / 2),
+            "program arguments too large"
+        );
+        let mut top = self.top();
+        let mut argv = Vec::with_capacity(args.len());
+        for arg in args {
+            top -= arg.len() + 1;
+            unsafe {
+                core::ptr::copy_nonoverlapping(arg.as_ptr(), top as *mut u8, arg.len());
+                *((top + arg.len()) as *mut u8) = 0;
+            }
+            argv.push(top);
+        }
+        // argc + argv[] + NULL + envp NULL + auxv AT_NULL (2 个字)
+        let words = 1 + args.len() + 1 + 1 + 2;
+        let sp = ((top & !7) - words * size_of::<usize>()) & !15;
+        let slots = unsafe { core::slice::from_raw_parts_mut(sp as *mut usize, words) };
+        slots.fill(0);
+        slots[0] = args.len();
+        slots[1..=args.len()].copy_from_slice(&argv);
+        (sp, sp + size_of::<usize>())
+    }
+}
+
+/// `push_args` 放入 `args` 需要的栈空间（字节，包括对齐填充）；参数大到长度溢出时返回 None
+pub fn args_size(args: &[String]) -> Option<usize> {
+    let strings = args.iter().try_fold(0usize, |sum, arg| sum.checked_add(arg.len())?.checked_add(1))?;
+    // argc + argv[] + NULL + envp NULL + auxv AT_NULL (2 个字)
+    let words = args.len().checked_add(5)?.checked_mul(size_of::<usize>())?;
+    // 字符串下面按 8 字节、sp 按 16 字节对齐，最多各多用 7 和 15 字节
+    strings.checked_add(words)?.checked_add(7 + 15)
+}
+
+impl Drop for UserStack {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+/// 用户线程在 TCB 中的状态
+pub struct UserContext {
+    pub stack: UserStack,
+    /// 从 U-mode trap 进来时使用的内核栈顶
+    pub kernel_sp: usize,
+    /// 线程运行的程序映像；内核中的函数为 None
+    pub image: Option<Arc<Image>>,
+    /// 已经被 `thread::kill` 标记，下次从 U-mode 进入内核时结束
+    pub killed: bool,
+}
+
+/// 用户线程从哪里开始执行
+enum Start {
+    /// `entry(arg)`，返回时执行 exit；从程序映像中的线程创建时继承它的映像
+    Function {
+        entry: usize,
+        arg: usize,
+        image: Option<Arc<Image>>,
+    },
+    /// 程序映像的入口，栈上放入命令行参数
+    Image { image: Arc<Image>, args: Vec<String> },
+}
+
+/// 创建并启动执行 `entry(arg)` 的用户线程
+///
+/// 说明：在用户线程中调用（spawn 系统调用）时，新线程与当前线程运行同一个程序映像
+pub(crate) fn spawn(builder: Builder, entry: usize, arg: usize) -> JoinHandle<ExitStatus> {
+    let image = {
+        let s = sched();
+        s.current()
+            .and_then(|id| s.find_thread(id))
+            .and_then(|t| t.user.as_ref())
+            .and_then(|user| user.image.clone())
+    };
+    let start = Start::Function { entry, arg, image };
+    builder.spawn(move || -> ExitStatus { enter_user(start) })
+}
+
+/// 创建并启动运行程序映像的用户线程，`args` 作为命令行参数
+///
+/// 说明：参数超过 `MAX_ARGS_SIZE` 时返回 `LoadError::ArgsTooLarge`，不创建线程
+pub(crate) fn spawn_image(
+    builder: Builder,
+    image: Arc<Image>,
+    args: Vec<String>,
+) -> Result<JoinHandle<ExitStatus>, LoadError> {
+    if !args_size(&args).is_some_and(|size| size <= MAX_ARGS_SIZE) {
+        return Err(LoadError::ArgsTooLarge);
+    }
+    let start = Start::Image { image, args };
+    Ok(builder.spawn(move || -> ExitStatus { enter_user(start) }))
+}
+
+/// 用户线程的任务：分配用户栈，构造初始 `TrapFrame` 并进入 U-mode（不会返回）
+fn enter_user(start: Start) -> ! {
+    let stack = UserStack::new(USER_STACK_SIZE);
+    let (frame, image) = match start {
+        Start::Function { entry, arg, image } => (
+            TrapFrame::new_user(entry, arg, stack.top(), __user_exit as *const () as usize),
+            image,
+        ),
+        Start::Image { image, args } => {
+            let (sp, argv) = stack.push_args(&args);
+            // 程序的 _start 不会返回，ra 为 0：返回时取指失败，线程被杀死
+            let mut frame = TrapFrame::new_user(image.entry(), args.len(), sp, 0);
+            frame.a[1] = argv;
+            (frame, Some(image))
+        }
+    };
+    // 之后从 U-mode trap 进来时，新的 TrapFrame 压在 frame 之下
+    let kernel_sp = &frame as *const TrapFrame as usize;
+    {
+        let mut s = sched();
+        let id = s.current().expect("user thread is not running");
+        s.reset_fp(hart::id(), id);
+        let thread = s.get_thread(id).expect("current thread not found");
+        thread.user = Some(UserContext {
+            stack,
+            kernel_sp,
+            image,
+            killed: false,
+        });
+        switch_to(Some(thread));
+    }
+    unsafe { __trap_return(&frame) }
+}
+
+/// 切换线程时设置本 hart 的用户线程上下文：trap 入口使用的内核栈/tp，以及用户栈和程序映像的 PMP 表项
+///
+/// 说明：`thread` 为 None（切回 idle）或不是用户线程时撤销
+pub(crate) fn switch_to(thread: Option<&TCB>) {
+    let local = hart::local();
+    match thread.and_then(|t| t.user.as_ref().map(|user| (t, user))) {
+        Some((t, user)) => {
+            local.set_user_trap_context(user.kernel_sp, t.tls.as_ref().map_or(0, TlsBlock::tp));
+            let (base, size) = user.stack.region();
+            pmp::set_napot(ENTRY_USER_STACK, base, size, PMP_R | PMP_W);
+            match &user.image {
+                Some(image) => {
+                    pmp::set_user_regions(image.regions());
+                    // 映像可能是在其它 hart 上写入的，执行前同步本 hart 的指令缓存
+                    unsafe { asm!("fence.i") };
+                }
+                None => pmp::set_user_regions(&[]),
+            }
+        }
+        None => {
+            local.set_user_trap_context(0, 0);
+            pmp::clear(ENTRY_USER_STACK);
+            pmp::set_user_regions(&[]);
+        }
+    }
+}
+
+/// 标记用户线程 `id` 被结束，正在睡眠时唤醒它
+pub(crate) fn kill(id: ThreadId) -> Result<(), ThreadError> {
+    let mut s = sched();
+    let thread = s.get_thread(id).ok_or(ThreadError::Stale(id))?;
+    if thread.state == ThreadState::Terminated {
+        return Err(ThreadError::Stale(id));
+    }
+    let user = thread.user.as_mut().ok_or(ThreadError::NotUser(id))?;
+    user.killed = true;
+    s.interrupt_sleep(id);
+    Ok(())
+}
+
+/// 当前用户线程已经被 `kill` 标记时结束它（trap 处理返回 U-mode 之前调用）
+pub(crate) fn exit_if_killed() {
+    let killed = {
+        let s = sched();
+        s.current()
+            .and_then(|id| s.find_thread(id))
+            .and_then(|t| t.user.as_ref())
+            .is_some_and(|user| user.killed)
+    };
+    if killed {
+        exit(ExitStatus::Terminated);
+    }
+}
+
+/// 结束当前用户线程（在 trap 处理中调用，不会返回）
+pub(crate) fn exit(status: ExitStatus) -> ! {
+    exit_current(Some(Box::new(status)))
+}
@@ -1,31 +1,30 @@
 //! ⏱️ 计时器模块
 //!
 //! 提供 RISC-V 机器定时器（CLINT/mtime）相关的基础接口。
-//! 目前基于 QEMU virt 的内存映射地址实现。
+//! CLINT 基地址和 timebase 频率来自设备树。
 
-use core::arch::asm;
 use core::ptr::{read_volatile, write_volatile};
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::trap;
+use crate::{dtb, hart, trap};
 
-/// 系统时钟频率（Hz）
-///
-/// 说明：
-/// - QEMU virt 默认 timebase 频率通常为 10MHz
-/// - 如果平台频率不同，请在这里调整
-pub const CLOCK_FREQ: usize = 10_000_000;
-
-// QEMU virt 平台 CLINT 基地址（mtime/mtimecmp）
-const CLINT_BASE: usize = 0x0200_0000;
-// mtime 寄存器地址（64-bit）
-const MTIME: usize = CLINT_BASE + 0xBFF8;
-// mtimecmp 寄存器基地址（64-bit），每个 hart 占用 8 字节
-const MTIMECMP_BASE: usize = CLINT_BASE + 0x4000;
+// mtime 寄存器相对 CLINT 基地址的偏移（64-bit）
+const MTIME_OFFSET: usize = 0xBFF8;
+// mtimecmp 寄存器相对 CLINT 基地址的偏移（64-bit），每个 hart 占用 8 字节
+const MTIMECMP_OFFSET: usize = 0x4000;
 
 /// 保存计时器中断处理函数指针（0 表示未设置）
 static TIMER_INTERRUPT_HANDLER: AtomicUsize = AtomicUsize::new(0);
 
+/// 系统时钟频率（Hz）
+///
+/// 说明：
+/// - 取自设备树 `/cpus/timebase-frequency`
+/// - QEMU virt 默认 timebase 频率为 10MHz
+pub fn clock_freq() -> usize {
+    dtb::platform().timebase_frequency
+}
+
 pub fn init(handler: fn()) {
     // 先注册处理函数并设置一个“安全”的初始触发时间，
     // 避免开启中断时 mtimecmp 仍为 0 导致立即进入中断并无法恢复。
@@ -67,8 +66,9 @@ pub fn set_next_trigger(time: usize) {
     if next <= now {
         next = now.wrapping_add(1);
     }
-    // 写入 64-bit mtimecmp（按 hart 选择对应 compare 寄存器）
+    // 写入 64-bit mtimecmp（按 hart 选择对应 compare 寄存器），并记录到 hart 私有数据
     unsafe { write_volatile(mtimecmp_addr() as *mut u64, next) };
+    hart::local().set_next_timer(next as usize);
 }
 
 /// 从已注册的中断处理函数中调用（可选的内部工具）
@@ -86,19 +86,10 @@ pub(crate) fn call_timer_interrupt_handler() {
 
 /// 读取 mtime（64-bit）
 fn read_mtime() -> u64 {
-    unsafe { read_volatile(MTIME as *const u64) }
-}
-
-/// 读取当前 hart id
-fn read_mhartid() -> usize {
-    let hart_id: usize;
-    unsafe {
-        asm!("csrr {0}, mhartid", out(reg) hart_id);
-    }
-    hart_id
+    unsafe { read_volatile((dtb::platform().clint + MTIME_OFFSET) as *const u64) }
 }
 
 /// 获取当前 hart 对应的 mtimecmp 地址
 fn mtimecmp_addr() -> usize {
-    MTIMECMP_BASE + read_mhartid() * core::mem::size_of::<u64>()
+    dtb::platform().clint + MTIMECMP_OFFSET + hart::id() * core::mem::size_of::<u64>()
 }
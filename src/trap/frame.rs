@@ -0,0 +1,116 @@
+//! 🧱 Trap 现场
+//!
+//! trap 入口把被打断的寄存器保存为一个 `TrapFrame`，交给 `trap_handler`；
+//! 系统调用从这里读参数、写返回值，第一次进入 U-mode 时也用它构造初始现场。
+
+use core::fmt::{Display, Formatter};
+
+/// mstatus.MIE 位
+const MSTATUS_MIE: usize = 1 << 3;
+/// mstatus.MPIE 位
+const MSTATUS_MPIE: usize = 1 << 7;
+/// mstatus.MPP 字段（trap 前的特权级：0 = U，3 = M）
+const MSTATUS_MPP: usize = 3 << 11;
+
+/// trap 现场：布局必须与 trap.S 一致
+///
+/// 说明：
+/// - 保存全部通用寄存器（x0 除外），调试器可以读写被打断代码的任意寄存器
+/// - `sp` 是 trap 发生时的栈指针（从 U-mode 进来时为用户栈）
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TrapFrame {
+    pub ra: usize,
+    pub t: [usize; 7],
+    pub a: [usize; 8],
+    pub mepc: usize,
+    pub mstatus: usize,
+    pub sp: usize,
+    pub tp: usize,
+    pub gp: usize,
+    pub s: [usize; 12],
+}
+
+impl TrapFrame {
+    /// 进入 U-mode 的初始现场：从 `entry` 开始执行，`a0 = arg`，函数返回时跳到 `ra`
+    ///
+    /// 说明：mstatus 取当前值，MPP 设为 U、MPIE 置位（回到 U-mode 后中断打开），MIE 清零
+    pub fn new_user(entry: usize, arg: usize, sp: usize, ra: usize) -> Self {
+        let mstatus: usize;
+        unsafe { core::arch::asm!("csrr {0}, mstatus", out(reg) mstatus) };
+        let mut frame = Self {
+            ra,
+            mepc: entry,
+            mstatus: (mstatus & !(MSTATUS_MPP | MSTATUS_MIE)) | MSTATUS_MPIE,
+            sp,
+            ..Self::default()
+        };
+        frame.a[0] = arg;
+        frame
+    }
+
+    /// trap 是否来自 U-mode
+    pub fn from_user(&self) -> bool {
+        self.mstatus & MSTATUS_MPP == 0
+    }
+
+    /// 按编号读取寄存器：0~31 为 x0~x31，32 为 pc（mepc），与 GDB 的 RISC-V 寄存器编号一致
+    ///
+    /// 说明：x0 恒为 0，超出范围时返回 None
+    pub fn reg(&self, index: usize) -> Option<usize> {
+        Some(match index {
+            0 => 0,
+            1 => self.ra,
+            2 => self.sp,
+            3 => self.gp,
+            4 => self.tp,
+            5..=7 => self.t[index - 5],
+            8..=9 => self.s[index - 8],
+            10..=17 => self.a[index - 10],
+            18..=27 => self.s[index - 16],
+            28..=31 => self.t[index - 25],
+            32 => self.mepc,
+            _ => return None,
+        })
+    }
+
+    /// 按编号写入寄存器（编号同 `reg`），写 x0 被忽略；超出范围时返回 false
+    pub fn set_reg(&mut self, index: usize, value: usize) -> bool {
+        let slot = match index {
+            0 => return true,
+            1 => &mut self.ra,
+            2 => &mut self.sp,
+            3 => &mut self.gp,
+            4 => &mut self.tp,
+            5..=7 => &mut self.t[index - 5],
+            8..=9 => &mut self.s[index - 8],
+            10..=17 => &mut self.a[index - 10],
+            18..=27 => &mut self.s[index - 16],
+            28..=31 => &mut self.t[index - 25],
+            32 => &mut self.mepc,
+            _ => return false,
+        };
+        *slot = value;
+        true
+    }
+
+    /// 系统调用号（a7）
+    pub fn syscall_id(&self) -> usize {
+        self.a[7]
+    }
+
+    /// 系统调用参数（a0 ~ a5）
+    pub fn syscall_args(&self) -> [usize; 6] {
+        [self.a[0], self.a[1], self.a[2], self.a[3], self.a[4], self.a[5]]
+    }
+}
+
+impl Display for TrapFrame {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "TrapFrame {{ mepc: 0x{:x}, mstatus: 0x{:x}, ra: 0x{:x}, sp: 0x{:x}, tp: 0x{:x}, a: {:x?} }}",
+            self.mepc, self.mstatus, self.ra, self.sp, self.tp, self.a
+        )
+    }
+}
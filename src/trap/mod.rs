@@ -1,25 +1,55 @@
 //! 🧷 Trap/中断处理模块
 //!
 //! 提供 RISC-V 机器模式 trap 入口与基本分发逻辑。
-//! 目前只处理机器定时器中断，其它异常直接记录并自旋。
+//! 处理机器定时器中断和核间中断；来自 U-mode 的 `ecall` 交给 `syscall` 模块，
+//! U-mode 的其它异常只杀死出错的用户线程；
+//! 内核的访存异常先检查是否是栈溢出，再按 PMP 表项解码成报告并 panic，其它异常直接记录并自旋。
 
 use core::arch::{asm, global_asm};
 use log::{error, info, warn};
 
-use crate::timer;
+use crate::thread::{self, ExitStatus};
+use crate::{gdbstub, hart, ipi, pmp, stack, timer};
+
+pub mod frame;
+pub mod syscall;
+
+pub use frame::TrapFrame;
 
 // 引入汇编 trap 入口
 global_asm!(include_str!("trap.S"));
 
 /// 机器定时器中断的 cause 值（RV64）
 const MCAUSE_MACHINE_TIMER: usize = 0x8000_0000_0000_0007;
+/// 机器软件中断（IPI）的 cause 值（RV64）
+const MCAUSE_MACHINE_SOFT: usize = 0x8000_0000_0000_0003;
+
+/// instruction access fault 的 cause 值
+const MCAUSE_INSTRUCTION_ACCESS_FAULT: usize = 1;
+/// load access fault 的 cause 值
+const MCAUSE_LOAD_ACCESS_FAULT: usize = 5;
+/// store/AMO access fault 的 cause 值
+const MCAUSE_STORE_ACCESS_FAULT: usize = 7;
+/// 断点（ebreak）的 cause 值
+const MCAUSE_BREAKPOINT: usize = 3;
+/// U-mode ecall 的 cause 值
+const MCAUSE_USER_ECALL: usize = 8;
+/// mcause 最高位：1 表示中断，0 表示异常
+const MCAUSE_INTERRUPT: usize = 1 << 63;
+
+/// mstatus.MIE 位
+const MSTATUS_MIE: usize = 1 << 3;
 
 /// 初始化 trap：设置 mtvec 并开启机器模式定时器中断
 ///
 /// 说明：
 /// - 使用 direct 模式（mtvec 低 2 位为 0）
-/// - 这里只打开 MIE.MTIE 和 mstatus.MIE
+/// - 这里打开 MIE.MTIE、MIE.MSIE（核间中断）和 mstatus.MIE
+/// - 每个 hart 都需要调用一次，同时把 mscratch 指向该 hart 的私有数据，并设置 PMP 和栈保护
 pub fn init() {
+    hart::init_local();
+    pmp::init_hart();
+    stack::init_hart();
     unsafe {
         // 设置 trap 向量入口
         set_mtvec(__trap_entry as usize);
@@ -28,6 +58,7 @@ pub fn init() {
         let mut mie: usize;
         asm!("csrr {0}, mie", out(reg) mie);
         mie |= 1 << 7; // MTIE
+        mie |= 1 << 3; // MSIE
         asm!("csrw mie, {0}", in(reg) mie);
 
         // 全局中断使能（mstatus.MIE）
@@ -42,9 +73,21 @@ pub fn init() {
 ///
 /// 说明：
 /// - 读取 mcause 判断中断类型
-/// - 目前仅处理机器定时器中断
+/// - 处理机器定时器中断、核间中断
+/// - 启用了 gdbstub 时，断点异常（ebreak）交给它处理，包括 U-mode 中的断点
+/// - 来自 U-mode 的异常：ecall 按系统调用处理，其它异常报告后杀死当前用户线程
+/// - 内核的访存异常解码后 panic（栈溢出单独报告）
+/// - 返回 U-mode 之前，结束已经被 `thread::kill` 标记的用户线程
 #[unsafe(no_mangle)]
-pub extern "C" fn trap_handler() {
+pub extern "C" fn trap_handler(frame: &mut TrapFrame) {
+    dispatch(frame);
+    if frame.from_user() {
+        thread::user::exit_if_killed();
+    }
+}
+
+/// 按 mcause 分发 trap
+fn dispatch(frame: &mut TrapFrame) {
     let cause = read_csr("mcause");
     let tval = read_csr("mtval");
     let epc = read_csr("mepc");
@@ -52,10 +95,42 @@ pub extern "C" fn trap_handler() {
     if cause == MCAUSE_MACHINE_TIMER {
         // 调用已注册的计时器中断处理函数（如果存在）
         // warn!("[INTERTUPT] Timer interrupt occurred");
+        gdbstub::poll(frame);
         timer::call_timer_interrupt_handler();
         return;
     }
 
+    if cause == MCAUSE_MACHINE_SOFT {
+        gdbstub::park_if_halted(frame);
+        ipi::handle_interrupt();
+        return;
+    }
+
+    if cause == MCAUSE_BREAKPOINT && gdbstub::is_enabled() {
+        gdbstub::handle_breakpoint(frame);
+        return;
+    }
+
+    if frame.from_user() && cause & MCAUSE_INTERRUPT == 0 {
+        if cause == MCAUSE_USER_ECALL {
+            syscall::handle(frame);
+            return;
+        }
+        kill_user_thread(cause, tval, epc);
+    }
+
+    if cause == MCAUSE_LOAD_ACCESS_FAULT || cause == MCAUSE_STORE_ACCESS_FAULT {
+        // 落在栈保护区内时报告栈溢出并 panic
+        stack::check_fault(tval, epc);
+    }
+    if matches!(
+        cause,
+        MCAUSE_INSTRUCTION_ACCESS_FAULT | MCAUSE_LOAD_ACCESS_FAULT | MCAUSE_STORE_ACCESS_FAULT
+    ) && let Some(fault) = pmp::decode_fault(cause, tval, epc)
+    {
+        panic!("{}", fault);
+    }
+
     // 其它异常/中断：记录并停机（避免无穷异常）
     error!(
         "Unhandled trap: mcause=0x{:x}, mtval=0x{:x}, mepc=0x{:x}",
@@ -66,6 +141,51 @@ pub extern "C" fn trap_handler() {
     }
 }
 
+/// U-mode 出现异常：报告原因并结束当前用户线程，内核继续运行（不会返回）
+fn kill_user_thread(cause: usize, tval: usize, epc: usize) -> ! {
+    let id = thread::current_thread().expect("user trap outside a thread").id();
+    match pmp::decode_fault(cause, tval, epc) {
+        Some(fault) => error!("Killing user thread {}: {}", id, fault),
+        None => error!(
+            "Killing user thread {}: mcause=0x{:x}, mtval=0x{:x}, mepc=0x{:x}",
+            id, cause, tval, epc
+        ),
+    }
+    thread::user::exit(ExitStatus::Killed { cause, tval, epc })
+}
+
+/// 关闭当前 hart 的全局中断，返回关闭前是否开启
+pub fn disable_interrupts() -> bool {
+    let mstatus: usize;
+    unsafe {
+        asm!("csrrci {0}, mstatus, 8", out(reg) mstatus);
+    }
+    mstatus & MSTATUS_MIE != 0
+}
+
+/// 开启当前 hart 的全局中断
+pub fn enable_interrupts() {
+    unsafe {
+        asm!("csrsi mstatus, 8");
+    }
+}
+
+/// 恢复 `disable_interrupts` 之前的中断状态
+pub fn restore_interrupts(enabled: bool) {
+    if enabled {
+        enable_interrupts();
+    }
+}
+
+/// 等待中断（wfi）
+///
+/// 说明：即使 mstatus.MIE 关闭，只要 mie 中使能的中断挂起，wfi 也会返回
+pub fn wait_for_interrupt() {
+    unsafe {
+        asm!("wfi");
+    }
+}
+
 /// 读取 CSR（小工具函数）
 fn read_csr(name: &str) -> usize {
     let value: usize;
@@ -0,0 +1,89 @@
+//! 📞 系统调用
+//!
+//! U-mode 线程通过 `ecall` 进入内核：a7 为系统调用号，a0~a5 为参数，返回值写回 a0，出错时返回负的 errno。
+//! 系统调用号沿用 Linux RISC-V 的编号，语义做了简化：
+//!
+//! | 编号 | 名称 | 参数 | 返回值 |
+//! |------|------|------|--------|
+//! | 64 | write | fd, buf, len | 写入的字节数（fd 只支持 1/2，输出到串口） |
+//! | 93 | exit | code | 不返回 |
+//! | 101 | sleep | ms | 0 |
+//! | 124 | yield | | 0 |
+//! | 169 | get_time | | 启动以来的毫秒数 |
+//! | 172 | getpid | | 当前线程 id（低 32 位槽位，高 32 位代数） |
+//! | 220 | spawn | entry, arg | 新用户线程的 id |
+//!
+//! 用户传入的指针按当前 hart 的 PMP 表项检查，U-mode 自己不能访问的内存内核也不替它访问
+
+use log::warn;
+
+use super::TrapFrame;
+use crate::pmp::{self, Access};
+use crate::thread::{self, Builder, ExitStatus, user};
+use crate::{console, timer};
+
+pub const SYS_WRITE: usize = 64;
+pub const SYS_EXIT: usize = 93;
+pub const SYS_SLEEP: usize = 101;
+pub const SYS_YIELD: usize = 124;
+pub const SYS_GET_TIME: usize = 169;
+pub const SYS_GETPID: usize = 172;
+pub const SYS_SPAWN: usize = 220;
+
+/// 错误的文件描述符
+pub const EBADF: isize = 9;
+/// 非法地址
+pub const EFAULT: isize = 14;
+/// 不支持的系统调用
+pub const ENOSYS: isize = 38;
+
+/// 处理来自 U-mode 的 `ecall`
+///
+/// 说明：
+/// - 返回地址跳过 ecall 指令，返回值写入 frame 的 a0
+/// - 在 trap 上下文中执行（中断关闭），yield/sleep 会在这里切换到其它线程，切换回来后再返回 U-mode
+pub fn handle(frame: &mut TrapFrame) {
+    frame.mepc += 4;
+    let [a0, a1, a2, ..] = frame.syscall_args();
+    let ret = match frame.syscall_id() {
+        SYS_WRITE => sys_write(a0, a1, a2),
+        SYS_EXIT => user::exit(ExitStatus::Exited(a0 as i32)),
+        SYS_SLEEP => {
+            thread::sleep(a0);
+            0
+        }
+        SYS_YIELD => {
+            thread::yield_now();
+            0
+        }
+        SYS_GET_TIME => (timer::get_time() / (timer::clock_freq() / 1000)) as isize,
+        SYS_GETPID => thread::current_thread().map_or(0, |handle| handle.id().as_raw() as isize),
+        SYS_SPAWN => sys_spawn(a0, a1),
+        id => {
+            warn!("Unknown syscall {} at pc 0x{:x}", id, frame.mepc - 4);
+            -ENOSYS
+        }
+    };
+    frame.a[0] = ret as usize;
+}
+
+fn sys_write(fd: usize, buf: usize, len: usize) -> isize {
+    if fd != 1 && fd != 2 {
+        return -EBADF;
+    }
+    if !pmp::user_accessible(buf, len, Access::Read) {
+        return -EFAULT;
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(buf as *const u8, len) };
+    console::write_bytes(bytes);
+    len as isize
+}
+
+fn sys_spawn(entry: usize, arg: usize) -> isize {
+    if !pmp::user_accessible(entry, 4, Access::Execute) {
+        return -EFAULT;
+    }
+    // 丢弃 JoinHandle：新线程是分离的，结束后立即回收
+    let handle = user::spawn(Builder::new(), entry, arg);
+    handle.thread().id().as_raw() as isize
+}
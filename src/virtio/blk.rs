@@ -0,0 +1,224 @@
+//! 💽 virtio-blk 块设备驱动
+//!
+//! 每个请求是一条三段的描述符链：
+//! - 请求头（设备只读）：类型和起始扇区
+//! - 数据（读请求设备只写，写请求设备只读）
+//! - 状态字节（设备只写）：0 成功，1 I/O 错误，2 不支持
+//!
+//! 请求同步完成：放入队列、通知设备后轮询已用环（还没有外部中断）。
+//!
+//! QEMU 中挂载磁盘镜像：`-drive file=disk.img,if=none,format=raw,id=hd0 -device virtio-blk-device,drive=hd0`
+//! （`make run DISK=disk.img`）
+
+use log::{info, warn};
+
+use super::queue::VirtQueue;
+use super::{DeviceType, MmioTransport, VirtioError};
+use crate::block::{BLOCK_SIZE, BlockDevice, BlockError};
+
+/// 扇区大小（virtio-blk 的地址单位，与设备实际的块大小无关）
+pub const SECTOR_SIZE: usize = 512;
+
+/// 特性位：设备只读
+const VIRTIO_BLK_F_RO: u64 = 1 << 5;
+/// 特性位：支持 flush 请求
+const VIRTIO_BLK_F_FLUSH: u64 = 1 << 9;
+
+/// 请求类型
+const VIRTIO_BLK_T_IN: u32 = 0;
+const VIRTIO_BLK_T_OUT: u32 = 1;
+const VIRTIO_BLK_T_FLUSH: u32 = 4;
+
+/// 请求状态
+const VIRTIO_BLK_S_OK: u8 = 0;
+const VIRTIO_BLK_S_UNSUPP: u8 = 2;
+
+/// 配置空间：容量（扇区数，u64）
+const CONFIG_CAPACITY: usize = 0;
+
+/// 请求头
+#[repr(C)]
+struct RequestHeader {
+    kind: u32,
+    reserved: u32,
+    sector: u64,
+}
+
+impl RequestHeader {
+    fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self as *const Self as *const u8, size_of::<Self>()) }
+    }
+}
+
+/// 💽 一个 virtio-blk 设备
+pub struct VirtioBlk {
+    transport: MmioTransport,
+    queue: VirtQueue,
+    /// 容量（扇区数）
+    capacity: usize,
+    read_only: bool,
+    flush: bool,
+}
+
+impl VirtioBlk {
+    /// 初始化 `transport` 上的块设备
+    pub fn new(mut transport: MmioTransport) -> Result<Self, VirtioError> {
+        if transport.device_type() != DeviceType::Block {
+            return Err(VirtioError::WrongDevice(transport.device_type()));
+        }
+        let features = transport.begin_init(VIRTIO_BLK_F_RO | VIRTIO_BLK_F_FLUSH)?;
+        let queue = VirtQueue::new(0);
+        transport.setup_queue(&queue)?;
+        transport.finish_init();
+
+        let capacity = transport.config_u64(CONFIG_CAPACITY) as usize;
+        let blk = Self {
+            transport,
+            queue,
+            capacity,
+            read_only: features & VIRTIO_BLK_F_RO != 0,
+            flush: features & VIRTIO_BLK_F_FLUSH != 0,
+        };
+        info!(
+            "💽 virtio-blk at 0x{:x}: {} sectors ({} KB){}",
+            blk.transport.base(),
+            capacity,
+            capacity * SECTOR_SIZE / 1024,
+            if blk.read_only { ", read-only" } else { "" }
+        );
+        Ok(blk)
+    }
+
+    /// 找到并初始化第一个 virtio-blk 设备
+    pub fn probe() -> Result<Self, VirtioError> {
+        let transport = super::find(DeviceType::Block).ok_or(VirtioError::NoDevice)?;
+        Self::new(transport).inspect_err(|err| warn!("virtio-blk init failed: {}", err))
+    }
+
+    /// 设备所在的 virtio-mmio 槽位
+    pub fn base(&self) -> usize {
+        self.transport.base()
+    }
+
+    /// 容量（扇区数）
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// 从第 `block` 个扇区开始读取，填满 `buf`
+    ///
+    /// 说明：`buf` 的长度必须是 `SECTOR_SIZE` 的整数倍，一次可以读多个扇区
+    pub fn read_block(&mut self, block: usize, buf: &mut [u8]) -> Result<(), VirtioError> {
+        self.check_range(block, buf.len())?;
+        let header = RequestHeader {
+            kind: VIRTIO_BLK_T_IN,
+            reserved: 0,
+            sector: block as u64,
+        };
+        let mut status = [0xffu8];
+        self.request(&[header.as_bytes()], &mut [buf, &mut status[..]])?;
+        check_status(status[0])
+    }
+
+    /// 把 `buf` 写到第 `block` 个扇区开始的位置
+    ///
+    /// 说明：`buf` 的长度必须是 `SECTOR_SIZE` 的整数倍
+    pub fn write_block(&mut self, block: usize, buf: &[u8]) -> Result<(), VirtioError> {
+        if self.read_only {
+            return Err(VirtioError::ReadOnly);
+        }
+        self.check_range(block, buf.len())?;
+        let header = RequestHeader {
+            kind: VIRTIO_BLK_T_OUT,
+            reserved: 0,
+            sector: block as u64,
+        };
+        let mut status = [0xffu8];
+        self.request(&[header.as_bytes(), buf], &mut [&mut status])?;
+        check_status(status[0])
+    }
+
+    /// 把设备的写缓存刷到磁盘镜像；设备不支持 flush 时直接返回成功
+    pub fn flush(&mut self) -> Result<(), VirtioError> {
+        if !self.flush {
+            return Ok(());
+        }
+        let header = RequestHeader {
+            kind: VIRTIO_BLK_T_FLUSH,
+            reserved: 0,
+            sector: 0,
+        };
+        let mut status = [0xffu8];
+        self.request(&[header.as_bytes()], &mut [&mut status])?;
+        check_status(status[0])
+    }
+
+    fn check_range(&self, block: usize, len: usize) -> Result<(), VirtioError> {
+        if len == 0 || !len.is_multiple_of(SECTOR_SIZE) {
+            return Err(VirtioError::InvalidBuffer);
+        }
+        let count = len / SECTOR_SIZE;
+        if block.checked_add(count).is_none_or(|end| end > self.capacity) {
+            return Err(VirtioError::OutOfRange { block, count });
+        }
+        Ok(())
+    }
+
+    /// 提交一个请求并等待它完成
+    ///
+    /// 说明：有了外部中断后，这里可以改为让出 CPU，由中断处理唤醒
+    fn request(&mut self, inputs: &[&[u8]], outputs: &mut [&mut [u8]]) -> Result<(), VirtioError> {
+        // 缓冲区在请求完成（pop_used）之前一直被借用，满足 add 的要求
+        let head = unsafe { self.queue.add(inputs, outputs)? };
+        self.transport.notify(self.queue.index());
+        loop {
+            if let Some((id, _)) = self.queue.pop_used() {
+                self.transport.ack_interrupt();
+                // 同一时间只有一个请求，取回的一定是刚才放入的
+                debug_assert_eq!(id, head);
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+// virtio-blk 的扇区就是 `BlockDevice` 的块
+const _: () = assert!(SECTOR_SIZE == BLOCK_SIZE);
+
+impl BlockDevice for VirtioBlk {
+    fn num_blocks(&self) -> usize {
+        self.capacity
+    }
+
+    fn read_blocks(&mut self, start: usize, buf: &mut [u8]) -> Result<(), BlockError> {
+        Ok(self.read_block(start, buf)?)
+    }
+
+    fn write_blocks(&mut self, start: usize, buf: &[u8]) -> Result<(), BlockError> {
+        Ok(self.write_block(start, buf)?)
+    }
+
+    fn flush(&mut self) -> Result<(), BlockError> {
+        Ok(VirtioBlk::flush(self)?)
+    }
+}
+
+impl Drop for VirtioBlk {
+    fn drop(&mut self) {
+        // 先停止设备，队列内存才能安全释放
+        self.transport.reset();
+    }
+}
+
+fn check_status(status: u8) -> Result<(), VirtioError> {
+    match status {
+        VIRTIO_BLK_S_OK => Ok(()),
+        VIRTIO_BLK_S_UNSUPP => Err(VirtioError::Unsupported),
+        _ => Err(VirtioError::IoError),
+    }
+}
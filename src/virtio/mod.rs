@@ -0,0 +1,380 @@
+//! 🔌 virtio-mmio 传输层
+//!
+//! QEMU virt 在 0x10001000 起有 8 个 virtio-mmio 槽位（地址来自设备树），`-device virtio-xxx-device`
+//! 依次接到这些槽位上。本模块负责：
+//! - 探测：检查魔数和设备 id，列出接了设备的槽位（`devices`/`find`）
+//! - 初始化：复位、特性协商、配置 virtqueue、置 DRIVER_OK（`MmioTransport`）
+//! - 分离式 virtqueue（见 `queue`）
+//! - 设备驱动：`blk`（块设备）和 `net`（网卡）
+//!
+//! 同时支持 legacy（版本 1，QEMU 默认）和 modern（版本 2，`-global virtio-mmio.force-legacy=false`）接口。
+//!
+//! 说明：还没有 PLIC 驱动，设备驱动以轮询方式等待请求完成
+//!
+//! 用法：
+//! ```ignore
+//! let mut blk = virtio::blk::VirtioBlk::probe().expect("no virtio-blk device");
+//! let mut buf = [0u8; virtio::blk::SECTOR_SIZE];
+//! blk.read_block(0, &mut buf)?;
+//! ```
+
+pub mod blk;
+pub mod net;
+pub mod queue;
+
+use core::arch::asm;
+use core::fmt::{Display, Formatter};
+use core::ptr::{read_volatile, write_volatile};
+
+use crate::dtb;
+use queue::VirtQueue;
+
+/// "virt" 的小端编码
+const MAGIC: u32 = 0x7472_6976;
+
+/// 寄存器偏移
+const REG_MAGIC: usize = 0x000;
+const REG_VERSION: usize = 0x004;
+const REG_DEVICE_ID: usize = 0x008;
+const REG_VENDOR_ID: usize = 0x00c;
+const REG_DEVICE_FEATURES: usize = 0x010;
+const REG_DEVICE_FEATURES_SEL: usize = 0x014;
+const REG_DRIVER_FEATURES: usize = 0x020;
+const REG_DRIVER_FEATURES_SEL: usize = 0x024;
+const REG_GUEST_PAGE_SIZE: usize = 0x028;
+const REG_QUEUE_SEL: usize = 0x030;
+const REG_QUEUE_NUM_MAX: usize = 0x034;
+const REG_QUEUE_NUM: usize = 0x038;
+const REG_QUEUE_ALIGN: usize = 0x03c;
+const REG_QUEUE_PFN: usize = 0x040;
+const REG_QUEUE_READY: usize = 0x044;
+const REG_QUEUE_NOTIFY: usize = 0x050;
+const REG_INTERRUPT_STATUS: usize = 0x060;
+const REG_INTERRUPT_ACK: usize = 0x064;
+const REG_STATUS: usize = 0x070;
+const REG_QUEUE_DESC_LOW: usize = 0x080;
+const REG_QUEUE_DESC_HIGH: usize = 0x084;
+const REG_QUEUE_DRIVER_LOW: usize = 0x090;
+const REG_QUEUE_DRIVER_HIGH: usize = 0x094;
+const REG_QUEUE_DEVICE_LOW: usize = 0x0a0;
+const REG_QUEUE_DEVICE_HIGH: usize = 0x0a4;
+const REG_CONFIG_GENERATION: usize = 0x0fc;
+/// 设备专用配置空间
+const REG_CONFIG: usize = 0x100;
+
+/// 设备状态位
+const STATUS_ACKNOWLEDGE: u32 = 1;
+const STATUS_DRIVER: u32 = 2;
+const STATUS_DRIVER_OK: u32 = 4;
+const STATUS_FEATURES_OK: u32 = 8;
+const STATUS_FAILED: u32 = 128;
+
+/// 设备遵循 virtio 1.0 规范（modern 接口必须协商）
+pub const VIRTIO_F_VERSION_1: u64 = 1 << 32;
+
+/// legacy 接口使用的页大小（QueuePFN 的单位，也是 used 环的对齐）
+pub const PAGE_SIZE: usize = 4096;
+
+/// virtio 设备类型（DeviceID）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceType {
+    Network,
+    Block,
+    Console,
+    Entropy,
+    Balloon,
+    Scsi,
+    Gpu,
+    Input,
+    Other(u32),
+}
+
+impl From<u32> for DeviceType {
+    fn from(id: u32) -> Self {
+        match id {
+            1 => DeviceType::Network,
+            2 => DeviceType::Block,
+            3 => DeviceType::Console,
+            4 => DeviceType::Entropy,
+            5 => DeviceType::Balloon,
+            8 => DeviceType::Scsi,
+            16 => DeviceType::Gpu,
+            18 => DeviceType::Input,
+            other => DeviceType::Other(other),
+        }
+    }
+}
+
+impl Display for DeviceType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DeviceType::Network => write!(f, "network"),
+            DeviceType::Block => write!(f, "block"),
+            DeviceType::Console => write!(f, "console"),
+            DeviceType::Entropy => write!(f, "entropy"),
+            DeviceType::Balloon => write!(f, "balloon"),
+            DeviceType::Scsi => write!(f, "scsi"),
+            DeviceType::Gpu => write!(f, "gpu"),
+            DeviceType::Input => write!(f, "input"),
+            DeviceType::Other(id) => write!(f, "device {}", id),
+        }
+    }
+}
+
+/// virtio 操作失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VirtioError {
+    /// 没有找到这种设备
+    NoDevice,
+    /// 不支持的 virtio-mmio 版本
+    UnsupportedVersion(u32),
+    /// 槽位上不是驱动需要的设备
+    WrongDevice(DeviceType),
+    /// 设备不接受协商的特性
+    FeaturesRejected,
+    /// 设备没有这个队列，或队列比 `queue::QUEUE_SIZE` 小
+    QueueUnavailable(u16),
+    /// 队列中没有足够的空闲描述符
+    QueueFull,
+    /// 缓冲区长度不对（块设备要求是扇区大小的整数倍且不为空）
+    InvalidBuffer,
+    /// 访问超出设备容量
+    OutOfRange { block: usize, count: usize },
+    /// 设备只读
+    ReadOnly,
+    /// 设备报告 I/O 错误
+    IoError,
+    /// 设备不支持这个请求
+    Unsupported,
+}
+
+impl Display for VirtioError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VirtioError::NoDevice => write!(f, "no such virtio device"),
+            VirtioError::UnsupportedVersion(version) => write!(f, "unsupported virtio-mmio version {}", version),
+            VirtioError::WrongDevice(kind) => write!(f, "unexpected {} device", kind),
+            VirtioError::FeaturesRejected => write!(f, "device rejected the negotiated features"),
+            VirtioError::QueueUnavailable(index) => write!(f, "virtqueue {} is unavailable", index),
+            VirtioError::QueueFull => write!(f, "virtqueue is full"),
+            VirtioError::InvalidBuffer => write!(f, "invalid buffer length"),
+            VirtioError::OutOfRange { block, count } => {
+                write!(f, "blocks {}..{} are out of range", block, block + count)
+            }
+            VirtioError::ReadOnly => write!(f, "device is read-only"),
+            VirtioError::IoError => write!(f, "device I/O error"),
+            VirtioError::Unsupported => write!(f, "request not supported by device"),
+        }
+    }
+}
+
+/// 在内存写入（描述符、环）和 MMIO 访问之间建立顺序
+fn io_fence() {
+    unsafe { asm!("fence iorw, iorw") };
+}
+
+/// 🔌 一个 virtio-mmio 设备
+#[derive(Debug)]
+pub struct MmioTransport {
+    base: usize,
+    version: u32,
+    device_type: DeviceType,
+}
+
+impl MmioTransport {
+    /// 检查一个槽位：魔数正确且接了设备（DeviceID 不为 0）时返回
+    pub fn probe(base: usize) -> Option<Self> {
+        let transport = Self {
+            base,
+            version: 0,
+            device_type: DeviceType::Other(0),
+        };
+        if transport.read(REG_MAGIC) != MAGIC {
+            return None;
+        }
+        let id = transport.read(REG_DEVICE_ID);
+        if id == 0 {
+            return None;
+        }
+        Some(Self {
+            version: transport.read(REG_VERSION),
+            device_type: DeviceType::from(id),
+            ..transport
+        })
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    /// virtio-mmio 版本：1 为 legacy，2 为 modern
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    pub fn vendor_id(&self) -> u32 {
+        self.read(REG_VENDOR_ID)
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        unsafe { read_volatile((self.base + offset) as *const u32) }
+    }
+
+    fn write(&self, offset: usize, value: u32) {
+        unsafe { write_volatile((self.base + offset) as *mut u32, value) };
+    }
+
+    fn set_status(&self, status: u32) {
+        self.write(REG_STATUS, status);
+    }
+
+    fn status(&self) -> u32 {
+        self.read(REG_STATUS)
+    }
+
+    /// 设备提供的特性位
+    pub fn device_features(&self) -> u64 {
+        self.write(REG_DEVICE_FEATURES_SEL, 0);
+        let low = self.read(REG_DEVICE_FEATURES) as u64;
+        self.write(REG_DEVICE_FEATURES_SEL, 1);
+        let high = self.read(REG_DEVICE_FEATURES) as u64;
+        (high << 32) | low
+    }
+
+    /// 复位设备并协商特性，返回双方都支持的特性
+    ///
+    /// 说明：
+    /// - `supported` 是驱动支持的设备特性，modern 设备还会自动加上 `VIRTIO_F_VERSION_1`
+    /// - 之后应配置队列（`setup_queue`），再调用 `finish_init`
+    pub fn begin_init(&mut self, supported: u64) -> Result<u64, VirtioError> {
+        if self.version != 1 && self.version != 2 {
+            return Err(VirtioError::UnsupportedVersion(self.version));
+        }
+        self.set_status(0);
+        self.set_status(STATUS_ACKNOWLEDGE);
+        self.set_status(STATUS_ACKNOWLEDGE | STATUS_DRIVER);
+
+        let supported = if self.version == 2 { supported | VIRTIO_F_VERSION_1 } else { supported };
+        let features = self.device_features() & supported;
+        self.write(REG_DRIVER_FEATURES_SEL, 0);
+        self.write(REG_DRIVER_FEATURES, features as u32);
+        self.write(REG_DRIVER_FEATURES_SEL, 1);
+        self.write(REG_DRIVER_FEATURES, (features >> 32) as u32);
+
+        // legacy 接口没有 FEATURES_OK 这一步
+        if self.version == 2 {
+            self.set_status(STATUS_ACKNOWLEDGE | STATUS_DRIVER | STATUS_FEATURES_OK);
+            if self.status() & STATUS_FEATURES_OK == 0 {
+                self.set_status(STATUS_FAILED);
+                return Err(VirtioError::FeaturesRejected);
+            }
+        } else {
+            self.write(REG_GUEST_PAGE_SIZE, PAGE_SIZE as u32);
+        }
+        Ok(features)
+    }
+
+    /// 把 `queue` 配置为设备的第 `queue.index()` 个队列
+    pub fn setup_queue(&mut self, queue: &VirtQueue) -> Result<(), VirtioError> {
+        let index = queue.index();
+        self.write(REG_QUEUE_SEL, index as u32);
+        let ready = if self.version == 2 { self.read(REG_QUEUE_READY) } else { self.read(REG_QUEUE_PFN) };
+        let max = self.read(REG_QUEUE_NUM_MAX) as usize;
+        if ready != 0 || max < queue::QUEUE_SIZE {
+            self.set_status(STATUS_FAILED);
+            return Err(VirtioError::QueueUnavailable(index));
+        }
+        self.write(REG_QUEUE_NUM, queue::QUEUE_SIZE as u32);
+        if self.version == 2 {
+            let (desc, driver, device) = (queue.desc_addr(), queue.avail_addr(), queue.used_addr());
+            self.write(REG_QUEUE_DESC_LOW, desc as u32);
+            self.write(REG_QUEUE_DESC_HIGH, (desc >> 32) as u32);
+            self.write(REG_QUEUE_DRIVER_LOW, driver as u32);
+            self.write(REG_QUEUE_DRIVER_HIGH, (driver >> 32) as u32);
+            self.write(REG_QUEUE_DEVICE_LOW, device as u32);
+            self.write(REG_QUEUE_DEVICE_HIGH, (device >> 32) as u32);
+            self.write(REG_QUEUE_READY, 1);
+        } else {
+            self.write(REG_QUEUE_ALIGN, PAGE_SIZE as u32);
+            self.write(REG_QUEUE_PFN, (queue.desc_addr() / PAGE_SIZE) as u32);
+        }
+        Ok(())
+    }
+
+    /// 初始化完成，设备开始工作
+    pub fn finish_init(&mut self) {
+        self.set_status(self.status() | STATUS_DRIVER_OK);
+    }
+
+    /// 通知设备第 `index` 个队列有新的请求
+    pub fn notify(&self, index: u16) {
+        io_fence();
+        self.write(REG_QUEUE_NOTIFY, index as u32);
+    }
+
+    /// 读取并应答中断状态（bit 0：used 环更新，bit 1：配置变化）
+    pub fn ack_interrupt(&self) -> u32 {
+        let status = self.read(REG_INTERRUPT_STATUS);
+        if status != 0 {
+            self.write(REG_INTERRUPT_ACK, status);
+        }
+        status
+    }
+
+    /// 读取配置空间中 `offset` 处的字节
+    pub fn config_u8(&self, offset: usize) -> u8 {
+        self.read_config(|| unsafe { read_volatile((self.base + REG_CONFIG + offset) as *const u8) })
+    }
+
+    pub fn config_u16(&self, offset: usize) -> u16 {
+        self.read_config(|| unsafe { read_volatile((self.base + REG_CONFIG + offset) as *const u16) })
+    }
+
+    pub fn config_u32(&self, offset: usize) -> u32 {
+        self.read_config(|| self.read(REG_CONFIG + offset))
+    }
+
+    /// 64 位字段分两次按 32 位读取（MMIO 寄存器最大按 4 字节访问）
+    pub fn config_u64(&self, offset: usize) -> u64 {
+        self.read_config(|| {
+            let low = self.read(REG_CONFIG + offset) as u64;
+            let high = self.read(REG_CONFIG + offset + 4) as u64;
+            (high << 32) | low
+        })
+    }
+
+    /// 读取配置空间
+    ///
+    /// 说明：modern 接口用 ConfigGeneration 保证多次读取得到的是同一版本的配置
+    fn read_config<T>(&self, read: impl Fn() -> T) -> T {
+        if self.version != 2 {
+            return read();
+        }
+        loop {
+            let generation = self.read(REG_CONFIG_GENERATION);
+            let value = read();
+            if self.read(REG_CONFIG_GENERATION) == generation {
+                return value;
+            }
+        }
+    }
+
+    /// 复位设备（之后队列内存可以安全释放）
+    pub fn reset(&mut self) {
+        self.set_status(0);
+    }
+}
+
+/// 列出所有接了设备的 virtio-mmio 槽位
+pub fn devices() -> impl Iterator<Item = MmioTransport> {
+    dtb::platform().virtio_mmio().iter().filter_map(|&base| MmioTransport::probe(base))
+}
+
+/// 找到第一个指定类型的设备
+pub fn find(device_type: DeviceType) -> Option<MmioTransport> {
+    devices().find(|transport| transport.device_type() == device_type)
+}
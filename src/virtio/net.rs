@@ -0,0 +1,181 @@
+//! 🌐 virtio-net 网卡驱动
+//!
+//! - 队列 0 接收、队列 1 发送；每个帧前面有一个 virtio-net 头部（不使用校验和卸载和 GSO，头部全为 0）
+//! - 接收：初始化时放入 `RX_BUFFERS` 个缓冲区（头部和帧各一个描述符），取出一帧后把缓冲区放回队列
+//! - 发送：同步完成，放入队列、通知设备后轮询已用环
+//!
+//! 头部长度：legacy 接口 10 字节；协商了 `VIRTIO_F_VERSION_1` 时多一个 `num_buffers` 字段，12 字节
+//!
+//! QEMU 中使用用户态网络：`-netdev user,id=net0 -device virtio-net-device,netdev=net0`（`make run NET=1`）
+
+extern crate alloc;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+use log::{info, warn};
+
+use super::queue::{QUEUE_SIZE, VirtQueue};
+use super::{DeviceType, MmioTransport, VIRTIO_F_VERSION_1, VirtioError};
+use crate::net::wire::MacAddr;
+use crate::net::{MAX_FRAME_LEN, NetDevice, NetError};
+
+/// 特性位：配置空间中有 MAC 地址
+const VIRTIO_NET_F_MAC: u64 = 1 << 5;
+/// 特性位：配置空间中有链路状态
+const VIRTIO_NET_F_STATUS: u64 = 1 << 16;
+
+/// 配置空间：MAC 地址（6 字节）和链路状态（u16）
+const CONFIG_MAC: usize = 0;
+const CONFIG_STATUS: usize = 6;
+const STATUS_LINK_UP: u16 = 1;
+
+const RX_QUEUE: u16 = 0;
+const TX_QUEUE: u16 = 1;
+
+/// 头部的最大长度
+const MAX_HEADER_LEN: usize = 12;
+/// 接收缓冲区数：每个占两个描述符
+const RX_BUFFERS: usize = QUEUE_SIZE / 2;
+
+/// 设备没有提供 MAC 地址时使用的地址（本地管理的单播地址）
+const DEFAULT_MAC: MacAddr = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+type RxBuffer = [u8; MAX_HEADER_LEN + MAX_FRAME_LEN];
+
+/// 🌐 一个 virtio-net 设备
+pub struct VirtioNet {
+    transport: MmioTransport,
+    rx: VirtQueue,
+    tx: VirtQueue,
+    /// 按链头 id 记录已放入接收队列的缓冲区
+    rx_buffers: Vec<Option<Box<RxBuffer>>>,
+    header_len: usize,
+    mac: MacAddr,
+}
+
+impl VirtioNet {
+    /// 初始化 `transport` 上的网卡
+    pub fn new(mut transport: MmioTransport) -> Result<Self, VirtioError> {
+        if transport.device_type() != DeviceType::Network {
+            return Err(VirtioError::WrongDevice(transport.device_type()));
+        }
+        let features = transport.begin_init(VIRTIO_NET_F_MAC | VIRTIO_NET_F_STATUS)?;
+        let rx = VirtQueue::new(RX_QUEUE);
+        let tx = VirtQueue::new(TX_QUEUE);
+        transport.setup_queue(&rx)?;
+        transport.setup_queue(&tx)?;
+        transport.finish_init();
+
+        let mac = if features & VIRTIO_NET_F_MAC != 0 {
+            core::array::from_fn(|i| transport.config_u8(CONFIG_MAC + i))
+        } else {
+            DEFAULT_MAC
+        };
+        let link_up = features & VIRTIO_NET_F_STATUS == 0 || transport.config_u16(CONFIG_STATUS) & STATUS_LINK_UP != 0;
+        let mut net = Self {
+            transport,
+            rx,
+            tx,
+            rx_buffers: (0..QUEUE_SIZE).map(|_| None).collect(),
+            header_len: if features & VIRTIO_F_VERSION_1 != 0 { 12 } else { 10 },
+            mac,
+        };
+        for _ in 0..RX_BUFFERS {
+            net.post_rx(Box::new([0; MAX_HEADER_LEN + MAX_FRAME_LEN]))?;
+        }
+        net.transport.notify(RX_QUEUE);
+        info!(
+            "🌐 virtio-net at 0x{:x}: mac {:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}, link {}",
+            net.transport.base(),
+            mac[0],
+            mac[1],
+            mac[2],
+            mac[3],
+            mac[4],
+            mac[5],
+            if link_up { "up" } else { "down" }
+        );
+        Ok(net)
+    }
+
+    /// 找到并初始化第一个 virtio-net 设备
+    pub fn probe() -> Result<Self, VirtioError> {
+        let transport = super::find(DeviceType::Network).ok_or(VirtioError::NoDevice)?;
+        Self::new(transport).inspect_err(|err| warn!("virtio-net init failed: {}", err))
+    }
+
+    /// 设备所在的 virtio-mmio 槽位
+    pub fn base(&self) -> usize {
+        self.transport.base()
+    }
+
+    pub fn mac(&self) -> MacAddr {
+        self.mac
+    }
+
+    /// 把接收缓冲区放入队列（不通知设备）
+    fn post_rx(&mut self, mut buffer: Box<RxBuffer>) -> Result<(), VirtioError> {
+        let (header, frame) = buffer.split_at_mut(self.header_len);
+        // 缓冲区由 rx_buffers 持有，直到 pop_used 取回之前不会被访问或释放
+        let head = unsafe { self.rx.add(&[], &mut [header, frame])? };
+        self.rx_buffers[head as usize] = Some(buffer);
+        Ok(())
+    }
+
+    /// 发送一帧，等待设备取走
+    pub fn send(&mut self, frame: &[u8]) -> Result<(), VirtioError> {
+        if frame.is_empty() || frame.len() > MAX_FRAME_LEN {
+            return Err(VirtioError::InvalidBuffer);
+        }
+        let header = [0u8; MAX_HEADER_LEN];
+        // 两个缓冲区在等到设备用完之前一直被借用
+        let head = unsafe { self.tx.add(&[&header[..self.header_len], frame], &mut [])? };
+        self.transport.notify(TX_QUEUE);
+        loop {
+            if let Some((id, _)) = self.tx.pop_used() {
+                self.transport.ack_interrupt();
+                debug_assert_eq!(id, head);
+                return Ok(());
+            }
+            core::hint::spin_loop();
+        }
+    }
+
+    /// 取出一个收到的帧复制到 `buf`，返回帧长；没有收到帧时返回 None
+    ///
+    /// 说明：`buf` 比帧短时多出的部分被丢弃
+    pub fn recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>, VirtioError> {
+        let Some((head, len)) = self.rx.pop_used() else {
+            return Ok(None);
+        };
+        self.transport.ack_interrupt();
+        let buffer = self.rx_buffers[head as usize].take().ok_or(VirtioError::IoError)?;
+        let frame_len = (len as usize).saturating_sub(self.header_len).min(MAX_FRAME_LEN);
+        let n = frame_len.min(buf.len());
+        buf[..n].copy_from_slice(&buffer[self.header_len..self.header_len + n]);
+        self.post_rx(buffer)?;
+        self.transport.notify(RX_QUEUE);
+        Ok(Some(n))
+    }
+}
+
+impl NetDevice for VirtioNet {
+    fn mac(&self) -> MacAddr {
+        self.mac
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), NetError> {
+        Ok(VirtioNet::send(self, frame)?)
+    }
+
+    fn recv(&mut self, buf: &mut [u8]) -> Result<Option<usize>, NetError> {
+        Ok(VirtioNet::recv(self, buf)?)
+    }
+}
+
+impl Drop for VirtioNet {
+    fn drop(&mut self) {
+        // 先停止设备，队列和接收缓冲区才能安全释放
+        self.transport.reset();
+    }
+}
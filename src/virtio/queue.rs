@@ -0,0 +1,217 @@
+//! 🔁 分离式 virtqueue（split virtqueue）
+//!
+//! 一个队列由三部分组成，按 legacy 接口的要求放在一块页对齐的连续内存中：
+//! - 描述符表：每个描述符指向一段缓冲区，用 `next` 串成链，一条链就是一个请求
+//! - 可用环（driver → device）：驱动放入链头，再增加 `idx`
+//! - 已用环（device → driver）：设备处理完后放入链头和写入的字节数，再增加 `idx`，放在下一页开头
+//!
+//! 没有 MMU，缓冲区的地址就是物理地址，设备直接 DMA 访问
+
+extern crate alloc;
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error};
+use core::alloc::Layout;
+use core::ptr::{NonNull, addr_of, addr_of_mut, read_volatile, write_volatile};
+use core::sync::atomic::{Ordering, fence};
+
+use super::{PAGE_SIZE, VirtioError};
+
+/// 每个队列的描述符数量
+pub const QUEUE_SIZE: usize = 16;
+
+/// 描述符标志：链中还有下一个描述符
+const DESC_F_NEXT: u16 = 1;
+/// 描述符标志：设备只写（否则设备只读）
+const DESC_F_WRITE: u16 = 2;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+#[repr(C)]
+struct AvailRing {
+    flags: u16,
+    idx: u16,
+    ring: [u16; QUEUE_SIZE],
+    used_event: u16,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UsedElem {
+    id: u32,
+    len: u32,
+}
+
+#[repr(C)]
+struct UsedRing {
+    flags: u16,
+    idx: u16,
+    ring: [UsedElem; QUEUE_SIZE],
+    avail_event: u16,
+}
+
+/// 已用环从新的一页开始（legacy 接口的 QueueAlign）
+#[repr(C, align(4096))]
+struct UsedPage(UsedRing);
+
+/// 队列占用的内存：描述符表和可用环在第一页，已用环在第二页
+#[repr(C, align(4096))]
+struct Rings {
+    desc: [Descriptor; QUEUE_SIZE],
+    avail: AvailRing,
+    used: UsedPage,
+}
+
+const _: () = assert!(core::mem::offset_of!(Rings, used) == PAGE_SIZE);
+
+/// 🔁 一个 virtqueue
+pub struct VirtQueue {
+    index: u16,
+    rings: NonNull<Rings>,
+    /// 空闲描述符链表的头
+    free_head: u16,
+    num_free: usize,
+    /// 下一个要写入可用环的位置
+    avail_idx: u16,
+    /// 下一个要从已用环取出的位置
+    last_used_idx: u16,
+}
+
+// 队列内存只通过 &mut self 访问
+unsafe impl Send for VirtQueue {}
+
+impl VirtQueue {
+    /// 为设备的第 `index` 个队列分配内存（之后交给 `MmioTransport::setup_queue`）
+    pub fn new(index: u16) -> Self {
+        let layout = Layout::new::<Rings>();
+        let ptr = unsafe { alloc_zeroed(layout) } as *mut Rings;
+        let Some(rings) = NonNull::new(ptr) else {
+            handle_alloc_error(layout);
+        };
+        let desc = unsafe { &mut (*rings.as_ptr()).desc };
+        for (i, desc) in desc.iter_mut().enumerate() {
+            desc.next = (i + 1) as u16;
+        }
+        Self {
+            index,
+            rings,
+            free_head: 0,
+            num_free: QUEUE_SIZE,
+            avail_idx: 0,
+            last_used_idx: 0,
+        }
+    }
+
+    pub fn index(&self) -> u16 {
+        self.index
+    }
+
+    /// 描述符表的地址
+    pub fn desc_addr(&self) -> usize {
+        self.rings.as_ptr() as usize
+    }
+
+    /// 可用环的地址
+    pub fn avail_addr(&self) -> usize {
+        unsafe { addr_of!((*self.rings.as_ptr()).avail) as usize }
+    }
+
+    /// 已用环的地址
+    pub fn used_addr(&self) -> usize {
+        unsafe { addr_of!((*self.rings.as_ptr()).used) as usize }
+    }
+
+    /// 空闲描述符数量
+    pub fn num_free(&self) -> usize {
+        self.num_free
+    }
+
+    /// 把一组缓冲区作为一条描述符链放入可用环，返回链头的 id
+    ///
+    /// 说明：
+    /// - `inputs` 是设备只读的缓冲区，`outputs` 是设备只写的缓冲区，按顺序串成一条链
+    /// - 放入后还需要调用 `MmioTransport::notify` 通知设备
+    ///
+    /// # Safety
+    /// 在 `pop_used` 取回这条链之前，缓冲区必须保持有效且不被访问
+    pub unsafe fn add(&mut self, inputs: &[&[u8]], outputs: &mut [&mut [u8]]) -> Result<u16, VirtioError> {
+        let count = inputs.len() + outputs.len();
+        if count == 0 {
+            return Err(VirtioError::InvalidBuffer);
+        }
+        if count > self.num_free {
+            return Err(VirtioError::QueueFull);
+        }
+        let rings = self.rings.as_ptr();
+        let buffers = inputs
+            .iter()
+            .map(|buf| (buf.as_ptr() as usize, buf.len(), 0))
+            .chain(outputs.iter_mut().map(|buf| (buf.as_mut_ptr() as usize, buf.len(), DESC_F_WRITE)));
+
+        let head = self.free_head;
+        for (i, (addr, len, flags)) in buffers.enumerate() {
+            let id = self.free_head;
+            let desc = unsafe { &mut (*rings).desc[id as usize] };
+            self.free_head = desc.next;
+            desc.addr = addr as u64;
+            desc.len = len as u32;
+            desc.flags = if i + 1 < count { flags | DESC_F_NEXT } else { flags };
+        }
+        self.num_free -= count;
+
+        unsafe {
+            let slot = self.avail_idx as usize % QUEUE_SIZE;
+            write_volatile(addr_of_mut!((*rings).avail.ring[slot]), head);
+            // 设备看到新的 idx 之前，描述符和环中的链头必须已经写入
+            fence(Ordering::SeqCst);
+            self.avail_idx = self.avail_idx.wrapping_add(1);
+            write_volatile(addr_of_mut!((*rings).avail.idx), self.avail_idx);
+        }
+        Ok(head)
+    }
+
+    /// 已用环中是否有还没取出的请求
+    pub fn can_pop(&self) -> bool {
+        let used_idx = unsafe { read_volatile(addr_of!((*self.rings.as_ptr()).used.0.idx)) };
+        used_idx != self.last_used_idx
+    }
+
+    /// 取出一个已完成的请求，返回链头的 id 和设备写入的字节数，并回收它的描述符
+    pub fn pop_used(&mut self) -> Option<(u16, u32)> {
+        if !self.can_pop() {
+            return None;
+        }
+        // 读取已用环中的元素之前先看到 idx 的更新
+        fence(Ordering::SeqCst);
+        let rings = self.rings.as_ptr();
+        let slot = self.last_used_idx as usize % QUEUE_SIZE;
+        let elem = unsafe { read_volatile(addr_of!((*rings).used.0.ring[slot])) };
+        self.last_used_idx = self.last_used_idx.wrapping_add(1);
+
+        let head = elem.id as u16;
+        let mut id = head;
+        loop {
+            let desc = unsafe { &mut (*rings).desc[id as usize] };
+            self.num_free += 1;
+            if desc.flags & DESC_F_NEXT == 0 {
+                desc.next = self.free_head;
+                break;
+            }
+            id = desc.next;
+        }
+        self.free_head = head;
+        Some((head, elem.len))
+    }
+}
+
+impl Drop for VirtQueue {
+    /// 说明：释放前必须先复位设备（`MmioTransport::reset`），否则设备可能还在访问这块内存
+    fn drop(&mut self) {
+        unsafe { dealloc(self.rings.as_ptr() as *mut u8, Layout::new::<Rings>()) };
+    }
+}
@@ -0,0 +1,10 @@
+//! 🔨 用户程序构建脚本：只给用户程序（bin）传入链接脚本 `app.ld`
+
+use std::env;
+use std::path::PathBuf;
+
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    println!("cargo:rustc-link-arg-bins=-T{}", manifest_dir.join("app.ld").display());
+    println!("cargo:rerun-if-changed=app.ld");
+}
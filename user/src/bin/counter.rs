@@ -0,0 +1,49 @@
+//! 🔢 独立编译的用户程序：使用自己的全局变量，并在映像内创建工作线程
+//!
+//! - 全局计数器位于映像的 .data/.bss 中，loader 为这些段设置了可读写的 PMP 表项
+//! - 工作线程由 spawn 系统调用创建，与主线程共享同一个映像
+//!
+//! 用法（内核中）: `loader::spawn("counter", &["counter", "4"])`，参数为工作线程数
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+user::entry!(main);
+
+/// 每个工作线程累加的次数
+const ROUNDS: usize = 1000;
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+static FINISHED: AtomicUsize = AtomicUsize::new(0);
+static GREETING: &str = "counter image";
+
+fn main(args: user::Args) -> i32 {
+    let workers = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(2);
+    user::println!("{}: starting {} workers", GREETING, workers);
+    for id in 0..workers {
+        if user::spawn(worker, id) < 0 {
+            user::println!("{}: failed to spawn worker {}", GREETING, id);
+            return 1;
+        }
+    }
+    while FINISHED.load(Ordering::Acquire) < workers {
+        user::sleep(5);
+    }
+    let total = COUNTER.load(Ordering::Relaxed);
+    user::println!("{}: counter = {} (expected {})", GREETING, total, workers * ROUNDS);
+    if total == workers * ROUNDS { 0 } else { 1 }
+}
+
+extern "C" fn worker(id: usize) -> i32 {
+    for round in 0..ROUNDS {
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+        if round % 100 == 0 {
+            user::yield_now();
+        }
+    }
+    user::println!("  worker {} done", id);
+    FINISHED.fetch_add(1, Ordering::Release);
+    0
+}
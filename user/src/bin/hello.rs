@@ -0,0 +1,16 @@
+//! 👋 独立编译的用户程序：打印命令行参数，以参数个数作为退出码
+//!
+//! 用法（内核中）: `loader::spawn("hello", &["hello", "world"])`
+
+#![no_std]
+#![no_main]
+
+user::entry!(main);
+
+fn main(args: user::Args) -> i32 {
+    user::println!("hello from a loaded image: thread {}, {} ms since boot", user::getpid(), user::get_time());
+    for (i, arg) in args.iter().enumerate() {
+        user::println!("  argv[{}] = {}", i, arg);
+    }
+    args.len() as i32
+}
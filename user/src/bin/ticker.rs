@@ -0,0 +1,18 @@
+//! ⏱️ 独立编译的用户程序：一直运行，每隔一段时间输出一次，用来演示 shell 的 `kill`
+//!
+//! 用法（shell 中）: `spawn ticker [interval_ms]`，默认每 2000 ms 输出一次
+
+#![no_std]
+#![no_main]
+
+user::entry!(main);
+
+fn main(args: user::Args) -> i32 {
+    let interval = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(2000);
+    let mut ticks = 0usize;
+    loop {
+        user::sleep(interval);
+        ticks += 1;
+        user::println!("ticker {}: tick {} at {} ms", user::getpid(), ticks, user::get_time());
+    }
+}
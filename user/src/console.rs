@@ -0,0 +1,66 @@
+//! 🖨️ 用户态控制台输出
+//!
+//! 格式化结果先写入栈上的缓冲区，满了或输出结束时再通过一次 write 系统调用写出，
+//! 减少陷入内核的次数，也让一行输出尽量不被其它线程打断
+
+use core::fmt::{self, Write};
+
+use crate::{STDOUT, write};
+
+const BUFFER_SIZE: usize = 256;
+
+struct Stdout {
+    buf: [u8; BUFFER_SIZE],
+    len: usize,
+}
+
+impl Stdout {
+    fn flush(&mut self) {
+        if self.len > 0 {
+            write(STDOUT, &self.buf[..self.len]);
+            self.len = 0;
+        }
+    }
+}
+
+impl Write for Stdout {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &byte in s.as_bytes() {
+            if self.len == BUFFER_SIZE {
+                self.flush();
+            }
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/// 输出格式化内容
+pub fn _print(args: fmt::Arguments) {
+    let mut stdout = Stdout {
+        buf: [0; BUFFER_SIZE],
+        len: 0,
+    };
+    let _ = stdout.write_fmt(args);
+    stdout.flush();
+}
+
+/// print! 宏
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => {
+        $crate::console::_print(format_args!($($arg)*))
+    };
+}
+
+/// println! 宏
+#[macro_export]
+macro_rules! println {
+    () => {
+        $crate::print!("\n")
+    };
+    ($fmt:literal $($arg:tt)*) => {
+        $crate::console::_print(format_args!(concat!($fmt, "\n") $($arg)*))
+    };
+}
@@ -0,0 +1,68 @@
+//! 👤 用户态库
+//!
+//! 运行在 U-mode 的代码使用的系统调用封装，通过 `ecall` 陷入内核。
+//! 系统调用号和语义见内核 `trap::syscall` 模块。
+//!
+//! ## 模块
+//! - `syscall.rs` - 系统调用号和原始 `ecall`
+//! - `console.rs` - 基于 write 系统调用的 `print!` / `println!`
+//! - `rt.rs` - 独立编译的用户程序（`src/bin`）的入口和 panic 处理，见 `entry!`
+//!
+//! 用户代码有两种形式：
+//! - 内核中的函数，由 `thread::spawn_user` 在 U-mode 运行：只能访问自己的用户栈和 `.rodata`，不能使用内核的全局变量和堆
+//! - `src/bin` 下独立编译的用户程序：链接到内核预留的用户程序区域（`app.ld`），由内核 loader 装载，
+//!   可以使用自己的全局变量
+
+#![no_std]
+
+pub mod console;
+pub mod rt;
+pub mod syscall;
+
+pub use rt::Args;
+
+use syscall::{SYS_EXIT, SYS_GET_TIME, SYS_GETPID, SYS_SLEEP, SYS_SPAWN, SYS_WRITE, SYS_YIELD, syscall};
+
+/// 标准输出
+pub const STDOUT: usize = 1;
+/// 标准错误
+pub const STDERR: usize = 2;
+
+/// 用户线程入口：参数为创建时传入的 `arg`，返回值作为退出码
+pub type Entry = extern "C" fn(usize) -> i32;
+
+/// 向文件描述符写入字节，返回写入的字节数或负的 errno
+pub fn write(fd: usize, buf: &[u8]) -> isize {
+    syscall(SYS_WRITE, [fd, buf.as_ptr() as usize, buf.len()])
+}
+
+/// 结束当前线程
+pub fn exit(code: i32) -> ! {
+    syscall(SYS_EXIT, [code as usize, 0, 0]);
+    unreachable!("exit returned");
+}
+
+/// 睡眠 `ms` 毫秒
+pub fn sleep(ms: usize) {
+    syscall(SYS_SLEEP, [ms, 0, 0]);
+}
+
+/// 让出 CPU
+pub fn yield_now() {
+    syscall(SYS_YIELD, [0, 0, 0]);
+}
+
+/// 启动以来的毫秒数
+pub fn get_time() -> usize {
+    syscall(SYS_GET_TIME, [0, 0, 0]) as usize
+}
+
+/// 当前线程 id
+pub fn getpid() -> usize {
+    syscall(SYS_GETPID, [0, 0, 0]) as usize
+}
+
+/// 创建新的用户线程执行 `entry(arg)`，返回新线程 id 或负的 errno
+pub fn spawn(entry: Entry, arg: usize) -> isize {
+    syscall(SYS_SPAWN, [entry as usize, arg, 0])
+}
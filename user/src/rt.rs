@@ -0,0 +1,95 @@
+//! 🚀 独立编译的用户程序运行时
+//!
+//! 内核 loader 装载用户程序后从 `_start` 开始执行，栈布局与 Linux 相同：
+//!
+//! ```text
+//! sp -> argc
+//!       argv[0] .. argv[argc - 1], NULL
+//!       envp: NULL
+//!       auxv: AT_NULL
+//!       ...参数字符串
+//! ```
+//!
+//! 同时 a0 = argc、a1 = argv。`entry!` 宏生成 `_start` 和 panic 处理函数，
+//! `_start` 把参数包装成 `Args` 交给用户的 main，并以 main 的返回值调用 exit。
+
+use core::ffi::CStr;
+use core::panic::PanicInfo;
+
+use crate::{exit, println};
+
+/// 命令行参数
+#[derive(Clone, Copy)]
+pub struct Args {
+    argc: usize,
+    argv: *const *const u8,
+}
+
+impl Args {
+    /// 由 `_start` 收到的 argc/argv 构造
+    ///
+    /// # Safety
+    /// `argv` 必须指向 `argc` 个以 NUL 结尾的字符串指针
+    pub unsafe fn from_raw(argc: usize, argv: *const *const u8) -> Self {
+        Self { argc, argv }
+    }
+
+    /// 参数个数（包括程序名）
+    pub fn len(&self) -> usize {
+        self.argc
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.argc == 0
+    }
+
+    /// 第 `index` 个参数；不是合法的 UTF-8 时返回 None
+    pub fn get(&self, index: usize) -> Option<&'static str> {
+        if index >= self.argc {
+            return None;
+        }
+        let arg = unsafe { CStr::from_ptr(*self.argv.add(index) as *const core::ffi::c_char) };
+        arg.to_str().ok()
+    }
+
+    /// 依次迭代所有参数（跳过不是合法 UTF-8 的参数）
+    pub fn iter(&self) -> impl Iterator<Item = &'static str> + '_ {
+        (0..self.argc).filter_map(|index| self.get(index))
+    }
+}
+
+/// 用户程序 panic：输出信息后以退出码 101 结束
+pub fn panic(info: &PanicInfo) -> ! {
+    println!("user panic: {}", info);
+    exit(101)
+}
+
+/// 🚀 声明独立编译的用户程序入口：`fn main(args: user::Args) -> i32`
+///
+/// ```ignore
+/// #![no_std]
+/// #![no_main]
+///
+/// user::entry!(main);
+///
+/// fn main(args: user::Args) -> i32 {
+///     user::println!("hello from {}", args.get(0).unwrap_or("?"));
+///     0
+/// }
+/// ```
+#[macro_export]
+macro_rules! entry {
+    ($main:path) => {
+        #[unsafe(no_mangle)]
+        #[unsafe(link_section = ".text.entry")]
+        extern "C" fn _start(argc: usize, argv: *const *const u8) -> ! {
+            let main: fn($crate::Args) -> i32 = $main;
+            $crate::exit(main(unsafe { $crate::Args::from_raw(argc, argv) }))
+        }
+
+        #[panic_handler]
+        fn panic(info: &core::panic::PanicInfo) -> ! {
+            $crate::rt::panic(info)
+        }
+    };
+}
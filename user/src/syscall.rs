@@ -0,0 +1,33 @@
+//! 📞 系统调用
+//!
+//! 编号沿用 Linux RISC-V，必须与内核 `trap::syscall` 保持一致
+
+pub const SYS_WRITE: usize = 64;
+pub const SYS_EXIT: usize = 93;
+pub const SYS_SLEEP: usize = 101;
+pub const SYS_YIELD: usize = 124;
+pub const SYS_GET_TIME: usize = 169;
+pub const SYS_GETPID: usize = 172;
+pub const SYS_SPAWN: usize = 220;
+
+/// 错误的文件描述符
+pub const EBADF: isize = 9;
+/// 非法地址
+pub const EFAULT: isize = 14;
+/// 不支持的系统调用
+pub const ENOSYS: isize = 38;
+
+/// 发起系统调用：a7 为编号，a0~a2 为参数，返回 a0
+pub fn syscall(id: usize, args: [usize; 3]) -> isize {
+    let ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("a0") args[0] => ret,
+            in("a1") args[1],
+            in("a2") args[2],
+            in("a7") id,
+        );
+    }
+    ret
+}